@@ -76,10 +76,12 @@ impl Kule for App {
             } => {
                 // Play sounds when certain keys are pressed
                 match key {
-                    Key::Space => ctx.play_sound(SoundId::Kick, app).unwrap(),
-                    Key::LShift => ctx
-                        .play_modified_sound(SoundId::Noise, app, |s| s.amplify(2.0))
-                        .unwrap(),
+                    Key::Space => {
+                        ctx.play_sound(SoundId::Kick, app)?;
+                    }
+                    Key::LShift => {
+                        ctx.play_modified_sound(SoundId::Noise, app, |s| s.amplify(2.0))?;
+                    }
                     _ => {}
                 }
             }
@@ -134,8 +136,9 @@ impl Kule for App {
         Ok(())
     }
     // The `teardown` method lets us call some code when the window is closed
-    fn teardown(app: Self, _ctx: &mut Context<Self::Resources>) {
+    fn teardown(app: Self, _ctx: &mut Context<Self::Resources>) -> CanFail {
         println!("Quit at {:?}.", app.pos);
+        Ok(())
     }
     // The `load_sound` method lets us define how to load sound data based on our sound id type
     // The sound id type for our `()` `Resources` is `()` which means we can only load one sound.
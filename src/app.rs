@@ -1,12 +1,20 @@
-use std::{fmt::Debug, hash::Hash, marker::PhantomData, time::Instant};
+use std::{
+    fmt::Debug,
+    hash::Hash,
+    marker::PhantomData,
+    time::{Duration, Instant},
+};
 
-use glium::{glutin::*, *};
+use glium::{
+    glutin::{platform::desktop::EventLoopExtDesktop, *},
+    *,
+};
 
 #[cfg(feature = "sound")]
-use crate::sound::{self, SoundBuffer};
+use crate::sound::SoundBuffer;
 use crate::{
-    Camera, CanFail, Canvas, Context, ContextBuilder, Drawer, Event, FloatingScalar, KuleResult,
-    StateTracker, Window,
+    CanFail, Canvas, Context, ContextBuilder, Drawer, Event, KuleResult, Runner, SceneStack,
+    Tweens, Two, UpdateMode, UserPayload, Vec2, WindowPosition,
 };
 
 /**
@@ -24,10 +32,30 @@ pub trait Kule: Sized + 'static {
     fn setup(ctx: &mut Context<Self::Resources>) -> KuleResult<Self>;
     /// Update function called often
     ///
-    /// `dt` is the amount of time that has passed since the last update
+    /// `dt` is the amount of time that has passed since the last update, conditioned by
+    /// `ContextBuilder::dt_filter`. The unconditioned time is available with `Context::raw_dt`.
+    /// With `UpdateMode::Fixed`, it is always `1.0 / ContextBuilder::update_frequency`.
     fn update(dt: f32, app: &mut Self, ctx: &mut Context<Self::Resources>) -> CanFail {
         Ok(())
     }
+    /// Update function called at a fixed rate, suitable for physics
+    ///
+    /// `dt` is always `1.0 / ContextBuilder::fixed_update_frequency`. Fixed updates
+    /// run before `update`, and multiple may run in a row to catch up.
+    fn fixed_update(dt: f32, app: &mut Self, ctx: &mut Context<Self::Resources>) -> CanFail {
+        Ok(())
+    }
+    /// Called once per frame after all events and updates have been handled,
+    /// right before drawing
+    ///
+    /// This is a good place to move the camera to follow things that moved during the update.
+    fn late_update(app: &mut Self, ctx: &mut Context<Self::Resources>) -> CanFail {
+        Ok(())
+    }
+    /// Called when the window is resized, before the `Event::Resize` is handled by `event`
+    fn on_resize(size: Vec2, app: &mut Self, ctx: &mut Context<Self::Resources>) -> CanFail {
+        Ok(())
+    }
     /// Draw
     fn draw<C>(
         draw: &mut Drawer<C, Self::Resources>,
@@ -39,12 +67,40 @@ pub trait Kule: Sized + 'static {
     {
         Ok(())
     }
+    /**
+    Draw the UI
+
+    This is called after `draw` with an absolute camera already applied, so UI
+    elements are positioned in window space and are drawn on top of the scene.
+    If `ContextBuilder::ui_scaling` is enabled, coordinates are in logical pixels,
+    so the UI is the same physical size on high-DPI displays. `Context::mouse_ui_coords`
+    gets the mouse position in the same space.
+    */
+    fn draw_ui<C>(
+        draw: &mut Drawer<C, Self::Resources>,
+        app: &Self,
+        ctx: &Context<Self::Resources>,
+    ) -> CanFail
+    where
+        C: Canvas,
+    {
+        Ok(())
+    }
     /// Handle events
     fn event(event: Event, app: &mut Self, ctx: &mut Context<Self::Resources>) -> CanFail {
         Ok(())
     }
+    /// Called when `setup` fails, before `run` returns the error
+    ///
+    /// This is a good place to show the user what went wrong, since the app
+    /// may not have a console
+    fn setup_failed(error: &crate::KuleError, ctx: &mut Context<Self::Resources>) {}
     /// Called when the app is closed
-    fn teardown(app: Self, ctx: &mut Context<Self::Resources>) {}
+    ///
+    /// Because the app no longer exists, errors are handled according to `Context::error_policy`
+    fn teardown(app: Self, ctx: &mut Context<Self::Resources>) -> CanFail {
+        Ok(())
+    }
     #[cfg(feature = "sound")]
     /// Load a sound
     fn load_sound(
@@ -53,148 +109,347 @@ pub trait Kule: Sized + 'static {
     ) -> KuleResult<Option<SoundBuffer>> {
         Ok(None)
     }
-    #[cfg(feature = "script")]
-    /// Handle an error
+    /// Handle an error returned from any of the app's methods, scenes, or scripts
     ///
-    /// The default implementation simply panics
+    /// The default implementation handles the error according to `Context::error_policy`
     fn handle_error(error: crate::KuleError, app: &mut Self, ctx: &mut Context<Self::Resources>) {
-        panic!("{}", error)
+        ctx.error_policy().handle(error, ctx)
     }
     /// Run the app and panic if setup fails
+    ///
+    /// When the app closes, the process exits with the code passed to `Context::request_exit`
     fn run_or_panic() -> ! {
-        Self::run().unwrap_or_else(|e| panic!("{}", e));
-        std::process::exit(0)
+        let code = Self::run().unwrap_or_else(|e| panic!("{}", e));
+        std::process::exit(code)
     }
     /// Run the app
     ///
-    /// This takes control of the current thread. If initial setup does not fail, then this
-    /// function will never return.
-    fn run() -> KuleResult<std::convert::Infallible> {
+    /// This takes control of the current thread until the app closes. Returns the exit code
+    /// passed to `Context::request_exit`, or `0` if the app was closed some other way.
+    fn run() -> KuleResult<i32> {
         let builder = Self::build()?;
-        #[cfg(feature = "script")]
-        let script_env = builder.script_env.clone();
-        let ContextBuilder {
-            title,
-            size,
-            icon,
-            samples,
-            automatic_close,
-            update_frequency,
-            ..
-        } = builder;
-        // Init audio
-        #[cfg(feature = "sound")]
-        let sink = sound::sink();
+        if builder.headless {
+            return Self::run_headless(builder);
+        }
         // Build event loop and display
         #[cfg(not(test))]
-        let event_loop = event_loop::EventLoop::new();
+        let mut event_loop = event_loop::EventLoop::<UserPayload>::with_user_event();
         #[cfg(test)]
-        let event_loop = {
+        let mut event_loop = {
             #[cfg(unix)]
             use platform::unix::EventLoopExtUnix;
             #[cfg(windows)]
             use platform::windows::EventLoopExtWindows;
-            event_loop::EventLoop::<()>::new_any_thread()
+            event_loop::EventLoop::<UserPayload>::new_any_thread()
         };
-        let wb = window::WindowBuilder::new()
-            .with_title(title)
-            .with_window_icon(icon)
-            .with_inner_size(dpi::LogicalSize::new(size[0], size[1]));
+        let mut wb = window::WindowBuilder::new()
+            .with_title(builder.title.clone())
+            .with_window_icon(builder.icon.clone())
+            .with_inner_size(dpi::LogicalSize::new(builder.size[0], builder.size[1]))
+            .with_resizable(builder.resizable)
+            // The window is shown after it is moved to its initial position
+            .with_visible(builder.position.is_none())
+            .with_transparent(builder.transparent)
+            .with_always_on_top(builder.always_on_top)
+            .with_decorations(builder.decorations)
+            .with_maximized(builder.maximized)
+            .with_fullscreen(
+                builder
+                    .fullscreen
+                    .and_then(|mode| mode.to_fullscreen(event_loop.primary_monitor())),
+            );
+        if let Some([w, h]) = builder.min_size {
+            wb = wb.with_min_inner_size(dpi::LogicalSize::new(w, h));
+        }
+        if let Some([w, h]) = builder.max_size {
+            wb = wb.with_max_inner_size(dpi::LogicalSize::new(w, h));
+        }
         let cb = glutin::ContextBuilder::new()
-            .with_multisampling(samples)
-            .with_stencil_buffer(1);
+            .with_multisampling(builder.samples)
+            .with_vsync(builder.vsync)
+            .with_depth_buffer(builder.depth_bits)
+            .with_stencil_buffer(builder.stencil_bits)
+            .with_srgb(builder.srgb)
+            .with_hardware_acceleration(builder.hardware_acceleration);
         let display = Display::new(wb, cb, &event_loop)?;
-        let window_size = display.gl_window().window().inner_size();
-        let program = crate::default_shaders(&display);
-        let mut ctx = Context {
-            program,
-            fonts: Default::default(),
-            meshes: Default::default(),
-            #[cfg(feature = "sound")]
-            mixer: sound::Mixer::new(&sink),
-            #[cfg(feature = "sound")]
-            sounds: sound::Sounds::default(),
-            tracker: StateTracker::default(),
-            camera: Camera {
-                center: [0.0; 2],
-                zoom: 1.0,
-                window_size: window_size.into(),
-            },
-            window: Window(display),
-            #[cfg(feature = "script")]
-            scripts: crate::Scripts::load(script_env),
-            should_close: false,
-            update_timer: Instant::now(),
-            fps_timer: Instant::now(),
-        };
+        let mut ctx = Context::new(&builder, Some(display))?;
+        if let Some(position) = builder.position {
+            match position {
+                WindowPosition::At(pos) => ctx.window.set_position(pos),
+                WindowPosition::Centered => {
+                    if let Some(monitor) = ctx.window.primary_monitor() {
+                        ctx.window.center_on(&monitor);
+                    }
+                }
+            }
+            ctx.window.inner().set_visible(true);
+        }
+        ctx.event_proxy = Some(event_loop.create_proxy());
         // Run app setup
-        let mut app = Some(Self::setup(&mut ctx)?);
+        let mut runner = Runner::<Self>::new(&builder, ctx)?;
         // Run the event loop
-        event_loop.run(move |event, _, cf| {
+        event_loop.run_return(|event, _, cf| {
             // Draw
             if let event::Event::RedrawEventsCleared = &event {
-                let now = Instant::now();
-                let dt = (now - ctx.fps_timer).as_secs_f32();
-                ctx.fps_timer = now;
-                ctx.tracker.fps = ctx.tracker.fps.lerp(1.0 / dt, 0.1);
-                if let Some(app) = &mut app {
-                    if let Err(e) = ctx.draw(|drawer| Self::draw(drawer, app, &ctx)) {
-                        Self::handle_error(e, app, &mut ctx)
-                    }
-                }
+                *cf = runner.draw();
             }
             // Handle events
-            for event in Event::from_glutin(event, &mut ctx.tracker, &mut ctx.camera) {
-                let automatic_close = event == Event::CloseRequest && automatic_close;
-                if automatic_close || ctx.should_close {
-                    *cf = event_loop::ControlFlow::Exit;
-                    if let Some(app) = app.take() {
-                        Self::teardown(app, &mut ctx);
-                    }
-                    break;
-                } else if let Some(app) = &mut app {
-                    // Run app event method
-                    if let Err(e) = Self::event(event, app, &mut ctx) {
-                        Self::handle_error(e, app, &mut ctx);
-                    }
-                    // Run event scripts
-                    #[cfg(feature = "script")]
-                    if let Ok(scripts) = ctx.scripts() {
-                        if let Err(e) = scripts.batch_call("event", move |lua, t, f| {
-                            let mut ser = crate::LuaSerializer::new(lua);
-                            let event = ser.serialize(&event)?;
-                            f.call((t, event))?;
-                            Ok(())
-                        }) {
-                            Self::handle_error(e, app, &mut ctx);
-                        }
-                    }
+            let ctx = runner.ctx_mut();
+            let events = match event {
+                event::Event::UserEvent(payload) => {
+                    Two::one(Event::User(ctx.insert_user_event(payload)))
                 }
+                event => Event::from_glutin(event, &mut ctx.tracker, &mut ctx.camera),
+            };
+            runner.handle_events(events);
+            if !runner.is_running() {
+                *cf = event_loop::ControlFlow::Exit;
             }
             // Update
-            let now = Instant::now();
-            let dt = (now - ctx.update_timer).as_secs_f32();
-            if dt >= 1.0 / update_frequency {
-                ctx.update_timer = now;
+            runner.update();
+        });
+        Ok(runner.finish())
+    }
+    /**
+    Run the app without a window
+
+    Nothing is drawn and no input events are received. The app is updated
+    until `Context::should_close` is set. Returns the exit code passed to `Context::request_exit`.
+    */
+    fn run_headless(builder: ContextBuilder) -> KuleResult<i32> {
+        let mut ctx = Context::new(&builder, None)?;
+        let mut app = Some(setup::<Self>(&mut ctx)?);
+        loop {
+            {
+                crate::profile_scope!("update");
+                run_updates(
+                    builder.update_frequency,
+                    builder.update_mode,
+                    &mut app,
+                    &mut ctx,
+                );
                 if let Some(app) = &mut app {
-                    // Run app update method
-                    if let Err(e) = Self::update(dt, app, &mut ctx) {
+                    if let Err(e) = Self::late_update(app, &mut ctx) {
                         Self::handle_error(e, app, &mut ctx);
                     }
-                    // Run update scripts
-                    #[cfg(feature = "script")]
-                    if let Ok(scripts) = ctx.scripts() {
-                        if let Err(e) = scripts.batch_call("update", move |_, t, f| {
+                }
+            }
+            // Each pass of the loop is a frame, and no spans are open here
+            ctx.profiler.finish_frame();
+            if ctx.should_close {
+                if let Some(app) = app.take() {
+                    teardown(app, &mut ctx);
+                }
+                return Ok(ctx.exit_code());
+            }
+            let next_update = next_update(builder.update_frequency, builder.update_mode, &ctx);
+            std::thread::sleep(next_update.saturating_duration_since(Instant::now()));
+        }
+    }
+}
+
+/// Run app setup, notifying the app if it fails
+pub(crate) fn setup<A>(ctx: &mut Context<A::Resources>) -> KuleResult<A>
+where
+    A: Kule,
+{
+    A::setup(ctx).inspect_err(|e| A::setup_failed(e, ctx))
+}
+
+/// Run app teardown, handling any error with the error policy
+pub(crate) fn teardown<A>(app: A, ctx: &mut Context<A::Resources>)
+where
+    A: Kule,
+{
+    if let Err(e) = A::teardown(app, ctx) {
+        ctx.error_policy().handle(e, ctx);
+    }
+}
+
+/// Pass an event to the app, scenes, and scripts
+pub(crate) fn dispatch_event<A>(event: Event, app: &mut A, ctx: &mut Context<A::Resources>)
+where
+    A: Kule,
+{
+    if let Event::Resize(size) = event {
+        if let Err(e) = A::on_resize(size, app, ctx) {
+            A::handle_error(e, app, ctx);
+        }
+    }
+    // Run app event method
+    if let Err(e) = A::event(event, app, ctx) {
+        A::handle_error(e, app, ctx);
+    }
+    // Run scene event method
+    if let Err(e) = SceneStack::event(ctx, event) {
+        A::handle_error(e, app, ctx);
+    }
+    // Run event scripts
+    #[cfg(feature = "script")]
+    if let Ok(scripts) = &ctx.scripts {
+        let errors = scripts
+            .with_camera(&mut ctx.camera, |scripts| {
+                let mut errors = scripts
+                    .batch_call("event", move |lua, t, f| {
+                        let mut ser = crate::LuaSerializer::new(lua);
+                        let event = ser.serialize(&event)?;
+                        f.call((t, event))?;
+                        Ok(())
+                    })
+                    .into_errors();
+                errors.extend(scripts.task_event(&event));
+                errors
+            })
+            .unwrap_or_else(|e| vec![e]);
+        for e in errors {
+            A::handle_error(e, app, ctx);
+        }
+    }
+}
+
+/// Run any updates that are due
+pub(crate) fn run_updates<A>(
+    update_frequency: f32,
+    update_mode: UpdateMode,
+    app: &mut Option<A>,
+    ctx: &mut Context<A::Resources>,
+) where
+    A: Kule,
+{
+    let now = Instant::now();
+    // Run fixed updates
+    // In deterministic mode, they are run in lockstep with updates instead
+    let elapsed = if ctx.deterministic {
+        0.0
+    } else {
+        (now - ctx.fixed_timer).as_secs_f32()
+    };
+    ctx.fixed_lag += ctx.dt_filter.clamp(elapsed);
+    ctx.fixed_timer = now;
+    while ctx.fixed_lag >= ctx.fixed_step {
+        ctx.fixed_lag -= ctx.fixed_step;
+        if let Some(app) = app {
+            if let Err(e) = A::fixed_update(ctx.fixed_step, app, ctx) {
+                A::handle_error(e, app, ctx);
+            }
+        }
+    }
+    // Run updates
+    let step = 1.0 / update_frequency;
+    match update_mode {
+        UpdateMode::Variable => {
+            let raw_dt = (now - ctx.update_timer).as_secs_f32();
+            if raw_dt >= step {
+                ctx.update_timer = now;
+                let dt = ctx.filter_dt(raw_dt);
+                update(dt, app, ctx);
+            }
+        }
+        UpdateMode::Fixed { max_steps } => {
+            let elapsed = (now - ctx.update_timer).as_secs_f32();
+            ctx.update_lag += ctx.dt_filter.clamp(elapsed);
+            ctx.update_timer = now;
+            let mut steps = 0;
+            while ctx.update_lag >= step && steps < max_steps {
+                ctx.update_lag -= step;
+                ctx.raw_dt = if steps == 0 { elapsed } else { 0.0 };
+                steps += 1;
+                update(step, app, ctx);
+            }
+            // Drop time that could not be caught up on
+            if steps == max_steps {
+                ctx.update_lag = ctx.update_lag.min(step);
+            }
+        }
+    }
+}
+
+/// Get the time of the next update
+pub(crate) fn next_update<R>(
+    update_frequency: f32,
+    update_mode: UpdateMode,
+    ctx: &Context<R>,
+) -> Instant
+where
+    R: Resources,
+{
+    let step = Duration::from_secs_f32(1.0 / update_frequency);
+    let next_update = match update_mode {
+        UpdateMode::Variable => ctx.update_timer + step,
+        UpdateMode::Fixed { .. } => {
+            ctx.update_timer + step
+                - Duration::from_secs_f32(ctx.update_lag.min(step.as_secs_f32()))
+        }
+    };
+    if ctx.deterministic {
+        return next_update;
+    }
+    let next_fixed_update =
+        ctx.fixed_timer + Duration::from_secs_f32((ctx.fixed_step - ctx.fixed_lag).max(0.0));
+    next_update.min(next_fixed_update)
+}
+
+/// Run a single update
+fn update<A>(dt: f32, app: &mut Option<A>, ctx: &mut Context<A::Resources>)
+where
+    A: Kule,
+{
+    ctx.dt = dt;
+    ctx.diagnostics.current.updates += 1;
+    ctx.tracker.update_axes(dt);
+    if let Some(app) = app {
+        // Handle queued events
+        let queued = std::mem::take(&mut ctx.queued_events);
+        if !queued.is_empty() {
+            for event in queued {
+                dispatch_event(event, app, ctx);
+            }
+            ctx.clear_user_events();
+        }
+        // Run fixed update in lockstep in deterministic mode
+        if ctx.deterministic {
+            if let Err(e) = A::fixed_update(dt, app, ctx) {
+                A::handle_error(e, app, ctx);
+            }
+        }
+        // Run app update method
+        if let Err(e) = A::update(dt, app, ctx) {
+            A::handle_error(e, app, ctx);
+        }
+        // Run scene update method
+        if let Err(e) = SceneStack::update(ctx, dt) {
+            A::handle_error(e, app, ctx);
+        }
+        // Resume coroutines
+        ctx.coroutines.update(dt, &ctx.tracker);
+        // Advance tweens
+        if let Err(e) = Tweens::update(ctx, dt) {
+            A::handle_error(e, app, ctx);
+        }
+        // Run update scripts
+        #[cfg(feature = "script")]
+        if let Ok(scripts) = &ctx.scripts {
+            let errors = scripts
+                .with_camera(&mut ctx.camera, |scripts| {
+                    let mut errors = scripts
+                        .batch_call("update", move |_, t, f| {
                             f.call((t, dt))?;
                             Ok(())
-                        }) {
-                            Self::handle_error(e, app, &mut ctx);
-                        }
-                    }
-                }
+                        })
+                        .into_errors();
+                    errors.extend(scripts.update_tasks(dt));
+                    errors
+                })
+                .unwrap_or_else(|e| vec![e]);
+            for e in errors {
+                A::handle_error(e, app, ctx);
             }
-        })
+        }
     }
+    // Despawn queued entities
+    ctx.world.maintain();
+    ctx.tracker.end_update();
+    ctx.ticks += 1;
 }
 
 /// Resource id types for an app
@@ -0,0 +1,151 @@
+use std::{
+    panic,
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use cpal::{
+    traits::{DeviceTrait, EventLoopTrait, HostTrait},
+    EventLoop, StreamData, StreamId, UnknownTypeInputBuffer,
+};
+use crossbeam_utils::atomic::AtomicCell;
+
+use crate::{KuleError, KuleResult, SoundBuffer};
+
+/**
+Records audio from an input device
+
+The recorded samples are streamed into a `SoundBuffer`, which can be
+played back like any other sound while recording is still in progress.
+
+Recording stops when the `Microphone` is dropped, which also stops the recording thread.
+*/
+pub struct Microphone {
+    event_loop: Arc<EventLoop>,
+    stream: StreamId,
+    buffer: SoundBuffer,
+    level: Arc<AtomicCell<f32>>,
+    peak: Arc<AtomicCell<f32>>,
+    stop: Arc<AtomicCell<bool>>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+/// The payload used to unwind out of the event loop, which never returns on its own
+struct Stop;
+
+/// How long dropping a `Microphone` waits for the recording thread to stop
+const STOP_TIMEOUT: Duration = Duration::from_millis(500);
+
+impl Microphone {
+    /// Start recording from the default input device
+    pub fn open_default() -> KuleResult<Self> {
+        let host = cpal::default_host();
+        let device = host
+            .default_input_device()
+            .ok_or_else(|| KuleError::AudioDevice("No input device available".into()))?;
+        let format = device
+            .default_input_format()
+            .map_err(|e| KuleError::AudioDevice(e.to_string()))?;
+        let event_loop = Arc::new(host.event_loop());
+        let stream = event_loop
+            .build_input_stream(&device, &format)
+            .map_err(|e| KuleError::AudioDevice(e.to_string()))?;
+        event_loop
+            .play_stream(stream.clone())
+            .map_err(|e| KuleError::AudioDevice(e.to_string()))?;
+        let buffer = SoundBuffer::streaming(format.sample_rate.0, format.channels);
+        let level = Arc::new(AtomicCell::new(0.0));
+        let peak = Arc::new(AtomicCell::new(0.0));
+        let thread_event_loop = Arc::clone(&event_loop);
+        let thread_buffer = buffer.clone();
+        let thread_level = Arc::clone(&level);
+        let thread_peak = Arc::clone(&peak);
+        let stop = Arc::new(AtomicCell::new(false));
+        let thread_stop = Arc::clone(&stop);
+        let thread = thread::spawn(move || {
+            // Unwinding with a payload instead of panicking skips the panic hook
+            let _ = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+                thread_event_loop.run(move |_, data| {
+                    if thread_stop.load() {
+                        panic::resume_unwind(Box::new(Stop));
+                    }
+                    let samples: Vec<f32> = match data {
+                        Ok(StreamData::Input {
+                            buffer: UnknownTypeInputBuffer::F32(buffer),
+                        }) => buffer.to_vec(),
+                        Ok(StreamData::Input {
+                            buffer: UnknownTypeInputBuffer::I16(buffer),
+                        }) => buffer.iter().map(|&s| s as f32 / i16::MAX as f32).collect(),
+                        Ok(StreamData::Input {
+                            buffer: UnknownTypeInputBuffer::U16(buffer),
+                        }) => buffer
+                            .iter()
+                            .map(|&s| s as f32 / u16::MAX as f32 * 2.0 - 1.0)
+                            .collect(),
+                        _ => return,
+                    };
+                    if samples.is_empty() {
+                        return;
+                    }
+                    let sum_sq: f32 = samples.iter().map(|s| s * s).sum();
+                    thread_level.store((sum_sq / samples.len() as f32).sqrt());
+                    thread_peak.store(samples.iter().fold(0f32, |max, s| max.max(s.abs())));
+                    thread_buffer.extend(samples);
+                })
+            }));
+        });
+        Ok(Microphone {
+            event_loop,
+            stream,
+            buffer,
+            level,
+            peak,
+            stop,
+            thread: Some(thread),
+        })
+    }
+    /// Get the buffer that the recording is streamed into
+    pub fn buffer(&self) -> &SoundBuffer {
+        &self.buffer
+    }
+    /// Get the root-mean-square level of the most recently recorded samples
+    pub fn level(&self) -> f32 {
+        self.level.load()
+    }
+    /// Get the peak level of the most recently recorded samples
+    pub fn peak(&self) -> f32 {
+        self.peak.load()
+    }
+    /// Pause recording
+    pub fn pause(&self) -> KuleResult<()> {
+        self.event_loop
+            .pause_stream(self.stream.clone())
+            .map_err(|e| KuleError::AudioDevice(e.to_string()))
+    }
+    /// Resume recording
+    pub fn resume(&self) -> KuleResult<()> {
+        self.event_loop
+            .play_stream(self.stream.clone())
+            .map_err(|e| KuleError::AudioDevice(e.to_string()))
+    }
+}
+
+impl Drop for Microphone {
+    fn drop(&mut self) {
+        self.stop.store(true);
+        if let Some(thread) = self.thread.take() {
+            // The thread only sees the flag when it receives data, so the stream must be playing
+            let _ = self.event_loop.play_stream(self.stream.clone());
+            let start = Instant::now();
+            while !thread.is_finished() && start.elapsed() < STOP_TIMEOUT {
+                thread::sleep(Duration::from_millis(1));
+            }
+            if thread.is_finished() {
+                let _ = thread.join();
+            }
+        }
+        self.event_loop.destroy_stream(self.stream.clone());
+        self.buffer.finish();
+    }
+}
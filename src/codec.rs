@@ -0,0 +1,267 @@
+use std::{
+    io::{Read, Seek},
+    time::Duration,
+};
+
+use rodio::decoder::{Decoder, DecoderError};
+
+use crate::Source;
+
+/// A decoder for any supported audio format
+pub(crate) struct CodecDecoder {
+    samples: Box<dyn Iterator<Item = f32> + Send>,
+    sample_rate: u32,
+    channels: u16,
+    duration: Option<Duration>,
+}
+
+impl<R> From<Decoder<R>> for CodecDecoder
+where
+    R: Read + Seek + Send + 'static,
+{
+    fn from(decoder: Decoder<R>) -> Self {
+        CodecDecoder {
+            sample_rate: decoder.sample_rate(),
+            channels: decoder.channels(),
+            duration: decoder.total_duration(),
+            samples: Box::new(decoder.convert_samples::<f32>()),
+        }
+    }
+}
+
+impl Iterator for CodecDecoder {
+    type Item = f32;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.samples.next()
+    }
+}
+
+impl Source for CodecDecoder {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+    fn total_duration(&self) -> Option<Duration> {
+        self.duration
+    }
+}
+
+/**
+Create a decoder for formats that rodio does not support
+
+Returns `None` if the data is not in one of these formats
+*/
+pub(crate) fn decoder(bytes: &[u8]) -> Option<Result<CodecDecoder, DecoderError>> {
+    #[cfg(feature = "opus")]
+    {
+        if opus::is_opus(bytes) {
+            return Some(opus::decoder(bytes.to_vec()));
+        }
+    }
+    #[cfg(feature = "aac")]
+    {
+        if aac::is_aac(bytes) {
+            return Some(aac::decoder(bytes.to_vec()));
+        }
+    }
+    None
+}
+
+#[cfg(feature = "opus")]
+mod opus {
+    use std::io::Cursor;
+
+    use audiopus::{coder::Decoder, Channels, SampleRate};
+    use ogg::PacketReader;
+    use rodio::decoder::DecoderError;
+
+    use super::CodecDecoder;
+
+    /// Opus always decodes at 48kHz
+    const OPUS_SAMPLE_RATE: u32 = 48000;
+    /// The maximum number of samples per channel in an Opus packet (120ms)
+    const MAX_FRAME_SIZE: usize = 5760;
+
+    pub fn is_opus(bytes: &[u8]) -> bool {
+        if !bytes.starts_with(b"OggS") || bytes.len() < 27 {
+            return false;
+        }
+        let start = 27 + bytes[26] as usize;
+        bytes.get(start..start + 8) == Some(b"OpusHead")
+    }
+
+    pub fn decoder(bytes: Vec<u8>) -> Result<CodecDecoder, DecoderError> {
+        let mut reader = PacketReader::new(Cursor::new(bytes));
+        let head = match reader.read_packet() {
+            Ok(Some(packet)) if packet.data.len() >= 19 => packet.data,
+            _ => return Err(DecoderError::UnrecognizedFormat),
+        };
+        let channels = match head[9] {
+            1 => Channels::Mono,
+            2 => Channels::Stereo,
+            _ => return Err(DecoderError::UnrecognizedFormat),
+        };
+        let pre_skip = u16::from_le_bytes([head[10], head[11]]) as usize * channels as usize;
+        // Skip the comment header
+        if reader.read_packet().is_err() {
+            return Err(DecoderError::UnrecognizedFormat);
+        }
+        let decoder = Decoder::new(SampleRate::Hz48000, channels)
+            .map_err(|_| DecoderError::UnrecognizedFormat)?;
+        Ok(CodecDecoder {
+            samples: Box::new(OpusSamples {
+                reader,
+                decoder,
+                channels: channels as usize,
+                buffer: Vec::new(),
+                i: 0,
+                skip: pre_skip,
+            }),
+            sample_rate: OPUS_SAMPLE_RATE,
+            channels: channels as u16,
+            duration: None,
+        })
+    }
+
+    struct OpusSamples {
+        reader: PacketReader<Cursor<Vec<u8>>>,
+        decoder: Decoder,
+        channels: usize,
+        buffer: Vec<f32>,
+        i: usize,
+        skip: usize,
+    }
+
+    impl Iterator for OpusSamples {
+        type Item = f32;
+        fn next(&mut self) -> Option<Self::Item> {
+            loop {
+                if let Some(sample) = self.buffer.get(self.i).copied() {
+                    self.i += 1;
+                    if self.skip > 0 {
+                        self.skip -= 1;
+                        continue;
+                    }
+                    return Some(sample);
+                }
+                let packet = self.reader.read_packet().ok()??;
+                self.buffer.resize(MAX_FRAME_SIZE * self.channels, 0.0);
+                let len = self
+                    .decoder
+                    .decode_float(Some(&packet.data), &mut self.buffer, false)
+                    .ok()?;
+                self.buffer.truncate(len * self.channels);
+                self.i = 0;
+            }
+        }
+    }
+}
+
+#[cfg(feature = "aac")]
+mod aac {
+    use std::{io::Cursor, time::Duration};
+
+    use rodio::decoder::DecoderError;
+    use symphonia::core::{
+        audio::SampleBuffer,
+        codecs::{self, DecoderOptions, CODEC_TYPE_NULL},
+        errors::Error,
+        formats::{FormatOptions, FormatReader},
+        io::MediaSourceStream,
+        meta::MetadataOptions,
+        probe::Hint,
+    };
+
+    use super::CodecDecoder;
+
+    pub fn is_aac(bytes: &[u8]) -> bool {
+        let mp4 = bytes.get(4..8) == Some(b"ftyp");
+        let adts = bytes.len() >= 2 && bytes[0] == 0xFF && bytes[1] & 0xF6 == 0xF0;
+        mp4 || adts
+    }
+
+    pub fn decoder(bytes: Vec<u8>) -> Result<CodecDecoder, DecoderError> {
+        let stream = MediaSourceStream::new(Box::new(Cursor::new(bytes)), Default::default());
+        let format = symphonia::default::get_probe()
+            .format(
+                &Hint::new(),
+                stream,
+                &FormatOptions::default(),
+                &MetadataOptions::default(),
+            )
+            .map_err(|_| DecoderError::UnrecognizedFormat)?
+            .format;
+        let track = format
+            .tracks()
+            .iter()
+            .find(|track| track.codec_params.codec != CODEC_TYPE_NULL)
+            .ok_or(DecoderError::UnrecognizedFormat)?;
+        let params = &track.codec_params;
+        let sample_rate = params.sample_rate.ok_or(DecoderError::UnrecognizedFormat)?;
+        let channels = params
+            .channels
+            .ok_or(DecoderError::UnrecognizedFormat)?
+            .count() as u16;
+        let duration = params
+            .n_frames
+            .map(|frames| Duration::from_secs_f64(frames as f64 / sample_rate as f64));
+        let track_id = track.id;
+        let decoder = symphonia::default::get_codecs()
+            .make(params, &DecoderOptions::default())
+            .map_err(|_| DecoderError::UnrecognizedFormat)?;
+        Ok(CodecDecoder {
+            samples: Box::new(AacSamples {
+                format,
+                decoder,
+                track_id,
+                buffer: Vec::new(),
+                i: 0,
+            }),
+            sample_rate,
+            channels,
+            duration,
+        })
+    }
+
+    struct AacSamples {
+        format: Box<dyn FormatReader>,
+        decoder: Box<dyn codecs::Decoder>,
+        track_id: u32,
+        buffer: Vec<f32>,
+        i: usize,
+    }
+
+    impl Iterator for AacSamples {
+        type Item = f32;
+        fn next(&mut self) -> Option<Self::Item> {
+            loop {
+                if let Some(sample) = self.buffer.get(self.i).copied() {
+                    self.i += 1;
+                    return Some(sample);
+                }
+                let packet = self.format.next_packet().ok()?;
+                if packet.track_id() != self.track_id {
+                    continue;
+                }
+                match self.decoder.decode(&packet) {
+                    Ok(decoded) => {
+                        let mut buffer =
+                            SampleBuffer::<f32>::new(decoded.capacity() as u64, *decoded.spec());
+                        buffer.copy_interleaved_ref(decoded);
+                        self.buffer.clear();
+                        self.buffer.extend_from_slice(buffer.samples());
+                        self.i = 0;
+                    }
+                    // Skip corrupt packets
+                    Err(Error::DecodeError(_)) => {}
+                    Err(_) => return None,
+                }
+            }
+        }
+    }
+}
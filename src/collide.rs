@@ -0,0 +1,450 @@
+use vector2math::*;
+
+use crate::{Circ, Rect, Vec2};
+
+/**
+Information about how two overlapping shapes touch
+
+`normal` points from the first shape toward the second. Moving the
+second shape by `normal.mul(depth)` separates the shapes.
+*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Contact {
+    /// The unit direction from the first shape toward the second
+    pub normal: Vec2,
+    /// How far the shapes overlap along the normal
+    pub depth: f32,
+    /// A point where the shapes touch
+    pub point: Vec2,
+}
+
+impl Contact {
+    /// Get the contact from the point of view of the other shape
+    pub fn flipped(self) -> Self {
+        Contact {
+            normal: self.normal.neg(),
+            ..self
+        }
+    }
+}
+
+/// The result of a swept collision test
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Hit {
+    /// The fraction of the movement from `0.0` to `1.0` at which the shapes first touch
+    pub time: f32,
+    /// The unit surface normal of the obstacle at the point of impact,
+    /// pointing toward the moving shape
+    pub normal: Vec2,
+}
+
+/// Get the contact between two circles
+pub fn circle_circle<A, B>(a: A, b: B) -> Option<Contact>
+where
+    A: Circle<Scalar = f32>,
+    B: Circle<Scalar = f32>,
+{
+    let (a, b): (Circ, Circ) = (a.map(), b.map());
+    let d = b.center().sub(a.center());
+    let dist = d.mag();
+    let radii = a.radius() + b.radius();
+    if dist >= radii {
+        return None;
+    }
+    let normal = if dist > 0.0 { d.div(dist) } else { [1.0, 0.0] };
+    Some(Contact {
+        normal,
+        depth: radii - dist,
+        point: a.center().add(normal.mul(a.radius())),
+    })
+}
+
+/// Get the contact between a circle and a rectangle
+pub fn circle_rect<C, R>(circ: C, rect: R) -> Option<Contact>
+where
+    C: Circle<Scalar = f32>,
+    R: Rectangle<Scalar = f32>,
+{
+    let (circ, rect): (Circ, Rect) = (circ.map(), rect.map());
+    let center = circ.center();
+    let radius = circ.radius();
+    let closest = [
+        center.x().max(rect.abs_left()).min(rect.abs_right()),
+        center.y().max(rect.abs_top()).min(rect.abs_bottom()),
+    ];
+    if closest == center {
+        // The center is inside the rectangle, so push out through the nearest edge
+        let edges = [
+            (center.x() - rect.abs_left(), [1.0, 0.0]),
+            (rect.abs_right() - center.x(), [-1.0, 0.0]),
+            (center.y() - rect.abs_top(), [0.0, 1.0]),
+            (rect.abs_bottom() - center.y(), [0.0, -1.0]),
+        ];
+        let (dist, normal) = edges
+            .iter()
+            .copied()
+            .min_by(|a, b| a.0.partial_cmp(&b.0).unwrap())
+            .unwrap();
+        return Some(Contact {
+            normal,
+            depth: dist + radius,
+            point: center.sub(normal.mul(dist)),
+        });
+    }
+    let d = closest.sub(center);
+    let dist = d.mag();
+    if dist >= radius {
+        return None;
+    }
+    Some(Contact {
+        normal: d.div(dist),
+        depth: radius - dist,
+        point: closest,
+    })
+}
+
+/// Get the contact between two rectangles
+pub fn rect_rect<A, B>(a: A, b: B) -> Option<Contact>
+where
+    A: Rectangle<Scalar = f32>,
+    B: Rectangle<Scalar = f32>,
+{
+    let (a, b): (Rect, Rect) = (a.map(), b.map());
+    let left = a.abs_left().max(b.abs_left());
+    let right = a.abs_right().min(b.abs_right());
+    let top = a.abs_top().max(b.abs_top());
+    let bottom = a.abs_bottom().min(b.abs_bottom());
+    let (overlap_x, overlap_y) = (right - left, bottom - top);
+    if overlap_x <= 0.0 || overlap_y <= 0.0 {
+        return None;
+    }
+    let d = b.center().sub(a.center());
+    let (normal, depth) = if overlap_x < overlap_y {
+        ([d.x().signum(), 0.0], overlap_x)
+    } else {
+        ([0.0, d.y().signum()], overlap_y)
+    };
+    Some(Contact {
+        normal,
+        depth,
+        point: [(left + right) / 2.0, (top + bottom) / 2.0],
+    })
+}
+
+/**
+Cast a ray against a rectangle
+
+Returns the distance along the ray, in multiples of `dir`, at which it enters the rectangle.
+If the ray starts inside the rectangle, the distance is `0.0`.
+*/
+pub fn ray_rect<R>(origin: Vec2, dir: Vec2, rect: R) -> Option<f32>
+where
+    R: Rectangle<Scalar = f32>,
+{
+    let rect: Rect = rect.map();
+    let min = rect.abs_top_left();
+    let max = rect.abs_bottom_right();
+    let mut t_enter = 0.0f32;
+    let mut t_exit = f32::INFINITY;
+    for axis in 0..2 {
+        if dir[axis] == 0.0 {
+            if origin[axis] < min[axis] || origin[axis] > max[axis] {
+                return None;
+            }
+            continue;
+        }
+        let t1 = (min[axis] - origin[axis]) / dir[axis];
+        let t2 = (max[axis] - origin[axis]) / dir[axis];
+        t_enter = t_enter.max(t1.min(t2));
+        t_exit = t_exit.min(t1.max(t2));
+        if t_enter > t_exit {
+            return None;
+        }
+    }
+    Some(t_enter)
+}
+
+fn project(poly: &[Vec2], axis: Vec2) -> (f32, f32) {
+    poly.iter()
+        .map(|v| v.dot(axis))
+        .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), p| {
+            (min.min(p), max.max(p))
+        })
+}
+
+fn centroid(poly: &[Vec2]) -> Vec2 {
+    poly.iter()
+        .fold([0.0; 2], |sum, v| sum.add(*v))
+        .div(poly.len().max(1) as f32)
+}
+
+/**
+Get the contact between two convex polygons using the separating axis theorem
+
+The contact point is the vertex of `b` that is deepest inside `a`.
+Polygons with fewer than 3 vertices never collide.
+*/
+pub fn polygon_polygon(a: &[Vec2], b: &[Vec2]) -> Option<Contact> {
+    if a.len() < 3 || b.len() < 3 {
+        return None;
+    }
+    let mut best: Option<(Vec2, f32)> = None;
+    for poly in [a, b] {
+        for (i, &v) in poly.iter().enumerate() {
+            let edge = poly[(i + 1) % poly.len()].sub(v);
+            let axis = [-edge.y(), edge.x()].unit();
+            if axis.x().is_nan() {
+                continue;
+            }
+            let (a_min, a_max) = project(a, axis);
+            let (b_min, b_max) = project(b, axis);
+            let overlap = a_max.min(b_max) - a_min.max(b_min);
+            if overlap <= 0.0 {
+                return None;
+            }
+            if !matches!(best, Some((_, depth)) if depth <= overlap) {
+                best = Some((axis, overlap));
+            }
+        }
+    }
+    let (mut normal, depth) = best?;
+    if centroid(b).sub(centroid(a)).dot(normal) < 0.0 {
+        normal = normal.neg();
+    }
+    let point = *b
+        .iter()
+        .min_by(|v, w| v.dot(normal).partial_cmp(&w.dot(normal)).unwrap())
+        .unwrap();
+    Some(Contact {
+        normal,
+        depth,
+        point,
+    })
+}
+
+/**
+Find when two moving circles first touch
+
+The circles move by `a_vel` and `b_vel` over the course of the test.
+If they already overlap, the hit time is `0.0`.
+The normal points from `b` toward `a`.
+*/
+pub fn sweep_circle_circle<A, B>(a: A, a_vel: Vec2, b: B, b_vel: Vec2) -> Option<Hit>
+where
+    A: Circle<Scalar = f32>,
+    B: Circle<Scalar = f32>,
+{
+    let (a, b): (Circ, Circ) = (a.map(), b.map());
+    let p = a.center().sub(b.center());
+    let v = a_vel.sub(b_vel);
+    let radii = a.radius() + b.radius();
+    let c = p.dot(p) - radii * radii;
+    let time = if c <= 0.0 {
+        0.0
+    } else {
+        let a = v.dot(v);
+        let b = 2.0 * p.dot(v);
+        let disc = b * b - 4.0 * a * c;
+        if a == 0.0 || disc < 0.0 {
+            return None;
+        }
+        let t = (-b - disc.sqrt()) / (2.0 * a);
+        if !(0.0..=1.0).contains(&t) {
+            return None;
+        }
+        t
+    };
+    let normal = p.add(v.mul(time)).unit();
+    Some(Hit {
+        time,
+        normal: if normal.x().is_nan() {
+            [1.0, 0.0]
+        } else {
+            normal
+        },
+    })
+}
+
+/**
+Find when a moving circle first touches a rectangle
+
+The circle moves by `vel` over the course of the test.
+If they already overlap, the hit time is `0.0`.
+*/
+pub fn sweep_circle_rect<C, R>(circ: C, vel: Vec2, rect: R) -> Option<Hit>
+where
+    C: Circle<Scalar = f32>,
+    R: Rectangle<Scalar = f32>,
+{
+    let (circ, rect): (Circ, Rect) = (circ.map(), rect.map());
+    if let Some(contact) = circle_rect(circ, rect) {
+        return Some(Hit {
+            time: 0.0,
+            normal: contact.normal.neg(),
+        });
+    }
+    let origin = circ.center();
+    let radius = circ.radius();
+    // Cast a ray against the rectangle expanded by the radius
+    let min = [rect.abs_left() - radius, rect.abs_top() - radius];
+    let max = [rect.abs_right() + radius, rect.abs_bottom() + radius];
+    let mut t_enter = 0.0f32;
+    let mut t_exit = 1.0f32;
+    let mut normal = [0.0; 2];
+    for axis in 0..2 {
+        if vel[axis] == 0.0 {
+            if origin[axis] < min[axis] || origin[axis] > max[axis] {
+                return None;
+            }
+            continue;
+        }
+        let t1 = (min[axis] - origin[axis]) / vel[axis];
+        let t2 = (max[axis] - origin[axis]) / vel[axis];
+        let (near, far) = if t1 < t2 { (t1, t2) } else { (t2, t1) };
+        if near > t_enter {
+            t_enter = near;
+            normal = [0.0; 2];
+            normal[axis] = -vel[axis].signum();
+        }
+        t_exit = t_exit.min(far);
+        if t_enter > t_exit {
+            return None;
+        }
+    }
+    // If the hit is in a corner region, the circle must hit the rounded corner
+    let p = origin.add(vel.mul(t_enter));
+    let corner_x = if p.x() < rect.abs_left() {
+        Some(rect.abs_left())
+    } else if p.x() > rect.abs_right() {
+        Some(rect.abs_right())
+    } else {
+        None
+    };
+    let corner_y = if p.y() < rect.abs_top() {
+        Some(rect.abs_top())
+    } else if p.y() > rect.abs_bottom() {
+        Some(rect.abs_bottom())
+    } else {
+        None
+    };
+    if let (Some(x), Some(y)) = (corner_x, corner_y) {
+        return sweep_circle_circle(circ, vel, ([x, y], 0.0), [0.0; 2]);
+    }
+    Some(Hit {
+        time: t_enter,
+        normal,
+    })
+}
+
+/// A shape that can be tested for collisions
+#[derive(Debug, Clone, PartialEq)]
+pub enum Shape {
+    /// A circle
+    Circle(Circ),
+    /// A rectangle
+    Rect(Rect),
+    /// A convex polygon
+    Polygon(Vec<Vec2>),
+}
+
+impl Shape {
+    /// Get the polygon vertices of a rectangle
+    fn rect_vertices(rect: Rect) -> Vec<Vec2> {
+        vec![
+            rect.abs_top_left(),
+            rect.abs_top_right(),
+            rect.abs_bottom_right(),
+            rect.abs_bottom_left(),
+        ]
+    }
+    /// Get the contact between this shape and another
+    pub fn contact(&self, other: &Shape) -> Option<Contact> {
+        match (self, other) {
+            (Shape::Circle(a), Shape::Circle(b)) => circle_circle(*a, *b),
+            (Shape::Circle(a), Shape::Rect(b)) => circle_rect(*a, *b),
+            (Shape::Rect(a), Shape::Circle(b)) => circle_rect(*b, *a).map(Contact::flipped),
+            (Shape::Rect(a), Shape::Rect(b)) => rect_rect(*a, *b),
+            (Shape::Rect(a), Shape::Polygon(b)) => polygon_polygon(&Shape::rect_vertices(*a), b),
+            (Shape::Polygon(a), Shape::Rect(b)) => polygon_polygon(a, &Shape::rect_vertices(*b)),
+            (Shape::Polygon(a), Shape::Polygon(b)) => polygon_polygon(a, b),
+            (Shape::Circle(a), Shape::Polygon(b)) => circle_polygon(*a, b),
+            (Shape::Polygon(a), Shape::Circle(b)) => circle_polygon(*b, a).map(Contact::flipped),
+        }
+    }
+    /// Check if this shape overlaps another
+    pub fn overlaps(&self, other: &Shape) -> bool {
+        self.contact(other).is_some()
+    }
+    /// Get the bounding rectangle of the shape
+    pub fn bounds(&self) -> Rect {
+        match self {
+            Shape::Circle(circ) => circ.to_square(),
+            Shape::Rect(rect) => *rect,
+            Shape::Polygon(poly) => Rect::bounding(poly.iter().copied()).unwrap_or_default(),
+        }
+    }
+}
+
+/// Get the contact between a circle and a convex polygon
+fn circle_polygon(circ: Circ, poly: &[Vec2]) -> Option<Contact> {
+    if poly.len() < 3 {
+        return None;
+    }
+    let center = circ.center();
+    let radius = circ.radius();
+    // Test the edge normals and the axis toward the closest vertex
+    let closest = *poly
+        .iter()
+        .min_by(|v, w| v.dist(center).partial_cmp(&w.dist(center)).unwrap())
+        .unwrap();
+    let axes = (0..poly.len())
+        .map(|i| {
+            let edge = poly[(i + 1) % poly.len()].sub(poly[i]);
+            [-edge.y(), edge.x()].unit()
+        })
+        .chain(Some(closest.sub(center).unit()));
+    let mut best: Option<(Vec2, f32)> = None;
+    for axis in axes {
+        if axis.x().is_nan() {
+            continue;
+        }
+        let (p_min, p_max) = project(poly, axis);
+        let c = center.dot(axis);
+        let overlap = p_max.min(c + radius) - p_min.max(c - radius);
+        if overlap <= 0.0 {
+            return None;
+        }
+        if !matches!(best, Some((_, depth)) if depth <= overlap) {
+            best = Some((axis, overlap));
+        }
+    }
+    let (mut normal, depth) = best?;
+    if centroid(poly).sub(center).dot(normal) < 0.0 {
+        normal = normal.neg();
+    }
+    Some(Contact {
+        normal,
+        depth,
+        point: center.add(normal.mul(radius - depth / 2.0)),
+    })
+}
+
+#[cfg(test)]
+#[test]
+fn collisions() {
+    let c = circle_circle(([0.0, 0.0], 1.0), ([1.5, 0.0], 1.0)).unwrap();
+    assert_eq!(c.normal, [1.0, 0.0]);
+    assert!((c.depth - 0.5).abs() < 1e-6);
+    assert!(circle_rect(([0.0, 0.0], 1.0), [2.0, -1.0, 1.0, 2.0]).is_none());
+    let c = rect_rect([0.0, 0.0, 2.0, 2.0], [1.5, 0.5, 2.0, 2.0]).unwrap();
+    assert_eq!(c.normal, [1.0, 0.0]);
+    let square = [[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]];
+    let tri = [[0.8, 0.5], [2.0, 0.0], [2.0, 1.0]];
+    let c = polygon_polygon(&square, &tri).unwrap();
+    assert_eq!(c.normal, [1.0, 0.0]);
+    let hit = sweep_circle_rect(([0.0, 0.0], 1.0), [10.0, 0.0], [5.0, -1.0, 1.0, 2.0]).unwrap();
+    assert!((hit.time - 0.4).abs() < 1e-6);
+    assert_eq!(hit.normal, [-1.0, 0.0]);
+    let hit = sweep_circle_circle(([0.0, 0.0], 1.0), [4.0, 0.0], ([3.0, 0.0], 1.0), [0.0; 2]);
+    assert!((hit.unwrap().time - 0.25).abs() < 1e-6);
+}
@@ -7,6 +7,33 @@ pub type Col = [f32; 4];
 pub trait Color: Copy {
     /// Create a new color from rgba components
     fn rgba(r: f32, g: f32, b: f32, a: f32) -> Self;
+    /**
+    Create an opaque color from hue, saturation, and value components
+
+    The hue is in degrees. Saturation and value range from `0.0` to `1.0`.
+    */
+    fn hsv(h: f32, s: f32, v: f32) -> Self {
+        Self::hsva(h, s, v, 1.0)
+    }
+    /// Create a color from hue, saturation, value, and alpha components
+    fn hsva(h: f32, s: f32, v: f32, a: f32) -> Self {
+        let [r, g, b] = hsv_to_rgb(h, s, v);
+        Self::rgba(r, g, b, a)
+    }
+    /**
+    Create an opaque color from hue, saturation, and lightness components
+
+    The hue is in degrees. Saturation and lightness range from `0.0` to `1.0`.
+    */
+    fn hsl(h: f32, s: f32, l: f32) -> Self {
+        Self::hsla(h, s, l, 1.0)
+    }
+    /// Create a color from hue, saturation, lightness, and alpha components
+    fn hsla(h: f32, s: f32, l: f32, a: f32) -> Self {
+        let v = l + s * l.min(1.0 - l);
+        let sv = if v == 0.0 { 0.0 } else { 2.0 * (1.0 - l / v) };
+        Self::hsva(h, sv, v, a)
+    }
     /// Get the red component
     fn r(self) -> f32;
     /// Get the green component
@@ -202,6 +229,136 @@ pub trait Color: Copy {
     fn as_gray(self) -> f32 {
         (self.r() + self.g() + self.b()) / 3.0
     }
+    /// Get the hue in degrees, and the saturation and value of the color
+    fn to_hsv(self) -> [f32; 3] {
+        let max = self.r().max(self.g()).max(self.b());
+        let min = self.r().min(self.g()).min(self.b());
+        let chroma = max - min;
+        let s = if max == 0.0 { 0.0 } else { chroma / max };
+        [hue_of(self, max, chroma), s, max]
+    }
+    /// Get the hue in degrees, and the saturation and lightness of the color
+    fn to_hsl(self) -> [f32; 3] {
+        let max = self.r().max(self.g()).max(self.b());
+        let min = self.r().min(self.g()).min(self.b());
+        let chroma = max - min;
+        let l = (max + min) / 2.0;
+        let s = if l == 0.0 || l == 1.0 {
+            0.0
+        } else {
+            chroma / (1.0 - (2.0 * l - 1.0).abs())
+        };
+        [hue_of(self, max, chroma), s, l]
+    }
+    /// Get the hue in degrees
+    fn hue(self) -> f32 {
+        self.to_hsv()[0]
+    }
+    /// Get the color with a different hue in degrees
+    fn with_hue(self, h: f32) -> Self {
+        let [_, s, v] = self.to_hsv();
+        Self::hsva(h, s, v, self.alpha())
+    }
+    /// Rotate the color's hue by some number of degrees
+    fn shift_hue(self, degrees: f32) -> Self {
+        let [h, s, v] = self.to_hsv();
+        Self::hsva(h + degrees, s, v, self.alpha())
+    }
+    /**
+    Blend another color on top of this one with a function of the rgb components
+
+    The function takes this color's component and the other's. The result is mixed
+    with this color by the other color's alpha. The new color has this color's alpha.
+    */
+    fn blend<C, F>(self, top: C, f: F) -> Self
+    where
+        C: Color,
+        F: Fn(f32, f32) -> f32,
+    {
+        let a = top.alpha();
+        self.map_rgb_other(top, |base, top| base + (f(base, top) - base) * a)
+    }
+    /// Blend another color on top of this one by multiplying, which darkens
+    fn multiply<C>(self, top: C) -> Self
+    where
+        C: Color,
+    {
+        self.blend(top, |base, top| base * top)
+    }
+    /// Blend another color on top of this one by inverse multiplying, which lightens
+    fn screen<C>(self, top: C) -> Self
+    where
+        C: Color,
+    {
+        self.blend(top, |base, top| 1.0 - (1.0 - base) * (1.0 - top))
+    }
+    /// Blend another color on top of this one by multiplying dark components
+    /// and screening light ones, which increases contrast
+    fn overlay<C>(self, top: C) -> Self
+    where
+        C: Color,
+    {
+        self.blend(top, |base, top| {
+            if base < 0.5 {
+                2.0 * base * top
+            } else {
+                1.0 - 2.0 * (1.0 - base) * (1.0 - top)
+            }
+        })
+    }
+    /// Blend another color on top of this one by adding, clamped to `1.0`
+    fn additive<C>(self, top: C) -> Self
+    where
+        C: Color,
+    {
+        self.blend(top, |base, top| (base + top).min(1.0))
+    }
+    /// Composite another color over this one using their alpha components
+    fn over<C>(self, top: C) -> Self
+    where
+        C: Color,
+    {
+        let (ta, ba) = (top.alpha(), self.alpha());
+        let alpha = ta + ba * (1.0 - ta);
+        if alpha == 0.0 {
+            return Self::rgba(0.0, 0.0, 0.0, 0.0);
+        }
+        self.map_rgb_other(top, |base, top| (top * ta + base * ba * (1.0 - ta)) / alpha)
+            .with_alpha(alpha)
+    }
+}
+
+fn hsv_to_rgb(h: f32, s: f32, v: f32) -> [f32; 3] {
+    let h = h.rem_euclid(360.0) / 60.0;
+    let chroma = v * s;
+    let x = chroma * (1.0 - (h % 2.0 - 1.0).abs());
+    let [r, g, b] = match h as u8 {
+        0 => [chroma, x, 0.0],
+        1 => [x, chroma, 0.0],
+        2 => [0.0, chroma, x],
+        3 => [0.0, x, chroma],
+        4 => [x, 0.0, chroma],
+        _ => [chroma, 0.0, x],
+    };
+    let m = v - chroma;
+    [r + m, g + m, b + m]
+}
+
+fn hue_of<C>(color: C, max: f32, chroma: f32) -> f32
+where
+    C: Color,
+{
+    if chroma == 0.0 {
+        return 0.0;
+    }
+    let h = if max == color.r() {
+        (color.g() - color.b()) / chroma
+    } else if max == color.g() {
+        (color.b() - color.r()) / chroma + 2.0
+    } else {
+        (color.r() - color.g()) / chroma + 4.0
+    };
+    (h * 60.0).rem_euclid(360.0)
 }
 
 impl Color for Col {
@@ -275,3 +432,39 @@ impl Color for (f32, f32, f32) {
         1.0
     }
 }
+
+#[cfg(test)]
+#[test]
+fn hsv_hsl() {
+    let close = |a: Col, b: Col| a.dist(b) < 1e-5;
+    assert!(close(Col::hsv(0.0, 1.0, 1.0), Col::red(1.0)));
+    assert!(close(Col::hsv(120.0, 1.0, 0.5), Col::green(0.5)));
+    assert!(close(Col::hsl(240.0, 1.0, 0.5), Col::blue(1.0)));
+    assert!(close(Col::hsl(60.0, 1.0, 0.75), Col::rgb(1.0, 1.0, 0.5)));
+    let color = Col::rgb(0.2, 0.6, 0.4);
+    let [h, s, v] = color.to_hsv();
+    assert!(close(Col::hsv(h, s, v), color));
+    let [h, s, l] = color.to_hsl();
+    assert!(close(Col::hsl(h, s, l), color));
+    assert!(close(Col::red(1.0).shift_hue(-240.0), Col::green(1.0)));
+}
+
+#[cfg(test)]
+#[test]
+fn blending() {
+    let gray = Col::gray(0.5);
+    assert_eq!(gray.multiply(gray), Col::gray(0.25));
+    assert_eq!(gray.screen(gray), Col::gray(0.75));
+    assert_eq!(Col::gray(0.25).overlay(gray), Col::gray(0.25));
+    assert_eq!(gray.additive(Col::white().with_alpha(0.5)), Col::gray(0.75));
+    assert_eq!(
+        Col::red(1.0).over(Col::blue(1.0).with_alpha(0.5)),
+        Col::rgb(0.5, 0.0, 0.5)
+    );
+    assert_eq!(
+        Col::red(1.0)
+            .with_alpha(0.0)
+            .over(Col::blue(1.0).with_alpha(0.5)),
+        Col::blue(1.0).with_alpha(0.5)
+    );
+}
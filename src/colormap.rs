@@ -0,0 +1,124 @@
+/*!
+Polynomial fits of perceptually uniform colormaps are from
+https://www.shadertoy.com/view/WlfXRN (CC0) and the turbo fit is from
+https://gist.github.com/mikhailov-work/0d177465a8151eb6ede1768d51d476c7 (Apache 2.0)
+*/
+
+use crate::{Col, Color};
+
+/// Evaluate a polynomial with rgb coefficients from lowest to highest degree
+fn polynomial(coefficients: &[[f32; 3]], t: f32) -> Col {
+    let t = t.clamp(0.0, 1.0);
+    let mut rgb = [0.0; 3];
+    for coefficient in coefficients.iter().rev() {
+        for (c, k) in rgb.iter_mut().zip(coefficient) {
+            *c = *c * t + k;
+        }
+    }
+    Col::rgb(rgb[0], rgb[1], rgb[2]).map_rgb(|c| c.clamp(0.0, 1.0))
+}
+
+/**
+The viridis colormap, which goes from dark purple through teal to yellow
+
+`t` is clamped to the range `0.0` to `1.0`.
+Viridis is perceptually uniform and readable by people with color blindness.
+
+```
+# use kule::*;
+let heat: Vec<Col> = [0.0, 0.5, 1.0].iter().map(|&t| viridis(t)).collect();
+assert!(heat[0].as_gray() < heat[1].as_gray() && heat[1].as_gray() < heat[2].as_gray());
+```
+*/
+pub fn viridis(t: f32) -> Col {
+    polynomial(
+        &[
+            [0.277_727_33, 0.005_407_344_5, 0.334_099_8],
+            [0.105_093_04, 1.404_613_5, 1.384_590_1],
+            [-0.330_861_83, 0.214_847_56, 0.095_095_16],
+            [-4.634_230_6, -5.799_101, -19.332_441],
+            [6.228_27, 14.179_933, 56.690_55],
+            [4.776_385, -13.745_145, -65.353_03],
+            [-5.435_456, 4.645_852_6, 26.312_435],
+        ],
+        t,
+    )
+}
+
+/**
+The magma colormap, which goes from black through purple and orange to pale yellow
+
+`t` is clamped to the range `0.0` to `1.0`
+*/
+pub fn magma(t: f32) -> Col {
+    polynomial(
+        &[
+            [-0.002_136_485, -0.000_749_655_05, -0.005_386_128],
+            [0.251_660_54, 0.677_523_24, 2.494_026_7],
+            [8.353_717, -3.577_719_4, 0.314_467_9],
+            [-27.668_733, 14.264_731, -13.649_213],
+            [52.176_14, -27.943_607, 12.944_169],
+            [-50.768_524, 29.046_583, 4.234_153],
+            [18.655_705, -11.489_774, -5.601_961_6],
+        ],
+        t,
+    )
+}
+
+/**
+The turbo colormap, a rainbow that goes from dark blue through green to dark red
+
+`t` is clamped to the range `0.0` to `1.0`.
+Turbo has more contrast than viridis, but it is not perceptually uniform.
+*/
+pub fn turbo(t: f32) -> Col {
+    polynomial(
+        &[
+            [0.135_721_38, 0.091_402_61, 0.106_673_3],
+            [4.615_392_6, 2.194_188_4, 12.641_946],
+            [-42.660_324, 4.842_966_6, -60.582_05],
+            [132.131_08, -14.185_033, 110.362_77],
+            [-152.942_4, 4.277_299, -89.903_11],
+            [59.286_38, 2.829_566, 27.348_25],
+        ],
+        t,
+    )
+}
+
+/**
+The cool-warm colormap, which diverges from blue through light gray to red
+
+`t` is clamped to the range `0.0` to `1.0`.
+Diverging colormaps are good for data with a meaningful midpoint.
+*/
+pub fn coolwarm(t: f32) -> Col {
+    const STOPS: [[f32; 3]; 9] = [
+        [0.231, 0.298, 0.753],
+        [0.384, 0.510, 0.918],
+        [0.553, 0.690, 0.996],
+        [0.722, 0.816, 0.976],
+        [0.867, 0.867, 0.867],
+        [0.961, 0.769, 0.678],
+        [0.957, 0.604, 0.482],
+        [0.871, 0.376, 0.302],
+        [0.706, 0.016, 0.149],
+    ];
+    let scaled = t.clamp(0.0, 1.0) * (STOPS.len() - 1) as f32;
+    let i = (scaled as usize).min(STOPS.len() - 2);
+    let [r, g, b] = STOPS[i];
+    let [nr, ng, nb] = STOPS[i + 1];
+    Col::rgb(r, g, b).lerp(Col::rgb(nr, ng, nb), scaled - i as f32)
+}
+
+#[cfg(test)]
+#[test]
+fn colormap_endpoints() {
+    let close = |a: Col, b: Col| a.dist(b) < 0.05;
+    assert!(close(viridis(0.0), Col::rgb(0.267, 0.005, 0.329)));
+    assert!(close(viridis(1.0), Col::rgb(0.993, 0.906, 0.144)));
+    assert!(close(magma(-1.0), Col::black()));
+    assert!(close(magma(1.0), Col::rgb(0.987, 0.991, 0.750)));
+    assert!(turbo(0.5).g() > 0.9 && turbo(1.0).r() > turbo(1.0).g());
+    assert!(close(coolwarm(0.5), Col::gray(0.867)));
+    assert_eq!(coolwarm(1.0), Col::rgb(0.706, 0.016, 0.149));
+}
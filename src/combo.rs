@@ -0,0 +1,172 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use crate::{ButtonState, Event, Input, PhysicalKey};
+
+/**
+A timed sequence of inputs
+
+A combo is matched when its inputs are pressed in order, with no other inputs
+pressed in between, and with no more than the combo's window between each press.
+
+When a combo is matched, an `Event::Combo` is sent with the combo's id.
+*/
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "ser",
+    derive(serde_derive::Serialize, serde_derive::Deserialize)
+)]
+pub struct Combo {
+    /// The id sent in `Event::Combo` when the combo is matched
+    pub id: u32,
+    /// The sequence of inputs
+    pub inputs: Vec<Input>,
+    /// The maximum time between each input
+    pub window: Duration,
+}
+
+impl Combo {
+    /// Create a new `Combo` with a window of 300ms
+    pub fn new<I>(id: u32, inputs: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<Input>,
+    {
+        Combo {
+            id,
+            inputs: inputs.into_iter().map(Into::into).collect(),
+            window: Duration::from_millis(300),
+        }
+    }
+    /// Set the maximum time between each input
+    pub fn window(self, window: Duration) -> Self {
+        Combo { window, ..self }
+    }
+}
+
+/// A single press in the input history
+#[derive(Debug, Clone, Copy)]
+struct Press {
+    input: Input,
+    physical: Option<PhysicalKey>,
+    time: Instant,
+}
+
+impl Press {
+    fn matches(&self, input: Input) -> bool {
+        self.input == input || self.physical.map(Input::PhysicalKey) == Some(input)
+    }
+}
+
+/**
+Recognizes combos from input events
+
+```
+# use kule::*;
+let mut matcher = ComboMatcher::new();
+// Double tap to dash
+matcher.add(Combo::new(0, vec![Key::D, Key::D]));
+// Quarter circle forward + punch
+matcher.add(Combo::new(1, vec![Key::S, Key::D, Key::J]));
+```
+*/
+#[derive(Debug, Clone, Default)]
+pub struct ComboMatcher {
+    combos: Vec<Combo>,
+    history: VecDeque<Press>,
+}
+
+impl ComboMatcher {
+    /// Create a new `ComboMatcher` with no combos
+    pub fn new() -> Self {
+        ComboMatcher::default()
+    }
+    /// Add a combo
+    pub fn add(&mut self, combo: Combo) {
+        self.combos.push(combo);
+    }
+    /// Remove all combos with some id
+    pub fn remove(&mut self, id: u32) {
+        self.combos.retain(|combo| combo.id != id);
+    }
+    /// Get the combos
+    pub fn combos(&self) -> &[Combo] {
+        &self.combos
+    }
+    /// Clear the input history
+    pub fn reset(&mut self) {
+        self.history.clear();
+    }
+    fn max_len(&self) -> usize {
+        self.combos
+            .iter()
+            .map(|combo| combo.inputs.len())
+            .max()
+            .unwrap_or(0)
+    }
+    fn matched(&self) -> Option<u32> {
+        self.combos
+            .iter()
+            .filter(|combo| {
+                let len = combo.inputs.len();
+                len > 0
+                    && len <= self.history.len()
+                    && self
+                        .history
+                        .iter()
+                        .skip(self.history.len() - len)
+                        .zip(&combo.inputs)
+                        .all(|(press, &input)| press.matches(input))
+                    && self
+                        .history
+                        .iter()
+                        .skip(self.history.len() - len)
+                        .zip(self.history.iter().skip(self.history.len() - len + 1))
+                        .all(|(a, b)| b.time - a.time <= combo.window)
+            })
+            // Prefer longer combos
+            .max_by_key(|combo| combo.inputs.len())
+            .map(|combo| combo.id)
+    }
+    pub(crate) fn process(&mut self, events: Vec<Event>, now: Instant) -> Vec<Event> {
+        if self.combos.is_empty() {
+            return events;
+        }
+        let mut processed = Vec::with_capacity(events.len());
+        for event in events {
+            processed.push(event);
+            let press = match event {
+                Event::Key {
+                    key,
+                    scancode,
+                    state: ButtonState::Pressed,
+                } => Press {
+                    input: Input::Key(key),
+                    physical: PhysicalKey::from_scancode(scancode),
+                    time: now,
+                },
+                Event::MouseButton {
+                    button,
+                    state: ButtonState::Pressed,
+                } => Press {
+                    input: Input::MouseButton(button),
+                    physical: None,
+                    time: now,
+                },
+                _ => continue,
+            };
+            self.history.push_back(press);
+            while self.history.len() > self.max_len() {
+                self.history.pop_front();
+            }
+            if let Some(id) = self.matched() {
+                processed.push(Event::Combo(id));
+                // Prevent overlapping matches
+                self.history.clear();
+            }
+        }
+        processed
+    }
+}
@@ -1,56 +1,309 @@
-use std::{cell::Ref, time::Instant};
+use std::{
+    any::Any,
+    cell::{Cell, Ref},
+    collections::{HashMap, VecDeque},
+    fs,
+    path::Path,
+    time::{Duration, Instant},
+};
+#[cfg(feature = "sound")]
+use std::{
+    collections::hash_map::DefaultHasher,
+    hash::{Hash, Hasher},
+};
 
 use glium::{glutin::*, *};
 use vector2math::*;
 
-pub use monitor::MonitorHandle;
-pub use window::{Fullscreen, WindowId};
+use event_loop::EventLoopProxy;
+pub use monitor::{MonitorHandle, VideoMode};
+pub use window::{CursorIcon, Fullscreen, Icon, WindowId};
 
 #[cfg(feature = "sound")]
 use crate::{
     rodio::{Sample, Source},
-    Kule, Mixer, SoundSource, Sounds,
+    Kule, Mixer, MusicTrack, PlayOptions, SoundBuffer, SoundHandle, SoundPreload, SoundSource,
+    Sounds,
 };
 use crate::{
-    Camera, CanFail, Drawer, Fonts, GlyphCache, KuleResult, MeshCache, Resources, StateTracker,
-    Vec2, WindowCanvas,
+    AssetLoader, AssetWatcher, Camera, CanFail, ComboMatcher, Coroutines, CustomCursor,
+    Diagnostics, Drawer, ErrorPolicy, Event, EventSender, FocusManager, Fonts, GlyphCache,
+    InputMap, KeyRepeat, KuleResult, LoadId, Loaded, MeshCache, Profiler, Resources, Rng,
+    SceneStack, StateTracker, Tweens, UserEventId, UserPayload, Vec2, WatchId, WatchedAsset,
+    WindowCanvas, World,
 };
 
-/// A handle to the app's window
-pub struct Window(pub(crate) Display);
+/**
+A handle to the app's window
+
+If the context is headless, there is no window, and most methods do nothing
+*/
+pub struct Window {
+    pub(crate) display: Option<Display>,
+    maximized: Cell<bool>,
+}
 
 impl Window {
-    /// Get a reference to the inner window
+    pub(crate) fn new(display: Option<Display>, maximized: bool) -> Self {
+        Window {
+            display,
+            maximized: Cell::new(maximized),
+        }
+    }
+    /**
+    Get a reference to the inner window
+
+    # Panics
+
+    Panics if the context is headless
+    */
     pub fn inner(&self) -> Ref<window::Window> {
-        Ref::map(self.0.gl_window(), |gl_window| gl_window.window())
+        self.try_inner().expect("The context is headless")
+    }
+    /// Get a reference to the inner window, or `None` if the context is headless
+    pub fn try_inner(&self) -> Option<Ref<'_, window::Window>> {
+        self.display
+            .as_ref()
+            .map(|display| Ref::map(display.gl_window(), |gl_window| gl_window.window()))
+    }
+    /// Check if the context is headless
+    pub fn is_headless(&self) -> bool {
+        self.display.is_none()
     }
     /// Get the position of the window
     pub fn position(&self) -> [i32; 2] {
-        let pos = self.inner().outer_position().unwrap();
-        [pos.x, pos.y]
+        self.try_inner()
+            .and_then(|window| window.outer_position().ok())
+            .map(|pos| [pos.x, pos.y])
+            .unwrap_or_default()
     }
     /// Set the position of the window
     pub fn set_position(&self, pos: [i32; 2]) {
-        self.inner()
-            .set_outer_position(dpi::PhysicalPosition::<i32>::from(pos));
+        if let Some(window) = self.try_inner() {
+            window.set_outer_position(dpi::PhysicalPosition::<i32>::from(pos));
+        }
     }
-    /// Get a handle to the window's current monitor
+    /**
+    Get a handle to the window's current monitor
+
+    # Panics
+
+    Panics if the context is headless
+    */
     pub fn current_monitor(&self) -> MonitorHandle {
         self.inner().current_monitor()
     }
     /// Set the window's fullscreen state
     pub fn set_fullscreen(&self, fullscreen: Option<Fullscreen>) {
-        self.inner().set_fullscreen(fullscreen)
+        if let Some(window) = self.try_inner() {
+            window.set_fullscreen(fullscreen)
+        }
+    }
+    /**
+    Set the window's fullscreen mode on its current monitor
+
+    Returns `false` if the mode is exclusive and the monitor has no video modes
+    */
+    pub fn set_fullscreen_mode(&self, mode: Option<FullscreenMode>) -> bool {
+        let window = if let Some(window) = self.try_inner() {
+            window
+        } else {
+            return false;
+        };
+        match mode {
+            Some(mode) => match mode.to_fullscreen(window.current_monitor()) {
+                Some(fullscreen) => window.set_fullscreen(Some(fullscreen)),
+                None => return false,
+            },
+            None => window.set_fullscreen(None),
+        }
+        true
+    }
+    /// Check if the window is fullscreen
+    pub fn is_fullscreen(&self) -> bool {
+        self.try_inner()
+            .map(|window| window.fullscreen().is_some())
+            .unwrap_or(false)
+    }
+    /// Toggle borderless fullscreen on the window's current monitor
+    pub fn toggle_fullscreen(&self) {
+        let mode = if self.is_fullscreen() {
+            None
+        } else {
+            Some(FullscreenMode::Borderless)
+        };
+        self.set_fullscreen_mode(mode);
+    }
+    /// Minimize the window
+    pub fn minimize(&self) {
+        self.set_minimized(true);
+    }
+    /// Set whether the window is minimized
+    pub fn set_minimized(&self, minimized: bool) {
+        if let Some(window) = self.try_inner() {
+            window.set_minimized(minimized);
+        }
+    }
+    /// Maximize the window
+    pub fn maximize(&self) {
+        self.set_maximized(true);
+    }
+    /// Set whether the window is maximized
+    pub fn set_maximized(&self, maximized: bool) {
+        if let Some(window) = self.try_inner() {
+            window.set_maximized(maximized);
+            self.maximized.set(maximized);
+        }
+    }
+    /**
+    Check if the window is maximized
+
+    The window system cannot be queried for this, so this only reflects
+    `ContextBuilder::maximized` and calls to `Window::set_maximized`
+    */
+    pub fn is_maximized(&self) -> bool {
+        self.maximized.get()
+    }
+    /**
+    Ask for the user's attention without taking focus
+
+    This usually flashes the window's taskbar or dock icon.
+    It is supported on macOS and X11. It does nothing on other platforms.
+    */
+    pub fn request_user_attention(&self) {
+        if let Some(_window) = self.try_inner() {
+            #[cfg(target_os = "macos")]
+            {
+                use platform::macos::{RequestUserAttentionType, WindowExtMacOS};
+                _window.request_user_attention(RequestUserAttentionType::Informational);
+            }
+        }
+        self.set_urgent(true);
+    }
+    /// Stop requesting the user's attention once the window is focused
+    pub(crate) fn clear_user_attention(&self) {
+        self.set_urgent(false);
+    }
+    fn set_urgent(&self, _urgent: bool) {
+        if let Some(_window) = self.try_inner() {
+            #[cfg(any(
+                target_os = "linux",
+                target_os = "dragonfly",
+                target_os = "freebsd",
+                target_os = "netbsd",
+                target_os = "openbsd"
+            ))]
+            {
+                use platform::unix::WindowExtUnix;
+                _window.set_urgent(_urgent);
+            }
+        }
+    }
+    /// Get all of the monitors
+    pub fn monitors(&self) -> Vec<Monitor> {
+        self.try_inner()
+            .map(|window| window.available_monitors().map(Monitor::from).collect())
+            .unwrap_or_default()
+    }
+    /// Get the primary monitor, or `None` if the context is headless
+    pub fn primary_monitor(&self) -> Option<Monitor> {
+        self.try_inner()
+            .map(|window| Monitor::from(window.primary_monitor()))
+    }
+    /// Move the window to the center of a monitor
+    pub fn center_on(&self, monitor: &Monitor) {
+        if let Some(window) = self.try_inner() {
+            let window_size = window.outer_size();
+            window.set_outer_position(dpi::PhysicalPosition::new(
+                monitor.position[0] + (monitor.size[0] as i32 - window_size.width as i32) / 2,
+                monitor.position[1] + (monitor.size[1] as i32 - window_size.height as i32) / 2,
+            ));
+        }
+    }
+    /// Get the window's position, size, and fullscreen state so that it can be restored later
+    pub fn placement(&self) -> WindowPlacement {
+        WindowPlacement {
+            position: self.position(),
+            size: self.size(),
+            fullscreen: self.is_fullscreen(),
+        }
+    }
+    /**
+    Restore the window's position, size, and fullscreen state
+
+    If the position is no longer on any monitor, like when the monitor has
+    been disconnected, the window is centered on the primary monitor instead.
+    */
+    pub fn set_placement(&self, placement: WindowPlacement) {
+        self.set_size(placement.size);
+        let on_monitor = self.monitors().iter().any(|monitor| {
+            let [x, y] = placement.position;
+            x >= monitor.position[0]
+                && y >= monitor.position[1]
+                && x < monitor.position[0] + monitor.size[0] as i32
+                && y < monitor.position[1] + monitor.size[1] as i32
+        });
+        if on_monitor {
+            self.set_position(placement.position);
+        } else if let Some(monitor) = self.primary_monitor() {
+            self.center_on(&monitor);
+        }
+        let mode = if placement.fullscreen {
+            Some(FullscreenMode::Borderless)
+        } else {
+            None
+        };
+        self.set_fullscreen_mode(mode);
+    }
+    /// Get the video modes supported by the window's current monitor
+    pub fn video_modes(&self) -> Vec<VideoMode> {
+        self.try_inner()
+            .map(|window| window.current_monitor().video_modes().collect())
+            .unwrap_or_default()
+    }
+    /// Get the ratio of physical pixels to logical pixels for the window
+    ///
+    /// This is `1.0` if the context is headless
+    pub fn scale_factor(&self) -> f64 {
+        self.try_inner()
+            .map(|window| window.scale_factor())
+            .unwrap_or(1.0)
     }
     /// Get the size of the window in pixels
     pub fn size(&self) -> [u32; 2] {
-        let size = self.inner().inner_size();
-        [size.width, size.height]
+        self.try_inner()
+            .map(|window| window.inner_size().into())
+            .unwrap_or_default()
     }
     /// Set the size of the window in pixels
     pub fn set_size(&self, size: [u32; 2]) {
-        self.inner()
-            .set_inner_size(dpi::PhysicalSize::<u32>::from(size));
+        if let Some(window) = self.try_inner() {
+            window.set_inner_size(dpi::PhysicalSize::<u32>::from(size));
+        }
+    }
+    /// Set whether the window can be resized by the user
+    pub fn set_resizable(&self, resizable: bool) {
+        if let Some(window) = self.try_inner() {
+            window.set_resizable(resizable);
+        }
+    }
+    /// Set the minimum size of the window in pixels
+    pub fn set_min_size(&self, min_size: Option<[u32; 2]>) {
+        if let Some(window) = self.try_inner() {
+            window.set_min_inner_size(min_size.map(dpi::PhysicalSize::<u32>::from));
+        }
+    }
+    /// Set the maximum size of the window in pixels
+    pub fn set_max_size(&self, max_size: Option<[u32; 2]>) {
+        if let Some(window) = self.try_inner() {
+            window.set_max_inner_size(max_size.map(dpi::PhysicalSize::<u32>::from));
+        }
+    }
+    /// Set the system cursor icon
+    pub fn set_cursor(&self, icon: CursorIcon) {
+        if let Some(window) = self.try_inner() {
+            window.set_cursor_icon(icon);
+        }
     }
     /// Get whether the cursor should be visible
     pub fn cursor_visible(&self) -> bool {
@@ -58,14 +311,172 @@ impl Window {
     }
     /// Set whether the cursor should be visible
     pub fn set_cursor_visible(&self, visible: bool) {
-        self.inner().set_cursor_visible(visible);
+        if let Some(window) = self.try_inner() {
+            window.set_cursor_visible(visible);
+        }
+    }
+    /**
+    Set the position of the input method editor's candidate window in pixels
+
+    This should be set to the position of the text cursor while editing text
+    */
+    pub fn set_ime_position(&self, pos: Vec2) {
+        if let Some(window) = self.try_inner() {
+            window.set_ime_position(dpi::PhysicalPosition::<f32>::from(pos));
+        }
     }
     /// Set the window icon using bitmap data
     pub fn set_icon(&self, rgba: Vec<u8>, width: u32, height: u32) -> KuleResult<()> {
-        self.inner()
-            .set_window_icon(Some(window::Icon::from_rgba(rgba, width, height)?));
+        let icon = window::Icon::from_rgba(rgba, width, height)?;
+        if let Some(window) = self.try_inner() {
+            window.set_window_icon(Some(icon));
+        }
         Ok(())
     }
+    #[cfg(feature = "icon")]
+    /// Set the window icon using encoded PNG or ICO data
+    pub fn set_icon_from_bytes(&self, bytes: &[u8]) -> KuleResult<()> {
+        let icon = icon_from_bytes(bytes)?;
+        if let Some(window) = self.try_inner() {
+            window.set_window_icon(Some(icon));
+        }
+        Ok(())
+    }
+}
+
+/// Information about a monitor
+#[derive(Debug, Clone)]
+pub struct Monitor {
+    /// The monitor's handle
+    pub handle: MonitorHandle,
+    /// The monitor's name
+    pub name: Option<String>,
+    /// The position of the monitor's top-left corner on the desktop in pixels
+    pub position: [i32; 2],
+    /// The monitor's size in pixels
+    pub size: [u32; 2],
+    /// The monitor's DPI scale factor
+    pub scale_factor: f64,
+    /// The highest refresh rate in Hz at the monitor's resolution, if it is known
+    pub refresh_rate: Option<u16>,
+}
+
+impl From<MonitorHandle> for Monitor {
+    fn from(handle: MonitorHandle) -> Self {
+        let size = handle.size();
+        let refresh_rate = handle
+            .video_modes()
+            .filter(|mode| mode.size() == size)
+            .map(|mode| mode.refresh_rate())
+            .max();
+        Monitor {
+            name: handle.name(),
+            position: handle.position().into(),
+            size: size.into(),
+            scale_factor: handle.scale_factor(),
+            refresh_rate,
+            handle,
+        }
+    }
+}
+
+/**
+A window's position, size, and fullscreen state
+
+This can be saved when the app closes and restored with `Window::set_placement`
+when it starts again.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "ser",
+    derive(serde_derive::Serialize, serde_derive::Deserialize)
+)]
+pub struct WindowPlacement {
+    /// The position of the window in pixels
+    pub position: [i32; 2],
+    /// The size of the window in pixels
+    pub size: [u32; 2],
+    /// Whether the window is fullscreen
+    pub fullscreen: bool,
+}
+
+/// Where the window is placed when it is created
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "ser",
+    derive(serde_derive::Serialize, serde_derive::Deserialize)
+)]
+pub enum WindowPosition {
+    /// At a position on the desktop in pixels
+    At([i32; 2]),
+    /// At the center of the primary monitor
+    Centered,
+}
+
+/// A way for the window to be fullscreen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FullscreenMode {
+    /// A borderless window that covers the monitor
+    Borderless,
+    /**
+    Exclusive fullscreen, which changes the monitor's video mode
+
+    The supported video mode closest to the given size and refresh rate is used.
+    If no refresh rate is given, the highest one is used.
+    */
+    Exclusive {
+        /// The resolution in pixels
+        size: [u32; 2],
+        /// The refresh rate in Hz
+        refresh_rate: Option<u16>,
+    },
+}
+
+impl FullscreenMode {
+    pub(crate) fn to_fullscreen(self, monitor: MonitorHandle) -> Option<Fullscreen> {
+        match self {
+            FullscreenMode::Borderless => Some(Fullscreen::Borderless(monitor)),
+            FullscreenMode::Exclusive { size, refresh_rate } => {
+                best_video_mode(&monitor, size, refresh_rate).map(Fullscreen::Exclusive)
+            }
+        }
+    }
+}
+
+/**
+Find the monitor's video mode closest to a size and refresh rate
+
+If no refresh rate is given, the highest one is used
+*/
+pub fn best_video_mode(
+    monitor: &MonitorHandle,
+    size: [u32; 2],
+    refresh_rate: Option<u16>,
+) -> Option<VideoMode> {
+    monitor.video_modes().min_by_key(|mode| {
+        let mode_size = mode.size();
+        let size_diff = (mode_size.width as i64 - size[0] as i64).abs()
+            + (mode_size.height as i64 - size[1] as i64).abs();
+        let rate_diff = match refresh_rate {
+            Some(rate) => (mode.refresh_rate() as i64 - rate as i64).abs(),
+            None => -(mode.refresh_rate() as i64),
+        };
+        (size_diff, rate_diff, -(mode.bit_depth() as i64))
+    })
+}
+
+#[cfg(feature = "icon")]
+/**
+Decode a window icon from encoded PNG or ICO data
+
+```ignore
+let icon = icon_from_bytes(include_bytes!("icon.png"))?;
+```
+*/
+pub fn icon_from_bytes(bytes: &[u8]) -> KuleResult<Icon> {
+    let image = image::load_from_memory(bytes)?.into_rgba8();
+    let (width, height) = image.dimensions();
+    Ok(Icon::from_rgba(image.into_raw(), width, height)?)
 }
 
 /// Holds the state of the engine
@@ -74,9 +485,34 @@ where
     R: Resources,
 {
     /// The main shader to use for drawing
-    pub program: Program,
+    ///
+    /// This is `None` if the context is headless
+    pub program: Option<Program>,
     /// Tracks the state of various inputs
     pub tracker: StateTracker,
+    /// Maps actions to inputs
+    pub input_map: InputMap,
+    /// Recognizes input combos
+    pub combos: ComboMatcher,
+    /// Moves keyboard focus between UI widgets
+    pub focus: FocusManager,
+    /// The scene stack
+    pub scenes: SceneStack<R>,
+    /// The running coroutines
+    pub coroutines: Coroutines,
+    /// The running tweens
+    pub tweens: Tweens<R>,
+    /// The entities and their components
+    pub world: World,
+    /// Watches asset paths for changes
+    pub watcher: AssetWatcher<R>,
+    /// Loads assets in the background
+    pub loader: AssetLoader<R>,
+    /// Frame timing diagnostics
+    pub diagnostics: Diagnostics,
+    /// Records `profile_scope!` spans
+    pub profiler: Profiler,
+    rng: Rng,
     /// The scene camera
     pub camera: Camera,
     /// A handle to the window
@@ -94,38 +530,406 @@ where
     #[cfg(feature = "script")]
     /// The scripting environment
     pub scripts: KuleResult<crate::Scripts>,
+    #[cfg(feature = "script")]
+    /// The Lua console, which is closed and has no toggle key by default
+    pub console: crate::Console,
+    #[cfg(feature = "save")]
+    /// The save files
+    pub saves: crate::Saves,
     /// Whether the window should close
     pub should_close: bool,
+    pub(crate) exit_code: i32,
+    error_policy: ErrorPolicy,
+    pub(crate) event_proxy: Option<EventLoopProxy<UserPayload>>,
+    user_events: HashMap<UserEventId, UserPayload>,
+    next_user_event: u64,
+    pub(crate) dt_filter: DtFilter,
+    dt_history: VecDeque<f32>,
+    pub(crate) raw_dt: f32,
+    pub(crate) dt: f32,
+    pub(crate) custom_cursor: Option<CustomCursor>,
     pub(crate) update_timer: Instant,
+    pub(crate) update_lag: f32,
+    pub(crate) deterministic: bool,
+    pub(crate) ticks: u64,
+    /// The instant that the tick clock counts from in deterministic mode
+    pub(crate) tick_epoch: Instant,
+    pub(crate) queued_events: Vec<Event>,
+    pub(crate) fixed_step: f32,
+    pub(crate) fixed_timer: Instant,
+    pub(crate) fixed_lag: f32,
+    pub(crate) alpha: f32,
+    pub(crate) fixed_alpha: f32,
     pub(crate) fps_timer: Instant,
+    pub(crate) vsync: bool,
+    pub(crate) max_fps: Option<f32>,
+    pub(crate) ui_scaling: bool,
 }
 
 impl<R> Context<R>
 where
     R: Resources,
 {
+    pub(crate) fn new(builder: &ContextBuilder, display: Option<Display>) -> KuleResult<Self> {
+        let window_size = display
+            .as_ref()
+            .map(|display| display.gl_window().window().inner_size().into())
+            .unwrap_or(builder.size);
+        let program = display.as_ref().map(crate::default_shaders);
+        Ok(Context {
+            program,
+            fonts: Default::default(),
+            meshes: Default::default(),
+            #[cfg(feature = "sound")]
+            mixer: Mixer::new(display.is_some())?,
+            #[cfg(feature = "sound")]
+            sounds: Sounds::default(),
+            tracker: StateTracker::default(),
+            input_map: Default::default(),
+            combos: Default::default(),
+            focus: Default::default(),
+            scenes: Default::default(),
+            coroutines: Default::default(),
+            tweens: Default::default(),
+            world: World::default(),
+            loader: Default::default(),
+            diagnostics: Default::default(),
+            profiler: Default::default(),
+            rng: builder.seed.map(Rng::new).unwrap_or_default(),
+            watcher: {
+                #[allow(unused_mut)]
+                let mut watcher = AssetWatcher::default();
+                #[cfg(feature = "script")]
+                if builder.hot_reload {
+                    watcher.watch(&builder.script_env.dir, WatchedAsset::Scripts);
+                }
+                watcher
+            },
+            camera: Camera {
+                center: [0.0; 2],
+                zoom: 1.0,
+                pixel_perfect: false,
+                design_resolution: builder.design_resolution,
+                window_size,
+            },
+            window: Window::new(display, builder.maximized),
+            #[cfg(feature = "script")]
+            scripts: crate::Scripts::load(builder.script_env.clone()),
+            #[cfg(feature = "script")]
+            console: crate::Console::default(),
+            #[cfg(feature = "save")]
+            saves: builder.saves.clone().unwrap_or_else(|| {
+                crate::Saves::new(&builder.title).unwrap_or_else(|_| crate::Saves::in_dir("saves"))
+            }),
+            should_close: false,
+            exit_code: 0,
+            event_proxy: None,
+            user_events: HashMap::new(),
+            next_user_event: 0,
+            dt_filter: builder.dt_filter,
+            error_policy: builder.error_policy,
+            dt_history: VecDeque::new(),
+            raw_dt: 0.0,
+            dt: 0.0,
+            custom_cursor: None,
+            update_timer: Instant::now(),
+            update_lag: 0.0,
+            deterministic: builder.deterministic,
+            ticks: 0,
+            tick_epoch: Instant::now(),
+            queued_events: Vec::new(),
+            fixed_step: 1.0 / builder.fixed_update_frequency,
+            fixed_timer: Instant::now(),
+            fixed_lag: 0.0,
+            alpha: 1.0,
+            fixed_alpha: 1.0,
+            vsync: builder.vsync,
+            max_fps: builder.max_fps,
+            ui_scaling: builder.ui_scaling,
+            fps_timer: Instant::now(),
+        })
+    }
+    /**
+    Create a headless context
+
+    A headless context has no window, draws nothing, and plays no sound.
+    This is useful for testing an app's `setup`, `update`, and `event` functions
+    without a display.
+
+    ```no_run
+    # use kule::*;
+    let mut ctx = Context::<()>::headless(&ContextBuilder::new()).unwrap();
+    ctx.tracker.add_axis("x", Axis::new().inputs(Key::A, Key::D));
+    ```
+    */
+    pub fn headless(builder: &ContextBuilder) -> KuleResult<Self> {
+        Context::new(builder, None)
+    }
+    /// Get all of the monitors
+    pub fn monitors(&self) -> Vec<Monitor> {
+        self.window.monitors()
+    }
+    /// Check if any of an action's inputs are held
+    pub fn action(&self, action: &str) -> bool {
+        self.input_map.pressed(action, &self.tracker)
+    }
+    /// Get the value of an axis action
+    pub fn axis(&self, action: &str) -> f32 {
+        self.input_map.axis(action, &self.tracker)
+    }
+    #[cfg(feature = "save")]
+    /// Save some state to a save slot
+    pub fn save_state<T>(&self, slot: &str, state: &T) -> CanFail
+    where
+        T: serde::Serialize,
+    {
+        self.saves.save(slot, state)
+    }
+    #[cfg(feature = "save")]
+    /// Load some state from a save slot, or `None` if the slot has no save
+    pub fn load_state<T>(&self, slot: &str) -> KuleResult<Option<T>>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        self.saves.load(slot)
+    }
+    #[cfg(feature = "save")]
+    /**
+    Capture the last drawn frame as a thumbnail for a save
+
+    Neither side of the thumbnail is longer than `max_size`.
+    Returns `None` if the context is headless.
+    */
+    pub fn capture_thumbnail(&self, max_size: u32) -> Option<crate::Thumbnail> {
+        let display = self.window.display.as_ref()?;
+        let image: glium::texture::RawImage2d<u8> = display.read_front_buffer().ok()?;
+        // OpenGL rows go from the bottom up
+        let row_len = image.width as usize * 4;
+        let pixels: Vec<u8> = image
+            .data
+            .chunks(row_len)
+            .rev()
+            .flatten()
+            .copied()
+            .collect();
+        crate::Thumbnail::new(image.width, image.height, pixels, max_size).ok()
+    }
     /// Get the world coordinates of the mouse cursor
     pub fn mouse_coords(&self) -> Vec2 {
         self.camera.pos_to_coords(self.tracker.mouse_pos())
     }
-    pub(crate) fn draw<F>(&self, mut f: F) -> CanFail
+    /**
+    Get the zoom of the camera used by `Kule::draw_ui`
+
+    This is the window's scale factor if UI scaling is enabled and there is
+    no design resolution. Otherwise, it is `1.0`.
+    */
+    pub fn ui_scale(&self) -> f32 {
+        if self.ui_scaling && self.camera.design_resolution.is_none() {
+            self.window.scale_factor() as f32
+        } else {
+            1.0
+        }
+    }
+    /// Get the UI coordinates of the mouse cursor
+    pub fn mouse_ui_coords(&self) -> Vec2 {
+        self.tracker.mouse_pos().div(self.ui_scale())
+    }
+    /// Draw a frame and get the time spent swapping buffers
+    pub(crate) fn draw<F>(&self, mut f: F) -> KuleResult<Duration>
     where
         F: FnMut(&mut Drawer<WindowCanvas, R>) -> CanFail,
     {
-        let mut frame = self.window.0.draw();
+        let (display, program) = match (&self.window.display, &self.program) {
+            (Some(display), Some(program)) => (display, program),
+            _ => return Ok(Duration::ZERO),
+        };
+        let mut frame = display.draw();
         let mut drawer = Drawer::new(
             &mut frame,
-            &self.window.0,
-            &self.program,
+            display,
+            program,
             &self.fonts,
             &self.meshes,
             self.camera,
         );
         f(&mut drawer)?;
+        if let Some(cursor) = &self.custom_cursor {
+            if self.tracker.mouse_in_window() {
+                drawer.custom_cursor(cursor, self.tracker.mouse_pos());
+            }
+        }
         // #[cfg(feature = "script")]
         // if let Ok(scripts) = self.scripts() {}
+        let swap_start = Instant::now();
         frame.finish().unwrap();
-        Ok(())
+        Ok(swap_start.elapsed())
+    }
+    /**
+    Get the random number generator
+
+    All of an app's randomness should come from here so that
+    runs can be reproduced by using the same seed
+    */
+    pub fn rng(&mut self) -> &mut Rng {
+        &mut self.rng
+    }
+    /// Check if the app is running in deterministic mode
+    pub fn is_deterministic(&self) -> bool {
+        self.deterministic
+    }
+    /**
+    Get the number of updates that have run
+
+    In deterministic mode, events are handled on tick boundaries,
+    so recording an event alongside this number is enough to replay it.
+    */
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+    /**
+    Queue an event to be handled at the start of the next update
+
+    This can be used to replay recorded input
+    */
+    pub fn queue_event(&mut self, event: Event) {
+        self.queued_events.push(event);
+    }
+    /**
+    Get how far the current frame is between the previous update and the next one
+
+    This is from `0.0` to `1.0` and is meant to be used in `Kule::draw` with
+    `Interpolated` values. With `UpdateMode::Variable`, it is always `1.0`.
+    */
+    pub fn alpha(&self) -> f32 {
+        self.alpha
+    }
+    /// Like `Context::alpha`, but for values that change in `Kule::fixed_update`
+    pub fn fixed_alpha(&self) -> f32 {
+        self.fixed_alpha
+    }
+    /// Get the `dt` passed to the most recent update
+    pub fn dt(&self) -> f32 {
+        self.dt
+    }
+    /**
+    Get the actual time in seconds between the two most recent updates
+
+    Unlike `Context::dt`, this is not clamped or smoothed
+    */
+    pub fn raw_dt(&self) -> f32 {
+        self.raw_dt
+    }
+    /// Get the filter used to condition `dt`
+    pub fn dt_filter(&self) -> DtFilter {
+        self.dt_filter
+    }
+    /// Set the filter used to condition `dt`
+    pub fn set_dt_filter(&mut self, dt_filter: DtFilter) {
+        self.dt_filter = dt_filter;
+        self.dt_history.clear();
+    }
+    /// Clamp and smooth a variable `dt`
+    pub(crate) fn filter_dt(&mut self, raw_dt: f32) -> f32 {
+        self.raw_dt = raw_dt;
+        let dt = self.dt_filter.clamp(raw_dt);
+        let smoothing = self.dt_filter.smoothing.max(1);
+        self.dt_history.push_back(dt);
+        while self.dt_history.len() > smoothing {
+            self.dt_history.pop_front();
+        }
+        self.dt_history.iter().sum::<f32>() / self.dt_history.len() as f32
+    }
+    /**
+    Get an `EventSender` for sending custom events from other threads
+
+    Returns `None` if the context is headless
+    */
+    pub fn event_sender(&self) -> Option<EventSender> {
+        self.event_proxy.clone().map(EventSender)
+    }
+    /**
+    Take the payload of an `Event::User`
+
+    Returns `None` if the payload is not of type `T` or was already taken.
+    Payloads that are not taken are dropped after the event is handled.
+    */
+    pub fn take_user_event<T>(&mut self, id: UserEventId) -> Option<T>
+    where
+        T: Any,
+    {
+        match self.user_events.remove(&id)?.downcast() {
+            Ok(payload) => Some(*payload),
+            Err(payload) => {
+                self.user_events.insert(id, payload);
+                None
+            }
+        }
+    }
+    pub(crate) fn insert_user_event(&mut self, payload: UserPayload) -> UserEventId {
+        let id = UserEventId(self.next_user_event);
+        self.next_user_event += 1;
+        self.user_events.insert(id, payload);
+        id
+    }
+    pub(crate) fn clear_user_events(&mut self) {
+        self.user_events.clear();
+    }
+    /**
+    Close the app with an exit code
+
+    The app is torn down, and `Kule::run` returns the code
+    */
+    pub fn request_exit(&mut self, code: i32) {
+        self.should_close = true;
+        self.exit_code = code;
+    }
+    /// Get the code that the app will exit with
+    pub fn exit_code(&self) -> i32 {
+        self.exit_code
+    }
+    /// Get how errors are handled by default
+    pub fn error_policy(&self) -> ErrorPolicy {
+        self.error_policy
+    }
+    /// Set how errors are handled by default
+    pub fn set_error_policy(&mut self, error_policy: ErrorPolicy) {
+        self.error_policy = error_policy;
+    }
+    /**
+    Set a custom cursor to be drawn at the mouse position every frame
+
+    While a custom cursor is set, the system cursor is hidden.
+    Setting it to `None` shows the system cursor again.
+    */
+    pub fn set_custom_cursor(&mut self, cursor: Option<CustomCursor>) {
+        self.window.set_cursor_visible(cursor.is_none());
+        self.custom_cursor = cursor;
+    }
+    /// Get the custom cursor
+    pub fn custom_cursor(&self) -> Option<&CustomCursor> {
+        self.custom_cursor.as_ref()
+    }
+    /// Check if vertical sync is enabled
+    pub fn vsync(&self) -> bool {
+        self.vsync
+    }
+    /// Get the maximum number of frames drawn per second
+    pub fn max_fps(&self) -> Option<f32> {
+        self.max_fps
+    }
+    /**
+    Set the maximum number of frames drawn per second
+
+    If this is `None`, frames are drawn as fast as possible
+    */
+    pub fn set_max_fps(&mut self, max_fps: Option<f32>) {
+        self.max_fps = max_fps;
+    }
+    pub(crate) fn frame_time(&self) -> Option<Duration> {
+        self.max_fps
+            .filter(|&fps| fps > 0.0)
+            .map(|fps| Duration::from_secs_f32(1.0 / fps))
     }
     #[cfg(feature = "script")]
     /// Get a reference to the scripting environment
@@ -139,8 +943,7 @@ where
     /**
     Access the scripting environment's Lua context
 
-    For the duration of the passed closue, the program's current directory
-    will be the script modules directory
+    `require` looks for modules in the script modules directory
     */
     pub fn lua<F, O>(&self, f: F) -> KuleResult<O>
     where
@@ -162,6 +965,149 @@ where
         self.fonts.load(font_id, bytes)
     }
     /**
+    Load a font from a file and reload it whenever the file changes
+
+    Returns the id of the watched path
+    */
+    pub fn watch_font<P>(&mut self, font_id: R::FontId, path: P) -> KuleResult<WatchId>
+    where
+        P: AsRef<Path>,
+    {
+        self.load_font(font_id, &fs::read(&path)?)?;
+        Ok(self.watcher.watch(path, WatchedAsset::Font(font_id)))
+    }
+    #[cfg(feature = "manifest")]
+    /**
+    Load all of the assets listed in an asset manifest or asset pack into their caches
+
+    Fonts, sounds, and meshes are loaded with ids made from their names in the
+    manifest. If the manifest lists a scripts directory, the script modules are
+    loaded from it, or from the pack if the manifest is packed.
+
+    Returns the loaded manifest
+    */
+    pub fn load_manifest<P>(&mut self, path: P) -> KuleResult<crate::AssetManifest>
+    where
+        P: AsRef<Path>,
+        R::FontId: From<crate::ResKey>,
+        R::MeshId: From<crate::ResKey>,
+        R::SoundId: From<crate::ResKey>,
+    {
+        use crate::{AssetManifest, AssetPack, KuleError, ResKey};
+        let path = path.as_ref();
+        let base = path.parent().unwrap_or_else(|| Path::new(""));
+        let bytes = fs::read(path)?;
+        let pack = if AssetPack::is_pack(&bytes) {
+            Some(AssetPack::from_bytes(&bytes)?)
+        } else {
+            None
+        };
+        let manifest = match &pack {
+            Some(pack) => pack.manifest()?,
+            None => AssetManifest::from_toml(
+                &String::from_utf8(bytes)
+                    .map_err(|e| KuleError::AssetLoad(format!("{}: {}", path.display(), e)))?,
+            )?,
+        };
+        let read = |file: &Path| -> KuleResult<Vec<u8>> {
+            match &pack {
+                Some(pack) => pack.get(file).map(<[u8]>::to_vec),
+                None => Ok(fs::read(base.join(file))?),
+            }
+        };
+        for (name, file) in &manifest.fonts {
+            self.load_font(ResKey::new(name).into(), &read(file)?)?;
+        }
+        #[cfg(feature = "sound")]
+        for (name, file) in &manifest.sounds {
+            let buffer = SoundBuffer::decode(read(file)?)?;
+            self.sounds.insert(ResKey::new(name).into(), buffer);
+        }
+        for (name, mesh) in &manifest.meshes {
+            let vertices = crate::manifest::mesh_vertices(mesh, read)?;
+            if let Some(display) = &self.window.display {
+                self.meshes
+                    .insert_polygon(display, ResKey::new(name).into(), &vertices);
+            }
+        }
+        #[cfg(feature = "script")]
+        if let Some(dir) = &manifest.scripts {
+            let env = match &self.scripts {
+                Ok(scripts) => scripts.env.clone(),
+                Err(_) => crate::ScriptEnv::default(),
+            };
+            // Scripts in a pack are read from the pack
+            self.scripts = crate::Scripts::load(match pack {
+                Some(pack) => crate::ScriptEnv {
+                    dir: dir.clone(),
+                    ..env
+                }
+                .pack(pack),
+                None => crate::ScriptEnv {
+                    dir: base.join(dir),
+                    pack: None,
+                    ..env
+                },
+            });
+        }
+        Ok(manifest)
+    }
+    /// Get the progress of background asset loading from `0.0` to `1.0`
+    pub fn load_progress(&self) -> f32 {
+        self.loader.progress()
+    }
+    /// Move any assets that finished loading in the background into their caches
+    pub(crate) fn finish_loads(&mut self) -> Vec<KuleResult<LoadId>> {
+        let mut finished = Vec::new();
+        for (id, res) in self.loader.poll() {
+            finished.push(res.map(|loaded| {
+                match loaded {
+                    Loaded::Font(font_id, font) => self.fonts.insert(font_id, font),
+                    #[cfg(feature = "sound")]
+                    Loaded::Sound(sound_id, buffer) => {
+                        if let Some(buffer) = buffer {
+                            self.sounds.insert(sound_id, buffer);
+                        }
+                    }
+                    Loaded::Mesh(mesh_id, vertices) => {
+                        if let Some(display) = &self.window.display {
+                            self.meshes.insert_polygon(display, mesh_id, &vertices);
+                        }
+                    }
+                }
+                id
+            }));
+        }
+        finished
+    }
+    /// Reload any watched assets that have changed and get their ids
+    pub(crate) fn reload_assets(&mut self) -> Vec<KuleResult<WatchId>> {
+        let mut reloaded = Vec::new();
+        for (id, path, asset) in self.watcher.poll(Instant::now()) {
+            let res = match asset {
+                WatchedAsset::Font(font_id) => fs::read(&path)
+                    .map_err(Into::into)
+                    .and_then(|bytes| self.fonts.load(font_id, &bytes)),
+                #[cfg(feature = "sound")]
+                WatchedAsset::Sound(sound_id) => {
+                    self.sounds.remove(sound_id);
+                    Ok(())
+                }
+                #[cfg(feature = "script")]
+                WatchedAsset::Scripts => match &mut self.scripts {
+                    Ok(scripts) => {
+                        scripts.start_reload();
+                        Ok(())
+                    }
+                    Err(_) => Ok(()),
+                },
+                WatchedAsset::Other => Ok(()),
+            };
+            reloaded.push(res.map(|_| id));
+        }
+        reloaded
+    }
+    /**
     Get the glyph cache for a font
 
     # Panics
@@ -177,36 +1123,177 @@ where
         self.fonts.get(font_id)
     }
     #[cfg(feature = "sound")]
-    /// Play an id'd sound
-    pub fn play_sound<A>(&mut self, sound_id: R::SoundId, app: &A) -> KuleResult<()>
+    /**
+    Play an id'd sound
+
+    Returns `None` if there is no sound for the given id
+    */
+    pub fn play_sound<A>(
+        &mut self,
+        sound_id: R::SoundId,
+        app: &A,
+    ) -> KuleResult<Option<SoundHandle>>
     where
         A: Kule<Resources = R>,
     {
         self.play_modified_sound(sound_id, app, |s| s)
     }
     #[cfg(feature = "sound")]
-    /// Play an id'd sound with a modified `Source`
+    /**
+    Play an id'd sound with a modified `Source`
+
+    Returns `None` if there is no sound for the given id
+    */
     pub fn play_modified_sound<A, F, S>(
         &mut self,
         sound_id: R::SoundId,
         app: &A,
         f: F,
-    ) -> KuleResult<()>
+    ) -> KuleResult<Option<SoundHandle>>
     where
         A: Kule<Resources = R>,
         F: Fn(SoundSource) -> S,
         S: Source + Send + 'static,
         S::Item: Sample,
+    {
+        Ok(self.sound_source(sound_id, app)?.map(|source| {
+            self.mixer.play_grouped(
+                f(source),
+                PlayOptions::default(),
+                Some(sound_group(sound_id)),
+            )
+        }))
+    }
+    #[cfg(feature = "sound")]
+    /**
+    Play an id'd sound with some options
+
+    Returns `None` if there is no sound for the given id
+    */
+    pub fn play_sound_with<A>(
+        &mut self,
+        sound_id: R::SoundId,
+        app: &A,
+        options: PlayOptions,
+    ) -> KuleResult<Option<SoundHandle>>
+    where
+        A: Kule<Resources = R>,
+    {
+        Ok(self.sound_source(sound_id, app)?.map(|source| {
+            self.mixer
+                .play_grouped(source, options, Some(sound_group(sound_id)))
+        }))
+    }
+    #[cfg(feature = "sound")]
+    /**
+    Play an id'd sound as music, crossfading from the current music
+
+    Returns `None` if there is no sound for the given id
+    */
+    pub fn play_music<A>(
+        &mut self,
+        sound_id: R::SoundId,
+        app: &A,
+        crossfade: Duration,
+    ) -> KuleResult<Option<SoundHandle>>
+    where
+        A: Kule<Resources = R>,
+    {
+        Ok(self
+            .sound_source(sound_id, app)?
+            .map(|source| self.mixer.play_music(source, crossfade)))
+    }
+    #[cfg(feature = "sound")]
+    /**
+    Play id'd sounds as the synchronized layers of a music track,
+    crossfading from the current music
+
+    Returns `None` if there is no sound for any of the given ids
+    */
+    pub fn play_layered_music<A, I>(
+        &mut self,
+        sound_ids: I,
+        app: &A,
+        intensity: f32,
+        crossfade: Duration,
+    ) -> KuleResult<Option<MusicTrack>>
+    where
+        A: Kule<Resources = R>,
+        I: IntoIterator<Item = R::SoundId>,
+    {
+        let mut layers = Vec::new();
+        for sound_id in sound_ids {
+            if let Some(source) = self.sound_source(sound_id, app)? {
+                layers.push(source);
+            } else {
+                return Ok(None);
+            }
+        }
+        Ok(Some(
+            self.mixer.play_layered_music(layers, intensity, crossfade),
+        ))
+    }
+    #[cfg(feature = "sound")]
+    /**
+    Load sounds into the sound cache ahead of time
+
+    Sounds are otherwise loaded the first time they are played,
+    which can cause a delay. Sounds that are already cached are not reloaded.
+    */
+    pub fn preload_sounds<A, I>(&mut self, sound_ids: I, app: &A) -> CanFail
+    where
+        A: Kule<Resources = R>,
+        I: IntoIterator<Item = R::SoundId>,
+    {
+        for sound_id in sound_ids {
+            if !self.sounds.contains(sound_id) {
+                if let Some(buffer) = A::load_sound(sound_id, app)? {
+                    self.sounds.insert(sound_id, buffer);
+                }
+            }
+        }
+        Ok(())
+    }
+    #[cfg(feature = "sound")]
+    /**
+    Load sounds into the sound cache on a background thread
+
+    Because the app cannot be shared with another thread, sounds are loaded
+    with the given function rather than `Kule::load_sound`. Sounds that are
+    already cached are not reloaded.
+
+    Call `SoundPreload::poll` with the context's `sounds` to move loaded sounds into the cache.
+    */
+    pub fn preload_sounds_in_background<I, F>(
+        &self,
+        sound_ids: I,
+        load: F,
+    ) -> SoundPreload<R::SoundId>
+    where
+        I: IntoIterator<Item = R::SoundId>,
+        F: Fn(R::SoundId) -> KuleResult<Option<SoundBuffer>> + Send + 'static,
+        R::SoundId: Send + 'static,
+    {
+        let sound_ids: Vec<R::SoundId> = sound_ids
+            .into_iter()
+            .filter(|&sound_id| !self.sounds.contains(sound_id))
+            .collect();
+        SoundPreload::spawn(sound_ids, move |&sound_id| load(sound_id))
+    }
+    #[cfg(feature = "sound")]
+    fn sound_source<A>(&mut self, sound_id: R::SoundId, app: &A) -> KuleResult<Option<SoundSource>>
+    where
+        A: Kule<Resources = R>,
     {
         if !self.sounds.contains(sound_id) {
             if let Some(buffer) = A::load_sound(sound_id, app)? {
                 self.sounds.insert(sound_id, buffer);
             }
         }
-        if let Some(buffer) = self.sounds.get(sound_id) {
-            self.mixer.play(f(SoundSource::from(buffer.clone())));
-        }
-        Ok(())
+        Ok(self
+            .sounds
+            .get(sound_id)
+            .map(|buffer| SoundSource::from(buffer.clone())))
     }
 }
 
@@ -231,17 +1318,100 @@ pub struct ContextBuilder {
     pub title: String,
     /// The window size
     pub size: [f32; 2],
+    /// Where the window is placed when it is created
+    ///
+    /// If this is `None`, the operating system decides
+    pub position: Option<WindowPosition>,
+    /// Whether the window can be resized by the user
+    pub resizable: bool,
+    /// The minimum window size
+    pub min_size: Option<[f32; 2]>,
+    /// The maximum window size
+    pub max_size: Option<[f32; 2]>,
     /// Whether the window should automatically close when clicking the "X"
     pub automatic_close: bool,
     /// How often to call the app's `update` function in Hz
     pub update_frequency: f32,
+    /// How the app's `update` function is called
+    pub update_mode: UpdateMode,
+    /// How often to call the app's `fixed_update` function in Hz
+    pub fixed_update_frequency: f32,
+    /// How `dt` is conditioned before being passed to `update`
+    pub dt_filter: DtFilter,
+    /// How errors are handled by default
+    pub error_policy: ErrorPolicy,
+    /// The seed for the context's random number generator
+    ///
+    /// If this is `None`, the current time is used
+    pub seed: Option<u64>,
+    /**
+    Whether the app runs in deterministic mode
+
+    In deterministic mode, updates use a fixed timestep, `fixed_update` is called once
+    before each `update` with the same `dt`, and events are queued and handled at the
+    start of the next update rather than as soon as they arrive. Key repeat, gestures,
+    and combos are timed by the number of updates rather than the wall clock. Combined
+    with a fixed seed, this means that identical input streams produce identical
+    simulations.
+    */
+    pub deterministic: bool,
+    /// Whether vertical sync is enabled
+    pub vsync: bool,
+    /// The maximum number of frames drawn per second
+    pub max_fps: Option<f32>,
     /// Samples to use for antialiasing
     pub samples: u16,
+    /// The number of bits in the depth buffer
+    ///
+    /// If this is `0`, there is no depth buffer
+    pub depth_bits: u8,
+    /// The number of bits in the stencil buffer
+    pub stencil_bits: u8,
+    /// Whether the framebuffer is sRGB
+    pub srgb: bool,
+    /// Whether hardware acceleration is required
+    ///
+    /// `Some(true)` requires it, `Some(false)` requires software rendering,
+    /// and `None` accepts either
+    pub hardware_acceleration: Option<bool>,
     /// The window's icon
     pub icon: Option<window::Icon>,
+    /// Whether the window's background is transparent
+    pub transparent: bool,
+    /// Whether the window is always on top of other windows
+    pub always_on_top: bool,
+    /// Whether the window has a border and title bar
+    pub decorations: bool,
+    /// Whether the window starts maximized
+    pub maximized: bool,
+    /// The window's fullscreen mode on the primary monitor at startup
+    pub fullscreen: Option<FullscreenMode>,
+    /// The design resolution used for letterboxing
+    pub design_resolution: Option<Vec2>,
+    /// Engine-generated key repeat, used instead of the operating system's
+    pub key_repeat: Option<KeyRepeat>,
+    /// Whether gestures should be recognized
+    pub gestures: bool,
+    /// Whether `Kule::draw_ui` is scaled by the window's scale factor
+    pub ui_scaling: bool,
+    /// Whether the app runs without a window
+    pub headless: bool,
+    /// Whether drawing is skipped while the window is minimized or the app is suspended
+    pub skip_draw_when_hidden: bool,
+    #[cfg(feature = "script")]
+    /// Whether script modules are reloaded when they change
+    pub hot_reload: bool,
+    #[cfg(feature = "sound")]
+    /// Whether audio should be paused while the window is not focused
+    pub pause_audio_on_unfocus: bool,
     #[cfg(feature = "script")]
     /// Configuration for the scripting environment
     pub script_env: crate::ScriptEnv,
+    #[cfg(feature = "save")]
+    /// Where and how save files are stored
+    ///
+    /// If this is `None`, saves are stored in the platform's data directory under the window title
+    pub saves: Option<crate::Saves>,
 }
 
 impl Default for ContextBuilder {
@@ -249,12 +1419,45 @@ impl Default for ContextBuilder {
         ContextBuilder {
             title: env!("CARGO_CRATE_NAME").into(),
             size: [800.0; 2],
+            position: None,
+            resizable: true,
+            min_size: None,
+            max_size: None,
             automatic_close: true,
             update_frequency: 120.0,
+            update_mode: UpdateMode::Variable,
+            fixed_update_frequency: 60.0,
+            dt_filter: DtFilter::new(),
+            error_policy: ErrorPolicy::Panic,
+            seed: None,
+            deterministic: false,
+            vsync: false,
+            max_fps: None,
             samples: 0,
+            depth_bits: 0,
+            stencil_bits: 1,
+            srgb: true,
+            hardware_acceleration: Some(true),
             icon: None,
+            transparent: false,
+            always_on_top: false,
+            decorations: true,
+            maximized: false,
+            fullscreen: None,
+            design_resolution: None,
+            key_repeat: None,
+            gestures: false,
+            ui_scaling: true,
+            headless: false,
+            skip_draw_when_hidden: true,
+            #[cfg(feature = "script")]
+            hot_reload: false,
+            #[cfg(feature = "sound")]
+            pause_audio_on_unfocus: false,
             #[cfg(feature = "script")]
             script_env: crate::ScriptEnv::default(),
+            #[cfg(feature = "save")]
+            saves: None,
         }
     }
 }
@@ -284,6 +1487,44 @@ impl ContextBuilder {
             ..self
         }
     }
+    /// Set the initial position of the window on the desktop in pixels
+    pub fn position(self, pos: [i32; 2]) -> Self {
+        ContextBuilder {
+            position: Some(WindowPosition::At(pos)),
+            ..self
+        }
+    }
+    /// Start the window centered on the primary monitor
+    pub fn centered(self) -> Self {
+        ContextBuilder {
+            position: Some(WindowPosition::Centered),
+            ..self
+        }
+    }
+    /// Set whether the window can be resized by the user
+    pub fn resizable(self, resizable: bool) -> Self {
+        ContextBuilder { resizable, ..self }
+    }
+    /// Set the minimum window size
+    pub fn min_size<V>(self, min_size: V) -> Self
+    where
+        V: Vector2<Scalar = f32>,
+    {
+        ContextBuilder {
+            min_size: Some(min_size.map()),
+            ..self
+        }
+    }
+    /// Set the maximum window size
+    pub fn max_size<V>(self, max_size: V) -> Self
+    where
+        V: Vector2<Scalar = f32>,
+    {
+        ContextBuilder {
+            max_size: Some(max_size.map()),
+            ..self
+        }
+    }
     /// Set whether the window should automatically close when clicking the "X"
     pub fn automatic_close(self, automatic_close: bool) -> Self {
         ContextBuilder {
@@ -291,10 +1532,102 @@ impl ContextBuilder {
             ..self
         }
     }
+    /// Set how often to call the app's `update` function in Hz
+    pub fn update_frequency(self, update_frequency: f32) -> Self {
+        ContextBuilder {
+            update_frequency,
+            ..self
+        }
+    }
+    /// Set how often to call the app's `fixed_update` function in Hz
+    pub fn fixed_update_frequency(self, fixed_update_frequency: f32) -> Self {
+        ContextBuilder {
+            fixed_update_frequency,
+            ..self
+        }
+    }
+    /// Set how the app's `update` function is called
+    pub fn update_mode(self, update_mode: UpdateMode) -> Self {
+        ContextBuilder {
+            update_mode,
+            ..self
+        }
+    }
+    /// Set how `dt` is conditioned before being passed to `update`
+    pub fn dt_filter(self, dt_filter: DtFilter) -> Self {
+        ContextBuilder { dt_filter, ..self }
+    }
+    /// Set how errors are handled by default
+    pub fn error_policy(self, error_policy: ErrorPolicy) -> Self {
+        ContextBuilder {
+            error_policy,
+            ..self
+        }
+    }
+    /// Set the seed for the context's random number generator
+    pub fn seed(self, seed: u64) -> Self {
+        ContextBuilder {
+            seed: Some(seed),
+            ..self
+        }
+    }
+    /**
+    Run the app in deterministic mode with a seed
+
+    This also switches to `UpdateMode::Fixed` if the update mode is variable.
+    See `ContextBuilder::deterministic` for details.
+    */
+    pub fn deterministic(self, seed: u64) -> Self {
+        let update_mode = match self.update_mode {
+            UpdateMode::Variable => UpdateMode::Fixed { max_steps: 5 },
+            mode => mode,
+        };
+        ContextBuilder {
+            seed: Some(seed),
+            deterministic: true,
+            update_mode,
+            ..self
+        }
+    }
+    /// Set whether vertical sync is enabled
+    pub fn vsync(self, vsync: bool) -> Self {
+        ContextBuilder { vsync, ..self }
+    }
+    /**
+    Set the maximum number of frames drawn per second
+
+    If this is `None`, frames are drawn as fast as possible.
+    It can be changed later with `Context::set_max_fps`.
+    */
+    pub fn max_fps(self, max_fps: Option<f32>) -> Self {
+        ContextBuilder { max_fps, ..self }
+    }
     /// Set the samples used for antialiasing
     pub fn samples(self, samples: u16) -> Self {
         ContextBuilder { samples, ..self }
     }
+    /// Set the number of bits in the depth buffer
+    pub fn depth_bits(self, depth_bits: u8) -> Self {
+        ContextBuilder { depth_bits, ..self }
+    }
+    /// Set the number of bits in the stencil buffer
+    pub fn stencil_bits(self, stencil_bits: u8) -> Self {
+        ContextBuilder {
+            stencil_bits,
+            ..self
+        }
+    }
+    /// Set whether the framebuffer is sRGB
+    pub fn srgb(self, srgb: bool) -> Self {
+        ContextBuilder { srgb, ..self }
+    }
+    /// Set whether hardware acceleration is required
+    pub fn hardware_acceleration(self, hardware_acceleration: Option<bool>) -> Self {
+        ContextBuilder {
+            hardware_acceleration,
+            ..self
+        }
+    }
     /// Set the window icon using bitmap data
     pub fn icon(self, rgba: Vec<u8>, width: u32, height: u32) -> KuleResult<Self> {
         Ok(ContextBuilder {
@@ -302,9 +1635,253 @@ impl ContextBuilder {
             ..self
         })
     }
+    #[cfg(feature = "icon")]
+    /// Set the window icon using encoded PNG or ICO data
+    pub fn icon_from_bytes(self, bytes: &[u8]) -> KuleResult<Self> {
+        Ok(ContextBuilder {
+            icon: Some(icon_from_bytes(bytes)?),
+            ..self
+        })
+    }
+    /**
+    Set whether the window's background is transparent
+
+    Parts of the window cleared with a transparent color will show what is behind the window
+    */
+    pub fn transparent(self, transparent: bool) -> Self {
+        ContextBuilder {
+            transparent,
+            ..self
+        }
+    }
+    /// Set whether the window is always on top of other windows
+    pub fn always_on_top(self, always_on_top: bool) -> Self {
+        ContextBuilder {
+            always_on_top,
+            ..self
+        }
+    }
+    /**
+    Set whether the window has a border and title bar
+
+    Disabling decorations makes a borderless window
+    */
+    pub fn decorations(self, decorations: bool) -> Self {
+        ContextBuilder {
+            decorations,
+            ..self
+        }
+    }
+    /// Set whether the window starts maximized
+    pub fn maximized(self, maximized: bool) -> Self {
+        ContextBuilder { maximized, ..self }
+    }
+    /// Set the window's fullscreen mode on the primary monitor at startup
+    pub fn fullscreen(self, fullscreen: Option<FullscreenMode>) -> Self {
+        ContextBuilder { fullscreen, ..self }
+    }
+    /**
+    Set the design resolution
+
+    The view will be letterboxed or pillarboxed to keep this resolution's aspect ratio,
+    and the visible world area will not change when the window is resized.
+    */
+    pub fn design_resolution<V>(self, size: V) -> Self
+    where
+        V: Vector2<Scalar = f32>,
+    {
+        ContextBuilder {
+            design_resolution: Some(size.map()),
+            ..self
+        }
+    }
+    /**
+    Set the engine-generated key repeat
+
+    If this is `Some`, `Event::KeyRepeat`s from the operating system are ignored,
+    and the engine generates them with the given delay and interval instead.
+    */
+    pub fn key_repeat(self, key_repeat: Option<KeyRepeat>) -> Self {
+        ContextBuilder { key_repeat, ..self }
+    }
+    /**
+    Set whether gestures should be recognized
+
+    If enabled, `Event::Gesture`s are sent for drags, swipes, pinches, and pans
+    */
+    pub fn gestures(self, gestures: bool) -> Self {
+        ContextBuilder { gestures, ..self }
+    }
+    /// Set whether `Kule::draw_ui` is scaled by the window's scale factor
+    pub fn ui_scaling(self, ui_scaling: bool) -> Self {
+        ContextBuilder { ui_scaling, ..self }
+    }
+    /**
+    Set whether the app runs without a window
+
+    A headless app draws nothing, plays no sound, and receives no input events.
+    It is updated until `Context::should_close` is set. This is useful for servers
+    and automated testing.
+    */
+    pub fn headless(self, headless: bool) -> Self {
+        ContextBuilder { headless, ..self }
+    }
+    /// Set whether drawing is skipped while the window is minimized or the app is suspended
+    pub fn skip_draw_when_hidden(self, skip_draw_when_hidden: bool) -> Self {
+        ContextBuilder {
+            skip_draw_when_hidden,
+            ..self
+        }
+    }
+    #[cfg(feature = "script")]
+    /**
+    Set whether script modules are reloaded when they change
+
+    Other assets can be watched with `Context::watcher`
+    */
+    pub fn hot_reload(self, hot_reload: bool) -> Self {
+        ContextBuilder { hot_reload, ..self }
+    }
+    #[cfg(feature = "sound")]
+    /// Set whether audio should be paused while the window is not focused
+    pub fn pause_audio_on_unfocus(self, pause_audio_on_unfocus: bool) -> Self {
+        ContextBuilder {
+            pause_audio_on_unfocus,
+            ..self
+        }
+    }
     #[cfg(feature = "script")]
     /// Configure the scripting environment
     pub fn script_env(self, script_env: crate::ScriptEnv) -> Self {
         ContextBuilder { script_env, ..self }
     }
+    #[cfg(feature = "save")]
+    /// Set where and how save files are stored
+    pub fn saves(self, saves: crate::Saves) -> Self {
+        ContextBuilder {
+            saves: Some(saves),
+            ..self
+        }
+    }
+}
+
+/// Determines how an app's `update` function is called
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpdateMode {
+    /**
+    `update` is called at most `update_frequency` times per second
+
+    `dt` is the time since the last update, so it varies from update to update.
+    */
+    Variable,
+    /**
+    `update` is called `update_frequency` times per second of elapsed time with a constant `dt`
+
+    If updates fall behind, several are run in a row to catch up. This makes updates
+    deterministic, which is useful for physics.
+    */
+    Fixed {
+        /**
+        The maximum number of updates to run at once to catch up
+
+        If more updates than this are needed, the extra time is dropped,
+        and the app runs slower instead of stalling.
+        */
+        max_steps: u32,
+    },
+}
+
+/**
+Conditions the time between updates
+
+Huge `dt`s, like those after the window is dragged or the app is paused in a debugger,
+are clamped so that they do not destabilize physics. With `UpdateMode::Variable`,
+`dt` can also be averaged over several updates to reduce jitter.
+*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DtFilter {
+    /**
+    The maximum amount of time in seconds that can pass between updates
+
+    With `UpdateMode::Fixed`, this limits how much time is caught up on at once
+    */
+    pub max: Option<f32>,
+    /// The number of updates to average `dt` over, where `1` disables smoothing
+    pub smoothing: usize,
+}
+
+impl Default for DtFilter {
+    fn default() -> Self {
+        DtFilter::new()
+    }
+}
+
+impl DtFilter {
+    /// Create a new `DtFilter` that clamps `dt` to a quarter of a second without smoothing
+    pub const fn new() -> Self {
+        DtFilter {
+            max: Some(0.25),
+            smoothing: 1,
+        }
+    }
+    /// Set the maximum amount of time in seconds that can pass between updates
+    pub const fn max(self, max: Option<f32>) -> Self {
+        DtFilter { max, ..self }
+    }
+    /// Set the number of updates to average `dt` over
+    pub const fn smoothing(self, smoothing: usize) -> Self {
+        DtFilter { smoothing, ..self }
+    }
+    /// Clamp a `dt` to the maximum
+    pub fn clamp(&self, dt: f32) -> f32 {
+        match self.max {
+            Some(max) => dt.min(max),
+            None => dt,
+        }
+    }
+}
+
+#[cfg(feature = "sound")]
+fn sound_group<S>(sound_id: S) -> u64
+where
+    S: Hash,
+{
+    let mut hasher = DefaultHasher::new();
+    sound_id.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+#[test]
+fn dt_filter() {
+    let mut ctx = Context::<()>::headless(&ContextBuilder::new()).unwrap();
+    assert_eq!(ctx.filter_dt(3.0), 0.25);
+    assert_eq!(ctx.raw_dt(), 3.0);
+    ctx.set_dt_filter(DtFilter::new().max(None).smoothing(2));
+    assert_eq!(ctx.filter_dt(1.0), 1.0);
+    assert_eq!(ctx.filter_dt(2.0), 1.5);
+    assert_eq!(ctx.filter_dt(4.0), 3.0);
+}
+
+#[cfg(test)]
+#[test]
+fn user_events() {
+    let mut ctx = Context::<()>::headless(&ContextBuilder::new()).unwrap();
+    assert!(ctx.event_sender().is_none());
+    let id = ctx.insert_user_event(Box::new(5u32));
+    assert_eq!(ctx.take_user_event::<String>(id), None);
+    assert_eq!(ctx.take_user_event::<u32>(id), Some(5));
+    assert_eq!(ctx.take_user_event::<u32>(id), None);
+}
+
+#[cfg(all(test, feature = "icon"))]
+#[test]
+fn icon_decoding() {
+    let image = image::DynamicImage::ImageRgba8(image::RgbaImage::new(2, 2));
+    let mut bytes = Vec::new();
+    image
+        .write_to(&mut bytes, image::ImageOutputFormat::Png)
+        .unwrap();
+    icon_from_bytes(&bytes).unwrap();
+    assert!(icon_from_bytes(&[1, 2, 3]).is_err());
 }
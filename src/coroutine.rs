@@ -0,0 +1,186 @@
+use std::{
+    cell::RefCell,
+    future::Future,
+    pin::Pin,
+    ptr,
+    rc::Rc,
+    task::{Context as TaskContext, Poll, RawWaker, RawWakerVTable, Waker},
+};
+
+use crate::StateTracker;
+
+/// The state shared between a runner and its coroutines
+#[derive(Default)]
+struct Shared {
+    time: f64,
+    dt: f32,
+    tracker: StateTracker,
+}
+
+/**
+A handle given to coroutines for waiting on time and input
+
+Coroutines are resumed once per update, so all waits are measured in updates.
+*/
+#[derive(Clone)]
+pub struct Co {
+    shared: Rc<RefCell<Shared>>,
+}
+
+impl Co {
+    /// Get the time in seconds since the runner was created
+    pub fn time(&self) -> f64 {
+        self.shared.borrow().time
+    }
+    /// Get the time in seconds since the last update
+    pub fn dt(&self) -> f32 {
+        self.shared.borrow().dt
+    }
+    /// Get the input state as of the current update
+    pub fn tracker(&self) -> StateTracker {
+        self.shared.borrow().tracker.clone()
+    }
+    /// Wait until the next update
+    pub fn frame(&self) -> NextFrame {
+        NextFrame { ready: false }
+    }
+    /// Wait for some number of updates
+    pub async fn frames(&self, frames: u32) {
+        for _ in 0..frames {
+            self.frame().await;
+        }
+    }
+    /// Wait for some number of seconds
+    pub async fn wait(&self, seconds: f32) {
+        let end = self.time() + seconds as f64;
+        while self.time() < end {
+            self.frame().await;
+        }
+    }
+    /**
+    Wait until a condition on the input state is true
+
+    The condition is checked once per update
+    */
+    pub async fn wait_until<F>(&self, mut condition: F)
+    where
+        F: FnMut(&StateTracker) -> bool,
+    {
+        while !condition(&self.shared.borrow().tracker) {
+            self.frame().await;
+        }
+    }
+}
+
+/// A future that completes on the next update
+pub struct NextFrame {
+    ready: bool,
+}
+
+impl Future for NextFrame {
+    type Output = ();
+    fn poll(mut self: Pin<&mut Self>, _: &mut TaskContext) -> Poll<Self::Output> {
+        if self.ready {
+            Poll::Ready(())
+        } else {
+            self.ready = true;
+            Poll::Pending
+        }
+    }
+}
+
+type Task = Pin<Box<dyn Future<Output = ()>>>;
+
+/// An id for a coroutine
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CoroutineId(u64);
+
+/**
+Runs coroutines for multi-update sequences like cutscenes and tutorials
+
+Coroutines are async functions that are resumed once per update. The context's
+coroutines are updated automatically after the app and scenes are.
+
+```
+# use kule::*;
+# use std::{cell::Cell, rc::Rc};
+let mut coroutines = Coroutines::default();
+let done = Rc::new(Cell::new(false));
+let done_clone = done.clone();
+coroutines.spawn(|co| async move {
+    co.wait(0.5).await;
+    co.wait_until(|tracker| tracker.key(Key::Space)).await;
+    done_clone.set(true);
+});
+coroutines.update(0.25, &StateTracker::default());
+assert!(!done.get());
+```
+*/
+#[derive(Default)]
+pub struct Coroutines {
+    shared: Rc<RefCell<Shared>>,
+    tasks: Vec<(CoroutineId, Task)>,
+    next_id: u64,
+}
+
+impl Coroutines {
+    /// Spawn a coroutine
+    pub fn spawn<F, T>(&mut self, f: F) -> CoroutineId
+    where
+        F: FnOnce(Co) -> T,
+        T: Future<Output = ()> + 'static,
+    {
+        let id = CoroutineId(self.next_id);
+        self.next_id += 1;
+        let co = Co {
+            shared: self.shared.clone(),
+        };
+        self.tasks.push((id, Box::pin(f(co))));
+        id
+    }
+    /// Stop a coroutine
+    pub fn cancel(&mut self, id: CoroutineId) {
+        self.tasks.retain(|(task_id, _)| *task_id != id);
+    }
+    /// Stop all coroutines
+    pub fn cancel_all(&mut self) {
+        self.tasks.clear();
+    }
+    /// Check if a coroutine is still running
+    pub fn is_running(&self, id: CoroutineId) -> bool {
+        self.tasks.iter().any(|(task_id, _)| *task_id == id)
+    }
+    /// Get the number of running coroutines
+    pub fn len(&self) -> usize {
+        self.tasks.len()
+    }
+    /// Check if there are no running coroutines
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty()
+    }
+    /// Resume all coroutines
+    pub fn update(&mut self, dt: f32, tracker: &StateTracker) {
+        if self.tasks.is_empty() {
+            return;
+        }
+        {
+            let mut shared = self.shared.borrow_mut();
+            shared.time += dt as f64;
+            shared.dt = dt;
+            shared.tracker.clone_from(tracker);
+        }
+        let waker = noop_waker();
+        let mut cx = TaskContext::from_waker(&waker);
+        self.tasks
+            .retain_mut(|(_, task)| task.as_mut().poll(&mut cx).is_pending());
+    }
+}
+
+fn noop_waker() -> Waker {
+    fn clone(_: *const ()) -> RawWaker {
+        RawWaker::new(ptr::null(), &VTABLE)
+    }
+    fn noop(_: *const ()) {}
+    static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+    unsafe { Waker::from_raw(RawWaker::new(ptr::null(), &VTABLE)) }
+}
@@ -0,0 +1,127 @@
+use std::{collections::VecDeque, time::Duration};
+
+/// A breakdown of where the time in a single frame went
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FrameTimes {
+    /// The time between the start of this frame and the start of the next
+    pub total: Duration,
+    /// The time spent handling events
+    pub events: Duration,
+    /// The time spent in updates
+    pub update: Duration,
+    /// The time spent drawing, not including buffer swapping
+    pub draw: Duration,
+    /// The time spent swapping buffers, which includes waiting for vsync
+    pub swap: Duration,
+    /// The time spent idle, waiting for the next event, update, or frame
+    pub wait: Duration,
+    /// The number of updates run
+    pub updates: u32,
+}
+
+impl FrameTimes {
+    /// Get the time spent doing work, which is everything but `wait`
+    pub fn busy(&self) -> Duration {
+        self.events + self.update + self.draw + self.swap
+    }
+}
+
+/**
+Frame timing diagnostics
+
+The engine records a `FrameTimes` for each frame drawn. A history of the most recent
+frames is kept, which is useful for finding the source of stutters.
+*/
+#[derive(Debug, Clone)]
+pub struct Diagnostics {
+    history: VecDeque<FrameTimes>,
+    capacity: usize,
+    pub(crate) current: FrameTimes,
+}
+
+impl Default for Diagnostics {
+    fn default() -> Self {
+        Diagnostics::new(240)
+    }
+}
+
+impl Diagnostics {
+    /// Create a new `Diagnostics` that keeps some number of frames
+    pub fn new(capacity: usize) -> Self {
+        Diagnostics {
+            history: VecDeque::with_capacity(capacity),
+            capacity,
+            current: FrameTimes::default(),
+        }
+    }
+    /// Iterate over the recorded frames from oldest to newest
+    pub fn history(&self) -> impl DoubleEndedIterator<Item = &FrameTimes> {
+        self.history.iter()
+    }
+    /// Get the most recent frame
+    pub fn last(&self) -> Option<FrameTimes> {
+        self.history.back().copied()
+    }
+    /// Get the recorded frame that took the longest
+    pub fn worst(&self) -> Option<FrameTimes> {
+        self.history.iter().max_by_key(|frame| frame.total).copied()
+    }
+    /// Get the average of the recorded frames
+    pub fn average(&self) -> FrameTimes {
+        let len = self.history.len().max(1) as u32;
+        let sum =
+            |f: fn(&FrameTimes) -> Duration| self.history.iter().map(f).sum::<Duration>() / len;
+        FrameTimes {
+            total: sum(|frame| frame.total),
+            events: sum(|frame| frame.events),
+            update: sum(|frame| frame.update),
+            draw: sum(|frame| frame.draw),
+            swap: sum(|frame| frame.swap),
+            wait: sum(|frame| frame.wait),
+            updates: self.history.iter().map(|frame| frame.updates).sum::<u32>() / len,
+        }
+    }
+    /// Get the maximum number of frames kept
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+    /// Set the maximum number of frames kept
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        while self.history.len() > capacity {
+            self.history.pop_front();
+        }
+    }
+    /// Clear the recorded frames
+    pub fn clear(&mut self) {
+        self.history.clear();
+    }
+    /// Record the current frame and start a new one
+    pub(crate) fn finish_frame(&mut self, total: Duration) {
+        let mut frame = std::mem::take(&mut self.current);
+        frame.total = total;
+        frame.wait = total.checked_sub(frame.busy()).unwrap_or_default();
+        if self.capacity == 0 {
+            return;
+        }
+        if self.history.len() == self.capacity {
+            self.history.pop_front();
+        }
+        self.history.push_back(frame);
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn diagnostics_history() {
+    let mut diagnostics = Diagnostics::new(2);
+    for ms in 1..=3 {
+        diagnostics.current.update = Duration::from_millis(ms);
+        diagnostics.finish_frame(Duration::from_millis(10));
+    }
+    assert_eq!(diagnostics.history().count(), 2);
+    let last = diagnostics.last().unwrap();
+    assert_eq!(last.update, Duration::from_millis(3));
+    assert_eq!(last.wait, Duration::from_millis(7));
+    assert_eq!(diagnostics.average().update, Duration::from_micros(2500));
+}
@@ -9,7 +9,10 @@ use std::{
 use glium::{backend::*, *};
 use vector2math::*;
 
-use crate::{Col, Color, Fonts, GlyphSize, GlyphSpec, Rect, Resources, Trans, Vec2};
+use crate::{
+    triangulate, Col, Color, Event, Fonts, Gesture, GlyphSize, GlyphSpec, MouseButton, Rect,
+    Resources, Trans, Vec2,
+};
 
 pub use index::PrimitiveType;
 
@@ -31,6 +34,22 @@ pub struct Camera {
     pub center: Vec2,
     /// The zoom factor
     pub zoom: f32,
+    /**
+    Whether the camera is pixel-perfect
+
+    A pixel-perfect camera snaps its zoom to integer factors (or their reciprocals)
+    and its center to whole world pixels, so thin lines and grids do not shimmer
+    as the camera moves.
+    */
+    pub pixel_perfect: bool,
+    /**
+    The design resolution
+
+    If this is set, the view is letterboxed or pillarboxed to keep the design
+    resolution's aspect ratio, and resizing the window does not change the visible
+    world area. Window space is then measured in design pixels.
+    */
+    pub design_resolution: Option<Vec2>,
     pub(crate) window_size: Vec2,
 }
 
@@ -47,6 +66,105 @@ impl Camera {
     pub fn with_zoom(self, zoom: f32) -> Self {
         Camera { zoom, ..self }
     }
+    /// Set the design resolution
+    pub fn with_design_resolution(self, design_resolution: Option<Vec2>) -> Self {
+        Camera {
+            design_resolution,
+            ..self
+        }
+    }
+    /// Get the size of the screen
+    ///
+    /// This is the design resolution if there is one and the window size otherwise
+    pub fn screen_size(self) -> Vec2 {
+        self.design_resolution.unwrap_or(self.window_size)
+    }
+    /// Get the rectangle in the window, in pixels, that the scene is drawn to
+    pub fn viewport(self) -> Rect {
+        if let Some(design) = self.design_resolution {
+            let scale = self.window_size.div2(design).min_dim();
+            let size = design.mul(scale);
+            Rect::centered(self.window_size.div(2.0), size)
+        } else {
+            Rect::new([0.0; 2], self.window_size)
+        }
+    }
+    /**
+    Get the bars that are not drawn to when letterboxing or pillarboxing
+
+    The rectangles are in window pixels. They are either left and right or top and bottom.
+    Both have zero area if there is no design resolution.
+    */
+    pub fn bars(self) -> [Rect; 2] {
+        let viewport = self.viewport();
+        if viewport.left() > 0.0 {
+            [
+                Rect::new([0.0; 2], [viewport.left(), self.window_size.y()]),
+                Rect::new(
+                    [viewport.right(), 0.0],
+                    [
+                        self.window_size.x() - viewport.right(),
+                        self.window_size.y(),
+                    ],
+                ),
+            ]
+        } else {
+            [
+                Rect::new([0.0; 2], [self.window_size.x(), viewport.top()]),
+                Rect::new(
+                    [0.0, viewport.bottom()],
+                    [
+                        self.window_size.x(),
+                        self.window_size.y() - viewport.bottom(),
+                    ],
+                ),
+            ]
+        }
+    }
+    pub(crate) fn gl_viewport(self) -> Option<glium::Rect> {
+        self.design_resolution?;
+        let viewport = self.viewport();
+        Some(glium::Rect {
+            left: viewport.left().round() as u32,
+            bottom: (self.window_size.y() - viewport.bottom()).round() as u32,
+            width: viewport.width().round() as u32,
+            height: viewport.height().round() as u32,
+        })
+    }
+    /// Set whether the camera is pixel-perfect
+    pub fn with_pixel_perfect(self, pixel_perfect: bool) -> Self {
+        Camera {
+            pixel_perfect,
+            ..self
+        }
+    }
+    /**
+    Get the camera with its zoom and center snapped to the pixel grid
+
+    This is what is used for rendering when `pixel_perfect` is enabled
+    */
+    pub fn snapped(self) -> Self {
+        let zoom = if self.zoom >= 1.0 {
+            self.zoom.round()
+        } else {
+            1.0 / (1.0 / self.zoom).round()
+        };
+        let half_window = self.screen_size().div(2.0);
+        let offset: Vec2 = self.center.mul(zoom).sub(half_window).map_with(f32::round);
+        let center = offset.add(half_window).div(zoom);
+        Camera {
+            center,
+            zoom,
+            ..self
+        }
+    }
+    fn effective(self) -> Self {
+        if self.pixel_perfect {
+            self.snapped()
+        } else {
+            self
+        }
+    }
     /// Multiply the zoom by some factor
     pub fn zoom_by(self, by: f32) -> Self {
         Camera {
@@ -70,32 +188,213 @@ impl Camera {
     }
     /// Convert a vector from window space to world space
     pub fn pos_to_coords(self, pos: Vec2) -> Vec2 {
-        pos.sub(self.window_size.div(2.0))
-            .div(self.zoom)
-            .add(self.center)
+        let cam = self.effective();
+        let viewport = cam.viewport();
+        let screen_size = cam.screen_size();
+        pos.sub(viewport.top_left())
+            .mul2(screen_size.div2(viewport.size()))
+            .sub(screen_size.div(2.0))
+            .div(cam.zoom)
+            .add(cam.center)
     }
     /// Convert a vector frrom world space to window space
     pub fn coords_to_pos(self, coords: Vec2) -> Vec2 {
+        let cam = self.effective();
+        let viewport = cam.viewport();
+        let screen_size = cam.screen_size();
         coords
-            .sub(self.center)
-            .div(2.0)
-            .mul(self.zoom)
-            .add(self.window_size.div(2.0))
+            .sub(cam.center)
+            .mul(cam.zoom)
+            .add(screen_size.div(2.0))
+            .mul2(viewport.size().div2(screen_size))
+            .add(viewport.top_left())
     }
     /// Get the rectangle that bounds the view
     pub fn view_rect(self) -> Rect {
-        Rect::centered(self.center, self.window_size.div(self.zoom))
+        let cam = self.effective();
+        Rect::centered(cam.center, cam.screen_size().div(cam.zoom))
+    }
+    /// Check if a point in world space is in view
+    pub fn sees_point(self, point: Vec2) -> bool {
+        self.view_rect().contains(point)
+    }
+    /// Check if any part of a rectangle in world space is in view
+    pub fn sees_rect<E>(self, rect: E) -> bool
+    where
+        E: Rectangle<Scalar = f32>,
+    {
+        let view = self.view_rect();
+        let rect: Rect = rect.map();
+        rect.abs_left() <= view.right()
+            && rect.abs_right() >= view.left()
+            && rect.abs_top() <= view.bottom()
+            && rect.abs_bottom() >= view.top()
+    }
+    /// Check if any part of a circle in world space is in view
+    pub fn sees_circle<E>(self, circ: E) -> bool
+    where
+        E: Circle<Scalar = f32>,
+    {
+        let view = self.view_rect();
+        let center: Vec2 = circ.center().map();
+        let nearest = [
+            center.x().max(view.left()).min(view.right()),
+            center.y().max(view.top()).min(view.bottom()),
+        ];
+        nearest.dist(center) <= circ.radius()
+    }
+    /**
+    Get a camera that frames a rectangle in world space
+
+    The rectangle is centered and zoomed to fit the screen, leaving at least
+    `margin` screen pixels on each side.
+    */
+    pub fn fit_rect<E>(self, rect: E, margin: f32) -> Self
+    where
+        E: Rectangle<Scalar = f32>,
+    {
+        let rect: Rect = rect.map();
+        let available = self.screen_size().sub([margin * 2.0; 2]);
+        let zoom = available.div2(rect.abs_size()).min_dim();
+        Camera {
+            center: rect.center(),
+            zoom: if zoom.is_finite() && zoom > 0.0 {
+                zoom
+            } else {
+                self.zoom
+            },
+            ..self
+        }
+    }
+    /**
+    Zoom the camera while keeping the world point under some window position fixed
+
+    This is useful for zooming toward the mouse cursor
+    */
+    pub fn zoom_at(self, pos: Vec2, by: f32) -> Self {
+        let before = self.pos_to_coords(pos);
+        let zoomed = self.zoom_by(by);
+        let after = zoomed.pos_to_coords(pos);
+        zoomed.translate(before.sub(after))
+    }
+    /**
+    Move the camera so that the scene moves by some offset in window space
+
+    This is useful for dragging the scene with the mouse or a finger
+    */
+    pub fn pan_pixels(self, delta: Vec2) -> Self {
+        let offset = self.pos_to_coords(delta).sub(self.pos_to_coords([0.0; 2]));
+        self.translate(offset.neg())
     }
     fn transform(&self) -> Trans {
-        Trans::new_translate(self.center.neg())
-            .scale([self.zoom; 2].mul2([1.0, -1.0]))
-            .scale::<Vec2>(self.window_size.map_with(|d| 1.0 / d))
+        let cam = self.effective();
+        Trans::new_translate(cam.center.neg())
+            .scale([cam.zoom; 2].mul2([1.0, -1.0]))
+            .scale::<Vec2>(cam.screen_size().map_with(|d| 1.0 / d))
             .zoom(2.0)
     }
 }
 
+/**
+Controls a `Camera` with the mouse and touch input
+
+Scrolling zooms toward the cursor, and pinching and panning with two fingers
+zooms and moves the camera. Optionally, dragging with a mouse button moves the camera.
+
+Pinching, panning, and dragging require `ContextBuilder::gestures` to be enabled.
+*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(
+    feature = "ser",
+    derive(serde_derive::Serialize, serde_derive::Deserialize)
+)]
+pub struct CameraControls {
+    /// The minimum zoom
+    pub min_zoom: f32,
+    /// The maximum zoom
+    pub max_zoom: f32,
+    /// The factor the zoom is multiplied by for each line scrolled, or `1.0` to disable scroll zooming
+    pub scroll_zoom: f32,
+    /// Whether pinching and panning with two fingers is enabled
+    pub touch: bool,
+    /// The mouse button used to drag the camera
+    pub drag_button: Option<MouseButton>,
+}
+
+impl Default for CameraControls {
+    fn default() -> Self {
+        CameraControls::new()
+    }
+}
+
+impl CameraControls {
+    /// Create a new `CameraControls` with default settings
+    pub const fn new() -> Self {
+        CameraControls {
+            min_zoom: 0.01,
+            max_zoom: 100.0,
+            scroll_zoom: 1.1,
+            touch: true,
+            drag_button: None,
+        }
+    }
+    /// Set the zoom limits
+    pub const fn zoom_limits(self, min_zoom: f32, max_zoom: f32) -> Self {
+        CameraControls {
+            min_zoom,
+            max_zoom,
+            ..self
+        }
+    }
+    /// Set the factor the zoom is multiplied by for each line scrolled
+    pub const fn scroll_zoom(self, scroll_zoom: f32) -> Self {
+        CameraControls {
+            scroll_zoom,
+            ..self
+        }
+    }
+    /// Set whether pinching and panning with two fingers is enabled
+    pub const fn touch(self, touch: bool) -> Self {
+        CameraControls { touch, ..self }
+    }
+    /// Set the mouse button used to drag the camera
+    pub const fn drag_button(self, drag_button: Option<MouseButton>) -> Self {
+        CameraControls {
+            drag_button,
+            ..self
+        }
+    }
+    fn zoom_at(&self, camera: Camera, pos: Vec2, by: f32) -> Camera {
+        let target = (camera.zoom * by).max(self.min_zoom).min(self.max_zoom);
+        camera.zoom_at(pos, target / camera.zoom)
+    }
+    /**
+    Apply an event to a camera
+
+    `mouse_pos` should be the position of the mouse cursor in window space.
+    Returns the new camera.
+    */
+    pub fn apply(&self, camera: Camera, event: Event, mouse_pos: Vec2) -> Camera {
+        match event {
+            Event::Scroll([_, y]) if self.scroll_zoom != 1.0 => {
+                self.zoom_at(camera, mouse_pos, self.scroll_zoom.powf(y))
+            }
+            Event::Gesture(Gesture::Pinch { center, scale }) if self.touch => {
+                self.zoom_at(camera, center, scale)
+            }
+            Event::Gesture(Gesture::Pan { delta }) if self.touch => camera.pan_pixels(delta),
+            Event::Gesture(Gesture::DragMove {
+                button: Some(button),
+                delta,
+                ..
+            }) if self.drag_button == Some(button) => camera.pan_pixels(delta),
+            _ => camera,
+        }
+    }
+}
+
 type Vertices = VertexBuffer<Vertex>;
-type Indices = IndexBuffer<u16>;
+type Indices = IndexBuffer<u32>;
 type MeshMap<R> = HashMap<DrawType<R>, (Vertices, Indices)>;
 
 /**
@@ -126,7 +425,7 @@ where
         &self,
         draw_type: DrawType<R>,
         vertices: VertexBuffer<Vertex>,
-        indices: IndexBuffer<u16>,
+        indices: Indices,
     ) {
         self.0.borrow_mut().insert(draw_type, (vertices, indices));
     }
@@ -158,6 +457,14 @@ where
     pub fn clear_all(&self) {
         self.0.borrow_mut().clear();
     }
+    /// Cache a polygon mesh
+    pub(crate) fn insert_polygon<F>(&self, facade: &F, mesh_id: R::MeshId, vertices: &[Vec2])
+    where
+        F: Facade,
+    {
+        let (vertices, indices) = polygon_buffers(facade, vertices);
+        self.insert(DrawType::Irregular(Some(mesh_id)), vertices, indices);
+    }
     /// Move a manually cached mesh
     pub fn remove_mesh(&self, mesh_id: R::MeshId) {
         self.0
@@ -166,6 +473,32 @@ where
     }
 }
 
+/// Create the gpu buffers for a polygon
+fn polygon_buffers<F>(facade: &F, positions: &[Vec2]) -> (Vertices, Indices)
+where
+    F: Facade,
+{
+    let vertices = VertexBuffer::new(
+        facade,
+        &positions
+            .iter()
+            .map(|&pos| Vertex { pos })
+            .collect::<Vec<_>>(),
+    )
+    .unwrap();
+    let indices = IndexBuffer::new(
+        facade,
+        PrimitiveType::TrianglesList,
+        &triangulate(positions)
+            .into_iter()
+            .flatten()
+            .map(|i| i as u32)
+            .collect::<Vec<_>>(),
+    )
+    .unwrap();
+    (vertices, indices)
+}
+
 /// Trait for defining drawing types
 pub trait Canvas {
     /// The gpu facade
@@ -251,6 +584,7 @@ where
     the camera is returned to its original state.
 
     The camera used is one where window space and world space are the same
+    (or design space and world space, if there is a design resolution)
     */
     pub fn with_absolute_camera<F, S>(&mut self, draw: F) -> S
     where
@@ -259,8 +593,53 @@ where
         let base_camera = self.camera;
         self.with_camera(
             |_| Camera {
-                center: base_camera.window_size.div(2.0),
+                center: base_camera.screen_size().div(2.0),
                 zoom: 1.0,
+                pixel_perfect: false,
+                design_resolution: base_camera.design_resolution,
+                window_size: base_camera.window_size,
+            },
+            draw,
+        )
+    }
+    /// Get the gpu facade
+    pub(crate) fn facade(&self) -> &'ctx T::Facade {
+        self.facade
+    }
+    /// Draw gpu buffers that are not in the mesh cache
+    pub(crate) fn draw_buffers<I>(
+        &mut self,
+        vertices: &VertexBuffer<Vertex>,
+        indices: &IndexBuffer<I>,
+        color: Col,
+        transform: Trans,
+    ) where
+        I: index::Index,
+    {
+        let mut draw_params = self.draw_params.clone();
+        if let Some(viewport) = self.camera.gl_viewport() {
+            draw_params.viewport = Some(viewport);
+        }
+        let uniforms = uniform! {
+            transform: extend_transform(transform.then(self.camera.transform())),
+            color: color
+        };
+        self.surface
+            .draw(vertices, indices, self.program, &uniforms, &draw_params)
+            .unwrap();
+    }
+    /// Temporarily use an absolute camera zoomed by the UI scale
+    pub(crate) fn with_ui_camera<F, S>(&mut self, scale: f32, draw: F) -> S
+    where
+        F: FnOnce(&mut Self) -> S,
+    {
+        let base_camera = self.camera;
+        self.with_camera(
+            |_| Camera {
+                center: base_camera.screen_size().div(2.0 * scale),
+                zoom: scale,
+                pixel_perfect: false,
+                design_resolution: base_camera.design_resolution,
                 window_size: base_camera.window_size,
             },
             draw,
@@ -365,24 +744,8 @@ where
         V: Vector2<Scalar = f32> + 'p,
         P: IntoIterator<Item = &'p V>,
     {
-        let vertices = VertexBuffer::new(
-            self.facade,
-            &vertices
-                .into_iter()
-                .map(|v| Vertex { pos: v.map() })
-                .collect::<Vec<_>>(),
-        )
-        .unwrap();
-        let len = vertices.len() as u16;
-        let indices = IndexBuffer::new(
-            self.facade,
-            PrimitiveType::TrianglesList,
-            &(1..(len - 2))
-                .flat_map(|n| once(0).chain(once(n)).chain(once(n + 1)))
-                .chain(once(0).chain(once(len - 2)).chain(once(len - 1)))
-                .collect::<Vec<_>>(),
-        )
-        .unwrap();
+        let vertices: Vec<Vec2> = vertices.into_iter().map(|v| v.map()).collect();
+        let (vertices, indices) = polygon_buffers(self.facade, &vertices);
         self.meshes
             .insert(DrawType::Irregular(mesh_id), vertices, indices);
         Transformable::new(
@@ -392,6 +755,27 @@ where
             Trans::identity(),
         )
     }
+    /**
+    Draw a mesh that is already cached
+
+    Meshes are cached by the `Drawer::cached_*` methods or by `AssetLoader::load_mesh`.
+    Returns `None` if the mesh is not cached.
+    */
+    pub fn mesh<C>(&mut self, color: C, mesh_id: R::MeshId) -> Option<Transformable<'ctx, '_, T, R>>
+    where
+        C: Color,
+    {
+        if self.meshes.contains_mesh(mesh_id) {
+            Some(Transformable::new(
+                self,
+                color.map(),
+                DrawType::Irregular(Some(mesh_id)),
+                Trans::identity(),
+            ))
+        } else {
+            None
+        }
+    }
     /// Draw a line
     pub fn line<C, P>(
         &mut self,
@@ -421,6 +805,68 @@ where
                 .translate(midpoint),
         )
     }
+    /// Draw a line through a series of points, such as a path from `NavGrid::find_path`
+    pub fn polyline<C, I>(&mut self, color: C, points: I, thickness: f32)
+    where
+        C: Color,
+        I: IntoIterator,
+        I::Item: Vector2<Scalar = f32>,
+    {
+        let color: Col = color.map();
+        let mut points = points.into_iter().map(|p| p.map::<Vec2>());
+        if let Some(mut prev) = points.next() {
+            for point in points {
+                self.line(color, [prev, point], thickness);
+                prev = point;
+            }
+        }
+    }
+}
+
+/**
+A cursor made of polygons that is drawn in place of the system cursor
+
+Vertices are in pixels relative to the cursor's hotspot,
+which is placed at the mouse position.
+
+See `Context::set_custom_cursor`
+*/
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CustomCursor {
+    shapes: Vec<(Col, Vec<Vec2>)>,
+}
+
+impl CustomCursor {
+    /// Create a new empty `CustomCursor`
+    pub fn new() -> Self {
+        CustomCursor::default()
+    }
+    /// Add a polygon to the cursor
+    pub fn polygon<'p, C, V, P>(mut self, color: C, vertices: P) -> Self
+    where
+        C: Color,
+        V: Vector2<Scalar = f32> + 'p,
+        P: IntoIterator<Item = &'p V>,
+    {
+        self.shapes
+            .push((color.map(), vertices.into_iter().map(|v| v.map()).collect()));
+        self
+    }
+}
+
+impl<'ctx, T, R> Drawer<'ctx, T, R>
+where
+    T: Canvas,
+    R: Resources,
+{
+    pub(crate) fn custom_cursor(&mut self, cursor: &CustomCursor, mouse_pos: Vec2) {
+        self.with_absolute_camera(|drawer| {
+            let pos = drawer.camera.pos_to_coords(mouse_pos);
+            for (color, vertices) in &cursor.shapes {
+                drawer.polygon(*color, vertices).translate(pos);
+            }
+        })
+    }
 }
 
 /// Parameters for drawing rounded lines
@@ -622,11 +1068,7 @@ impl<R> DrawType<R>
 where
     R: Resources,
 {
-    fn vertices_indices<F>(
-        self,
-        facade: &F,
-        fonts: &Fonts<R::FontId>,
-    ) -> (VertexBuffer<Vertex>, IndexBuffer<u16>)
+    fn vertices_indices<F>(self, facade: &F, fonts: &Fonts<R::FontId>) -> (Vertices, Indices)
     where
         F: Facade,
     {
@@ -641,7 +1083,8 @@ where
                     .map(|i| (i as f32 / n as f32 * f32::TAU + angle_offset).angle_as_vector())
                     .map(|pos| Vertex { pos })
                     .collect();
-                let indices: Vec<u16> = (1..(n - 2))
+                let n = n as u32;
+                let indices: Vec<u32> = (1..(n - 2))
                     .flat_map(|n| once(0).chain(once(n)).chain(once(n + 1)))
                     .chain(once(0).chain(once(n - 2)).chain(once(n - 1)))
                     .collect();
@@ -668,9 +1111,9 @@ where
                         .collect::<Vec<_>>(),
                 )
                 .unwrap();
+                let indices: Vec<u32> = geometry.indices.iter().map(|&i| i as u32).collect();
                 let indices =
-                    IndexBuffer::new(facade, PrimitiveType::TrianglesList, &geometry.indices)
-                        .unwrap();
+                    IndexBuffer::new(facade, PrimitiveType::TrianglesList, &indices).unwrap();
                 (vertices, indices)
             }
         }
@@ -814,6 +1257,11 @@ where
     */
     pub fn draw(&mut self) {
         let camera_transform = self.drawer.camera.transform();
+        let mut draw_params = self.drawer.draw_params.clone();
+        if let Some(viewport) = self.drawer.camera.gl_viewport() {
+            draw_params.viewport = Some(viewport);
+        }
+        let draw_params = &draw_params;
         for item in self.items.iter() {
             let Drawer {
                 meshes,
@@ -821,7 +1269,6 @@ where
                 fonts,
                 surface,
                 program,
-                draw_params,
                 ..
             } = &mut self.drawer;
             if !meshes.contains(&item.ty) {
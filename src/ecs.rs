@@ -0,0 +1,381 @@
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+};
+
+/**
+An id for an entity in a `World`
+
+Entity ids are generational. Once an entity is despawned, its id
+never refers to another entity, even if its slot is reused.
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "ser",
+    derive(serde_derive::Serialize, serde_derive::Deserialize)
+)]
+pub struct Entity {
+    index: u32,
+    generation: u32,
+}
+
+impl Entity {
+    /// Get the index of the entity's slot
+    pub fn index(self) -> u32 {
+        self.index
+    }
+    /// Get the entity's generation
+    pub fn generation(self) -> u32 {
+        self.generation
+    }
+}
+
+trait Storage: Any {
+    fn remove_index(&mut self, index: u32);
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+struct Components<T> {
+    slots: Vec<Option<T>>,
+}
+
+impl<T> Components<T> {
+    fn get(&self, index: u32) -> Option<&T> {
+        self.slots.get(index as usize).and_then(Option::as_ref)
+    }
+    fn get_mut(&mut self, index: u32) -> Option<&mut T> {
+        self.slots.get_mut(index as usize).and_then(Option::as_mut)
+    }
+}
+
+impl<T> Storage for Components<T>
+where
+    T: 'static,
+{
+    fn remove_index(&mut self, index: u32) {
+        if let Some(slot) = self.slots.get_mut(index as usize) {
+            *slot = None;
+        }
+    }
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/**
+A lightweight store of entities and their components
+
+Any `'static` type can be a component. Each entity has at most one component of each type.
+
+The context has a `World` that is maintained by the engine. Entities queued for
+despawning with `World::queue_despawn` are despawned after each update, which makes
+it safe to queue despawns while iterating over a query.
+
+```
+# use kule::*;
+struct Pos(Vec2);
+struct Vel(Vec2);
+
+let mut world = World::default();
+let e = world.spawn();
+world.insert(e, Pos([0.0, 0.0]));
+world.insert(e, Vel([1.0, 2.0]));
+for (_, pos, vel) in world.query2_mut::<Pos, Vel>() {
+    pos.0 = pos.0.add(vel.0);
+}
+assert_eq!(world.get::<Pos>(e).unwrap().0, [1.0, 2.0]);
+world.despawn(e);
+assert!(world.get::<Pos>(e).is_none());
+```
+*/
+#[derive(Default)]
+pub struct World {
+    generations: Vec<u32>,
+    alive: Vec<bool>,
+    free: Vec<u32>,
+    storages: HashMap<TypeId, Box<dyn Storage>>,
+    despawn_queue: Vec<Entity>,
+}
+
+impl World {
+    /// Create a new empty `World`
+    pub fn new() -> Self {
+        World::default()
+    }
+    /// Spawn a new entity with no components
+    pub fn spawn(&mut self) -> Entity {
+        if let Some(index) = self.free.pop() {
+            self.alive[index as usize] = true;
+            Entity {
+                index,
+                generation: self.generations[index as usize],
+            }
+        } else {
+            let index = self.generations.len() as u32;
+            self.generations.push(0);
+            self.alive.push(true);
+            Entity {
+                index,
+                generation: 0,
+            }
+        }
+    }
+    /// Check if an entity has not been despawned
+    pub fn is_alive(&self, entity: Entity) -> bool {
+        let i = entity.index as usize;
+        i < self.alive.len() && self.alive[i] && self.generations[i] == entity.generation
+    }
+    /**
+    Despawn an entity and remove all of its components
+
+    Returns `false` if the entity was already despawned
+    */
+    pub fn despawn(&mut self, entity: Entity) -> bool {
+        if !self.is_alive(entity) {
+            return false;
+        }
+        let i = entity.index as usize;
+        self.alive[i] = false;
+        self.generations[i] = self.generations[i].wrapping_add(1);
+        self.free.push(entity.index);
+        for storage in self.storages.values_mut() {
+            storage.remove_index(entity.index);
+        }
+        true
+    }
+    /// Queue an entity to be despawned by `World::maintain`
+    pub fn queue_despawn(&mut self, entity: Entity) {
+        self.despawn_queue.push(entity);
+    }
+    /// Despawn entities queued with `World::queue_despawn`
+    ///
+    /// This is called by the engine after each update
+    pub fn maintain(&mut self) {
+        for entity in std::mem::take(&mut self.despawn_queue) {
+            self.despawn(entity);
+        }
+    }
+    /// Despawn all entities
+    pub fn clear(&mut self) {
+        for entity in self.entities().collect::<Vec<_>>() {
+            self.despawn(entity);
+        }
+    }
+    /// Get the number of living entities
+    pub fn len(&self) -> usize {
+        self.alive.len() - self.free.len()
+    }
+    /// Check if there are no living entities
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// Iterate over all living entities
+    pub fn entities(&self) -> impl Iterator<Item = Entity> + '_ {
+        self.alive
+            .iter()
+            .zip(&self.generations)
+            .enumerate()
+            .filter(|(_, (alive, _))| **alive)
+            .map(|(index, (_, &generation))| Entity {
+                index: index as u32,
+                generation,
+            })
+    }
+    fn storage<T>(&self) -> Option<&Components<T>>
+    where
+        T: 'static,
+    {
+        self.storages
+            .get(&TypeId::of::<T>())
+            .and_then(|storage| storage.as_any().downcast_ref())
+    }
+    fn storage_mut<T>(&mut self) -> Option<&mut Components<T>>
+    where
+        T: 'static,
+    {
+        self.storages
+            .get_mut(&TypeId::of::<T>())
+            .and_then(|storage| storage.as_any_mut().downcast_mut())
+    }
+    /**
+    Add a component to an entity, replacing any component of the same type
+
+    Returns the replaced component.
+
+    # Panics
+
+    Panics if the entity has been despawned
+    */
+    pub fn insert<T>(&mut self, entity: Entity, component: T) -> Option<T>
+    where
+        T: 'static,
+    {
+        assert!(self.is_alive(entity), "{:?} has been despawned", entity);
+        let storage = self
+            .storages
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(Components::<T> { slots: Vec::new() }))
+            .as_any_mut()
+            .downcast_mut::<Components<T>>()
+            .unwrap();
+        let i = entity.index as usize;
+        if storage.slots.len() <= i {
+            storage.slots.resize_with(i + 1, || None);
+        }
+        storage.slots[i].replace(component)
+    }
+    /// Remove a component from an entity
+    pub fn remove<T>(&mut self, entity: Entity) -> Option<T>
+    where
+        T: 'static,
+    {
+        if !self.is_alive(entity) {
+            return None;
+        }
+        self.storage_mut::<T>()?
+            .slots
+            .get_mut(entity.index as usize)?
+            .take()
+    }
+    /// Get an entity's component
+    pub fn get<T>(&self, entity: Entity) -> Option<&T>
+    where
+        T: 'static,
+    {
+        if !self.is_alive(entity) {
+            return None;
+        }
+        self.storage::<T>()?.get(entity.index)
+    }
+    /// Get an entity's component mutably
+    pub fn get_mut<T>(&mut self, entity: Entity) -> Option<&mut T>
+    where
+        T: 'static,
+    {
+        if !self.is_alive(entity) {
+            return None;
+        }
+        self.storage_mut::<T>()?.get_mut(entity.index)
+    }
+    /// Check if an entity has a component
+    pub fn has<T>(&self, entity: Entity) -> bool
+    where
+        T: 'static,
+    {
+        self.get::<T>(entity).is_some()
+    }
+    /// Iterate over all entities with a component
+    pub fn query<T>(&self) -> impl Iterator<Item = (Entity, &T)>
+    where
+        T: 'static,
+    {
+        let generations = &self.generations;
+        self.storage::<T>()
+            .into_iter()
+            .flat_map(|storage| storage.slots.iter().enumerate())
+            .filter_map(move |(i, slot)| {
+                slot.as_ref().map(|component| {
+                    let entity = Entity {
+                        index: i as u32,
+                        generation: generations[i],
+                    };
+                    (entity, component)
+                })
+            })
+    }
+    /// Iterate mutably over all entities with a component
+    pub fn query_mut<T>(&mut self) -> impl Iterator<Item = (Entity, &mut T)>
+    where
+        T: 'static,
+    {
+        let generations = &self.generations;
+        self.storages
+            .get_mut(&TypeId::of::<T>())
+            .and_then(|storage| storage.as_any_mut().downcast_mut::<Components<T>>())
+            .into_iter()
+            .flat_map(|storage| storage.slots.iter_mut().enumerate())
+            .filter_map(move |(i, slot)| {
+                slot.as_mut().map(|component| {
+                    let entity = Entity {
+                        index: i as u32,
+                        generation: generations[i],
+                    };
+                    (entity, component)
+                })
+            })
+    }
+    /// Iterate over all entities with both of two components
+    pub fn query2<A, B>(&self) -> impl Iterator<Item = (Entity, &A, &B)>
+    where
+        A: 'static,
+        B: 'static,
+    {
+        let b = self.storage::<B>();
+        self.query::<A>().filter_map(move |(entity, a)| {
+            b.and_then(|b| b.get(entity.index)).map(|b| (entity, a, b))
+        })
+    }
+    /**
+    Iterate over all entities with both of two components,
+    where the first component is mutable
+
+    # Panics
+
+    Panics if `A` and `B` are the same type
+    */
+    pub fn query2_mut<A, B>(&mut self) -> impl Iterator<Item = (Entity, &mut A, &B)>
+    where
+        A: 'static,
+        B: 'static,
+    {
+        assert_ne!(
+            TypeId::of::<A>(),
+            TypeId::of::<B>(),
+            "query2_mut requires two different component types"
+        );
+        let generations = &self.generations;
+        let (mut a, mut b) = (None, None);
+        for (id, storage) in &mut self.storages {
+            if *id == TypeId::of::<A>() {
+                a = Some(storage);
+            } else if *id == TypeId::of::<B>() {
+                b = Some(storage);
+            }
+        }
+        let a = a.and_then(|a| a.as_any_mut().downcast_mut::<Components<A>>());
+        let b = b.and_then(|b| b.as_any().downcast_ref::<Components<B>>());
+        a.zip(b)
+            .into_iter()
+            .flat_map(|(a, b)| a.slots.iter_mut().enumerate().map(move |slot| (slot, b)))
+            .filter_map(move |((i, slot), b)| {
+                let a = slot.as_mut()?;
+                let b = b.get(i as u32)?;
+                let entity = Entity {
+                    index: i as u32,
+                    generation: generations[i],
+                };
+                Some((entity, a, b))
+            })
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn world_generations() {
+    let mut world = World::new();
+    let a = world.spawn();
+    world.insert(a, 5u32);
+    world.queue_despawn(a);
+    assert!(world.is_alive(a));
+    world.maintain();
+    let b = world.spawn();
+    assert_eq!(a.index(), b.index());
+    assert_ne!(a, b);
+    assert!(!world.is_alive(a));
+    assert_eq!(world.get::<u32>(b), None);
+    assert_eq!(world.len(), 1);
+}
@@ -0,0 +1,321 @@
+use std::{
+    f32::consts::PI,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use crate::Source;
+
+/// An audio effect
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Effect {
+    /// A low-pass filter that attenuates frequencies above the cutoff
+    LowPass {
+        /// The cutoff frequency in Hz
+        cutoff: f32,
+    },
+    /// An echo that repeats the sound after a delay
+    Echo {
+        /// The time between repeats
+        delay: Duration,
+        /// How much of each repeat is fed into the next one, from `0.0` to `1.0`
+        feedback: f32,
+        /// How much of the echo is mixed into the output, from `0.0` to `1.0`
+        mix: f32,
+    },
+    /// A simple reverb
+    Reverb {
+        /// The size of the simulated room, from `0.0` to `1.0`
+        room_size: f32,
+        /// How quickly high frequencies die out, from `0.0` to `1.0`
+        damping: f32,
+        /// How much of the reverb is mixed into the output, from `0.0` to `1.0`
+        mix: f32,
+    },
+}
+
+impl Effect {
+    fn same_kind(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Effect::LowPass { .. }, Effect::LowPass { .. }) => true,
+            (Effect::Echo { delay: a, .. }, Effect::Echo { delay: b, .. }) => a == b,
+            (Effect::Reverb { .. }, Effect::Reverb { .. }) => true,
+            _ => false,
+        }
+    }
+}
+
+/**
+A chain of audio effects
+
+Effects are applied in order. The chain can be modified at any time,
+and changes take effect immediately on the audio thread.
+
+Effect chains are cheap to clone, and clones refer to the same chain.
+*/
+#[derive(Debug, Clone, Default)]
+pub struct EffectChain {
+    effects: Arc<Mutex<Vec<Effect>>>,
+    version: Arc<AtomicUsize>,
+}
+
+impl EffectChain {
+    fn modify<F, T>(&self, f: F) -> T
+    where
+        F: FnOnce(&mut Vec<Effect>) -> T,
+    {
+        let res = f(&mut self.effects.lock().unwrap());
+        self.version.fetch_add(1, Ordering::Release);
+        res
+    }
+    /// Add an effect to the end of the chain and get its index
+    pub fn push(&self, effect: Effect) -> usize {
+        self.modify(|effects| {
+            effects.push(effect);
+            effects.len() - 1
+        })
+    }
+    /**
+    Replace the effect at some index
+
+    If the new effect is of the same kind as the old one, its state is kept,
+    so parameters can be adjusted smoothly.
+
+    # Panics
+
+    Panics if the index is out of bounds
+    */
+    pub fn set(&self, index: usize, effect: Effect) {
+        self.modify(|effects| effects[index] = effect)
+    }
+    /// Get the effect at some index
+    pub fn get(&self, index: usize) -> Option<Effect> {
+        self.effects.lock().unwrap().get(index).copied()
+    }
+    /**
+    Remove the effect at some index
+
+    # Panics
+
+    Panics if the index is out of bounds
+    */
+    pub fn remove(&self, index: usize) -> Effect {
+        self.modify(|effects| effects.remove(index))
+    }
+    /// Remove all effects
+    pub fn clear(&self) {
+        self.modify(Vec::clear)
+    }
+    /// Get the number of effects in the chain
+    pub fn len(&self) -> usize {
+        self.effects.lock().unwrap().len()
+    }
+    /// Check if the chain has no effects
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    pub(crate) fn apply<S>(&self, source: S) -> EffectSource<S> {
+        EffectSource {
+            source,
+            chain: self.clone(),
+            version: usize::MAX,
+            processors: Vec::new(),
+            channel: 0,
+        }
+    }
+}
+
+pub(crate) struct EffectSource<S> {
+    source: S,
+    chain: EffectChain,
+    version: usize,
+    processors: Vec<Processor>,
+    channel: usize,
+}
+
+impl<S> EffectSource<S> {
+    fn sync(&mut self) {
+        let version = self.chain.version.load(Ordering::Acquire);
+        if version == self.version {
+            return;
+        }
+        self.version = version;
+        let effects = self.chain.effects.lock().unwrap();
+        self.processors.truncate(effects.len());
+        for (i, effect) in effects.iter().enumerate() {
+            if let Some(processor) = self.processors.get_mut(i) {
+                if processor.effect.same_kind(effect) {
+                    processor.effect = *effect;
+                } else {
+                    *processor = Processor::new(*effect);
+                }
+            } else {
+                self.processors.push(Processor::new(*effect));
+            }
+        }
+    }
+}
+
+impl<S> Iterator for EffectSource<S>
+where
+    S: Source<Item = f32>,
+{
+    type Item = f32;
+    fn next(&mut self) -> Option<Self::Item> {
+        let sample = self.source.next()?;
+        self.sync();
+        let channels = self.source.channels().max(1) as usize;
+        let sample_rate = self.source.sample_rate() as f32;
+        let channel = self.channel % channels;
+        self.channel = (channel + 1) % channels;
+        Some(
+            self.processors
+                .iter_mut()
+                .fold(sample, |sample, processor| {
+                    processor.process(sample, channel, sample_rate)
+                }),
+        )
+    }
+}
+
+impl<S> Source for EffectSource<S>
+where
+    S: Source<Item = f32>,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        self.source.current_frame_len()
+    }
+    fn channels(&self) -> u16 {
+        self.source.channels()
+    }
+    fn sample_rate(&self) -> u32 {
+        self.source.sample_rate()
+    }
+    fn total_duration(&self) -> Option<Duration> {
+        self.source.total_duration()
+    }
+}
+
+struct Processor {
+    effect: Effect,
+    states: Vec<ChannelState>,
+}
+
+enum ChannelState {
+    LowPass(f32),
+    Echo(DelayLine),
+    Reverb(Box<ReverbState>),
+}
+
+impl Processor {
+    fn new(effect: Effect) -> Self {
+        Processor {
+            effect,
+            states: Vec::new(),
+        }
+    }
+    fn process(&mut self, sample: f32, channel: usize, sample_rate: f32) -> f32 {
+        while self.states.len() <= channel {
+            self.states.push(match self.effect {
+                Effect::LowPass { .. } => ChannelState::LowPass(0.0),
+                Effect::Echo { delay, .. } => ChannelState::Echo(DelayLine::new(
+                    (delay.as_secs_f32() * sample_rate).max(1.0) as usize,
+                )),
+                Effect::Reverb { .. } => {
+                    ChannelState::Reverb(Box::new(ReverbState::new(sample_rate)))
+                }
+            });
+        }
+        match (self.effect, &mut self.states[channel]) {
+            (Effect::LowPass { cutoff }, ChannelState::LowPass(prev)) => {
+                let rc = 1.0 / (2.0 * PI * cutoff.max(1.0));
+                let dt = 1.0 / sample_rate;
+                *prev += (sample - *prev) * dt / (rc + dt);
+                *prev
+            }
+            (Effect::Echo { feedback, mix, .. }, ChannelState::Echo(line)) => {
+                let delayed = line.read();
+                line.write(sample + delayed * feedback.min(0.99));
+                sample + delayed * mix
+            }
+            (
+                Effect::Reverb {
+                    room_size,
+                    damping,
+                    mix,
+                },
+                ChannelState::Reverb(reverb),
+            ) => {
+                let wet = reverb.process(sample, room_size, damping);
+                sample * (1.0 - mix) + wet * mix
+            }
+            _ => sample,
+        }
+    }
+}
+
+struct DelayLine {
+    buffer: Vec<f32>,
+    i: usize,
+}
+
+impl DelayLine {
+    fn new(len: usize) -> Self {
+        DelayLine {
+            buffer: vec![0.0; len],
+            i: 0,
+        }
+    }
+    fn read(&self) -> f32 {
+        self.buffer[self.i]
+    }
+    fn write(&mut self, sample: f32) {
+        self.buffer[self.i] = sample;
+        self.i = (self.i + 1) % self.buffer.len();
+    }
+}
+
+const COMB_TUNINGS: [usize; 4] = [1116, 1188, 1277, 1356];
+const ALLPASS_TUNINGS: [usize; 2] = [556, 441];
+
+struct ReverbState {
+    combs: Vec<(DelayLine, f32)>,
+    allpasses: Vec<DelayLine>,
+}
+
+impl ReverbState {
+    fn new(sample_rate: f32) -> Self {
+        let scale = |len: usize| (len as f32 * sample_rate / 44100.0).max(1.0) as usize;
+        ReverbState {
+            combs: COMB_TUNINGS
+                .iter()
+                .map(|&len| (DelayLine::new(scale(len)), 0.0))
+                .collect(),
+            allpasses: ALLPASS_TUNINGS
+                .iter()
+                .map(|&len| DelayLine::new(scale(len)))
+                .collect(),
+        }
+    }
+    fn process(&mut self, sample: f32, room_size: f32, damping: f32) -> f32 {
+        let feedback = room_size.clamp(0.0, 1.0) * 0.28 + 0.7;
+        let damping = damping.clamp(0.0, 1.0) * 0.4;
+        let input = sample * 0.015;
+        let mut out = 0.0;
+        for (line, filter) in &mut self.combs {
+            let delayed = line.read();
+            *filter = delayed * (1.0 - damping) + *filter * damping;
+            line.write(input + *filter * feedback);
+            out += delayed;
+        }
+        for line in &mut self.allpasses {
+            let delayed = line.read();
+            line.write(out + delayed * 0.5);
+            out = delayed - out;
+        }
+        out * 3.0
+    }
+}
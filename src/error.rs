@@ -1,6 +1,6 @@
 use std::error::Error;
 
-use crate::{Context, Kule};
+use crate::{Context, Kule, Resources};
 
 /// A kule error type
 #[derive(Debug, thiserror::Error)]
@@ -17,18 +17,63 @@ pub enum KuleError {
     /// Error creating the display
     #[error("{0}")]
     DisplayCreation(#[from] glium::backend::glutin::DisplayCreationError),
+    /// The OpenGL context of an external window is not supported
+    #[error("{0}")]
+    IncompatibleOpenGl(#[from] glium::IncompatibleOpenGl),
     /// Bad window icon data
     #[error("{0}")]
     BadIcon(#[from] glium::glutin::window::BadIcon),
+    #[cfg(feature = "icon")]
+    /// An image decoding error
+    #[error("{0}")]
+    Image(#[from] image::ImageError),
     #[cfg(feature = "sound")]
     /// Audio decode error
     #[error("{0}")]
     AudioDecode(#[from] rodio::decoder::DecoderError),
-    #[cfg(feature = "script")]
+    #[cfg(feature = "sound")]
+    /// Audio device error
+    #[error("Audio device error: {0}")]
+    AudioDevice(String),
+    #[cfg(feature = "sound")]
+    /// An error from loading a sound on a background thread
+    #[error("Failed to load sound: {0}")]
+    SoundLoad(String),
+    /// An error from a system run by `Systems`
+    #[error("System {0:?} failed: {1}")]
+    System(String, String),
+    /// An error from loading an asset on a background thread
+    #[error("Failed to load asset: {0}")]
+    AssetLoad(String),
+    #[cfg(feature = "save")]
+    /// A save file serialization error
+    #[error("{0}")]
+    Save(#[from] bincode::Error),
+    #[cfg(feature = "save")]
+    /// A save slot name cannot be used as a file name
+    #[error("Invalid save slot name {0:?}")]
+    InvalidSlot(String),
+    #[cfg(feature = "save")]
+    /// A save file is malformed
+    #[error("Invalid save file: {0}")]
+    InvalidSave(&'static str),
+    #[cfg(feature = "save")]
+    /// A thumbnail's pixel data does not match its size
+    #[error("Thumbnail has {0} bytes of pixels but its size needs {1}")]
+    InvalidThumbnail(usize, usize),
+    #[cfg(feature = "save")]
+    /// A save was written by a newer version of the app
+    #[error("Save version {0} is newer than the supported version {1}")]
+    SaveTooNew(u32, u32),
+    #[cfg(feature = "save")]
+    /// A save is from a version that has no migration to the next version
+    #[error("No migration from save version {0}")]
+    MissingMigration(u32),
+    #[cfg(feature = "ser")]
     /// A toml serialization error
     #[error("{0}")]
     TomlSerialize(#[from] toml::ser::Error),
-    #[cfg(feature = "script")]
+    #[cfg(feature = "ser")]
     /// A toml deserialization error
     #[error("{0}")]
     TomlDeserialize(#[from] toml::de::Error),
@@ -41,6 +86,44 @@ pub enum KuleError {
     #[error("{0}")]
     LuaSerialization(#[from] crate::LuaSerializeError),
     #[cfg(feature = "script")]
+    /// A lua deserialization error
+    #[error("{0}")]
+    LuaDeserialization(#[from] crate::LuaDeserializeError),
+    #[cfg(feature = "script")]
+    /// An error from calling a script module's method
+    #[error("Error in script method {module}.{method}: {traceback}")]
+    Script {
+        /// The name of the module
+        module: String,
+        /// The name of the method
+        method: String,
+        /// The error message and Lua stack traceback
+        traceback: String,
+    },
+    #[cfg(feature = "script")]
+    /// A script module was disabled because it failed too many batch calls in a row
+    #[error("Script module {0:?} was disabled because it failed too many times in a row")]
+    ScriptDisabled(String),
+    #[cfg(feature = "script")]
+    /// A script module's setting is invalid
+    #[error("Invalid setting {setting:?} for module {module:?}: {reason}")]
+    InvalidSetting {
+        /// The name of the module
+        module: String,
+        /// The name of the setting
+        setting: String,
+        /// Why the setting is invalid
+        reason: String,
+    },
+    #[cfg(feature = "script")]
+    /// The script config file's extension does not match any of the `ScriptEnv`'s formats
+    #[error("No script config format for the extension {0:?}")]
+    UnknownConfigFormat(String),
+    #[cfg(feature = "script")]
+    /// The script config file could not be parsed
+    #[error("Invalid script config file: {0}")]
+    InvalidConfig(String),
+    #[cfg(feature = "script")]
     /// A scripting enevironment initialization error
     #[error("The scripting environment failed to initialize: {0}")]
     ScriptInitialization(String),
@@ -63,6 +146,44 @@ impl KuleError {
     }
 }
 
+/**
+How errors returned from app methods are handled
+
+This is used by the default implementation of `Kule::handle_error`.
+It is set with `ContextBuilder::error_policy` and `Context::set_error_policy`.
+*/
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "ser",
+    derive(serde_derive::Serialize, serde_derive::Deserialize)
+)]
+pub enum ErrorPolicy {
+    /// Panic with the error message
+    #[default]
+    Panic,
+    /// Print the error to stderr and keep running
+    LogAndContinue,
+    /// Print the error to stderr and close the app with an exit code
+    Exit(i32),
+}
+
+impl ErrorPolicy {
+    /// Handle an error according to this policy
+    pub fn handle<R>(self, error: KuleError, ctx: &mut Context<R>)
+    where
+        R: Resources,
+    {
+        match self {
+            ErrorPolicy::Panic => panic!("{}", error),
+            ErrorPolicy::LogAndContinue => eprintln!("{}", error),
+            ErrorPolicy::Exit(code) => {
+                eprintln!("{}", error);
+                ctx.request_exit(code);
+            }
+        }
+    }
+}
+
 /// A kule result type
 pub type KuleResult<T> = std::result::Result<T, KuleError>;
 
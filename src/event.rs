@@ -1,13 +1,21 @@
-use std::collections::HashSet;
+use std::{
+    any::Any,
+    collections::{HashMap, HashSet},
+    time::{Duration, Instant},
+};
 
-use glutin::event::{self, *};
+use glutin::{
+    event::{self, *},
+    event_loop::EventLoopProxy,
+};
 use vector2math::*;
 
-use crate::{Camera, Vec2};
+use crate::{Axis, Camera, Gesture, Vec2};
 
 pub use event::ElementState as ButtonState;
 pub use event::ModifiersState as Modifiers;
 pub use event::MouseButton;
+pub use event::TouchPhase;
 
 /// An input event
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -36,62 +44,248 @@ pub enum Event {
         /// The new state
         state: ButtonState,
     },
+    /**
+    A held key has repeated
+
+    By default, these come from the operating system's key repeat.
+    If `ContextBuilder::key_repeat` is set, they are generated by the engine instead.
+    */
+    KeyRepeat {
+        /// The key
+        key: Key,
+        /// The scancode
+        scancode: u32,
+    },
+    /**
+    A character was typed
+
+    This takes the keyboard layout, dead keys, and input methods into account,
+    so it should be used for text input rather than `Event::Key`.
+
+    Control characters such as backspace are also sent.
+    */
+    Char(char),
     /// The window was resized
     Resize(Vec2),
     /// The window was moved
     Move(Vec2),
     /// The window has gained or lost focus
     Focus(bool),
+    /**
+    The window was minimized or restored
+
+    Some platforms do not report minimization
+    */
+    Minimize(bool),
+    /**
+    The app was suspended or resumed
+
+    This is only sent on mobile platforms
+    */
+    Suspend(bool),
     /// The mouse wheel was scrolled
     Scroll(Vec2),
+    /// A touch has started, moved, or ended
+    Touch {
+        /// The id of the touch, which is unique while the touch lasts
+        id: u64,
+        /// The phase of the touch
+        phase: TouchPhase,
+        /// The position of the touch in window space
+        pos: Vec2,
+    },
+    /**
+    A pen or stylus has touched, moved on, or left the screen
+
+    This is sent after the corresponding `Event::Touch` on platforms that
+    report touch pressure (currently iOS and Windows).
+    */
+    Pen {
+        /// The id of the touch, which is unique while the touch lasts
+        id: u64,
+        /// The phase of the touch
+        phase: TouchPhase,
+        /// The position of the pen in window space
+        pos: Vec2,
+        /// The pressure from `0.0` to `1.0`
+        pressure: f32,
+        /// The angle in radians between the pen and the screen, if it is known
+        ///
+        /// `0.0` means the pen is parallel to the screen and `PI / 2` means it is perpendicular.
+        tilt: Option<f32>,
+    },
+    /**
+    A gesture was recognized
+
+    Gestures are only recognized if `ContextBuilder::gestures` is enabled
+    */
+    Gesture(Gesture),
+    /**
+    A combo was matched
+
+    The value is the combo's id. Combos are added to `Context::combos`.
+    */
+    Combo(u32),
+    /**
+    Keyboard focus moved to a different UI widget
+
+    The value is the id of the newly focused widget. Widgets are added to `Context::focus`.
+    */
+    FocusChanged(Option<u32>),
+    /// The focused UI widget was activated with `Enter` or `Space`
+    ///
+    /// The value is the widget's id
+    Activate(u32),
+    /**
+    A watched asset changed and was reloaded
+
+    The value is the id returned when the path was added to `Context::watcher`.
+    */
+    AssetChanged(crate::WatchId),
+    /**
+    An asset finished loading in the background
+
+    The value is the id returned when the load was queued with `Context::loader`.
+    */
+    AssetLoaded(crate::LoadId),
+    /// All assets queued with `Context::loader` finished loading
+    LoadingFinished,
+    /**
+    An event was sent with an `EventSender`
+
+    The payload can be retrieved with `Context::take_user_event`
+    */
+    User(UserEventId),
     /// The window was requested to close
     CloseRequest,
+    #[cfg(feature = "sound")]
+    /// A sound has finished playing or was stopped
+    ///
+    /// The value is the id of the sound's `SoundHandle`
+    SoundFinished(u64),
+    #[cfg(feature = "sound")]
+    /// The current music has reached a beat
+    ///
+    /// The music must have a `BeatClock`
+    Beat {
+        /// The beat number
+        beat: u64,
+        /// The bar number
+        bar: u64,
+    },
+    #[cfg(feature = "script")]
+    /// The scripts finished reloading after they changed
+    ScriptsReloaded,
 }
 
 impl Event {
-    pub(crate) fn from_glutin(
-        event: event::Event<()>,
+    pub(crate) fn from_glutin<T>(
+        event: event::Event<T>,
         tracker: &mut StateTracker,
         camera: &mut Camera,
     ) -> Two<Self> {
-        let window_event = if let event::Event::WindowEvent { event, .. } = event {
-            event
-        } else {
-            return Two::none();
+        let window_event = match event {
+            event::Event::WindowEvent { event, .. } => event,
+            event::Event::Suspended => {
+                tracker.suspended = true;
+                return Event::Suspend(true).into();
+            }
+            event::Event::Resumed if tracker.suspended => {
+                tracker.suspended = false;
+                return Event::Suspend(false).into();
+            }
+            _ => return Two::none(),
         };
         match window_event {
             WindowEvent::CloseRequested => Event::CloseRequest.into(),
+            // Minimized windows are resized to zero on some platforms
+            WindowEvent::Resized(size) if size.width == 0 || size.height == 0 => {
+                if tracker.minimized {
+                    Two::none()
+                } else {
+                    tracker.minimized = true;
+                    Event::Minimize(true).into()
+                }
+            }
             WindowEvent::Resized(size) => {
                 let size = [size.width as f32, size.height as f32];
                 camera.window_size = size;
-                Event::Resize(size).into()
+                if tracker.minimized {
+                    tracker.minimized = false;
+                    Two::two(Event::Minimize(false), Event::Resize(size))
+                } else {
+                    Event::Resize(size).into()
+                }
             }
             WindowEvent::Moved(size) => Event::Move([size.x as f32, size.y as f32]).into(),
             WindowEvent::Focused(foc) => Event::Focus(foc).into(),
             WindowEvent::CursorMoved { position, .. } => {
                 let pos = [position.x as f32, position.y as f32];
-                let two = Two::two(
-                    Event::MouseAbsolute(pos),
-                    Event::MouseRelative(pos.sub(tracker.mouse_pos)),
-                );
+                let delta = pos.sub(tracker.mouse_pos);
+                tracker.mouse_delta = tracker.mouse_delta.add(delta);
+                let two = Two::two(Event::MouseAbsolute(pos), Event::MouseRelative(delta));
                 tracker.mouse_pos = pos;
+                tracker.mouse_in_window = true;
                 two
             }
+            WindowEvent::CursorEntered { .. } => {
+                tracker.mouse_in_window = true;
+                Two::none()
+            }
+            WindowEvent::CursorLeft { .. } => {
+                tracker.mouse_in_window = false;
+                Two::none()
+            }
             WindowEvent::MouseInput { button, state, .. } => {
                 match state {
-                    ButtonState::Pressed => tracker.mouse_buttons.insert(button),
-                    ButtonState::Released => tracker.mouse_buttons.remove(&button),
-                };
+                    ButtonState::Pressed => {
+                        if tracker.mouse_buttons.insert(button) {
+                            tracker.mouse_buttons_pressed.insert(button);
+                        }
+                    }
+                    ButtonState::Released => {
+                        if tracker.mouse_buttons.remove(&button) {
+                            tracker.mouse_buttons_released.insert(button);
+                        }
+                    }
+                }
                 Event::MouseButton { button, state }.into()
             }
-            WindowEvent::MouseWheel {
-                delta: MouseScrollDelta::LineDelta(x, y),
-                ..
-            } => Event::Scroll([x, y]).into(),
-            WindowEvent::MouseWheel {
-                delta: MouseScrollDelta::PixelDelta(pos),
-                ..
-            } => Event::Scroll([pos.x as f32, pos.y as f32]).into(),
+            WindowEvent::MouseWheel { delta, .. } => {
+                let delta = match delta {
+                    MouseScrollDelta::LineDelta(x, y) => [x, y],
+                    MouseScrollDelta::PixelDelta(pos) => [pos.x as f32, pos.y as f32],
+                };
+                tracker.scroll_delta = tracker.scroll_delta.add(delta);
+                Event::Scroll(delta).into()
+            }
+            WindowEvent::ReceivedCharacter(c) => Event::Char(c).into(),
+            WindowEvent::Touch(touch) => {
+                let pos = [touch.location.x as f32, touch.location.y as f32];
+                let event = Event::Touch {
+                    id: touch.id,
+                    phase: touch.phase,
+                    pos,
+                };
+                if let Some(force) = touch.force {
+                    let tilt = match force {
+                        Force::Calibrated { altitude_angle, .. } => altitude_angle,
+                        Force::Normalized(_) => None,
+                    };
+                    Two::two(
+                        event,
+                        Event::Pen {
+                            id: touch.id,
+                            phase: touch.phase,
+                            pos,
+                            pressure: force.normalized() as f32,
+                            tilt: tilt.map(|tilt| tilt as f32),
+                        },
+                    )
+                } else {
+                    event.into()
+                }
+            }
             WindowEvent::ModifiersChanged(modifiers) => {
                 tracker.modifiers = modifiers;
                 Two::none()
@@ -101,10 +295,30 @@ impl Event {
                     .virtual_keycode
                     .map(Key::from_glutin)
                     .unwrap_or(Key::Unknown);
+                if key != Key::Unknown {
+                    tracker.layout.insert(input.scancode, key);
+                }
                 match input.state {
+                    ButtonState::Pressed => tracker.scancodes.insert(input.scancode),
+                    ButtonState::Released => tracker.scancodes.remove(&input.scancode),
+                };
+                let changed = match input.state {
                     ButtonState::Pressed => tracker.keys.insert(key),
                     ButtonState::Released => tracker.keys.remove(&key),
                 };
+                if changed {
+                    match input.state {
+                        ButtonState::Pressed => tracker.keys_pressed.insert(key),
+                        ButtonState::Released => tracker.keys_released.insert(key),
+                    };
+                }
+                if !changed && input.state == ButtonState::Pressed {
+                    return Event::KeyRepeat {
+                        key,
+                        scancode: input.scancode,
+                    }
+                    .into();
+                }
                 Event::Key {
                     key,
                     scancode: input.scancode,
@@ -129,9 +343,21 @@ The context updates its `StateTracker` automatically.
 )]
 pub struct StateTracker {
     mouse_pos: Vec2,
+    mouse_in_window: bool,
     modifiers: Modifiers,
     keys: HashSet<Key>,
+    scancodes: HashSet<u32>,
+    layout: HashMap<u32, Key>,
     mouse_buttons: HashSet<MouseButton>,
+    keys_pressed: HashSet<Key>,
+    keys_released: HashSet<Key>,
+    mouse_buttons_pressed: HashSet<MouseButton>,
+    mouse_buttons_released: HashSet<MouseButton>,
+    mouse_delta: Vec2,
+    scroll_delta: Vec2,
+    axes: HashMap<String, Axis>,
+    minimized: bool,
+    suspended: bool,
     pub(crate) fps: f32,
 }
 
@@ -140,6 +366,18 @@ impl StateTracker {
     pub fn mouse_pos(&self) -> Vec2 {
         self.mouse_pos
     }
+    /// Get the total movement of the mouse cursor since the last update
+    pub fn mouse_delta(&self) -> Vec2 {
+        self.mouse_delta
+    }
+    /// Get the total scroll wheel movement since the last update
+    pub fn scroll_delta(&self) -> Vec2 {
+        self.scroll_delta
+    }
+    /// Check if the mouse cursor is inside the window
+    pub fn mouse_in_window(&self) -> bool {
+        self.mouse_in_window
+    }
     /// Get the state of modifier keys
     pub fn modifiers(&self) -> Modifiers {
         self.modifiers
@@ -148,10 +386,52 @@ impl StateTracker {
     pub fn key(&self, key: Key) -> bool {
         self.keys.contains(&key)
     }
+    /// Check if a key was pressed since the last update
+    pub fn key_pressed_this_frame(&self, key: Key) -> bool {
+        self.keys_pressed.contains(&key)
+    }
+    /// Check if a key was released since the last update
+    pub fn key_released_this_frame(&self, key: Key) -> bool {
+        self.keys_released.contains(&key)
+    }
+    /// Get the state of a key by its scancode
+    pub fn scancode(&self, scancode: u32) -> bool {
+        self.scancodes.contains(&scancode)
+    }
+    /**
+    Get the state of a key by its physical position
+
+    Unlike `StateTracker::key`, this does not depend on the keyboard layout
+    */
+    pub fn physical_key(&self, key: PhysicalKey) -> bool {
+        self.scancode(key.scancode())
+    }
+    /**
+    Get the key that a physical key produces in the current keyboard layout
+
+    This is useful for showing the correct label for a physical key binding.
+
+    The layout is learned as keys are pressed. Until a physical key has been pressed,
+    this assumes a US QWERTY layout.
+    */
+    pub fn physical_key_label(&self, key: PhysicalKey) -> Key {
+        self.layout
+            .get(&key.scancode())
+            .copied()
+            .unwrap_or_else(|| key.qwerty())
+    }
     /// Get the state of a mouse button
     pub fn mouse_button(&self, mb: MouseButton) -> bool {
         self.mouse_buttons.contains(&mb)
     }
+    /// Check if a mouse button was pressed since the last update
+    pub fn mouse_pressed_this_frame(&self, mb: MouseButton) -> bool {
+        self.mouse_buttons_pressed.contains(&mb)
+    }
+    /// Check if a mouse button was released since the last update
+    pub fn mouse_released_this_frame(&self, mb: MouseButton) -> bool {
+        self.mouse_buttons_released.contains(&mb)
+    }
     /**
     Get a scalar representing the difference between two key states
 
@@ -173,10 +453,126 @@ impl StateTracker {
             self.key_diff_scalar(up, down),
         ]
     }
+    /// Add a named virtual axis, replacing any axis with the same name
+    pub fn add_axis<N>(&mut self, name: N, axis: Axis)
+    where
+        N: Into<String>,
+    {
+        self.axes.insert(name.into(), axis);
+    }
+    /// Remove a named virtual axis
+    pub fn remove_axis(&mut self, name: &str) -> Option<Axis> {
+        self.axes.remove(name)
+    }
+    /// Get a named virtual axis
+    pub fn get_axis(&self, name: &str) -> Option<&Axis> {
+        self.axes.get(name)
+    }
+    /// Get the value of a named virtual axis, or `0.0` if there is no axis with that name
+    pub fn axis(&self, name: &str) -> f32 {
+        self.get_axis(name).map(Axis::value).unwrap_or(0.0)
+    }
+    /// Check if the window is minimized
+    pub fn minimized(&self) -> bool {
+        self.minimized
+    }
+    /// Check if the app is suspended
+    pub fn suspended(&self) -> bool {
+        self.suspended
+    }
     /// Get the temporally-normalized frames per second
     pub fn fps(&self) -> f32 {
         self.fps
     }
+    /// Update the virtual axes before an update
+    pub(crate) fn update_axes(&mut self, dt: f32) {
+        let mut axes = std::mem::take(&mut self.axes);
+        for axis in axes.values_mut() {
+            axis.update(dt, self);
+        }
+        self.axes = axes;
+    }
+    /// Clear the per-update state after an update
+    pub(crate) fn end_update(&mut self) {
+        self.keys_pressed.clear();
+        self.keys_released.clear();
+        self.mouse_buttons_pressed.clear();
+        self.mouse_buttons_released.clear();
+        self.mouse_delta = [0.0; 2];
+        self.scroll_delta = [0.0; 2];
+    }
+}
+
+/// Configuration for engine-generated key repeat
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyRepeat {
+    /// The time a key must be held before it starts repeating
+    pub delay: Duration,
+    /// The time between repeats
+    pub interval: Duration,
+}
+
+impl KeyRepeat {
+    /// Create a new `KeyRepeat`
+    pub const fn new(delay: Duration, interval: Duration) -> Self {
+        KeyRepeat { delay, interval }
+    }
+}
+
+impl Default for KeyRepeat {
+    fn default() -> Self {
+        KeyRepeat::new(Duration::from_millis(500), Duration::from_millis(33))
+    }
+}
+
+/// Generates key repeat events in place of the operating system's
+pub(crate) struct KeyRepeater {
+    config: KeyRepeat,
+    held: HashMap<Key, (u32, Instant)>,
+}
+
+impl KeyRepeater {
+    pub fn new(config: KeyRepeat) -> Self {
+        KeyRepeater {
+            config,
+            held: HashMap::new(),
+        }
+    }
+    pub fn process(&mut self, events: Vec<Event>, now: Instant) -> Vec<Event> {
+        let mut processed = Vec::with_capacity(events.len());
+        for event in events {
+            match event {
+                Event::KeyRepeat { .. } => continue,
+                Event::Key {
+                    key,
+                    scancode,
+                    state: ButtonState::Pressed,
+                } => {
+                    self.held.insert(key, (scancode, now + self.config.delay));
+                }
+                Event::Key {
+                    key,
+                    state: ButtonState::Released,
+                    ..
+                } => {
+                    self.held.remove(&key);
+                }
+                _ => {}
+            }
+            processed.push(event);
+        }
+        let interval = self.config.interval.max(Duration::from_millis(1));
+        for (&key, (scancode, next)) in &mut self.held {
+            while *next <= now {
+                processed.push(Event::KeyRepeat {
+                    key,
+                    scancode: *scancode,
+                });
+                *next += interval;
+            }
+        }
+        processed
+    }
 }
 
 pub(crate) struct Two<T>(Option<T>, Option<T>);
@@ -206,6 +602,41 @@ impl<T> Iterator for Two<T> {
     }
 }
 
+/// An id for an event sent with an `EventSender`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "ser",
+    derive(serde_derive::Serialize, serde_derive::Deserialize)
+)]
+pub struct UserEventId(pub u64);
+
+/// The payload of an event sent with an `EventSender`
+pub(crate) type UserPayload = Box<dyn Any + Send>;
+
+/**
+Sends custom events to the app from other threads
+
+Each sent payload arrives in `Kule::event` as an `Event::User`,
+and can be retrieved with `Context::take_user_event`.
+Get an `EventSender` with `Context::event_sender`.
+*/
+#[derive(Clone)]
+pub struct EventSender(pub(crate) EventLoopProxy<UserPayload>);
+
+impl EventSender {
+    /**
+    Send an event with some payload
+
+    Returns `false` if the app has closed
+    */
+    pub fn send<T>(&self, payload: T) -> bool
+    where
+        T: Any + Send,
+    {
+        self.0.send_event(Box::new(payload)).is_ok()
+    }
+}
+
 macro_rules! keys {
     ($(($key:ident, $glutinkey:ident),)*) => {
         #[allow(missing_docs)]
@@ -392,3 +823,209 @@ keys!(
     (Paste, Paste),
     (Cut, Cut),
 );
+
+impl Key {
+    /**
+    Get the character that the key produces with some modifiers on a US QWERTY keyboard
+
+    Returns `None` for keys that do not produce characters or if control, alt, or logo is held.
+
+    This is useful for simple text entry and for displaying key binding labels.
+    For real text input, which respects the keyboard layout and input methods, use `Event::Char`.
+
+    ```
+    # use kule::*;
+    assert_eq!(Key::A.to_char(Modifiers::empty()), Some('a'));
+    assert_eq!(Key::Num2.to_char(Modifiers::SHIFT), Some('@'));
+    assert_eq!(Key::A.to_char(Modifiers::CTRL), None);
+    ```
+    */
+    pub fn to_char(self, modifiers: Modifiers) -> Option<char> {
+        if modifiers.ctrl() || modifiers.alt() || modifiers.logo() {
+            return None;
+        }
+        let shift = modifiers.shift();
+        let (lower, upper) = match self {
+            Key::Num1 => ('1', '!'),
+            Key::Num2 => ('2', '@'),
+            Key::Num3 => ('3', '#'),
+            Key::Num4 => ('4', '$'),
+            Key::Num5 => ('5', '%'),
+            Key::Num6 => ('6', '^'),
+            Key::Num7 => ('7', '&'),
+            Key::Num8 => ('8', '*'),
+            Key::Num9 => ('9', '('),
+            Key::Num0 => ('0', ')'),
+            Key::Numpad0 => return Some('0'),
+            Key::Numpad1 => return Some('1'),
+            Key::Numpad2 => return Some('2'),
+            Key::Numpad3 => return Some('3'),
+            Key::Numpad4 => return Some('4'),
+            Key::Numpad5 => return Some('5'),
+            Key::Numpad6 => return Some('6'),
+            Key::Numpad7 => return Some('7'),
+            Key::Numpad8 => return Some('8'),
+            Key::Numpad9 => return Some('9'),
+            Key::Add => return Some('+'),
+            Key::Subtract => return Some('-'),
+            Key::Multiply => return Some('*'),
+            Key::Divide => return Some('/'),
+            Key::Decimal => return Some('.'),
+            Key::NumpadComma => return Some(','),
+            Key::NumpadEquals => return Some('='),
+            Key::Space => return Some(' '),
+            Key::Tab => return Some('\t'),
+            Key::Enter | Key::NumpadEnter => return Some('\n'),
+            Key::Minus => ('-', '_'),
+            Key::Equals => ('=', '+'),
+            Key::LBracket => ('[', '{'),
+            Key::RBracket => (']', '}'),
+            Key::Backslash => ('\\', '|'),
+            Key::Semicolon => (';', ':'),
+            Key::Apostrophe => ('\'', '"'),
+            Key::Grave => ('`', '~'),
+            Key::Comma => (',', '<'),
+            Key::Period => ('.', '>'),
+            Key::Slash => ('/', '?'),
+            Key::Colon => return Some(':'),
+            Key::At => return Some('@'),
+            Key::Caret => return Some('^'),
+            Key::Underline => return Some('_'),
+            Key::A => ('a', 'A'),
+            Key::B => ('b', 'B'),
+            Key::C => ('c', 'C'),
+            Key::D => ('d', 'D'),
+            Key::E => ('e', 'E'),
+            Key::F => ('f', 'F'),
+            Key::G => ('g', 'G'),
+            Key::H => ('h', 'H'),
+            Key::I => ('i', 'I'),
+            Key::J => ('j', 'J'),
+            Key::K => ('k', 'K'),
+            Key::L => ('l', 'L'),
+            Key::M => ('m', 'M'),
+            Key::N => ('n', 'N'),
+            Key::O => ('o', 'O'),
+            Key::P => ('p', 'P'),
+            Key::Q => ('q', 'Q'),
+            Key::R => ('r', 'R'),
+            Key::S => ('s', 'S'),
+            Key::T => ('t', 'T'),
+            Key::U => ('u', 'U'),
+            Key::V => ('v', 'V'),
+            Key::W => ('w', 'W'),
+            Key::X => ('x', 'X'),
+            Key::Y => ('y', 'Y'),
+            Key::Z => ('z', 'Z'),
+            _ => return None,
+        };
+        Some(if shift { upper } else { lower })
+    }
+}
+
+macro_rules! physical_keys {
+    ($(($key:ident, $qwerty:ident, $scancode:literal, $mac:literal),)*) => {
+        /**
+        A key identified by its physical position on the keyboard
+
+        Keys are named after the keys in those positions on a US QWERTY keyboard
+        */
+        #[allow(missing_docs)]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        #[cfg_attr(
+            feature = "ser",
+            derive(serde_derive::Serialize, serde_derive::Deserialize)
+        )]
+        pub enum PhysicalKey {
+            $($key,)*
+        }
+
+        impl PhysicalKey {
+            /// Get the physical key for a scancode
+            pub fn from_scancode(scancode: u32) -> Option<Self> {
+                match scancode {
+                    $(#[cfg(not(target_os = "macos"))]
+                    $scancode => Some(PhysicalKey::$key),)*
+                    $(#[cfg(target_os = "macos")]
+                    $mac => Some(PhysicalKey::$key),)*
+                    _ => None,
+                }
+            }
+            /// Get the scancode of the physical key
+            pub fn scancode(self) -> u32 {
+                match self {
+                    $(#[cfg(not(target_os = "macos"))]
+                    PhysicalKey::$key => $scancode,)*
+                    $(#[cfg(target_os = "macos")]
+                    PhysicalKey::$key => $mac,)*
+                }
+            }
+            /// Get the key in this position on a US QWERTY keyboard
+            pub fn qwerty(self) -> Key {
+                match self {
+                    $(PhysicalKey::$key => Key::$qwerty,)*
+                }
+            }
+        }
+    };
+}
+
+// (key, qwerty key, PC scancode, macOS key code)
+physical_keys!(
+    (Escape, Escape, 1, 53),
+    (Num1, Num1, 2, 18),
+    (Num2, Num2, 3, 19),
+    (Num3, Num3, 4, 20),
+    (Num4, Num4, 5, 21),
+    (Num5, Num5, 6, 23),
+    (Num6, Num6, 7, 22),
+    (Num7, Num7, 8, 26),
+    (Num8, Num8, 9, 28),
+    (Num9, Num9, 10, 25),
+    (Num0, Num0, 11, 29),
+    (Minus, Minus, 12, 27),
+    (Equals, Equals, 13, 24),
+    (Back, Back, 14, 51),
+    (Tab, Tab, 15, 48),
+    (Q, Q, 16, 12),
+    (W, W, 17, 13),
+    (E, E, 18, 14),
+    (R, R, 19, 15),
+    (T, T, 20, 17),
+    (Y, Y, 21, 16),
+    (U, U, 22, 32),
+    (I, I, 23, 34),
+    (O, O, 24, 31),
+    (P, P, 25, 35),
+    (LBracket, LBracket, 26, 33),
+    (RBracket, RBracket, 27, 30),
+    (Enter, Enter, 28, 36),
+    (LControl, LControl, 29, 59),
+    (A, A, 30, 0),
+    (S, S, 31, 1),
+    (D, D, 32, 2),
+    (F, F, 33, 3),
+    (G, G, 34, 5),
+    (H, H, 35, 4),
+    (J, J, 36, 38),
+    (K, K, 37, 40),
+    (L, L, 38, 37),
+    (Semicolon, Semicolon, 39, 41),
+    (Apostrophe, Apostrophe, 40, 39),
+    (Grave, Grave, 41, 50),
+    (LShift, LShift, 42, 56),
+    (Backslash, Backslash, 43, 42),
+    (Z, Z, 44, 6),
+    (X, X, 45, 7),
+    (C, C, 46, 8),
+    (V, V, 47, 9),
+    (B, B, 48, 11),
+    (N, N, 49, 45),
+    (M, M, 50, 46),
+    (Comma, Comma, 51, 43),
+    (Period, Period, 52, 47),
+    (Slash, Slash, 53, 44),
+    (RShift, RShift, 54, 60),
+    (LAlt, LAlt, 56, 58),
+    (Space, Space, 57, 49),
+);
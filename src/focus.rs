@@ -0,0 +1,300 @@
+use vector2math::*;
+
+use crate::{ButtonState, Canvas, Color, Drawer, Event, Key, Rect, Resources, Vec2};
+
+/// A widget that can receive keyboard focus
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Focusable {
+    id: u32,
+    rect: Rect,
+    enabled: bool,
+}
+
+/**
+Moves keyboard focus between UI widgets
+
+Widgets are added with an id and a rectangle in UI coordinates. The order they
+are added in is their tab order.
+
+While a widget is focused, key events are used to navigate:
+- `Tab` and `Shift+Tab` move to the next and previous widget
+- The arrow keys move to the nearest widget in that direction
+- `Enter` and `Space` activate the focused widget
+
+Key presses that are used this way are not passed on as events.
+While nothing is focused, all keys are passed on, so focus must first be given
+to a widget with [`FocusManager::focus`].
+
+When the focus changes, an `Event::FocusChanged` is sent. When a widget is
+activated, an `Event::Activate` is sent with its id.
+
+```
+# use kule::*;
+let mut focus = FocusManager::new();
+focus.add(0, [10.0, 10.0, 100.0, 30.0]);
+focus.add(1, [10.0, 50.0, 100.0, 30.0]);
+focus.focus(Some(0));
+assert_eq!(focus.focused(), Some(0));
+```
+*/
+#[derive(Debug, Clone, Default)]
+pub struct FocusManager {
+    widgets: Vec<Focusable>,
+    focused: Option<u32>,
+    shift: bool,
+}
+
+impl FocusManager {
+    /// Create a new `FocusManager` with no widgets
+    pub fn new() -> Self {
+        FocusManager::default()
+    }
+    /// Add a widget, or move it if a widget with the same id already exists
+    pub fn add<R>(&mut self, id: u32, rect: R)
+    where
+        R: Rectangle<Scalar = f32>,
+    {
+        let rect = rect.map();
+        if let Some(widget) = self.widgets.iter_mut().find(|widget| widget.id == id) {
+            widget.rect = rect;
+        } else {
+            self.widgets.push(Focusable {
+                id,
+                rect,
+                enabled: true,
+            });
+        }
+    }
+    /// Remove a widget
+    ///
+    /// If the widget is focused, nothing is focused afterward
+    pub fn remove(&mut self, id: u32) {
+        self.widgets.retain(|widget| widget.id != id);
+        if self.focused == Some(id) {
+            self.focused = None;
+        }
+    }
+    /// Remove all widgets
+    pub fn clear(&mut self) {
+        self.widgets.clear();
+        self.focused = None;
+    }
+    /**
+    Set whether a widget can be focused
+
+    Disabled widgets are skipped during navigation. Disabling the focused
+    widget unfocuses it.
+    */
+    pub fn set_enabled(&mut self, id: u32, enabled: bool) {
+        if let Some(widget) = self.widgets.iter_mut().find(|widget| widget.id == id) {
+            widget.enabled = enabled;
+        }
+        if !enabled && self.focused == Some(id) {
+            self.focused = None;
+        }
+    }
+    /// Get the id of the focused widget
+    pub fn focused(&self) -> Option<u32> {
+        self.focused
+    }
+    /// Check if a widget is focused
+    pub fn is_focused(&self, id: u32) -> bool {
+        self.focused == Some(id)
+    }
+    /// Get the rectangle of the focused widget
+    pub fn focused_rect(&self) -> Option<Rect> {
+        self.focused
+            .and_then(|id| self.widgets.iter().find(|widget| widget.id == id))
+            .map(|widget| widget.rect)
+    }
+    /**
+    Focus a widget, or unfocus everything with `None`
+
+    Nothing happens if the widget does not exist or is disabled.
+    This does not send an `Event::FocusChanged`.
+    */
+    pub fn focus(&mut self, id: Option<u32>) {
+        match id {
+            Some(id) => {
+                if self.enabled().any(|widget| widget.id == id) {
+                    self.focused = Some(id);
+                }
+            }
+            None => self.focused = None,
+        }
+    }
+    fn enabled(&self) -> impl Iterator<Item = &Focusable> + Clone {
+        self.widgets.iter().filter(|widget| widget.enabled)
+    }
+    /// Get the id of the next enabled widget in tab order
+    fn step(&self, forward: bool) -> Option<u32> {
+        let ids: Vec<u32> = self.enabled().map(|widget| widget.id).collect();
+        if ids.is_empty() {
+            return None;
+        }
+        let n = ids.len();
+        let i = match self
+            .focused
+            .and_then(|id| ids.iter().position(|&i| i == id))
+        {
+            Some(i) if forward => (i + 1) % n,
+            Some(i) => (i + n - 1) % n,
+            None if forward => 0,
+            None => n - 1,
+        };
+        Some(ids[i])
+    }
+    /// Get the id of the nearest enabled widget in some direction
+    fn nearest(&self, dir: Vec2) -> Option<u32> {
+        let from = match self.focused_rect() {
+            Some(rect) => rect.center(),
+            None => return self.step(dir.x() + dir.y() > 0.0),
+        };
+        self.enabled()
+            .filter(|widget| Some(widget.id) != self.focused)
+            .filter_map(|widget| {
+                let offset = widget.rect.center().sub(from);
+                let along = offset.dot(dir);
+                if along <= 0.0 {
+                    return None;
+                }
+                // Prefer widgets that are closer to straight ahead
+                let across = (offset.x() * dir.y() - offset.y() * dir.x()).abs();
+                Some((widget.id, along + 2.0 * across))
+            })
+            .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
+            .map(|(id, _)| id)
+    }
+    pub(crate) fn process(&mut self, events: Vec<Event>) -> Vec<Event> {
+        if self.widgets.is_empty() {
+            return events;
+        }
+        let mut processed = Vec::with_capacity(events.len());
+        for event in events {
+            match event {
+                Event::Key {
+                    key: Key::LShift | Key::RShift,
+                    state,
+                    ..
+                } => self.shift = state == ButtonState::Pressed,
+                Event::Focus(false) => self.shift = false,
+                _ => {}
+            }
+            let focused = match self.focused {
+                Some(id) => id,
+                None => {
+                    processed.push(event);
+                    continue;
+                }
+            };
+            let (key, repeat) = match event {
+                Event::Key {
+                    key,
+                    state: ButtonState::Pressed,
+                    ..
+                } => (key, false),
+                Event::KeyRepeat { key, .. } => (key, true),
+                // Releases of navigation keys are consumed along with their presses
+                Event::Key { key, .. } if is_navigation(key) => continue,
+                _ => {
+                    processed.push(event);
+                    continue;
+                }
+            };
+            let target = match key {
+                Key::Tab => self.step(!self.shift),
+                Key::Up => self.nearest([0.0, -1.0]),
+                Key::Down => self.nearest([0.0, 1.0]),
+                Key::Left => self.nearest([-1.0, 0.0]),
+                Key::Right => self.nearest([1.0, 0.0]),
+                Key::Enter | Key::NumpadEnter | Key::Space => {
+                    if !repeat {
+                        processed.push(Event::Activate(focused));
+                    }
+                    continue;
+                }
+                _ => {
+                    processed.push(event);
+                    continue;
+                }
+            };
+            if target.is_some() && target != self.focused {
+                self.focused = target;
+                processed.push(Event::FocusChanged(target));
+            }
+        }
+        processed
+    }
+}
+
+/// Check if a key is used to navigate between widgets
+fn is_navigation(key: Key) -> bool {
+    matches!(
+        key,
+        Key::Tab
+            | Key::Up
+            | Key::Down
+            | Key::Left
+            | Key::Right
+            | Key::Enter
+            | Key::NumpadEnter
+            | Key::Space
+    )
+}
+
+impl<'ctx, T, R> Drawer<'ctx, T, R>
+where
+    T: Canvas,
+    R: Resources,
+{
+    /// Draw an outline around the focused widget
+    pub fn focus_highlight<C>(&mut self, focus: &FocusManager, color: C, thickness: f32)
+    where
+        C: Color,
+    {
+        if let Some(rect) = focus.focused_rect() {
+            let rect = [
+                rect.abs_left() - thickness / 2.0,
+                rect.abs_top() - thickness / 2.0,
+                rect.abs_width() + thickness,
+                rect.abs_height() + thickness,
+            ];
+            self.polyline(
+                color,
+                vec![
+                    rect.top_left(),
+                    rect.top_right(),
+                    rect.bottom_right(),
+                    rect.bottom_left(),
+                    rect.top_left(),
+                ],
+                thickness,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn focus_navigation() {
+    let press = |key| Event::Key {
+        key,
+        scancode: 0,
+        state: ButtonState::Pressed,
+    };
+    let mut focus = FocusManager::new();
+    focus.add(0, [0.0, 0.0, 10.0, 10.0]);
+    focus.add(1, [20.0, 0.0, 10.0, 10.0]);
+    focus.add(2, [0.0, 20.0, 10.0, 10.0]);
+    // Nothing is focused, so keys are passed on
+    let events = focus.process(vec![press(Key::Tab)]);
+    assert_eq!(events, [press(Key::Tab)]);
+    focus.focus(Some(0));
+    let events = focus.process(vec![press(Key::Right), press(Key::A)]);
+    assert_eq!(events, [Event::FocusChanged(Some(1)), press(Key::A)]);
+    focus.set_enabled(2, false);
+    focus.process(vec![press(Key::LShift), press(Key::Tab)]);
+    assert_eq!(focus.focused(), Some(0));
+    let events = focus.process(vec![press(Key::Down), press(Key::Space)]);
+    assert_eq!(events.last(), Some(&Event::Activate(0)));
+}
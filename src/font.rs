@@ -111,6 +111,9 @@ where
         );
         Ok(())
     }
+    pub(crate) fn insert(&mut self, id: G, font: Font) {
+        self.0.insert(id, font.into());
+    }
     /// Get a glyph cache with the given id
     pub fn get(&self, id: G) -> Option<&GlyphCache> {
         self.0.get(&id)
@@ -0,0 +1,228 @@
+use std::{collections::BTreeMap, time::Instant};
+
+use vector2math::*;
+
+use crate::{ButtonState, Event, MouseButton, TouchPhase, Vec2};
+
+/// The distance in pixels the pointer must move before a press becomes a drag
+const DRAG_THRESHOLD: f32 = 4.0;
+/// The minimum speed in pixels per second for a drag to be a swipe
+const SWIPE_SPEED: f32 = 1000.0;
+/// The maximum duration in seconds of a swipe
+const SWIPE_TIME: f32 = 0.5;
+
+/// A high-level gesture recognized from mouse and touch input
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(
+    feature = "ser",
+    derive(serde_derive::Serialize, serde_derive::Deserialize)
+)]
+pub enum Gesture {
+    /// A drag has started
+    DragStart {
+        /// The mouse button, or `None` for a touch
+        button: Option<MouseButton>,
+        /// The position where the drag started
+        pos: Vec2,
+    },
+    /// The pointer has moved during a drag
+    DragMove {
+        /// The mouse button, or `None` for a touch
+        button: Option<MouseButton>,
+        /// The current position
+        pos: Vec2,
+        /// The movement since the last `DragMove`
+        delta: Vec2,
+        /// The movement since the drag started
+        total: Vec2,
+    },
+    /// A drag has ended
+    DragEnd {
+        /// The mouse button, or `None` for a touch
+        button: Option<MouseButton>,
+        /// The position where the drag ended
+        pos: Vec2,
+        /// The movement since the drag started
+        total: Vec2,
+    },
+    /// A quick drag in some direction
+    ///
+    /// This is sent after the `DragEnd`
+    Swipe {
+        /// The normalized direction of the swipe
+        direction: Vec2,
+        /// The speed of the swipe in pixels per second
+        speed: f32,
+    },
+    /// Two touches have moved closer together or farther apart
+    Pinch {
+        /// The point between the touches
+        center: Vec2,
+        /// The ratio of the distance between the touches to the distance at the last `Pinch`
+        scale: f32,
+    },
+    /// Two touches have moved together
+    Pan {
+        /// The movement of the point between the touches since the last `Pan`
+        delta: Vec2,
+    },
+}
+
+struct Drag {
+    button: Option<MouseButton>,
+    start: Vec2,
+    pos: Vec2,
+    started: Option<Instant>,
+}
+
+impl Drag {
+    fn new(button: Option<MouseButton>, pos: Vec2) -> Self {
+        Drag {
+            button,
+            start: pos,
+            pos,
+            started: None,
+        }
+    }
+    fn move_to(&mut self, pos: Vec2, now: Instant, events: &mut Vec<Event>) {
+        if self.started.is_none() {
+            if pos.dist(self.start) < DRAG_THRESHOLD {
+                return;
+            }
+            self.started = Some(now);
+            events.push(Event::Gesture(Gesture::DragStart {
+                button: self.button,
+                pos: self.start,
+            }));
+        }
+        events.push(Event::Gesture(Gesture::DragMove {
+            button: self.button,
+            pos,
+            delta: pos.sub(self.pos),
+            total: pos.sub(self.start),
+        }));
+        self.pos = pos;
+    }
+    fn end(self, now: Instant, events: &mut Vec<Event>) {
+        let started = if let Some(started) = self.started {
+            started
+        } else {
+            return;
+        };
+        let total = self.pos.sub(self.start);
+        events.push(Event::Gesture(Gesture::DragEnd {
+            button: self.button,
+            pos: self.pos,
+            total,
+        }));
+        let time = (now - started).as_secs_f32();
+        let speed = total.mag() / time.max(f32::EPSILON);
+        if time <= SWIPE_TIME && speed >= SWIPE_SPEED {
+            events.push(Event::Gesture(Gesture::Swipe {
+                direction: total.unit(),
+                speed,
+            }));
+        }
+    }
+}
+
+/// Produces gesture events from mouse and touch events
+#[derive(Default)]
+pub(crate) struct GestureRecognizer {
+    mouse_pos: Vec2,
+    mouse_drag: Option<Drag>,
+    touches: BTreeMap<u64, Vec2>,
+    touch_drag: Option<(u64, Drag)>,
+    pinch: Option<(f32, Vec2)>,
+}
+
+impl GestureRecognizer {
+    pub fn process(&mut self, events: Vec<Event>, now: Instant) -> Vec<Event> {
+        let mut processed = Vec::with_capacity(events.len());
+        for event in events {
+            processed.push(event);
+            match event {
+                Event::MouseAbsolute(pos) => {
+                    self.mouse_pos = pos;
+                    if let Some(drag) = &mut self.mouse_drag {
+                        drag.move_to(pos, now, &mut processed);
+                    }
+                }
+                Event::MouseButton {
+                    button,
+                    state: ButtonState::Pressed,
+                } if self.mouse_drag.is_none() => {
+                    self.mouse_drag = Some(Drag::new(Some(button), self.mouse_pos));
+                }
+                Event::MouseButton {
+                    button,
+                    state: ButtonState::Released,
+                } if self.mouse_drag.as_ref().and_then(|drag| drag.button) == Some(button) => {
+                    self.mouse_drag.take().unwrap().end(now, &mut processed);
+                }
+                Event::Touch { id, phase, pos } => self.touch(id, phase, pos, now, &mut processed),
+                _ => {}
+            }
+        }
+        processed
+    }
+    fn touch(
+        &mut self,
+        id: u64,
+        phase: TouchPhase,
+        pos: Vec2,
+        now: Instant,
+        events: &mut Vec<Event>,
+    ) {
+        match phase {
+            TouchPhase::Started => {
+                self.touches.insert(id, pos);
+                if self.touches.len() == 1 {
+                    self.touch_drag = Some((id, Drag::new(None, pos)));
+                } else if let Some((_, drag)) = self.touch_drag.take() {
+                    // A second touch turns a drag into a pinch
+                    drag.end(now, events);
+                }
+                self.pinch = self.pinch_state();
+            }
+            TouchPhase::Moved => {
+                self.touches.insert(id, pos);
+                match &mut self.touch_drag {
+                    Some((drag_id, drag)) if *drag_id == id => drag.move_to(pos, now, events),
+                    _ => {}
+                }
+                if let (Some((prev_dist, prev_center)), Some((dist, center))) =
+                    (self.pinch, self.pinch_state())
+                {
+                    if dist != prev_dist && prev_dist > 0.0 {
+                        events.push(Event::Gesture(Gesture::Pinch {
+                            center,
+                            scale: dist / prev_dist,
+                        }));
+                    }
+                    if center != prev_center {
+                        events.push(Event::Gesture(Gesture::Pan {
+                            delta: center.sub(prev_center),
+                        }));
+                    }
+                    self.pinch = Some((dist, center));
+                }
+            }
+            TouchPhase::Ended | TouchPhase::Cancelled => {
+                self.touches.remove(&id);
+                match self.touch_drag.take() {
+                    Some((drag_id, drag)) if drag_id == id => drag.end(now, events),
+                    other => self.touch_drag = other,
+                }
+                self.pinch = self.pinch_state();
+            }
+        }
+    }
+    /// Get the distance between and center of the first two touches
+    fn pinch_state(&self) -> Option<(f32, Vec2)> {
+        let mut touches = self.touches.values();
+        let a = *touches.next()?;
+        let b = *touches.next()?;
+        Some((a.dist(b), a.lerp(b, 0.5)))
+    }
+}
@@ -0,0 +1,262 @@
+use crate::{Col, Color, Interpolate};
+
+/// A color space that colors are interpolated in
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "ser",
+    derive(serde_derive::Serialize, serde_derive::Deserialize)
+)]
+pub enum ColorSpace {
+    /// Interpolate the rgb components directly
+    ///
+    /// This is the cheapest, but midpoints are often dark and muddy
+    #[default]
+    Srgb,
+    /// Interpolate the rgb components in linear light
+    LinearRgb,
+    /// Interpolate hue, saturation, and value, taking the shorter way around the hue circle
+    Hsv,
+    /// Interpolate in the Oklab perceptual color space
+    ///
+    /// Midpoints have the brightness and saturation a person would expect
+    Oklab,
+}
+
+fn to_linear(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn from_linear(c: f32) -> f32 {
+    if c <= 0.003_130_8 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+fn to_oklab(color: Col) -> [f32; 3] {
+    let [r, g, b] = [
+        to_linear(color.r()),
+        to_linear(color.g()),
+        to_linear(color.b()),
+    ];
+    let l = (0.412_221_46 * r + 0.536_332_55 * g + 0.051_445_995 * b).cbrt();
+    let m = (0.211_903_5 * r + 0.680_699_5 * g + 0.107_396_96 * b).cbrt();
+    let s = (0.088_302_46 * r + 0.281_718_85 * g + 0.629_978_7 * b).cbrt();
+    [
+        0.210_454_26 * l + 0.793_617_8 * m - 0.004_072_047 * s,
+        1.977_998_5 * l - 2.428_592_2 * m + 0.450_593_7 * s,
+        0.025_904_037 * l + 0.782_771_77 * m - 0.808_675_77 * s,
+    ]
+}
+
+fn from_oklab([ok_l, ok_a, ok_b]: [f32; 3], alpha: f32) -> Col {
+    let l = (ok_l + 0.396_337_78 * ok_a + 0.215_803_76 * ok_b).powi(3);
+    let m = (ok_l - 0.105_561_346 * ok_a - 0.063_854_17 * ok_b).powi(3);
+    let s = (ok_l - 0.089_484_18 * ok_a - 1.291_485_5 * ok_b).powi(3);
+    Col::rgba(
+        from_linear(4.076_741_7 * l - 3.307_711_6 * m + 0.230_969_94 * s),
+        from_linear(-1.268_438 * l + 2.609_757_4 * m - 0.341_319_38 * s),
+        from_linear(-0.004_196_086_3 * l - 0.703_418_6 * m + 1.707_614_7 * s),
+        alpha,
+    )
+}
+
+impl ColorSpace {
+    /// Interpolate between two colors in this color space
+    pub fn interpolate<C>(self, a: C, b: C, t: f32) -> C
+    where
+        C: Color,
+    {
+        let (a, b): (Col, Col) = (a.map(), b.map());
+        let alpha = a.alpha().interpolate(b.alpha(), t);
+        let color = match self {
+            ColorSpace::Srgb => a.lerp(b, t),
+            ColorSpace::LinearRgb => {
+                let a = a.map_rgb(to_linear);
+                let b = b.map_rgb(to_linear);
+                a.lerp(b, t).map_rgb(from_linear)
+            }
+            ColorSpace::Hsv => {
+                let [ah, as_, av] = a.to_hsv();
+                let [bh, bs, bv] = b.to_hsv();
+                // Gray colors have no hue, so they take the other color's hue
+                let ah = if as_ == 0.0 { bh } else { ah };
+                let bh = if bs == 0.0 { ah } else { bh };
+                let dh = (bh - ah + 180.0).rem_euclid(360.0) - 180.0;
+                Col::hsva(
+                    ah + dh * t,
+                    as_.interpolate(bs, t),
+                    av.interpolate(bv, t),
+                    alpha,
+                )
+            }
+            ColorSpace::Oklab => from_oklab(to_oklab(a).interpolate(to_oklab(b), t), alpha),
+        };
+        Color::map(color.with_alpha(alpha))
+    }
+}
+
+/// A color at some position in a `Gradient`
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(
+    feature = "ser",
+    derive(serde_derive::Serialize, serde_derive::Deserialize)
+)]
+pub struct GradientStop {
+    /// The position, usually from `0.0` to `1.0`
+    pub position: f32,
+    /// The color
+    pub color: Col,
+}
+
+/**
+A smooth transition between colors
+
+Gradients can be used to tint things over their lifetimes, color height maps,
+or anything else that maps a number to a color.
+
+```
+# use kule::*;
+let fire = Gradient::even(&[Col::yellow(1.0), Col::red(1.0), Col::gray(0.2)])
+    .space(ColorSpace::Oklab);
+assert_eq!(fire.sample(0.0), Col::yellow(1.0));
+assert_eq!(fire.sample(2.0), Col::gray(0.2));
+```
+*/
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(
+    feature = "ser",
+    derive(serde_derive::Serialize, serde_derive::Deserialize)
+)]
+pub struct Gradient {
+    #[cfg_attr(feature = "ser", serde(deserialize_with = "sorted_stops"))]
+    stops: Vec<GradientStop>,
+    /// The color space that colors are interpolated in
+    #[cfg_attr(feature = "ser", serde(default))]
+    pub space: ColorSpace,
+}
+
+#[cfg(feature = "ser")]
+/// Deserialize stops and sort them by position, since sampling assumes they are sorted
+fn sorted_stops<'de, D>(deserializer: D) -> Result<Vec<GradientStop>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let mut stops: Vec<GradientStop> = serde::Deserialize::deserialize(deserializer)?;
+    stops.sort_by(|a, b| a.position.total_cmp(&b.position));
+    Ok(stops)
+}
+
+impl Gradient {
+    /// Create a new `Gradient` with no stops
+    pub fn new() -> Self {
+        Gradient::default()
+    }
+    /// Create a new `Gradient` with colors evenly spaced from `0.0` to `1.0`
+    pub fn even<C>(colors: &[C]) -> Self
+    where
+        C: Color,
+    {
+        let last = colors.len().saturating_sub(1).max(1) as f32;
+        colors
+            .iter()
+            .enumerate()
+            .fold(Gradient::new(), |gradient, (i, color)| {
+                gradient.stop(i as f32 / last, *color)
+            })
+    }
+    /// Add a color stop
+    pub fn stop<C>(mut self, position: f32, color: C) -> Self
+    where
+        C: Color,
+    {
+        let i = self.stops.partition_point(|stop| stop.position <= position);
+        self.stops.insert(
+            i,
+            GradientStop {
+                position,
+                color: color.map(),
+            },
+        );
+        self
+    }
+    /// Set the color space that colors are interpolated in
+    pub fn space(self, space: ColorSpace) -> Self {
+        Gradient { space, ..self }
+    }
+    /// Get the color stops in order of position
+    pub fn stops(&self) -> &[GradientStop] {
+        &self.stops
+    }
+    /// Reverse the order of the colors
+    pub fn reversed(mut self) -> Self {
+        let (first, last) = match (self.stops.first(), self.stops.last()) {
+            (Some(first), Some(last)) => (first.position, last.position),
+            _ => return self,
+        };
+        self.stops.reverse();
+        for stop in &mut self.stops {
+            stop.position = first + last - stop.position;
+        }
+        self
+    }
+    /**
+    Get the color at some position
+
+    Positions outside of the stops get the color of the nearest stop.
+    A gradient with no stops is transparent.
+    */
+    pub fn sample(&self, t: f32) -> Col {
+        let next = self.stops.partition_point(|stop| stop.position <= t);
+        match (
+            next.checked_sub(1).map(|i| &self.stops[i]),
+            self.stops.get(next),
+        ) {
+            (Some(prev), _) if prev.position == t => prev.color,
+            (Some(prev), Some(next)) => {
+                let t = (t - prev.position) / (next.position - prev.position);
+                self.space.interpolate(prev.color, next.color, t)
+            }
+            (Some(stop), None) | (None, Some(stop)) => stop.color,
+            (None, None) => Col::black().with_alpha(0.0),
+        }
+    }
+    /// Get some number of evenly spaced colors from `0.0` to `1.0`
+    pub fn samples(&self, count: usize) -> Vec<Col> {
+        let last = count.saturating_sub(1).max(1) as f32;
+        (0..count).map(|i| self.sample(i as f32 / last)).collect()
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn gradient_sampling() {
+    let close = |a: Col, b: Col| a.dist(b) < 1e-3;
+    let gradient = Gradient::new()
+        .stop(1.0, Col::blue(1.0))
+        .stop(0.0, Col::red(1.0));
+    assert!(close(gradient.sample(0.5), Col::rgb(0.5, 0.0, 0.5)));
+    let hsv = gradient.clone().space(ColorSpace::Hsv);
+    assert!(close(hsv.sample(0.5), Col::magenta(1.0)));
+    let oklab = gradient.clone().space(ColorSpace::Oklab);
+    assert!(close(oklab.sample(0.0), Col::red(1.0)));
+    assert!(oklab.sample(0.5).as_gray() > gradient.sample(0.5).as_gray());
+    #[cfg(feature = "ser")]
+    {
+        let loaded: Gradient = toml::from_str(
+            "stops = [{ position = 1.0, color = [0, 0, 1, 1] }, \
+            { position = 0.0, color = [1, 0, 0, 1] }]",
+        )
+        .unwrap();
+        assert_eq!(loaded.stops(), gradient.stops());
+    }
+    assert!(close(
+        gradient.reversed().sample(0.25),
+        Col::rgb(0.25, 0.0, 0.75)
+    ));
+}
@@ -0,0 +1,299 @@
+use std::collections::{HashMap, HashSet};
+
+use glium::{index::PrimitiveType, IndexBuffer, VertexBuffer};
+use vector2math::*;
+
+use crate::{triangulate, Canvas, Col, Color, Drawer, Rect, Resources, Trans, Vec2, Vertex};
+
+/// The shape of a cell in a `ChunkedGrid`
+#[derive(Debug, Clone, PartialEq)]
+pub enum CellShape {
+    /// A square that fills the cell
+    Square,
+    /// A square shrunk by a margin on each side
+    Inset(f32),
+    /// A polygon with vertices relative to the cell's top-left corner
+    Polygon(Vec<Vec2>),
+}
+
+/// A colored shape in a `ChunkedGrid`
+#[derive(Debug, Clone, PartialEq)]
+pub struct GridCell {
+    /// The shape
+    pub shape: CellShape,
+    /// The color
+    pub color: Col,
+}
+
+impl GridCell {
+    /// Create a new `GridCell` that fills the cell with a color
+    pub fn new<C>(color: C) -> Self
+    where
+        C: Color,
+    {
+        GridCell {
+            shape: CellShape::Square,
+            color: color.map(),
+        }
+    }
+    /// Set the shape
+    pub fn shape(self, shape: CellShape) -> Self {
+        GridCell { shape, ..self }
+    }
+}
+
+type ChunkMesh = (Col, VertexBuffer<Vertex>, IndexBuffer<u32>);
+
+/**
+Draws a large grid of cells by caching chunks of cells as meshes
+
+A closure maps each cell's column and row to an optional `GridCell`. When the grid
+is drawn with `Drawer::grid`, every visible chunk that has not been built yet is
+tessellated into one mesh per color. Chunks are only rebuilt after they are marked
+dirty, so drawing tens of thousands of cells costs only a few draw calls.
+
+Cell `[0, 0]` has its top-left corner at the origin.
+
+```no_run
+# use kule::*;
+# fn draw_grid<C: Canvas>(draw: &mut Drawer<C>) {
+let mut grid = ChunkedGrid::new(16.0, |[x, y]| {
+    if (x + y) % 2 == 0 {
+        Some(GridCell::new(Col::gray(0.2)))
+    } else {
+        None
+    }
+});
+// In `Kule::draw`
+draw.grid(&mut grid);
+// After changing the data the closure reads
+grid.mark_dirty([3, 4]);
+# }
+```
+*/
+pub struct ChunkedGrid<F> {
+    cell_size: f32,
+    chunk_size: i32,
+    keep_margin: i32,
+    cell_fn: F,
+    chunks: HashMap<[i32; 2], Vec<ChunkMesh>>,
+    dirty: HashSet<[i32; 2]>,
+}
+
+impl<F> ChunkedGrid<F>
+where
+    F: FnMut([i32; 2]) -> Option<GridCell>,
+{
+    /**
+    Create a new `ChunkedGrid` with chunks of 32 by 32 cells
+
+    # Panics
+
+    Panics if the cell size is not positive
+    */
+    pub fn new(cell_size: f32, cell_fn: F) -> Self {
+        assert!(cell_size > 0.0, "Grid cell size must be positive");
+        ChunkedGrid {
+            cell_size,
+            chunk_size: 32,
+            keep_margin: 2,
+            cell_fn,
+            chunks: HashMap::new(),
+            dirty: HashSet::new(),
+        }
+    }
+    /// Set the number of cells along each side of a chunk
+    pub fn chunk_size(self, chunk_size: u16) -> Self {
+        ChunkedGrid {
+            chunk_size: chunk_size.max(1) as i32,
+            chunks: HashMap::new(),
+            ..self
+        }
+    }
+    /// Set how many chunks outside of the view stay cached
+    pub fn keep_margin(self, keep_margin: u16) -> Self {
+        ChunkedGrid {
+            keep_margin: keep_margin as i32,
+            ..self
+        }
+    }
+    /// Get the cell size
+    pub fn cell_size(&self) -> f32 {
+        self.cell_size
+    }
+    /// Get the cell that contains a point
+    pub fn cell_at(&self, pos: Vec2) -> [i32; 2] {
+        [
+            (pos.x() / self.cell_size).floor() as i32,
+            (pos.y() / self.cell_size).floor() as i32,
+        ]
+    }
+    /// Get the rectangle a cell covers
+    pub fn cell_rect(&self, cell: [i32; 2]) -> Rect {
+        [
+            cell[0] as f32 * self.cell_size,
+            cell[1] as f32 * self.cell_size,
+            self.cell_size,
+            self.cell_size,
+        ]
+    }
+    fn chunk_of(&self, cell: [i32; 2]) -> [i32; 2] {
+        [
+            cell[0].div_euclid(self.chunk_size),
+            cell[1].div_euclid(self.chunk_size),
+        ]
+    }
+    /// Mark a cell as changed so that its chunk is rebuilt the next time it is drawn
+    pub fn mark_dirty(&mut self, cell: [i32; 2]) {
+        let chunk = self.chunk_of(cell);
+        self.dirty.insert(chunk);
+    }
+    /// Mark every cell that overlaps a rectangle as changed
+    pub fn mark_rect_dirty<R>(&mut self, rect: R)
+    where
+        R: Rectangle<Scalar = f32>,
+    {
+        let rect: Rect = rect.map();
+        let [x0, y0] = self.chunk_of(self.cell_at(rect.abs_top_left()));
+        let [x1, y1] = self.chunk_of(self.cell_at(rect.abs_bottom_right()));
+        for x in x0..=x1 {
+            for y in y0..=y1 {
+                self.dirty.insert([x, y]);
+            }
+        }
+    }
+    /// Mark every cell as changed
+    pub fn mark_all_dirty(&mut self) {
+        self.chunks.clear();
+        self.dirty.clear();
+    }
+    /// Get the number of chunks that are cached
+    pub fn cached_chunks(&self) -> usize {
+        self.chunks.len()
+    }
+    /// Get the vertices of each color's triangles in a chunk
+    fn tessellate(&mut self, chunk: [i32; 2]) -> Vec<(Col, Vec<Vec2>, Vec<u32>)> {
+        let mut by_color: Vec<(Col, Vec<Vec2>, Vec<u32>)> = Vec::new();
+        for dx in 0..self.chunk_size {
+            for dy in 0..self.chunk_size {
+                let cell = [
+                    chunk[0] * self.chunk_size + dx,
+                    chunk[1] * self.chunk_size + dy,
+                ];
+                let grid_cell = match (self.cell_fn)(cell) {
+                    Some(grid_cell) => grid_cell,
+                    None => continue,
+                };
+                let rect = self.cell_rect(cell);
+                let color = grid_cell.color;
+                let polygon = match grid_cell.shape {
+                    CellShape::Square => rect_corners(rect),
+                    CellShape::Inset(margin) => rect_corners([
+                        rect[0] + margin,
+                        rect[1] + margin,
+                        rect[2] - 2.0 * margin,
+                        rect[3] - 2.0 * margin,
+                    ]),
+                    CellShape::Polygon(vertices) => vertices
+                        .into_iter()
+                        .map(|v| v.add(rect.top_left()))
+                        .collect(),
+                };
+                let i = match by_color.iter().position(|(c, ..)| *c == color) {
+                    Some(i) => i,
+                    None => {
+                        by_color.push((color, Vec::new(), Vec::new()));
+                        by_color.len() - 1
+                    }
+                };
+                let (_, vertices, indices) = &mut by_color[i];
+                let base = vertices.len() as u32;
+                indices.extend(
+                    triangulate(&polygon)
+                        .into_iter()
+                        .flatten()
+                        .map(|j| base + j as u32),
+                );
+                vertices.extend(polygon);
+            }
+        }
+        by_color
+    }
+}
+
+fn rect_corners(rect: Rect) -> Vec<Vec2> {
+    vec![
+        rect.top_left(),
+        rect.top_right(),
+        rect.bottom_right(),
+        rect.bottom_left(),
+    ]
+}
+
+impl<'ctx, T, R> Drawer<'ctx, T, R>
+where
+    T: Canvas,
+    R: Resources,
+{
+    /**
+    Draw the visible chunks of a grid
+
+    Chunks that are missing or dirty are built first. Cached chunks far
+    outside of the view are dropped.
+    */
+    pub fn grid<F>(&mut self, grid: &mut ChunkedGrid<F>)
+    where
+        F: FnMut([i32; 2]) -> Option<GridCell>,
+    {
+        let view = self.camera.view_rect();
+        let [x0, y0] = grid.chunk_of(grid.cell_at(view.abs_top_left()));
+        let [x1, y1] = grid.chunk_of(grid.cell_at(view.abs_bottom_right()));
+        for chunk in std::mem::take(&mut grid.dirty) {
+            grid.chunks.remove(&chunk);
+        }
+        let margin = grid.keep_margin;
+        grid.chunks.retain(|[x, y], _| {
+            *x >= x0 - margin && *x <= x1 + margin && *y >= y0 - margin && *y <= y1 + margin
+        });
+        for x in x0..=x1 {
+            for y in y0..=y1 {
+                if !grid.chunks.contains_key(&[x, y]) {
+                    let facade = self.facade();
+                    let meshes = grid
+                        .tessellate([x, y])
+                        .into_iter()
+                        .map(|(color, vertices, indices)| {
+                            let vertices: Vec<Vertex> =
+                                vertices.into_iter().map(|pos| Vertex { pos }).collect();
+                            (
+                                color,
+                                VertexBuffer::new(facade, &vertices).unwrap(),
+                                IndexBuffer::new(facade, PrimitiveType::TrianglesList, &indices)
+                                    .unwrap(),
+                            )
+                        })
+                        .collect();
+                    grid.chunks.insert([x, y], meshes);
+                }
+                for (color, vertices, indices) in &grid.chunks[&[x, y]] {
+                    self.draw_buffers(vertices, indices, *color, Trans::identity());
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn grid_chunks() {
+    let mut grid = ChunkedGrid::new(10.0, |_| Some(GridCell::new(Col::white()))).chunk_size(4);
+    assert_eq!(grid.cell_at([-0.5, 25.0]), [-1, 2]);
+    assert_eq!(grid.cell_rect([-1, 2]), [-10.0, 20.0, 10.0, 10.0]);
+    assert_eq!(grid.chunk_of([-1, 4]), [-1, 1]);
+    grid.mark_rect_dirty([0.0, 0.0, 45.0, 5.0]);
+    assert_eq!(grid.dirty.len(), 2);
+    let meshes = grid.tessellate([0, 0]);
+    assert_eq!(meshes.len(), 1);
+    assert_eq!(meshes[0].1.len(), 64);
+    assert_eq!(meshes[0].2.len(), 96);
+}
@@ -0,0 +1,337 @@
+use std::collections::HashMap;
+
+use crate::{Key, MouseButton, PhysicalKey, StateTracker};
+
+/// A physical input that can be bound to an action
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "ser",
+    derive(serde_derive::Serialize, serde_derive::Deserialize)
+)]
+pub enum Input {
+    /// A keyboard key
+    Key(Key),
+    /// A keyboard key identified by its physical position
+    PhysicalKey(PhysicalKey),
+    /// A mouse button
+    MouseButton(MouseButton),
+}
+
+impl From<Key> for Input {
+    fn from(key: Key) -> Self {
+        Input::Key(key)
+    }
+}
+
+impl From<PhysicalKey> for Input {
+    fn from(key: PhysicalKey) -> Self {
+        Input::PhysicalKey(key)
+    }
+}
+
+impl From<MouseButton> for Input {
+    fn from(button: MouseButton) -> Self {
+        Input::MouseButton(button)
+    }
+}
+
+impl Input {
+    /// Check if the input is currently held
+    pub fn is_held(self, tracker: &StateTracker) -> bool {
+        match self {
+            Input::Key(key) => tracker.key(key),
+            Input::PhysicalKey(key) => tracker.physical_key(key),
+            Input::MouseButton(button) => tracker.mouse_button(button),
+        }
+    }
+}
+
+/// A binding of an input to an action
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(
+    feature = "ser",
+    derive(serde_derive::Serialize, serde_derive::Deserialize)
+)]
+pub struct Binding {
+    /// The bound input
+    pub input: Input,
+    /// The value the input contributes to the action's axis while it is held
+    pub value: f32,
+}
+
+/**
+Maps named actions to inputs
+
+Actions can be queried as buttons with `InputMap::pressed` or as axes with `InputMap::axis`.
+
+Input maps can be serialized, so they can be saved to let users rebind their controls.
+
+```
+# use kule::*;
+let mut input_map = InputMap::new();
+input_map.bind("jump", Key::Space);
+input_map.bind("jump", MouseButton::Left);
+input_map.bind_axis("move_x", Key::A, Key::D);
+```
+*/
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(
+    feature = "ser",
+    derive(serde_derive::Serialize, serde_derive::Deserialize)
+)]
+pub struct InputMap {
+    actions: HashMap<String, Vec<Binding>>,
+}
+
+impl InputMap {
+    /// Create a new empty `InputMap`
+    pub fn new() -> Self {
+        InputMap::default()
+    }
+    /// Bind an input to an action
+    pub fn bind<A, I>(&mut self, action: A, input: I)
+    where
+        A: Into<String>,
+        I: Into<Input>,
+    {
+        self.bind_value(action, input, 1.0)
+    }
+    /// Bind an input to an action with the value it contributes to the action's axis
+    pub fn bind_value<A, I>(&mut self, action: A, input: I, value: f32)
+    where
+        A: Into<String>,
+        I: Into<Input>,
+    {
+        let input = input.into();
+        let bindings = self.actions.entry(action.into()).or_default();
+        bindings.retain(|binding| binding.input != input);
+        bindings.push(Binding { input, value });
+    }
+    /// Bind a pair of inputs to the negative and positive directions of an axis action
+    pub fn bind_axis<A, N, P>(&mut self, action: A, neg: N, pos: P)
+    where
+        A: Into<String>,
+        N: Into<Input>,
+        P: Into<Input>,
+    {
+        let action = action.into();
+        self.bind_value(action.clone(), neg, -1.0);
+        self.bind_value(action, pos, 1.0);
+    }
+    /// Unbind an input from an action
+    pub fn unbind<I>(&mut self, action: &str, input: I)
+    where
+        I: Into<Input>,
+    {
+        let input = input.into();
+        if let Some(bindings) = self.actions.get_mut(action) {
+            bindings.retain(|binding| binding.input != input);
+        }
+    }
+    /// Remove all of an action's bindings
+    pub fn clear(&mut self, action: &str) {
+        self.actions.remove(action);
+    }
+    /// Get an action's bindings
+    pub fn bindings(&self, action: &str) -> &[Binding] {
+        self.actions.get(action).map(Vec::as_slice).unwrap_or(&[])
+    }
+    /// Get an iterator over all action names
+    pub fn actions(&self) -> impl Iterator<Item = &str> {
+        self.actions.keys().map(String::as_str)
+    }
+    /// Get an iterator over the actions an input is bound to
+    pub fn actions_for<I>(&self, input: I) -> impl Iterator<Item = &str>
+    where
+        I: Into<Input>,
+    {
+        let input = input.into();
+        self.actions
+            .iter()
+            .filter(move |(_, bindings)| bindings.iter().any(|binding| binding.input == input))
+            .map(|(action, _)| action.as_str())
+    }
+    /// Check if any of an action's inputs are held
+    pub fn pressed(&self, action: &str, tracker: &StateTracker) -> bool {
+        self.bindings(action)
+            .iter()
+            .any(|binding| binding.input.is_held(tracker))
+    }
+    /**
+    Get the value of an axis action
+
+    This is the sum of the values of all held inputs, clamped between `-1.0` and `1.0`
+    */
+    pub fn axis(&self, action: &str, tracker: &StateTracker) -> f32 {
+        self.bindings(action)
+            .iter()
+            .filter(|binding| binding.input.is_held(tracker))
+            .map(|binding| binding.value)
+            .sum::<f32>()
+            .clamp(-1.0, 1.0)
+    }
+    #[cfg(feature = "script")]
+    /// Load an input map from a toml file
+    pub fn load<P>(path: P) -> crate::KuleResult<Self>
+    where
+        P: AsRef<std::path::Path>,
+    {
+        let text = std::fs::read_to_string(path)?;
+        Ok(toml::from_str(&text)?)
+    }
+    #[cfg(feature = "script")]
+    /// Save the input map to a toml file
+    pub fn save<P>(&self, path: P) -> crate::CanFail
+    where
+        P: AsRef<std::path::Path>,
+    {
+        std::fs::write(path, toml::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+/// A source of input for an `Axis`
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(
+    feature = "ser",
+    derive(serde_derive::Serialize, serde_derive::Deserialize)
+)]
+pub enum AxisSource {
+    /// A pair of inputs for the negative and positive directions
+    Inputs {
+        /// The input for the negative direction
+        neg: Input,
+        /// The input for the positive direction
+        pos: Input,
+    },
+    /// Horizontal mouse movement in pixels
+    MouseX,
+    /// Vertical mouse movement in pixels
+    MouseY,
+    /// Horizontal scroll wheel movement
+    ScrollX,
+    /// Vertical scroll wheel movement
+    ScrollY,
+}
+
+impl AxisSource {
+    fn value(self, tracker: &StateTracker) -> f32 {
+        match self {
+            AxisSource::Inputs { neg, pos } => {
+                pos.is_held(tracker) as i8 as f32 - neg.is_held(tracker) as i8 as f32
+            }
+            AxisSource::MouseX => tracker.mouse_delta()[0],
+            AxisSource::MouseY => tracker.mouse_delta()[1],
+            AxisSource::ScrollX => tracker.scroll_delta()[0],
+            AxisSource::ScrollY => tracker.scroll_delta()[1],
+        }
+    }
+}
+
+/**
+A virtual axis that combines several input sources into a single value from `-1.0` to `1.0`
+
+Axes are added to the `StateTracker` by name and updated before every update.
+
+```
+# use kule::*;
+let axis = Axis::new()
+    .inputs(Key::A, Key::D)
+    .source(AxisSource::MouseX)
+    .sensitivity(0.1)
+    .dead_zone(0.2)
+    .smoothing(0.05);
+```
+*/
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "ser",
+    derive(serde_derive::Serialize, serde_derive::Deserialize)
+)]
+pub struct Axis {
+    /// The axis's input sources
+    pub sources: Vec<AxisSource>,
+    /// The value below which the axis is considered to be `0.0`
+    ///
+    /// Values above the dead zone are rescaled so that the axis still goes smoothly from `0.0` to `1.0`
+    pub dead_zone: f32,
+    /// The value that raw input is multiplied by
+    pub sensitivity: f32,
+    /// The approximate time in seconds it takes the axis to reach a new value
+    pub smoothing: f32,
+    value: f32,
+}
+
+impl Default for Axis {
+    fn default() -> Self {
+        Axis::new()
+    }
+}
+
+impl Axis {
+    /// Create a new `Axis` with no sources, no dead zone, no smoothing, and a sensitivity of `1.0`
+    pub const fn new() -> Self {
+        Axis {
+            sources: Vec::new(),
+            dead_zone: 0.0,
+            sensitivity: 1.0,
+            smoothing: 0.0,
+            value: 0.0,
+        }
+    }
+    /// Add an input source
+    pub fn source(mut self, source: AxisSource) -> Self {
+        self.sources.push(source);
+        self
+    }
+    /// Add a pair of inputs for the negative and positive directions
+    pub fn inputs<N, P>(self, neg: N, pos: P) -> Self
+    where
+        N: Into<Input>,
+        P: Into<Input>,
+    {
+        self.source(AxisSource::Inputs {
+            neg: neg.into(),
+            pos: pos.into(),
+        })
+    }
+    /// Set the dead zone
+    pub fn dead_zone(self, dead_zone: f32) -> Self {
+        Axis { dead_zone, ..self }
+    }
+    /// Set the sensitivity
+    pub fn sensitivity(self, sensitivity: f32) -> Self {
+        Axis {
+            sensitivity,
+            ..self
+        }
+    }
+    /// Set the smoothing time
+    pub fn smoothing(self, smoothing: f32) -> Self {
+        Axis { smoothing, ..self }
+    }
+    /// Get the axis's current value
+    pub fn value(&self) -> f32 {
+        self.value
+    }
+    pub(crate) fn update(&mut self, dt: f32, tracker: &StateTracker) {
+        let raw = (self
+            .sources
+            .iter()
+            .map(|source| source.value(tracker))
+            .sum::<f32>()
+            * self.sensitivity)
+            .clamp(-1.0, 1.0);
+        let target = if raw.abs() <= self.dead_zone {
+            0.0
+        } else {
+            raw.signum() * (raw.abs() - self.dead_zone) / (1.0 - self.dead_zone)
+        };
+        let t = if self.smoothing > 0.0 {
+            (dt / self.smoothing).min(1.0)
+        } else {
+            1.0
+        };
+        self.value += (target - self.value) * t;
+    }
+}
@@ -0,0 +1,103 @@
+/// Trait for values that can be linearly interpolated
+pub trait Interpolate: Copy {
+    /// Interpolate between this value and another, where `t` is from `0.0` to `1.0`
+    fn interpolate(self, other: Self, t: f32) -> Self;
+}
+
+impl Interpolate for f32 {
+    fn interpolate(self, other: Self, t: f32) -> Self {
+        self + (other - self) * t
+    }
+}
+
+impl Interpolate for f64 {
+    fn interpolate(self, other: Self, t: f32) -> Self {
+        self + (other - self) * t as f64
+    }
+}
+
+macro_rules! array_interpolate {
+    ($($n:literal),*) => {
+        $(
+            impl Interpolate for [f32; $n] {
+                fn interpolate(mut self, other: Self, t: f32) -> Self {
+                    for (a, b) in self.iter_mut().zip(other.iter()) {
+                        *a = a.interpolate(*b, t);
+                    }
+                    self
+                }
+            }
+        )*
+    };
+}
+
+array_interpolate!(2, 3, 4);
+
+/**
+A value that changes at fixed updates but is drawn smoothly
+
+The value from the previous update is kept alongside the current one. When drawing,
+`Interpolated::get` blends them using `Context::alpha`, so motion looks smooth even
+when frames are drawn more often than updates are run.
+
+```
+# use kule::*;
+let mut pos = Interpolated::new([0.0, 0.0]);
+pos.set([10.0, 0.0]);
+assert_eq!(pos.get(0.5), [5.0, 0.0]);
+```
+*/
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(
+    feature = "ser",
+    derive(serde_derive::Serialize, serde_derive::Deserialize)
+)]
+pub struct Interpolated<T> {
+    previous: T,
+    current: T,
+}
+
+impl<T> Interpolated<T>
+where
+    T: Interpolate,
+{
+    /// Create a new `Interpolated` that starts at a value
+    pub fn new(value: T) -> Self {
+        Interpolated {
+            previous: value,
+            current: value,
+        }
+    }
+    /// Set a new current value, keeping the old one as the previous value
+    ///
+    /// This should be called once per update
+    pub fn set(&mut self, value: T) {
+        self.previous = self.current;
+        self.current = value;
+    }
+    /// Modify the current value, keeping the old one as the previous value
+    pub fn update<F>(&mut self, f: F)
+    where
+        F: FnOnce(T) -> T,
+    {
+        self.set(f(self.current));
+    }
+    /// Set both the previous and current value, so that the change is not interpolated
+    ///
+    /// This is useful for teleporting
+    pub fn snap(&mut self, value: T) {
+        *self = Interpolated::new(value);
+    }
+    /// Get the current value
+    pub fn current(&self) -> T {
+        self.current
+    }
+    /// Get the previous value
+    pub fn previous(&self) -> T {
+        self.previous
+    }
+    /// Get the value between the previous and current values, where `alpha` is from `0.0` to `1.0`
+    pub fn get(&self, alpha: f32) -> T {
+        self.previous.interpolate(self.current, alpha)
+    }
+}
@@ -41,16 +41,96 @@ pub use error::*;
 mod event;
 pub use event::Event;
 pub use event::*;
+mod gesture;
+pub use gesture::*;
+mod input;
+pub use input::*;
+mod combo;
+pub use combo::*;
+mod focus;
+pub use focus::*;
+mod scene;
+pub use scene::*;
+mod coroutine;
+pub use coroutine::*;
+mod watch;
+pub use watch::*;
+mod loader;
+pub use loader::*;
+mod diagnostics;
+pub use diagnostics::*;
+mod profile;
+pub use profile::*;
+mod rng;
+pub use rng::*;
+mod reskey;
+pub use reskey::*;
+mod runner;
+pub use runner::*;
+mod system;
+pub use system::*;
+mod interpolate;
+pub use interpolate::*;
+mod ecs;
+pub use ecs::*;
+mod collide;
+pub use collide::*;
+mod spatial;
+pub use spatial::*;
+mod nav;
+pub use nav::*;
+mod tween;
+pub use tween::*;
+mod timeline;
+pub use timeline::*;
+mod skeleton;
+pub use skeleton::*;
+mod polygon;
+pub use polygon::*;
+mod shapes;
+pub use shapes::*;
+mod grid;
+pub use grid::*;
+#[cfg(feature = "manifest")]
+mod manifest;
+#[cfg(feature = "manifest")]
+pub use manifest::*;
 mod draw;
 pub use draw::*;
 mod color;
 pub use color::*;
+mod gradient;
+pub use gradient::*;
+mod colormap;
+pub use colormap::*;
 mod font;
 pub use font::*;
 #[cfg(feature = "sound")]
 mod sound;
 #[cfg(feature = "sound")]
 pub use sound::*;
+#[cfg(feature = "capture")]
+mod capture;
+#[cfg(any(feature = "opus", feature = "aac"))]
+mod codec;
+#[cfg(feature = "capture")]
+pub use capture::*;
+#[cfg(feature = "sound")]
+mod effect;
+#[cfg(feature = "sound")]
+pub use effect::*;
+#[cfg(feature = "sound")]
+mod music;
+#[cfg(feature = "sound")]
+pub use music::*;
+#[cfg(feature = "sound")]
+mod synth;
+#[cfg(feature = "sound")]
+pub use synth::*;
+#[cfg(feature = "save")]
+mod saves;
+#[cfg(feature = "save")]
+pub use saves::*;
 #[cfg(feature = "script")]
 mod script;
 #[cfg(feature = "script")]
@@ -114,17 +194,16 @@ mod test {
                     state: ButtonState::Pressed,
                 } => app.pos = ctx.mouse_coords(),
                 Event::Scroll([_, y]) => {
-                    let old_coords = ctx.mouse_coords();
-                    ctx.camera = ctx.camera.zoom_by(1.1f32.powf(y));
-                    let new_coords = ctx.mouse_coords();
-                    ctx.camera.center.sub_assign(new_coords.sub(old_coords));
+                    ctx.camera = ctx.camera.zoom_at(ctx.tracker.mouse_pos(), 1.1f32.powf(y))
                 }
                 Event::Key {
                     key,
                     state: ButtonState::Pressed,
                     ..
                 } => match key {
-                    Key::Space => ctx.play_sound("examples/kick.ogg", app).unwrap(),
+                    Key::Space => {
+                        ctx.play_sound("examples/kick.ogg", app).unwrap();
+                    }
                     Key::T => {
                         if let Ok(scripts) = ctx.scripts() {
                             scripts
@@ -173,8 +252,9 @@ mod test {
             draw.circle([1.0, 1.0, 0.0, 0.3], (ctx.mouse_coords(), 5.0), 10);
             Ok(())
         }
-        fn teardown(app: Self, _: &mut Context<Recs>) {
+        fn teardown(app: Self, _: &mut Context<Recs>) -> CanFail {
             println!("{:?}", app);
+            Ok(())
         }
         fn load_sound(
             sound_id: &'static str,
@@ -188,4 +268,129 @@ mod test {
     fn test() {
         App::run_or_panic();
     }
+
+    #[test]
+    fn headless() {
+        struct Counter(u32);
+        impl Kule for Counter {
+            type Resources = ();
+            fn setup(_: &mut Context) -> KuleResult<Self> {
+                Ok(Counter(0))
+            }
+            fn update(_: f32, app: &mut Self, _: &mut Context) -> CanFail {
+                app.0 += 1;
+                Ok(())
+            }
+        }
+        let mut ctx = Context::headless(&ContextBuilder::new()).unwrap();
+        let mut app = Counter::setup(&mut ctx).unwrap();
+        Counter::update(0.1, &mut app, &mut ctx).unwrap();
+        Counter::update(0.1, &mut app, &mut ctx).unwrap();
+        assert_eq!(app.0, 2);
+        assert!(ctx.window.is_headless());
+        // Run a few frames through the headless loop
+        static FRAMES: std::sync::atomic::AtomicU32 = std::sync::atomic::AtomicU32::new(0);
+        struct Frames;
+        impl Kule for Frames {
+            type Resources = ();
+            fn setup(ctx: &mut Context) -> KuleResult<Self> {
+                assert!(ctx.window.is_headless());
+                Ok(Frames)
+            }
+            fn late_update(_: &mut Self, ctx: &mut Context) -> CanFail {
+                let frames = FRAMES.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1;
+                if frames == 5 {
+                    ctx.request_exit(0);
+                }
+                Ok(())
+            }
+        }
+        let builder = ContextBuilder::new()
+            .headless(true)
+            .update_frequency(1000.0);
+        assert_eq!(Frames::run_headless(builder).unwrap(), 0);
+        assert_eq!(FRAMES.load(std::sync::atomic::Ordering::SeqCst), 5);
+    }
+
+    #[test]
+    fn exit_code() {
+        struct Quitter(u32);
+        impl Kule for Quitter {
+            type Resources = ();
+            fn build() -> KuleResult<ContextBuilder> {
+                Ok(ContextBuilder::new()
+                    .headless(true)
+                    .update_frequency(1000.0))
+            }
+            fn setup(_: &mut Context) -> KuleResult<Self> {
+                Ok(Quitter(0))
+            }
+            fn update(_: f32, app: &mut Self, ctx: &mut Context) -> CanFail {
+                app.0 += 1;
+                if app.0 == 3 {
+                    ctx.request_exit(3);
+                }
+                Ok(())
+            }
+        }
+        assert_eq!(Quitter::run().unwrap(), 3);
+    }
+
+    #[test]
+    fn error_policy() {
+        struct Failer;
+        impl Kule for Failer {
+            type Resources = ();
+            fn build() -> KuleResult<ContextBuilder> {
+                Ok(ContextBuilder::new()
+                    .headless(true)
+                    .error_policy(ErrorPolicy::Exit(2)))
+            }
+            fn setup(_: &mut Context) -> KuleResult<Self> {
+                Ok(Failer)
+            }
+            fn update(_: f32, _: &mut Self, _: &mut Context) -> CanFail {
+                Err(KuleError::Static("failed"))
+            }
+        }
+        assert_eq!(Failer::run().unwrap(), 2);
+    }
+
+    #[test]
+    fn deterministic() {
+        struct Sim(Vec<(u64, u32)>);
+        impl Kule for Sim {
+            type Resources = ();
+            fn build() -> KuleResult<ContextBuilder> {
+                Ok(ContextBuilder::new()
+                    .headless(true)
+                    .update_frequency(1000.0)
+                    .deterministic(7))
+            }
+            fn setup(ctx: &mut Context) -> KuleResult<Self> {
+                ctx.queue_event(Event::Focus(true));
+                Ok(Sim(Vec::new()))
+            }
+            fn event(event: Event, app: &mut Self, ctx: &mut Context) -> CanFail {
+                assert_eq!(event, Event::Focus(true));
+                assert_eq!(ctx.ticks(), 0);
+                app.0.push((ctx.ticks(), 0));
+                Ok(())
+            }
+            fn update(_: f32, app: &mut Self, ctx: &mut Context) -> CanFail {
+                let n = ctx.rng().next_u32();
+                app.0.push((ctx.ticks(), n));
+                if ctx.ticks() == 3 {
+                    let sum = app
+                        .0
+                        .iter()
+                        .map(|&(t, n)| t as u32 ^ n)
+                        .fold(0, u32::wrapping_add);
+                    ctx.request_exit((sum % 100) as i32);
+                }
+                Ok(())
+            }
+        }
+        assert_eq!(Sim::run().unwrap(), Sim::run().unwrap());
+    }
 }
@@ -0,0 +1,214 @@
+use std::{collections::HashMap, sync::mpsc, thread};
+
+use fontdue::Font;
+
+#[cfg(feature = "sound")]
+use crate::SoundBuffer;
+use crate::{KuleError, KuleResult, Resources, Vec2};
+
+/// An id for a background asset load
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "ser",
+    derive(serde_derive::Serialize, serde_derive::Deserialize)
+)]
+pub struct LoadId(pub u64);
+
+/// The data produced by a background load
+enum Payload {
+    Font(Font),
+    #[cfg(feature = "sound")]
+    Sound(Option<SoundBuffer>),
+    Mesh(Vec<Vec2>),
+}
+
+type LoadResult = (LoadId, Result<Payload, String>);
+
+/// An asset that has finished loading
+pub(crate) enum Loaded<R>
+where
+    R: Resources,
+{
+    Font(R::FontId, Font),
+    #[cfg(feature = "sound")]
+    Sound(R::SoundId, Option<SoundBuffer>),
+    Mesh(R::MeshId, Vec<Vec2>),
+}
+
+/// The id of the asset a load is for
+#[derive(Clone, Copy)]
+enum Target<R>
+where
+    R: Resources,
+{
+    Font(R::FontId),
+    #[cfg(feature = "sound")]
+    Sound(R::SoundId),
+    Mesh(R::MeshId),
+}
+
+/**
+Loads fonts, sounds, and meshes on background threads
+
+Loads can be queued during `Kule::setup` or later. Each queued load runs on its own
+thread. Finished assets are moved into the context's caches by the engine, and an
+`Event::AssetLoaded` is sent for each one. Once every queued load has finished,
+`Event::LoadingFinished` is sent.
+
+Use `AssetLoader::progress` to animate a loading screen.
+*/
+pub struct AssetLoader<R = ()>
+where
+    R: Resources,
+{
+    send: mpsc::Sender<LoadResult>,
+    recv: mpsc::Receiver<LoadResult>,
+    targets: HashMap<LoadId, Target<R>>,
+    total: usize,
+    loaded: usize,
+    next_id: u64,
+}
+
+impl<R> Default for AssetLoader<R>
+where
+    R: Resources,
+{
+    fn default() -> Self {
+        let (send, recv) = mpsc::channel();
+        AssetLoader {
+            send,
+            recv,
+            targets: HashMap::new(),
+            total: 0,
+            loaded: 0,
+            next_id: 0,
+        }
+    }
+}
+
+impl<R> AssetLoader<R>
+where
+    R: Resources,
+{
+    fn spawn<F>(&mut self, target: Target<R>, load: F) -> LoadId
+    where
+        F: FnOnce() -> Result<Payload, String> + Send + 'static,
+    {
+        let id = LoadId(self.next_id);
+        self.next_id += 1;
+        self.total += 1;
+        self.targets.insert(id, target);
+        let send = self.send.clone();
+        thread::spawn(move || {
+            let _ = send.send((id, load()));
+        });
+        id
+    }
+    /**
+    Load a font in the background
+
+    The `load` function gets the font's bytes. The font is parsed on the background thread as well.
+    */
+    pub fn load_font<F>(&mut self, font_id: R::FontId, load: F) -> LoadId
+    where
+        F: FnOnce() -> KuleResult<Vec<u8>> + Send + 'static,
+    {
+        self.spawn(Target::Font(font_id), move || {
+            let bytes = load().map_err(|e| e.to_string())?;
+            let font = Font::from_bytes(bytes, Default::default())?;
+            Ok(Payload::Font(font))
+        })
+    }
+    #[cfg(feature = "sound")]
+    /**
+    Load a sound in the background
+
+    Because the app cannot be shared with another thread, the sound is loaded
+    with the given function rather than `Kule::load_sound`
+    */
+    pub fn load_sound<F>(&mut self, sound_id: R::SoundId, load: F) -> LoadId
+    where
+        F: FnOnce() -> KuleResult<Option<SoundBuffer>> + Send + 'static,
+    {
+        self.spawn(Target::Sound(sound_id), move || {
+            let buffer = load().map_err(|e| e.to_string())?;
+            Ok(Payload::Sound(buffer))
+        })
+    }
+    /**
+    Generate a mesh in the background
+
+    The `load` function gets the vertices of a polygon. Once the mesh is cached,
+    it can be drawn with `Drawer::mesh`.
+    */
+    pub fn load_mesh<F>(&mut self, mesh_id: R::MeshId, load: F) -> LoadId
+    where
+        F: FnOnce() -> KuleResult<Vec<Vec2>> + Send + 'static,
+    {
+        self.spawn(Target::Mesh(mesh_id), move || {
+            let vertices = load().map_err(|e| e.to_string())?;
+            Ok(Payload::Mesh(vertices))
+        })
+    }
+    /// Get the number of loads that have finished
+    pub fn loaded(&self) -> usize {
+        self.loaded
+    }
+    /// Get the total number of queued loads
+    pub fn total(&self) -> usize {
+        self.total
+    }
+    /// Get the loading progress from `0.0` to `1.0`
+    pub fn progress(&self) -> f32 {
+        if self.total == 0 {
+            1.0
+        } else {
+            self.loaded as f32 / self.total as f32
+        }
+    }
+    /// Check if all queued loads have finished
+    pub fn is_done(&self) -> bool {
+        self.loaded == self.total
+    }
+    /// Get the loads that have finished since the last poll
+    pub(crate) fn poll(&mut self) -> Vec<(LoadId, KuleResult<Loaded<R>>)> {
+        let mut finished = Vec::new();
+        for (id, payload) in self.recv.try_iter() {
+            self.loaded += 1;
+            let target = self.targets.remove(&id).expect("Unknown load id");
+            let loaded =
+                payload
+                    .map_err(KuleError::AssetLoad)
+                    .map(|payload| match (target, payload) {
+                        (Target::Font(font_id), Payload::Font(font)) => Loaded::Font(font_id, font),
+                        #[cfg(feature = "sound")]
+                        (Target::Sound(sound_id), Payload::Sound(buffer)) => {
+                            Loaded::Sound(sound_id, buffer)
+                        }
+                        (Target::Mesh(mesh_id), Payload::Mesh(vertices)) => {
+                            Loaded::Mesh(mesh_id, vertices)
+                        }
+                        _ => unreachable!("Load payload does not match its target"),
+                    });
+            finished.push((id, loaded));
+        }
+        finished
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn loader_progress() {
+    let mut loader = AssetLoader::<()>::default();
+    assert_eq!(loader.progress(), 1.0);
+    let id = loader.load_mesh((), || Ok(vec![[0.0; 2], [1.0, 0.0], [0.0, 1.0]]));
+    loader.load_mesh((), || Err(KuleError::Static("oops")));
+    assert_eq!(loader.total(), 2);
+    let mut finished = Vec::new();
+    while !loader.is_done() {
+        finished.extend(loader.poll());
+    }
+    assert_eq!(loader.progress(), 1.0);
+    assert!(finished.iter().any(|(i, res)| *i == id && res.is_ok()));
+    assert!(finished.iter().any(|(i, res)| *i != id && res.is_err()));
+}
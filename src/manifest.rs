@@ -0,0 +1,284 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    convert::TryInto,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde_derive::{Deserialize, Serialize};
+
+use crate::{KuleError, KuleResult, Vec2};
+
+/// The vertices of a mesh listed in an asset manifest
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum MeshSource {
+    /// A path to a toml file with a `vertices` list
+    Path(PathBuf),
+    /// The vertices of a polygon
+    Vertices(Vec<Vec2>),
+}
+
+#[derive(Deserialize)]
+struct MeshFile {
+    vertices: Vec<Vec2>,
+}
+
+/**
+A list of assets to load with `Context::load_manifest`
+
+Manifests are written in toml. Assets are listed by id, and paths are
+relative to the manifest file.
+
+```toml
+scripts = "modules"
+
+[fonts]
+title = "fonts/title.ttf"
+
+[sounds]
+jump = "sounds/jump.ogg"
+
+[meshes]
+star = "sprites/star.toml"
+triangle = [[0, -1], [1, 1], [-1, 1]]
+```
+
+Ids are turned into `ResKey`s, so resource ids must implement `From<ResKey>`.
+`KeyedResources` works out of the box.
+*/
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AssetManifest {
+    /// The directory that contains script modules
+    ///
+    /// Every file in the directory is included in asset packs
+    #[serde(default)]
+    pub scripts: Option<PathBuf>,
+    /// Font file paths
+    #[serde(default)]
+    pub fonts: BTreeMap<String, PathBuf>,
+    /// Sound file paths
+    #[serde(default)]
+    pub sounds: BTreeMap<String, PathBuf>,
+    /// Mesh vertices or file paths
+    #[serde(default)]
+    pub meshes: BTreeMap<String, MeshSource>,
+}
+
+impl AssetManifest {
+    /// Parse a manifest from toml
+    pub fn from_toml(text: &str) -> KuleResult<Self> {
+        Ok(toml::from_str(text)?)
+    }
+    /// Get the paths of all the files the manifest references, except for scripts
+    pub fn files(&self) -> impl Iterator<Item = &Path> {
+        self.fonts
+            .values()
+            .chain(self.sounds.values())
+            .map(PathBuf::as_path)
+            .chain(self.meshes.values().filter_map(|mesh| match mesh {
+                MeshSource::Path(path) => Some(path.as_path()),
+                MeshSource::Vertices(_) => None,
+            }))
+    }
+}
+
+/// Read the vertices of a mesh
+pub(crate) fn mesh_vertices<F>(mesh: &MeshSource, read: F) -> KuleResult<Vec<Vec2>>
+where
+    F: FnOnce(&Path) -> KuleResult<Vec<u8>>,
+{
+    match mesh {
+        MeshSource::Vertices(vertices) => Ok(vertices.clone()),
+        MeshSource::Path(path) => {
+            let bytes = read(path)?;
+            let text = String::from_utf8(bytes)
+                .map_err(|e| KuleError::AssetLoad(format!("{}: {}", path.display(), e)))?;
+            let file: MeshFile = toml::from_str(&text)?;
+            Ok(file.vertices)
+        }
+    }
+}
+
+const PACK_MAGIC: &[u8; 8] = b"KULEPACK";
+const PACK_MANIFEST: &str = "manifest.toml";
+
+/**
+A manifest and all of the files it references packed into a single file
+
+Packs are created with `AssetPack::from_manifest` and can be passed to
+`Context::load_manifest` in place of the manifest itself.
+*/
+#[derive(Debug, Clone, Default)]
+pub struct AssetPack {
+    files: HashMap<String, Vec<u8>>,
+}
+
+fn pack_key(path: &Path) -> String {
+    path.components()
+        .map(|comp| comp.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Add every file in a directory to a pack, keyed by its path relative to `base`
+fn pack_dir(files: &mut HashMap<String, Vec<u8>>, base: &Path, rel: &Path) -> KuleResult<()> {
+    for entry in fs::read_dir(base.join(rel))? {
+        let entry = entry?;
+        let rel = rel.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            pack_dir(files, base, &rel)?;
+        } else {
+            files.insert(pack_key(&rel), fs::read(entry.path())?);
+        }
+    }
+    Ok(())
+}
+
+fn bad_pack() -> KuleError {
+    KuleError::AssetLoad("Invalid asset pack".into())
+}
+
+impl AssetPack {
+    /// Pack a manifest file and every file it references
+    pub fn from_manifest<P>(path: P) -> KuleResult<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let base = path.parent().unwrap_or_else(|| Path::new(""));
+        let text = fs::read_to_string(path)?;
+        let manifest = AssetManifest::from_toml(&text)?;
+        let mut files = HashMap::new();
+        for file in manifest.files() {
+            files.insert(pack_key(file), fs::read(base.join(file))?);
+        }
+        if let Some(scripts) = &manifest.scripts {
+            pack_dir(&mut files, base, scripts)?;
+        }
+        files.insert(PACK_MANIFEST.into(), text.into_bytes());
+        Ok(AssetPack { files })
+    }
+    /**
+    Pack every file in a directory
+
+    Paths in the pack are relative to the directory. This is useful for
+    distributing a set of script modules as a single file.
+    */
+    pub fn from_dir<P>(dir: P) -> KuleResult<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let mut files = HashMap::new();
+        pack_dir(&mut files, dir.as_ref(), Path::new(""))?;
+        Ok(AssetPack { files })
+    }
+    /// Check if some bytes are an asset pack
+    pub fn is_pack(bytes: &[u8]) -> bool {
+        bytes.starts_with(PACK_MAGIC)
+    }
+    /// Read a pack from its bytes
+    pub fn from_bytes(bytes: &[u8]) -> KuleResult<Self> {
+        fn take<'a>(bytes: &mut &'a [u8], n: usize) -> KuleResult<&'a [u8]> {
+            if bytes.len() < n {
+                return Err(bad_pack());
+            }
+            let (taken, rest) = bytes.split_at(n);
+            *bytes = rest;
+            Ok(taken)
+        }
+        let mut bytes = bytes;
+        if take(&mut bytes, PACK_MAGIC.len())? != PACK_MAGIC {
+            return Err(bad_pack());
+        }
+        let count = u32::from_le_bytes(take(&mut bytes, 4)?.try_into().unwrap());
+        let mut files = HashMap::new();
+        for _ in 0..count {
+            let name_len = u32::from_le_bytes(take(&mut bytes, 4)?.try_into().unwrap());
+            let name = String::from_utf8(take(&mut bytes, name_len as usize)?.to_vec())
+                .map_err(|_| bad_pack())?;
+            let data_len = u64::from_le_bytes(take(&mut bytes, 8)?.try_into().unwrap());
+            let data = take(&mut bytes, data_len as usize)?.to_vec();
+            files.insert(name, data);
+        }
+        Ok(AssetPack { files })
+    }
+    /// Get the bytes of the pack
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = PACK_MAGIC.to_vec();
+        bytes.extend((self.files.len() as u32).to_le_bytes());
+        let mut names: Vec<&String> = self.files.keys().collect();
+        names.sort();
+        for name in names {
+            let data = &self.files[name];
+            bytes.extend((name.len() as u32).to_le_bytes());
+            bytes.extend(name.as_bytes());
+            bytes.extend((data.len() as u64).to_le_bytes());
+            bytes.extend(data);
+        }
+        bytes
+    }
+    /// Read a pack from a file
+    pub fn load<P>(path: P) -> KuleResult<Self>
+    where
+        P: AsRef<Path>,
+    {
+        AssetPack::from_bytes(&fs::read(path)?)
+    }
+    /// Write the pack to a file
+    pub fn save<P>(&self, path: P) -> KuleResult<()>
+    where
+        P: AsRef<Path>,
+    {
+        Ok(fs::write(path, self.to_bytes())?)
+    }
+    /// Get the manifest
+    pub fn manifest(&self) -> KuleResult<AssetManifest> {
+        let bytes = self.get(Path::new(PACK_MANIFEST))?;
+        let text = std::str::from_utf8(bytes).map_err(|_| bad_pack())?;
+        AssetManifest::from_toml(text)
+    }
+    /// Check if the pack contains a file
+    pub fn contains(&self, path: &Path) -> bool {
+        self.files.contains_key(&pack_key(path))
+    }
+    /// Get the bytes of a file in the pack by its manifest path
+    pub fn get(&self, path: &Path) -> KuleResult<&[u8]> {
+        self.files
+            .get(&pack_key(path))
+            .map(Vec::as_slice)
+            .ok_or_else(|| KuleError::AssetLoad(format!("{} is not in the pack", path.display())))
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn asset_pack_round_trip() {
+    let dir = std::env::temp_dir().join("kule_manifest_test");
+    fs::create_dir_all(dir.join("sprites")).unwrap();
+    fs::write(
+        dir.join("assets.toml"),
+        "[meshes]\nstar = \"sprites/star.toml\"\ntri = [[0, 0], [1, 0], [0, 1]]\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.join("sprites/star.toml"),
+        "vertices = [[0, 0], [2, 0], [0, 2]]\n",
+    )
+    .unwrap();
+    let pack = AssetPack::from_manifest(dir.join("assets.toml")).unwrap();
+    let bytes = pack.to_bytes();
+    assert!(AssetPack::is_pack(&bytes));
+    let pack = AssetPack::from_bytes(&bytes).unwrap();
+    let manifest = pack.manifest().unwrap();
+    let star = mesh_vertices(&manifest.meshes["star"], |path| {
+        pack.get(path).map(<[u8]>::to_vec)
+    })
+    .unwrap();
+    assert_eq!(star, vec![[0.0, 0.0], [2.0, 0.0], [0.0, 2.0]]);
+    assert_eq!(
+        manifest.meshes["tri"],
+        MeshSource::Vertices(vec![[0.0, 0.0], [1.0, 0.0], [0.0, 1.0]])
+    );
+    fs::remove_dir_all(dir).unwrap();
+}
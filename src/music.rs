@@ -0,0 +1,195 @@
+use std::{sync::Arc, time::Duration};
+
+use crossbeam_utils::atomic::AtomicCell;
+
+use crate::SoundHandle;
+
+/**
+A musical clock that converts a playback position into beats
+
+```
+# use kule::*;
+# use std::time::Duration;
+let clock = BeatClock::new(120.0).beats_per_bar(3);
+assert_eq!(clock.beat_at(Duration::from_secs(2)), 4.0);
+assert_eq!(clock.bar_of(4), 1);
+```
+*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BeatClock {
+    /// The number of beats per minute
+    pub bpm: f32,
+    /// The number of beats in each bar
+    pub beats_per_bar: u32,
+    /// The playback position of the first beat
+    pub offset: Duration,
+}
+
+impl BeatClock {
+    /// Create a new `BeatClock` with 4 beats per bar and no offset
+    pub const fn new(bpm: f32) -> Self {
+        BeatClock {
+            bpm,
+            beats_per_bar: 4,
+            offset: Duration::from_secs(0),
+        }
+    }
+    /// Set the number of beats in each bar
+    pub const fn beats_per_bar(self, beats_per_bar: u32) -> Self {
+        BeatClock {
+            beats_per_bar,
+            ..self
+        }
+    }
+    /// Set the playback position of the first beat
+    pub const fn offset(self, offset: Duration) -> Self {
+        BeatClock { offset, ..self }
+    }
+    /// Get the duration of a single beat
+    pub fn beat_duration(&self) -> Duration {
+        Duration::from_secs_f64(60.0 / self.bpm as f64)
+    }
+    /**
+    Get the beat at some playback position
+
+    The whole part is the beat number and the fractional part is the phase
+    within the beat. Positions before the offset give negative beats.
+    */
+    pub fn beat_at(&self, position: Duration) -> f64 {
+        (position.as_secs_f64() - self.offset.as_secs_f64()) * self.bpm as f64 / 60.0
+    }
+    /// Get the bar that a beat is in
+    pub fn bar_of(&self, beat: u64) -> u64 {
+        beat / self.beats_per_bar.max(1) as u64
+    }
+}
+
+/**
+A music track made of one or more synchronized layers
+
+Layers all start playing at the same time. Which layers are audible
+is controlled by the track's intensity.
+
+An intensity of `n` means that the first `n` layers are fully audible.
+A fractional intensity partially fades in the next layer. For example,
+with an intensity of `1.5`, the first layer plays at full gain,
+the second at half gain, and the rest are silent.
+
+Music tracks are cheap to clone, and clones refer to the same track.
+*/
+#[derive(Debug, Clone)]
+pub struct MusicTrack {
+    layers: Arc<Vec<SoundHandle>>,
+    intensity: Arc<AtomicCell<f32>>,
+    clock: Arc<AtomicCell<Option<BeatClock>>>,
+}
+
+impl MusicTrack {
+    pub(crate) fn new(layers: Vec<SoundHandle>) -> Self {
+        MusicTrack {
+            layers: Arc::new(layers),
+            intensity: Arc::new(AtomicCell::new(0.0)),
+            clock: Arc::new(AtomicCell::new(None)),
+        }
+    }
+    fn layer_gain(&self, index: usize, intensity: f32) -> f32 {
+        (intensity - index as f32).clamp(0.0, 1.0)
+    }
+    pub(crate) fn fade_in(&self, intensity: f32, duration: Duration) {
+        self.intensity.store(intensity);
+        for (i, layer) in self.layers.iter().enumerate() {
+            layer.fade_from(0.0, self.layer_gain(i, intensity), duration);
+        }
+    }
+    /// Get the handles to the track's layers
+    pub fn layers(&self) -> &[SoundHandle] {
+        &self.layers
+    }
+    /// Get the handle to one of the track's layers
+    pub fn layer(&self, index: usize) -> Option<&SoundHandle> {
+        self.layers.get(index)
+    }
+    /// Get the track's intensity
+    pub fn intensity(&self) -> f32 {
+        self.intensity.load()
+    }
+    /// Fade the track's layers to match a new intensity over some duration
+    pub fn set_intensity(&self, intensity: f32, duration: Duration) {
+        self.intensity.store(intensity);
+        for (i, layer) in self.layers.iter().enumerate() {
+            layer.fade_to(self.layer_gain(i, intensity), duration);
+        }
+    }
+    /// Fade a single layer to some gain over some duration
+    ///
+    /// This overrides the intensity for that layer until the intensity is set again
+    pub fn fade_layer(&self, index: usize, gain: f32, duration: Duration) {
+        if let Some(layer) = self.layers.get(index) {
+            layer.fade_to(gain, duration);
+        }
+    }
+    /// Fade out and stop all layers
+    pub fn fade_out(&self, duration: Duration) {
+        for layer in self.layers.iter() {
+            layer.fade_out(duration);
+        }
+    }
+    /// Stop all layers
+    pub fn stop(&self) {
+        for layer in self.layers.iter() {
+            layer.stop();
+        }
+    }
+    /// Pause all layers
+    pub fn pause(&self) {
+        for layer in self.layers.iter() {
+            layer.pause();
+        }
+    }
+    /// Resume all layers
+    pub fn resume(&self) {
+        for layer in self.layers.iter() {
+            layer.resume();
+        }
+    }
+    /**
+    Set the clock used to track the track's beats
+
+    While the track is the current music, an `Event::Beat` is sent to the app on every beat
+    */
+    pub fn set_beat_clock(&self, clock: Option<BeatClock>) {
+        self.clock.store(clock);
+    }
+    /// Get the clock used to track the track's beats
+    pub fn beat_clock(&self) -> Option<BeatClock> {
+        self.clock.load()
+    }
+    /// Get the playback position of the track
+    pub fn position(&self) -> Duration {
+        self.layers
+            .first()
+            .map(SoundHandle::position)
+            .unwrap_or_default()
+    }
+    /**
+    Get the current beat of the track
+
+    The whole part is the beat number and the fractional part is the phase
+    within the beat. Returns `None` if the track has no beat clock.
+
+    Because it is based on the samples played, this stays in sync with the music
+    even if playback is paused or delayed.
+    */
+    pub fn beat(&self) -> Option<f64> {
+        self.beat_clock()
+            .map(|clock| clock.beat_at(self.position()))
+    }
+    /// Get the phase within the current beat from `0.0` to `1.0`
+    pub fn beat_phase(&self) -> Option<f32> {
+        self.beat().map(|beat| beat.rem_euclid(1.0) as f32)
+    }
+    /// Check if all layers have finished playing or have been stopped
+    pub fn is_finished(&self) -> bool {
+        self.layers.iter().all(SoundHandle::is_finished)
+    }
+}
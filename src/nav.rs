@@ -0,0 +1,433 @@
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, HashMap},
+    f32::consts::SQRT_2,
+    hash::Hash,
+};
+
+use vector2math::*;
+
+use crate::{Rect, Vec2};
+
+/**
+Trait for graphs that can be searched for paths
+
+Implement this for your own navigation data, or use `NavGrid`.
+It is also implemented for adjacency maps of nodes to their neighbors and edge costs.
+*/
+pub trait NavGraph {
+    /// The type that identifies a node
+    type Node: Copy + Eq + Hash;
+    /// Get the neighbors of a node along with the cost of moving to each
+    fn neighbors(&self, node: Self::Node) -> Vec<(Self::Node, f32)>;
+    /**
+    Estimate the cost of moving between two nodes
+
+    This must never overestimate the true cost. The default of `0.0` makes
+    `find_path` behave like Dijkstra's algorithm.
+    */
+    fn heuristic(&self, from: Self::Node, to: Self::Node) -> f32 {
+        let _ = (from, to);
+        0.0
+    }
+}
+
+impl<N> NavGraph for HashMap<N, Vec<(N, f32)>>
+where
+    N: Copy + Eq + Hash,
+{
+    type Node = N;
+    fn neighbors(&self, node: Self::Node) -> Vec<(Self::Node, f32)> {
+        self.get(&node).cloned().unwrap_or_default()
+    }
+}
+
+/// A path found by `find_path`
+#[derive(Debug, Clone, PartialEq)]
+pub struct NavPath<N> {
+    /// The nodes of the path, including the start and goal
+    pub nodes: Vec<N>,
+    /// The total cost of the path
+    pub cost: f32,
+}
+
+struct Open<N> {
+    score: f32,
+    node: N,
+}
+
+impl<N> PartialEq for Open<N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.score == other.score
+    }
+}
+
+impl<N> Eq for Open<N> {}
+
+impl<N> PartialOrd for Open<N> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<N> Ord for Open<N> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Reversed so that the heap pops the lowest score
+        other
+            .score
+            .partial_cmp(&self.score)
+            .unwrap_or(Ordering::Equal)
+    }
+}
+
+/// Find the cheapest path between two nodes using A*
+pub fn find_path<G>(graph: &G, start: G::Node, goal: G::Node) -> Option<NavPath<G::Node>>
+where
+    G: NavGraph,
+{
+    let mut open = BinaryHeap::new();
+    let mut costs = HashMap::new();
+    let mut came_from = HashMap::new();
+    costs.insert(start, 0.0);
+    open.push(Open {
+        score: graph.heuristic(start, goal),
+        node: start,
+    });
+    while let Some(Open { node, .. }) = open.pop() {
+        let cost = costs[&node];
+        if node == goal {
+            let mut nodes = vec![goal];
+            let mut node = goal;
+            while let Some(&prev) = came_from.get(&node) {
+                nodes.push(prev);
+                node = prev;
+            }
+            nodes.reverse();
+            return Some(NavPath { nodes, cost });
+        }
+        for (next, step) in graph.neighbors(node) {
+            let next_cost = cost + step;
+            if !matches!(costs.get(&next), Some(&c) if c <= next_cost) {
+                costs.insert(next, next_cost);
+                came_from.insert(next, node);
+                open.push(Open {
+                    score: next_cost + graph.heuristic(next, goal),
+                    node: next,
+                });
+            }
+        }
+    }
+    None
+}
+
+/// Get the cost of the cheapest path from a node to every node reachable from it
+pub fn path_costs<G>(graph: &G, start: G::Node) -> HashMap<G::Node, f32>
+where
+    G: NavGraph,
+{
+    let mut open = BinaryHeap::new();
+    let mut costs = HashMap::new();
+    costs.insert(start, 0.0);
+    open.push(Open {
+        score: 0.0,
+        node: start,
+    });
+    while let Some(Open { score, node }) = open.pop() {
+        if score > costs[&node] {
+            continue;
+        }
+        for (next, step) in graph.neighbors(node) {
+            let next_cost = score + step;
+            if !matches!(costs.get(&next), Some(&c) if c <= next_cost) {
+                costs.insert(next, next_cost);
+                open.push(Open {
+                    score: next_cost,
+                    node: next,
+                });
+            }
+        }
+    }
+    costs
+}
+
+/**
+Remove points from a path that do not change its direction by much
+
+A point is removed if it is within `tolerance` of the line between its neighbors.
+*/
+pub fn simplify_path(points: &[Vec2], tolerance: f32) -> Vec<Vec2> {
+    let mut simplified: Vec<Vec2> = Vec::with_capacity(points.len());
+    for (i, &point) in points.iter().enumerate() {
+        if let (Some(&prev), Some(&next)) = (simplified.last(), points.get(i + 1)) {
+            let line = next.sub(prev);
+            let len = line.mag();
+            let dist = if len == 0.0 {
+                point.dist(prev)
+            } else {
+                (line.x() * (point.y() - prev.y()) - line.y() * (point.x() - prev.x())).abs() / len
+            };
+            if dist <= tolerance {
+                continue;
+            }
+        }
+        simplified.push(point);
+    }
+    simplified
+}
+
+/**
+A grid of open and blocked cells that covers an area of the world
+
+Cells are identified by their column and row. Paths can move between
+orthogonally adjacent cells, and also diagonally unless `NavGrid::diagonal`
+is turned off. Diagonal moves never cut past blocked corners.
+
+```
+# use kule::*;
+let grid = NavGrid::from_rects([0.0, 0.0, 100.0, 100.0], 10.0, vec![[30.0, 0.0, 10.0, 80.0]]);
+let path = grid.find_path([5.0, 5.0], [95.0, 5.0]).unwrap();
+assert_eq!(path.first(), Some(&[5.0, 5.0]));
+assert_eq!(path.last(), Some(&[95.0, 5.0]));
+assert!(path.iter().any(|p| p[1] > 80.0));
+```
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct NavGrid {
+    bounds: Rect,
+    cell_size: f32,
+    size: [i32; 2],
+    blocked: Vec<bool>,
+    diagonal: bool,
+}
+
+impl NavGrid {
+    /**
+    Create a new grid with no blocked cells
+
+    # Panics
+
+    Panics if the cell size is not positive
+    */
+    pub fn new(bounds: Rect, cell_size: f32) -> Self {
+        assert!(cell_size > 0.0, "Nav grid cell size must be positive");
+        let size = [
+            (bounds.abs_width() / cell_size).ceil() as i32,
+            (bounds.abs_height() / cell_size).ceil() as i32,
+        ];
+        NavGrid {
+            bounds: [
+                bounds.abs_left(),
+                bounds.abs_top(),
+                bounds.abs_width(),
+                bounds.abs_height(),
+            ],
+            cell_size,
+            size,
+            blocked: vec![false; (size[0] * size[1]) as usize],
+            diagonal: true,
+        }
+    }
+    /// Create a new grid where every cell that overlaps an obstacle is blocked
+    pub fn from_rects<I>(bounds: Rect, cell_size: f32, obstacles: I) -> Self
+    where
+        I: IntoIterator<Item = Rect>,
+    {
+        let mut grid = NavGrid::new(bounds, cell_size);
+        for rect in obstacles {
+            grid.block_rect(rect);
+        }
+        grid
+    }
+    /// Set whether paths can move diagonally
+    pub fn diagonal(self, diagonal: bool) -> Self {
+        NavGrid { diagonal, ..self }
+    }
+    /// Get the number of columns and rows
+    pub fn size(&self) -> [i32; 2] {
+        self.size
+    }
+    /// Get the cell size
+    pub fn cell_size(&self) -> f32 {
+        self.cell_size
+    }
+    /// Get the area the grid covers
+    pub fn bounds(&self) -> Rect {
+        self.bounds
+    }
+    fn index(&self, cell: [i32; 2]) -> Option<usize> {
+        if cell[0] >= 0 && cell[1] >= 0 && cell[0] < self.size[0] && cell[1] < self.size[1] {
+            Some((cell[1] * self.size[0] + cell[0]) as usize)
+        } else {
+            None
+        }
+    }
+    /// Check if a cell is blocked. Cells outside of the grid are always blocked.
+    pub fn is_blocked(&self, cell: [i32; 2]) -> bool {
+        match self.index(cell) {
+            Some(i) => self.blocked[i],
+            None => true,
+        }
+    }
+    /// Set whether a cell is blocked
+    pub fn set_blocked(&mut self, cell: [i32; 2], blocked: bool) {
+        if let Some(i) = self.index(cell) {
+            self.blocked[i] = blocked;
+        }
+    }
+    /// Block every cell that overlaps a rectangle
+    pub fn block_rect(&mut self, rect: Rect) {
+        let min = rect.abs_top_left().sub(self.bounds.top_left());
+        let max = rect.abs_bottom_right().sub(self.bounds.top_left());
+        let x0 = (min.x() / self.cell_size).floor() as i32;
+        let y0 = (min.y() / self.cell_size).floor() as i32;
+        let x1 = (max.x() / self.cell_size).ceil() as i32;
+        let y1 = (max.y() / self.cell_size).ceil() as i32;
+        for x in x0.max(0)..x1.min(self.size[0]) {
+            for y in y0.max(0)..y1.min(self.size[1]) {
+                self.set_blocked([x, y], true);
+            }
+        }
+    }
+    /// Get the cell that contains a point, if it is in the grid
+    pub fn cell_at(&self, pos: Vec2) -> Option<[i32; 2]> {
+        let local = pos.sub(self.bounds.top_left()).div(self.cell_size);
+        let cell = [local.x().floor() as i32, local.y().floor() as i32];
+        self.index(cell).map(|_| cell)
+    }
+    /// Get the center of a cell
+    pub fn cell_center(&self, cell: [i32; 2]) -> Vec2 {
+        self.bounds
+            .top_left()
+            .add([cell[0] as f32 + 0.5, cell[1] as f32 + 0.5].mul(self.cell_size))
+    }
+    /// Check if a straight line between two points only passes through open cells
+    pub fn line_of_sight(&self, a: Vec2, b: Vec2) -> bool {
+        let a = a.sub(self.bounds.top_left()).div(self.cell_size);
+        let b = b.sub(self.bounds.top_left()).div(self.cell_size);
+        let mut cell = [a.x().floor() as i32, a.y().floor() as i32];
+        let end = [b.x().floor() as i32, b.y().floor() as i32];
+        let d = b.sub(a);
+        let step = [d.x().signum() as i32, d.y().signum() as i32];
+        let mut t_max = [f32::INFINITY; 2];
+        let mut t_delta = [f32::INFINITY; 2];
+        for axis in 0..2 {
+            if d[axis] != 0.0 {
+                let next = (cell[axis] + (step[axis] > 0) as i32) as f32;
+                t_max[axis] = (next - a[axis]) / d[axis];
+                t_delta[axis] = 1.0 / d[axis].abs();
+            }
+        }
+        loop {
+            if self.is_blocked(cell) {
+                return false;
+            }
+            if cell == end {
+                return true;
+            }
+            let axis = if t_max[0] < t_max[1] { 0 } else { 1 };
+            if t_max[axis] > 1.0 {
+                return true;
+            }
+            cell[axis] += step[axis];
+            t_max[axis] += t_delta[axis];
+        }
+    }
+    /**
+    Shorten a path by skipping points that can be seen from earlier points
+
+    The first and last points are always kept.
+    */
+    pub fn smooth_path(&self, points: &[Vec2]) -> Vec<Vec2> {
+        let mut smoothed = Vec::new();
+        let mut i = 0;
+        while i < points.len() {
+            smoothed.push(points[i]);
+            let next = (i + 2..points.len())
+                .rev()
+                .find(|&j| self.line_of_sight(points[i], points[j]))
+                .unwrap_or(i + 1);
+            i = next;
+        }
+        smoothed
+    }
+    /**
+    Find a smoothed path between two points in the world
+
+    Returns `None` if either point is in a blocked cell or no path exists.
+    The path starts and ends at the given points.
+    */
+    pub fn find_path(&self, from: Vec2, to: Vec2) -> Option<Vec<Vec2>> {
+        let start = self.cell_at(from).filter(|&cell| !self.is_blocked(cell))?;
+        let goal = self.cell_at(to).filter(|&cell| !self.is_blocked(cell))?;
+        let path = find_path(self, start, goal)?;
+        let mut points: Vec<Vec2> = path
+            .nodes
+            .into_iter()
+            .map(|cell| self.cell_center(cell))
+            .collect();
+        points[0] = from;
+        if points.len() == 1 {
+            points.push(to);
+        } else {
+            *points.last_mut().unwrap() = to;
+        }
+        Some(self.smooth_path(&points))
+    }
+}
+
+impl NavGraph for NavGrid {
+    type Node = [i32; 2];
+    fn neighbors(&self, [x, y]: Self::Node) -> Vec<(Self::Node, f32)> {
+        let mut neighbors = Vec::with_capacity(8);
+        for &(dx, dy) in &[(1, 0), (-1, 0), (0, 1), (0, -1)] {
+            if !self.is_blocked([x + dx, y + dy]) {
+                neighbors.push(([x + dx, y + dy], 1.0));
+            }
+        }
+        if self.diagonal {
+            for &(dx, dy) in &[(1, 1), (1, -1), (-1, 1), (-1, -1)] {
+                if !self.is_blocked([x + dx, y + dy])
+                    && !self.is_blocked([x + dx, y])
+                    && !self.is_blocked([x, y + dy])
+                {
+                    neighbors.push(([x + dx, y + dy], SQRT_2));
+                }
+            }
+        }
+        neighbors
+    }
+    fn heuristic(&self, [x0, y0]: Self::Node, [x1, y1]: Self::Node) -> f32 {
+        let dx = (x1 - x0).abs() as f32;
+        let dy = (y1 - y0).abs() as f32;
+        if self.diagonal {
+            dx.max(dy) + (SQRT_2 - 1.0) * dx.min(dy)
+        } else {
+            dx + dy
+        }
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn nav_paths() {
+    let mut graph = HashMap::new();
+    graph.insert('a', vec![('b', 1.0), ('c', 5.0)]);
+    graph.insert('b', vec![('c', 1.0)]);
+    let path = find_path(&graph, 'a', 'c').unwrap();
+    assert_eq!(path.nodes, vec!['a', 'b', 'c']);
+    assert_eq!(path.cost, 2.0);
+    assert_eq!(path_costs(&graph, 'a')[&'c'], 2.0);
+    assert!(find_path(&graph, 'c', 'a').is_none());
+    let grid = NavGrid::new([0.0, 0.0, 4.0, 1.0], 1.0).diagonal(false);
+    let path = find_path(&grid, [0, 0], [3, 0]).unwrap();
+    assert_eq!(path.nodes.len(), 4);
+    assert_eq!(
+        grid.find_path([0.5, 0.5], [3.5, 0.5]),
+        Some(vec![[0.5, 0.5], [3.5, 0.5]])
+    );
+    let points = [[0.0, 0.0], [1.0, 0.01], [2.0, 0.0], [2.0, 2.0]];
+    assert_eq!(
+        simplify_path(&points, 0.1),
+        vec![[0.0, 0.0], [2.0, 0.0], [2.0, 2.0]]
+    );
+}
@@ -0,0 +1,229 @@
+use vector2math::*;
+
+use crate::Vec2;
+
+fn cross(o: Vec2, a: Vec2, b: Vec2) -> f32 {
+    let (a, b) = (a.sub(o), b.sub(o));
+    a.x() * b.y() - a.y() * b.x()
+}
+
+/**
+Get the signed area of a polygon
+
+The area is positive if the vertices wind counterclockwise in y-up coordinates,
+which is clockwise on the screen, since y points down.
+*/
+pub fn signed_area(points: &[Vec2]) -> f32 {
+    let n = points.len();
+    (0..n)
+        .map(|i| {
+            let (a, b) = (points[i], points[(i + 1) % n]);
+            a.x() * b.y() - b.x() * a.y()
+        })
+        .sum::<f32>()
+        / 2.0
+}
+
+/// Check if a polygon is convex. Collinear vertices are allowed.
+pub fn is_convex(points: &[Vec2]) -> bool {
+    let n = points.len();
+    if n < 3 {
+        return false;
+    }
+    let mut sign = 0.0;
+    for i in 0..n {
+        let c = cross(points[i], points[(i + 1) % n], points[(i + 2) % n]);
+        if c != 0.0 {
+            if sign * c < 0.0 {
+                return false;
+            }
+            sign = c;
+        }
+    }
+    true
+}
+
+/**
+Get the smallest convex polygon that contains all of some points
+
+The hull has a positive `signed_area`. Collinear points on its edges are left out.
+*/
+pub fn convex_hull(points: &[Vec2]) -> Vec<Vec2> {
+    let mut points = points.to_vec();
+    points.sort_by(|a, b| a[0].total_cmp(&b[0]).then(a[1].total_cmp(&b[1])));
+    points.dedup();
+    if points.len() < 3 {
+        return points;
+    }
+    let mut hull: Vec<Vec2> = Vec::with_capacity(points.len() * 2);
+    // Build the lower hull, then the upper hull
+    for pass in 0..2 {
+        let start = hull.len();
+        for &p in &points {
+            while hull.len() >= start + 2
+                && cross(hull[hull.len() - 2], hull[hull.len() - 1], p) <= 0.0
+            {
+                hull.pop();
+            }
+            hull.push(p);
+        }
+        hull.pop();
+        if pass == 0 {
+            points.reverse();
+        }
+    }
+    hull
+}
+
+fn in_triangle(p: Vec2, a: Vec2, b: Vec2, c: Vec2) -> bool {
+    cross(a, b, p) >= 0.0 && cross(b, c, p) >= 0.0 && cross(c, a, p) >= 0.0
+}
+
+/**
+Split a simple polygon into triangles
+
+Returns the indices of each triangle's vertices. The polygon may be concave.
+If it intersects itself, any part that cannot be clipped into ears is filled
+with a fan.
+
+Convex polygons are filled with a fan directly, which takes linear time.
+*/
+pub fn triangulate(points: &[Vec2]) -> Vec<[usize; 3]> {
+    if points.len() < 3 {
+        return Vec::new();
+    }
+    let mut remaining: Vec<usize> = (0..points.len()).collect();
+    if signed_area(points) < 0.0 {
+        remaining.reverse();
+    }
+    let mut triangles = Vec::with_capacity(points.len() - 2);
+    if is_convex(points) {
+        for i in 1..remaining.len() - 1 {
+            triangles.push([remaining[0], remaining[i], remaining[i + 1]]);
+        }
+        return triangles;
+    }
+    while remaining.len() > 3 {
+        let n = remaining.len();
+        let ear = (0..n).find(|&i| {
+            let (a, b, c) = (
+                remaining[(i + n - 1) % n],
+                remaining[i],
+                remaining[(i + 1) % n],
+            );
+            let (pa, pb, pc) = (points[a], points[b], points[c]);
+            cross(pa, pb, pc) >= 0.0
+                && !remaining
+                    .iter()
+                    .any(|&j| j != a && j != b && j != c && in_triangle(points[j], pa, pb, pc))
+        });
+        match ear {
+            Some(i) => {
+                triangles.push([
+                    remaining[(i + n - 1) % n],
+                    remaining[i],
+                    remaining[(i + 1) % n],
+                ]);
+                remaining.remove(i);
+            }
+            None => {
+                for i in 1..n - 1 {
+                    triangles.push([remaining[0], remaining[i], remaining[i + 1]]);
+                }
+                return triangles;
+            }
+        }
+    }
+    triangles.push([remaining[0], remaining[1], remaining[2]]);
+    triangles
+}
+
+/**
+Split a simple polygon into convex pieces
+
+The polygon is triangulated, then neighboring pieces are merged as long as
+the result stays convex. This does not always find the fewest pieces, but it
+never produces more than twice as many as needed.
+
+Every piece has a positive `signed_area`, so pieces can be passed to `polygon_polygon`.
+*/
+pub fn convex_decomposition(points: &[Vec2]) -> Vec<Vec<Vec2>> {
+    if is_convex(points) {
+        let mut piece = points.to_vec();
+        if signed_area(&piece) < 0.0 {
+            piece.reverse();
+        }
+        return vec![piece];
+    }
+    let mut pieces: Vec<Vec<usize>> = triangulate(points)
+        .into_iter()
+        .map(|tri| tri.to_vec())
+        .collect();
+    let as_points = |piece: &[usize]| piece.iter().map(|&i| points[i]).collect::<Vec<_>>();
+    'merging: loop {
+        for i in 0..pieces.len() {
+            for j in i + 1..pieces.len() {
+                if let Some(merged) = merge_pieces(&pieces[i], &pieces[j]) {
+                    if is_convex(&as_points(&merged)) {
+                        pieces[i] = merged;
+                        pieces.swap_remove(j);
+                        continue 'merging;
+                    }
+                }
+            }
+        }
+        break;
+    }
+    pieces.iter().map(|piece| as_points(piece)).collect()
+}
+
+/// Merge two pieces with the same winding that share an edge
+fn merge_pieces(a: &[usize], b: &[usize]) -> Option<Vec<usize>> {
+    let (an, bn) = (a.len(), b.len());
+    for i in 0..an {
+        let (u, v) = (a[i], a[(i + 1) % an]);
+        for j in 0..bn {
+            if b[j] == v && b[(j + 1) % bn] == u {
+                // Walk `a` from v around to u, then `b` from just after u around to just before v
+                let mut merged: Vec<usize> = (1..=an).map(|k| a[(i + k) % an]).collect();
+                merged.extend((2..bn).map(|k| b[(j + k) % bn]));
+                return Some(merged);
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+#[test]
+fn polygon_utilities() {
+    let hull = convex_hull(&[
+        [0.0, 0.0],
+        [2.0, 0.0],
+        [1.0, 1.0],
+        [2.0, 2.0],
+        [0.0, 2.0],
+        [1.0, 0.0],
+    ]);
+    assert_eq!(hull, vec![[0.0, 0.0], [2.0, 0.0], [2.0, 2.0], [0.0, 2.0]]);
+    assert!(signed_area(&hull) > 0.0);
+    // An L shape
+    let l = [
+        [0.0, 0.0],
+        [2.0, 0.0],
+        [2.0, 1.0],
+        [1.0, 1.0],
+        [1.0, 2.0],
+        [0.0, 2.0],
+    ];
+    let triangles = triangulate(&l);
+    assert_eq!(triangles.len(), 4);
+    let area: f32 = triangles
+        .iter()
+        .map(|t| signed_area(&[l[t[0]], l[t[1]], l[t[2]]]))
+        .sum();
+    assert_eq!(area, 3.0);
+    let pieces = convex_decomposition(&l);
+    assert_eq!(pieces.len(), 2);
+    assert!(pieces.iter().all(|piece| is_convex(piece)));
+}
@@ -0,0 +1,374 @@
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    fmt::Write,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use vector2math::*;
+
+use crate::{Canvas, Col, Color, Drawer, KuleResult, Rect, Resources};
+
+/**
+Profile the rest of the enclosing scope
+
+The time from this point to the end of the scope is recorded as a span with
+the given name in the current frame of `Context::profiler`. Scopes can be nested.
+
+Nothing is recorded unless the profiler is enabled.
+
+```
+# use kule::*;
+fn step_physics() {
+    profile_scope!("physics");
+    for _ in 0..3 {
+        profile_scope!("collisions");
+    }
+}
+```
+*/
+#[macro_export]
+macro_rules! profile_scope {
+    ($name:expr) => {
+        let _profile_scope = $crate::ProfileScope::new($name);
+    };
+}
+
+/// A named span of time in a `ProfileFrame`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProfileSpan {
+    /// The name passed to `profile_scope!`
+    pub name: &'static str,
+    /// The time from the start of the frame to the start of the span
+    pub start: Duration,
+    /// The length of the span
+    pub duration: Duration,
+    /// The number of spans this span is nested in
+    pub depth: usize,
+}
+
+/// The spans recorded during a single frame
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ProfileFrame {
+    /// The time from when the profiler was created to the start of the frame
+    pub start: Duration,
+    /// The length of the frame
+    pub duration: Duration,
+    /// The spans in the order they started
+    pub spans: Vec<ProfileSpan>,
+}
+
+impl ProfileFrame {
+    /// Get the total time spent in spans with some name
+    pub fn total(&self, name: &str) -> Duration {
+        self.spans
+            .iter()
+            .filter(|span| span.name == name)
+            .map(|span| span.duration)
+            .sum()
+    }
+    /// Get the deepest nesting level of any span
+    pub fn max_depth(&self) -> usize {
+        self.spans.iter().map(|span| span.depth).max().unwrap_or(0)
+    }
+}
+
+struct ThreadProfile {
+    enabled: bool,
+    frame: u64,
+    frame_start: Instant,
+    stack: Vec<usize>,
+    spans: Vec<ProfileSpan>,
+}
+
+thread_local! {
+    static PROFILE: RefCell<ThreadProfile> = RefCell::new(ThreadProfile {
+        enabled: false,
+        frame: 0,
+        frame_start: Instant::now(),
+        stack: Vec::new(),
+        spans: Vec::new(),
+    });
+}
+
+/**
+A guard that records a span when it is dropped
+
+This is usually created with `profile_scope!`
+*/
+#[derive(Debug)]
+pub struct ProfileScope {
+    span: Option<(u64, usize)>,
+    start: Instant,
+}
+
+impl ProfileScope {
+    /// Start a new span
+    pub fn new(name: &'static str) -> Self {
+        let start = Instant::now();
+        let span = PROFILE.with(|profile| {
+            let mut profile = profile.borrow_mut();
+            if !profile.enabled {
+                return None;
+            }
+            let index = profile.spans.len();
+            let span = ProfileSpan {
+                name,
+                start: start - profile.frame_start,
+                duration: Duration::default(),
+                depth: profile.stack.len(),
+            };
+            profile.spans.push(span);
+            profile.stack.push(index);
+            Some((profile.frame, index))
+        });
+        ProfileScope { span, start }
+    }
+}
+
+impl Drop for ProfileScope {
+    fn drop(&mut self) {
+        if let Some((frame, index)) = self.span {
+            let duration = self.start.elapsed();
+            PROFILE.with(|profile| {
+                let mut profile = profile.borrow_mut();
+                // Spans still open when a frame finished were closed at the end of that frame
+                if profile.frame == frame {
+                    profile.spans[index].duration = duration;
+                    profile.stack.pop();
+                }
+            });
+        }
+    }
+}
+
+/**
+Records `profile_scope!` spans into per-frame hierarchies
+
+The engine itself records `events`, `update`, and `draw` spans. Spans are
+only recorded on the main thread.
+
+The profiler is disabled by default.
+*/
+#[derive(Debug, Clone)]
+pub struct Profiler {
+    history: VecDeque<ProfileFrame>,
+    capacity: usize,
+    epoch: Instant,
+}
+
+impl Default for Profiler {
+    fn default() -> Self {
+        Profiler::new(240)
+    }
+}
+
+impl Profiler {
+    /// Create a new `Profiler` that keeps some number of frames
+    pub fn new(capacity: usize) -> Self {
+        Profiler {
+            history: VecDeque::with_capacity(capacity),
+            capacity,
+            epoch: Instant::now(),
+        }
+    }
+    /// Check if spans are being recorded
+    pub fn is_enabled(&self) -> bool {
+        PROFILE.with(|profile| profile.borrow().enabled)
+    }
+    /// Start or stop recording spans
+    pub fn set_enabled(&mut self, enabled: bool) {
+        PROFILE.with(|profile| profile.borrow_mut().enabled = enabled);
+    }
+    /// Iterate over the recorded frames from oldest to newest
+    pub fn frames(&self) -> impl DoubleEndedIterator<Item = &ProfileFrame> {
+        self.history.iter()
+    }
+    /// Get the most recent frame
+    pub fn last(&self) -> Option<&ProfileFrame> {
+        self.history.back()
+    }
+    /// Get the maximum number of frames kept
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+    /// Set the maximum number of frames kept
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        while self.history.len() > capacity {
+            self.history.pop_front();
+        }
+    }
+    /// Clear the recorded frames
+    pub fn clear(&mut self) {
+        self.history.clear();
+    }
+    /// Record the current frame and start a new one
+    pub(crate) fn finish_frame(&mut self) {
+        let now = Instant::now();
+        let frame = PROFILE.with(|profile| {
+            let mut profile = profile.borrow_mut();
+            // Close spans that are still open at the end of the frame
+            let open = std::mem::take(&mut profile.stack);
+            for index in open {
+                let start = profile.frame_start + profile.spans[index].start;
+                profile.spans[index].duration = now.saturating_duration_since(start);
+            }
+            let frame = ProfileFrame {
+                start: profile.frame_start.saturating_duration_since(self.epoch),
+                duration: now - profile.frame_start,
+                spans: std::mem::take(&mut profile.spans),
+            };
+            profile.frame += 1;
+            profile.frame_start = now;
+            if profile.enabled {
+                Some(frame)
+            } else {
+                None
+            }
+        });
+        let frame = match frame {
+            Some(frame) if self.capacity > 0 => frame,
+            _ => return,
+        };
+        if self.history.len() == self.capacity {
+            self.history.pop_front();
+        }
+        self.history.push_back(frame);
+    }
+    /**
+    Format the recorded frames in the Chrome tracing format
+
+    The result can be opened with `chrome://tracing` or similar tools.
+    */
+    pub fn chrome_trace(&self) -> String {
+        let mut json = String::from("{\"traceEvents\":[");
+        let mut first = true;
+        for frame in &self.history {
+            for span in &frame.spans {
+                if !first {
+                    json.push(',');
+                }
+                first = false;
+                let _ = write!(
+                    json,
+                    "{{\"name\":\"{}\",\"ph\":\"X\",\"ts\":{},\"dur\":{},\"pid\":0,\"tid\":0}}",
+                    json_escape(span.name),
+                    (frame.start + span.start).as_micros(),
+                    span.duration.as_micros()
+                );
+            }
+        }
+        json.push_str("]}");
+        json
+    }
+    /// Save the recorded frames to a file in the Chrome tracing format
+    pub fn save_chrome_trace<P>(&self, path: P) -> KuleResult<()>
+    where
+        P: AsRef<Path>,
+    {
+        std::fs::write(path, self.chrome_trace())?;
+        Ok(())
+    }
+}
+
+/// Escape a string for use in a JSON string literal
+fn json_escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(escaped, "\\u{:04x}", c as u32);
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Pick a color for a span from its name
+fn span_color(name: &str) -> Col {
+    const PALETTE: [[f32; 3]; 6] = [
+        [0.9, 0.4, 0.3],
+        [0.9, 0.7, 0.3],
+        [0.5, 0.8, 0.4],
+        [0.3, 0.7, 0.8],
+        [0.5, 0.5, 0.9],
+        [0.8, 0.4, 0.8],
+    ];
+    let hash = name.bytes().fold(0usize, |hash, b| {
+        hash.wrapping_mul(31).wrapping_add(b as usize)
+    });
+    let [r, g, b] = PALETTE[hash % PALETTE.len()];
+    Col::rgb(r, g, b)
+}
+
+impl<'ctx, T, R> Drawer<'ctx, T, R>
+where
+    T: Canvas,
+    R: Resources,
+{
+    /**
+    Draw a frame's spans as a flame chart
+
+    The width of the rectangle is the length of the frame. Each nesting level
+    is drawn one row lower. This is best drawn in `Kule::draw_ui`.
+    */
+    pub fn flame_chart<E>(&mut self, frame: &ProfileFrame, rect: E)
+    where
+        E: Rectangle<Scalar = f32>,
+    {
+        let rect: Rect = rect.map();
+        let frame_secs = frame.duration.as_secs_f32();
+        if frame_secs <= 0.0 {
+            return;
+        }
+        let row_height = rect.height() / (frame.max_depth() + 1) as f32;
+        for span in &frame.spans {
+            let x = rect.left() + span.start.as_secs_f32() / frame_secs * rect.width();
+            let width = span.duration.as_secs_f32() / frame_secs * rect.width();
+            let y = rect.top() + span.depth as f32 * row_height;
+            self.rectangle(span_color(span.name), [x, y, width, row_height])
+                .border(Col::black().with_alpha(0.5), 1.0);
+        }
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn profile_frames() {
+    let mut profiler = Profiler::new(2);
+    profiler.set_enabled(true);
+    profiler.finish_frame();
+    {
+        profile_scope!("outer");
+        std::thread::sleep(Duration::from_millis(1));
+        profile_scope!("inner");
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    profiler.finish_frame();
+    let frame = profiler.last().unwrap();
+    assert_eq!(frame.spans.len(), 2);
+    assert_eq!(frame.spans[1].depth, 1);
+    assert!(frame.total("outer") > frame.total("inner"));
+    assert!(profiler.chrome_trace().contains("\"name\":\"inner\""));
+    // Spans that are open when a frame finishes are closed with the frame
+    {
+        profile_scope!("open");
+        profile_scope!("child \"quoted\"\n");
+        profiler.finish_frame();
+    }
+    let frame = profiler.last().unwrap();
+    assert_eq!(frame.spans.len(), 2);
+    assert_eq!(frame.spans[1].depth, 1);
+    assert!(profiler
+        .chrome_trace()
+        .contains(r#""name":"child \"quoted\"\n""#));
+    profiler.set_enabled(false);
+}
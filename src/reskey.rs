@@ -0,0 +1,137 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Mutex, OnceLock},
+};
+
+use crate::GenericResources;
+
+struct Interner {
+    ids: HashMap<&'static str, u32>,
+    names: Vec<&'static str>,
+}
+
+fn interner() -> &'static Mutex<Interner> {
+    static INTERNER: OnceLock<Mutex<Interner>> = OnceLock::new();
+    INTERNER.get_or_init(|| {
+        Mutex::new(Interner {
+            ids: HashMap::new(),
+            names: Vec::new(),
+        })
+    })
+}
+
+/**
+An interned string resource id
+
+Unlike an enum id, a `ResKey` can be created at runtime from any string, so
+resources listed in a data file or added by a mod can be registered without
+knowing them at compile time. Interning makes a `ResKey` `Copy`, and comparing
+or hashing one is as cheap as for an integer.
+
+Interned strings live for the rest of the program, so keys should come from
+a bounded set of names.
+
+Use `KeyedResources` to use `ResKey` for fonts, meshes, and sounds.
+
+```
+# use kule::*;
+let a = ResKey::new("fonts/title.ttf");
+let b = ResKey::from("fonts/title.ttf");
+assert_eq!(a, b);
+assert_eq!(a.as_str(), "fonts/title.ttf");
+```
+*/
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ResKey(u32);
+
+impl ResKey {
+    /// Get the key for a string, interning it if it has not been seen before
+    pub fn new(name: &str) -> Self {
+        let mut interner = interner().lock().unwrap();
+        if let Some(&id) = interner.ids.get(name) {
+            return ResKey(id);
+        }
+        let name: &'static str = Box::leak(name.to_owned().into_boxed_str());
+        let id = interner.names.len() as u32;
+        interner.names.push(name);
+        interner.ids.insert(name, id);
+        ResKey(id)
+    }
+    /// Get the key for a string only if it has already been interned
+    pub fn get(name: &str) -> Option<Self> {
+        interner()
+            .lock()
+            .unwrap()
+            .ids
+            .get(name)
+            .copied()
+            .map(ResKey)
+    }
+    /// Get the string for this key
+    pub fn as_str(self) -> &'static str {
+        interner().lock().unwrap().names[self.0 as usize]
+    }
+}
+
+impl From<&str> for ResKey {
+    fn from(name: &str) -> Self {
+        ResKey::new(name)
+    }
+}
+
+impl From<String> for ResKey {
+    fn from(name: String) -> Self {
+        ResKey::new(&name)
+    }
+}
+
+impl fmt::Debug for ResKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ResKey({:?})", self.as_str())
+    }
+}
+
+impl fmt::Display for ResKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+#[cfg(feature = "ser")]
+impl serde::Serialize for ResKey {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+#[cfg(feature = "ser")]
+impl<'de> serde::Deserialize<'de> for ResKey {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let name = <std::borrow::Cow<str>>::deserialize(deserializer)?;
+        Ok(ResKey::new(&name))
+    }
+}
+
+/**
+A resources type that uses `ResKey`s for fonts, meshes, and sounds
+
+This allows resources to be registered at runtime by name
+*/
+pub type KeyedResources = GenericResources<ResKey, ResKey, ResKey>;
+
+#[cfg(test)]
+#[test]
+fn res_key_interning() {
+    let a = ResKey::new("test/a");
+    assert_eq!(ResKey::get("test/a"), Some(a));
+    assert_eq!(ResKey::get("test/never"), None);
+    assert_ne!(a, ResKey::new("test/b"));
+    assert_eq!(format!("{:?}", a), "ResKey(\"test/a\")");
+}
@@ -0,0 +1,203 @@
+use std::{
+    ops::Range,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use vector2math::*;
+
+use crate::Vec2;
+
+/**
+A fast, seedable random number generator
+
+The same seed always produces the same sequence of numbers, which makes
+replays and bug reports reproducible. The context's generator is accessed
+with `Context::rng` and seeded with `ContextBuilder::seed`.
+
+The generator's state can be saved and restored to continue a sequence.
+
+```
+# use kule::*;
+let mut a = Rng::new(5);
+let mut b = Rng::new(5);
+assert_eq!(a.range(0..10), b.range(0..10));
+let x = a.range(-1.0..1.0);
+assert!(x >= -1.0 && x < 1.0);
+```
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "ser",
+    derive(serde_derive::Serialize, serde_derive::Deserialize)
+)]
+pub struct Rng {
+    seed: u64,
+    state: u64,
+}
+
+impl Default for Rng {
+    fn default() -> Self {
+        Rng::from_time()
+    }
+}
+
+impl Rng {
+    /// Create a new `Rng` with a seed
+    pub const fn new(seed: u64) -> Self {
+        Rng { seed, state: seed }
+    }
+    /// Create a new `Rng` seeded with the current time
+    pub fn from_time() -> Self {
+        let seed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|time| time.as_nanos() as u64)
+            .unwrap_or_default();
+        Rng::new(seed)
+    }
+    /// Get the seed that the generator was created with
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+    /// Restart the generator with a new seed
+    pub fn reseed(&mut self, seed: u64) {
+        *self = Rng::new(seed);
+    }
+    /// Generate a random `u64`
+    pub fn next_u64(&mut self) -> u64 {
+        // SplitMix64
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+    /// Generate a random `u32`
+    pub fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+    /// Generate a random `f32` in the range `[0, 1)`
+    pub fn f32(&mut self) -> f32 {
+        (self.next_u32() >> 8) as f32 / (1u32 << 24) as f32
+    }
+    /// Generate a random `f64` in the range `[0, 1)`
+    pub fn f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+    /// Generate a random number in a range
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is empty
+    pub fn range<T>(&mut self, range: Range<T>) -> T
+    where
+        T: RngRange,
+    {
+        T::sample(self, range)
+    }
+    /// Generate `true` with some probability from `0.0` to `1.0`
+    pub fn chance(&mut self, probability: f32) -> bool {
+        self.f32() < probability
+    }
+    /// Generate a random point in a rectangle
+    pub fn in_rect<E>(&mut self, rect: E) -> Vec2
+    where
+        E: Rectangle<Scalar = f32>,
+    {
+        let rect: [f32; 4] = rect.map();
+        [
+            rect.left() + self.f32() * rect.width(),
+            rect.top() + self.f32() * rect.height(),
+        ]
+    }
+    /// Generate a random point in a circle
+    pub fn in_circle<E>(&mut self, circ: E) -> Vec2
+    where
+        E: Circle<Scalar = f32>,
+    {
+        let radius = circ.radius() * self.f32().sqrt();
+        let center: Vec2 = circ.center().map();
+        center.add(self.direction().mul(radius))
+    }
+    /// Generate a random unit vector
+    pub fn direction(&mut self) -> Vec2 {
+        let angle = self.f32() * f32::TAU;
+        [angle.cos(), angle.sin()]
+    }
+    /// Choose a random element of a slice, or `None` if it is empty
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
+        if items.is_empty() {
+            None
+        } else {
+            Some(&items[self.range(0..items.len())])
+        }
+    }
+    /// Shuffle a slice
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.range(0..i + 1));
+        }
+    }
+}
+
+/// Trait for types that can be randomly generated in a range
+pub trait RngRange: Sized {
+    /// Generate a random value in a range
+    fn sample(rng: &mut Rng, range: Range<Self>) -> Self;
+}
+
+macro_rules! int_rng_range {
+    ($(($ty:ty, $uty:ty)),*) => {
+        $(
+            impl RngRange for $ty {
+                fn sample(rng: &mut Rng, range: Range<Self>) -> Self {
+                    assert!(range.start < range.end, "Cannot sample an empty range");
+                    let span = range.end.wrapping_sub(range.start) as $uty as u64;
+                    range.start.wrapping_add((rng.next_u64() % span) as $ty)
+                }
+            }
+        )*
+    };
+}
+
+int_rng_range!(
+    (u8, u8),
+    (u16, u16),
+    (u32, u32),
+    (u64, u64),
+    (usize, usize),
+    (i8, u8),
+    (i16, u16),
+    (i32, u32),
+    (i64, u64),
+    (isize, usize)
+);
+
+impl RngRange for f32 {
+    fn sample(rng: &mut Rng, range: Range<Self>) -> Self {
+        assert!(range.start < range.end, "Cannot sample an empty range");
+        range.start + rng.f32() * (range.end - range.start)
+    }
+}
+
+impl RngRange for f64 {
+    fn sample(rng: &mut Rng, range: Range<Self>) -> Self {
+        assert!(range.start < range.end, "Cannot sample an empty range");
+        range.start + rng.f64() * (range.end - range.start)
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn rng_ranges() {
+    let mut rng = Rng::new(0);
+    for _ in 0..1000 {
+        let i = rng.range(-100i8..100);
+        assert!((-100..100).contains(&i));
+        let p = rng.in_rect([1.0, 2.0, 3.0, 4.0]);
+        assert!(p[0] >= 1.0 && p[0] < 4.0 && p[1] >= 2.0 && p[1] < 6.0);
+    }
+    let mut items = [1, 2, 3, 4, 5];
+    rng.shuffle(&mut items);
+    items.sort();
+    assert_eq!(items, [1, 2, 3, 4, 5]);
+}
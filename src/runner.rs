@@ -0,0 +1,321 @@
+use std::time::{Duration, Instant};
+
+use glium::{
+    glutin::{event, event_loop::ControlFlow, NotCurrent, WindowedContext},
+    Display,
+};
+
+use crate::{
+    app::{dispatch_event, next_update, run_updates, setup, teardown},
+    Context, ContextBuilder, Event, FloatingScalar, GestureRecognizer, KeyRepeater, Kule,
+    KuleResult, UpdateMode,
+};
+
+/**
+Drives an app and its context
+
+`Kule::run` uses a `Runner` internally, but one can also be created from an
+externally-owned window with `Runner::from_windowed_context`. The engine is then
+pumped manually with `Runner::tick`. This allows kule views to be embedded in
+editors or other frameworks that own the event loop.
+*/
+pub struct Runner<A>
+where
+    A: Kule,
+{
+    app: Option<A>,
+    ctx: Context<A::Resources>,
+    automatic_close: bool,
+    update_frequency: f32,
+    update_mode: UpdateMode,
+    skip_draw_when_hidden: bool,
+    #[cfg(feature = "sound")]
+    pause_audio_on_unfocus: bool,
+    key_repeater: Option<KeyRepeater>,
+    gesture_recognizer: Option<GestureRecognizer>,
+}
+
+impl<A> Runner<A>
+where
+    A: Kule,
+{
+    /// Set up the app with a context
+    pub(crate) fn new(
+        builder: &ContextBuilder,
+        mut ctx: Context<A::Resources>,
+    ) -> KuleResult<Self> {
+        let app = Some(setup::<A>(&mut ctx)?);
+        Ok(Runner {
+            app,
+            ctx,
+            automatic_close: builder.automatic_close,
+            update_frequency: builder.update_frequency,
+            update_mode: builder.update_mode,
+            skip_draw_when_hidden: builder.skip_draw_when_hidden,
+            #[cfg(feature = "sound")]
+            pause_audio_on_unfocus: builder.pause_audio_on_unfocus,
+            key_repeater: builder.key_repeat.map(KeyRepeater::new),
+            gesture_recognizer: if builder.gestures {
+                Some(GestureRecognizer::default())
+            } else {
+                None
+            },
+        })
+    }
+    /**
+    Set up the app in an externally-owned window
+
+    The context is configured with `Kule::build`, but the window options are ignored
+    because the window already exists. Events from the window's event loop should be
+    passed to `Runner::tick`.
+    */
+    pub fn from_windowed_context(gl_window: WindowedContext<NotCurrent>) -> KuleResult<Self> {
+        let builder = A::build()?;
+        let display = Display::from_gl_window(gl_window)?;
+        let ctx = Context::new(&builder, Some(display))?;
+        Runner::new(&builder, ctx)
+    }
+    /// Get the app, or `None` if it has closed
+    pub fn app(&self) -> Option<&A> {
+        self.app.as_ref()
+    }
+    /// Get the app mutably, or `None` if it has closed
+    pub fn app_mut(&mut self) -> Option<&mut A> {
+        self.app.as_mut()
+    }
+    /// Get the context
+    pub fn ctx(&self) -> &Context<A::Resources> {
+        &self.ctx
+    }
+    /// Get the context mutably
+    pub fn ctx_mut(&mut self) -> &mut Context<A::Resources> {
+        &mut self.ctx
+    }
+    /// Check if the app is still running
+    pub fn is_running(&self) -> bool {
+        self.app.is_some()
+    }
+    /**
+    Pump the engine
+
+    The events are handled, any due updates are run, and a frame is drawn if one is due.
+    This should be called at least once per frame, even if there are no events.
+
+    Returns whether the app is still running.
+    */
+    pub fn tick<'a, T, I>(&mut self, events: I) -> bool
+    where
+        T: 'static,
+        I: IntoIterator<Item = event::Event<'a, T>>,
+    {
+        let mut converted = Vec::new();
+        for event in events {
+            converted.extend(Event::from_glutin(
+                event,
+                &mut self.ctx.tracker,
+                &mut self.ctx.camera,
+            ));
+        }
+        self.handle_events(converted);
+        self.update();
+        self.draw();
+        self.is_running()
+    }
+    /**
+    Tear down the app if it has not been already
+
+    Returns the exit code passed to `Context::request_exit`
+    */
+    pub fn finish(mut self) -> i32 {
+        if let Some(app) = self.app.take() {
+            teardown(app, &mut self.ctx);
+        }
+        self.ctx.exit_code()
+    }
+    /// Handle events, along with any events generated by the engine
+    pub(crate) fn handle_events<I>(&mut self, events: I)
+    where
+        I: IntoIterator<Item = Event>,
+    {
+        let ctx = &mut self.ctx;
+        crate::profile_scope!("events");
+        let events_start = Instant::now();
+        let events = events.into_iter();
+        #[cfg(feature = "sound")]
+        let events = events
+            .chain(ctx.mixer.finished_sounds().map(Event::SoundFinished))
+            .chain(
+                ctx.mixer
+                    .music_beats()
+                    .into_iter()
+                    .map(|(beat, bar)| Event::Beat { beat, bar }),
+            );
+        let mut events: Vec<Event> = events.collect();
+        // In deterministic mode, key repeat, gestures, and combos are timed by the
+        // tick clock so that they only move forward with updates
+        let now = if ctx.deterministic {
+            ctx.tick_epoch
+                + Duration::from_secs_f64(ctx.ticks as f64 / self.update_frequency as f64)
+        } else {
+            Instant::now()
+        };
+        if let Some(key_repeater) = &mut self.key_repeater {
+            events = key_repeater.process(events, now);
+        }
+        if let Some(gesture_recognizer) = &mut self.gesture_recognizer {
+            events = gesture_recognizer.process(events, now);
+        }
+        events = ctx.combos.process(events, now);
+        // The console consumes typing before it can move focus
+        #[cfg(feature = "script")]
+        if let Ok(scripts) = &ctx.scripts {
+            events = ctx.console.process(scripts, events);
+        }
+        events = ctx.focus.process(events);
+        // Reload changed assets
+        for res in ctx.reload_assets() {
+            match res {
+                Ok(id) => events.push(Event::AssetChanged(id)),
+                Err(e) => {
+                    if let Some(app) = &mut self.app {
+                        A::handle_error(e, app, ctx);
+                    }
+                }
+            }
+        }
+        // Advance script reloads
+        #[cfg(feature = "script")]
+        if let Ok(scripts) = &mut ctx.scripts {
+            match scripts.step_reload() {
+                Some(Ok(())) => events.push(Event::ScriptsReloaded),
+                Some(Err(e)) => {
+                    if let Some(app) = &mut self.app {
+                        A::handle_error(e, app, ctx);
+                    }
+                }
+                None => {}
+            }
+        }
+        // Finish background loads
+        let finished = ctx.finish_loads();
+        let loading_finished = !finished.is_empty() && ctx.loader.is_done();
+        for res in finished {
+            match res {
+                Ok(id) => events.push(Event::AssetLoaded(id)),
+                Err(e) => {
+                    if let Some(app) = &mut self.app {
+                        A::handle_error(e, app, ctx);
+                    }
+                }
+            }
+        }
+        if loading_finished {
+            events.push(Event::LoadingFinished);
+        }
+        for event in events {
+            let automatic_close = event == Event::CloseRequest && self.automatic_close;
+            if automatic_close || ctx.should_close {
+                if let Some(app) = self.app.take() {
+                    teardown(app, ctx);
+                }
+                break;
+            } else if let Some(app) = &mut self.app {
+                if event == Event::Focus(true) {
+                    ctx.window.clear_user_attention();
+                }
+                // Pause audio when unfocused
+                #[cfg(feature = "sound")]
+                if let Event::Focus(focused) = event {
+                    if self.pause_audio_on_unfocus {
+                        ctx.mixer.set_focus_paused(!focused);
+                    }
+                }
+                // Events are handled on tick boundaries in deterministic mode
+                if ctx.deterministic {
+                    ctx.queue_event(event);
+                } else {
+                    dispatch_event(event, app, ctx);
+                }
+            }
+        }
+        if !ctx.deterministic {
+            ctx.clear_user_events();
+        }
+        ctx.diagnostics.current.events += events_start.elapsed();
+    }
+    /// Run any updates that are due
+    pub(crate) fn update(&mut self) {
+        crate::profile_scope!("update");
+        let update_start = Instant::now();
+        run_updates(
+            self.update_frequency,
+            self.update_mode,
+            &mut self.app,
+            &mut self.ctx,
+        );
+        self.ctx.diagnostics.current.update += update_start.elapsed();
+    }
+    /// Draw a frame if one is due and get how long the event loop should wait
+    pub(crate) fn draw(&mut self) -> ControlFlow {
+        let ctx = &mut self.ctx;
+        let now = Instant::now();
+        let frame_time = ctx.frame_time();
+        let hidden =
+            self.skip_draw_when_hidden && (ctx.tracker.minimized() || ctx.tracker.suspended());
+        let frame_due = !hidden
+            && match frame_time {
+                Some(frame_time) => now >= ctx.fps_timer + frame_time,
+                None => true,
+            };
+        if frame_due {
+            ctx.diagnostics.finish_frame(now - ctx.fps_timer);
+            ctx.profiler.finish_frame();
+            let dt = (now - ctx.fps_timer).as_secs_f32();
+            ctx.fps_timer = now;
+            ctx.tracker.fps = ctx.tracker.fps.lerp(1.0 / dt, 0.1);
+            // Find how far between updates this frame is
+            ctx.alpha = match self.update_mode {
+                UpdateMode::Variable => 1.0,
+                UpdateMode::Fixed { .. } => {
+                    let lag = ctx.update_lag + (now - ctx.update_timer).as_secs_f32();
+                    (lag * self.update_frequency).min(1.0)
+                }
+            };
+            ctx.fixed_alpha = if ctx.deterministic {
+                ctx.alpha
+            } else {
+                let lag = ctx.fixed_lag + (now - ctx.fixed_timer).as_secs_f32();
+                (lag / ctx.fixed_step).min(1.0)
+            };
+            if let Some(app) = &mut self.app {
+                crate::profile_scope!("draw");
+                if let Err(e) = A::late_update(app, ctx) {
+                    A::handle_error(e, app, ctx);
+                }
+                let ctx_ref = &*ctx;
+                match ctx_ref.draw(|drawer| {
+                    A::draw(drawer, app, ctx_ref)?;
+                    ctx_ref.scenes.draw(drawer, ctx_ref)?;
+                    drawer.with_ui_camera(ctx_ref.ui_scale(), |drawer| {
+                        A::draw_ui(drawer, app, ctx_ref)
+                    })
+                }) {
+                    Ok(swap) => {
+                        ctx.diagnostics.current.draw = now.elapsed() - swap;
+                        ctx.diagnostics.current.swap = swap;
+                    }
+                    Err(e) => A::handle_error(e, app, ctx),
+                }
+            }
+        }
+        // Sleep until the next frame or update when the frame rate is limited
+        let next_update = next_update(self.update_frequency, self.update_mode, ctx);
+        if hidden {
+            ControlFlow::WaitUntil(next_update)
+        } else if let Some(frame_time) = frame_time {
+            ControlFlow::WaitUntil((ctx.fps_timer + frame_time).min(next_update))
+        } else {
+            ControlFlow::Poll
+        }
+    }
+}
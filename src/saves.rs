@@ -0,0 +1,474 @@
+use std::{
+    collections::BTreeMap,
+    fmt, fs,
+    io::{Read, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{CanFail, KuleError, KuleResult};
+
+/// The extension used for save files
+const SAVE_EXTENSION: &str = "sav";
+/// The magic bytes at the start of versioned save data
+const SAVE_MAGIC: [u8; 4] = *b"KSAV";
+/// The version of the save file layout, which follows the magic bytes
+const FORMAT_VERSION: u8 = 1;
+/// The header flag for saves whose data is gzip compressed
+const COMPRESSED: u8 = 1;
+
+type Migration = dyn Fn(Vec<u8>) -> KuleResult<Vec<u8>> + Send + Sync;
+
+/**
+A small image stored alongside a save
+
+Thumbnails are usually made with `Context::capture_thumbnail`.
+*/
+#[derive(Debug, Clone, PartialEq, Eq, serde_derive::Serialize, serde_derive::Deserialize)]
+pub struct Thumbnail {
+    /// The width in pixels
+    pub width: u32,
+    /// The height in pixels
+    pub height: u32,
+    /// The rgba pixels, row by row from the top
+    pub pixels: Vec<u8>,
+}
+
+impl Thumbnail {
+    /**
+    Create a new `Thumbnail` from rgba pixels
+
+    The image is shrunk so that neither side is longer than `max_size`.
+    Fails if there are not exactly `width * height * 4` bytes of pixels.
+    */
+    pub fn new(width: u32, height: u32, pixels: Vec<u8>, max_size: u32) -> KuleResult<Self> {
+        let expected = width as usize * height as usize * 4;
+        if pixels.len() != expected {
+            return Err(KuleError::InvalidThumbnail(pixels.len(), expected));
+        }
+        let scale = (max_size as f32 / width.max(height).max(1) as f32).min(1.0);
+        let new_width = ((width as f32 * scale) as u32).max(1);
+        let new_height = ((height as f32 * scale) as u32).max(1);
+        if new_width == width && new_height == height {
+            return Ok(Thumbnail {
+                width,
+                height,
+                pixels,
+            });
+        }
+        let mut scaled = Vec::with_capacity((new_width * new_height * 4) as usize);
+        for y in 0..new_height {
+            let src_y = y * height / new_height;
+            for x in 0..new_width {
+                let src_x = x * width / new_width;
+                let i = ((src_y * width + src_x) * 4) as usize;
+                scaled.extend_from_slice(&pixels[i..i + 4]);
+            }
+        }
+        Ok(Thumbnail {
+            width: new_width,
+            height: new_height,
+            pixels: scaled,
+        })
+    }
+}
+
+/// Information about a slot's save
+#[derive(Debug, Clone, PartialEq)]
+pub struct SaveInfo {
+    /// The slot name
+    pub slot: String,
+    /// The schema version the save was written with
+    pub version: u32,
+    /// When the save was written
+    pub timestamp: SystemTime,
+    /// The save's thumbnail
+    pub thumbnail: Option<Thumbnail>,
+}
+
+#[derive(serde_derive::Serialize, serde_derive::Deserialize)]
+struct SaveFile {
+    version: u32,
+    timestamp: u64,
+    thumbnail: Option<Thumbnail>,
+    data: Vec<u8>,
+}
+
+/**
+Saves and loads serializable app state in named slots
+
+Each slot is stored in its own file in the save directory. Writes are atomic,
+so a crash while saving never corrupts an existing save. Slot names are used as
+file names, so they cannot be empty or contain dots or path separators.
+
+Saves can optionally be compressed. Compressed and uncompressed saves can
+both be loaded regardless of whether compression is enabled.
+
+Every save records the schema version it was written with. When the app's state
+type changes, bump the version and register a migration from the old version.
+Older saves are migrated one version at a time when they are loaded. Before an
+older save is overwritten, it is backed up next to the new one if it can be read.
+
+```no_run
+# use kule::*;
+#[derive(serde_derive::Serialize, serde_derive::Deserialize)]
+struct OldState { gold: u32 }
+#[derive(serde_derive::Serialize, serde_derive::Deserialize)]
+struct State { gold: u32, gems: u32 }
+
+let saves = Saves::new("my game")?
+    .version(1)
+    .migration(0, |old: OldState| State { gold: old.gold, gems: 0 });
+let state: Option<State> = saves.load("slot 1")?;
+# Ok::<(), KuleError>(())
+```
+*/
+#[derive(Clone)]
+pub struct Saves {
+    dir: PathBuf,
+    compress: bool,
+    version: u32,
+    migrations: BTreeMap<u32, Arc<Migration>>,
+}
+
+impl fmt::Debug for Saves {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Saves")
+            .field("dir", &self.dir)
+            .field("compress", &self.compress)
+            .field("version", &self.version)
+            .field("migrations", &self.migrations.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl Saves {
+    /**
+    Create a new `Saves` in the platform's data directory
+
+    The saves are put in a subdirectory with the app's name
+    */
+    pub fn new(app_name: &str) -> KuleResult<Self> {
+        let dir = dirs::data_dir()
+            .ok_or(KuleError::Static("No data directory available"))?
+            .join(app_name);
+        Ok(Saves::in_dir(dir))
+    }
+    /// Create a new `Saves` in some directory
+    pub fn in_dir<P>(dir: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        Saves {
+            dir: dir.into(),
+            compress: false,
+            version: 0,
+            migrations: BTreeMap::new(),
+        }
+    }
+    /// Set whether saves are compressed
+    pub fn compressed(self, compress: bool) -> Self {
+        Saves { compress, ..self }
+    }
+    /**
+    Set the schema version that saves are written with
+
+    Saves written before versioning existed have version `0`, which is also the default
+    */
+    pub fn version(self, version: u32) -> Self {
+        Saves { version, ..self }
+    }
+    /// Get the schema version that saves are written with
+    pub fn current_version(&self) -> u32 {
+        self.version
+    }
+    /// Register a function that migrates saves from version `from` to version `from + 1`
+    pub fn migration<A, B, F>(mut self, from: u32, f: F) -> Self
+    where
+        A: DeserializeOwned,
+        B: Serialize,
+        F: Fn(A) -> B + Send + Sync + 'static,
+    {
+        let migration = move |data: Vec<u8>| -> KuleResult<Vec<u8>> {
+            let old: A = bincode::deserialize(&data)?;
+            Ok(bincode::serialize(&f(old))?)
+        };
+        self.migrations.insert(from, Arc::new(migration));
+        self
+    }
+    /// Get the save directory
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+    /// Get the path to a slot's save file
+    ///
+    /// Fails if the slot name cannot be used as a file name
+    pub fn path(&self, slot: &str) -> KuleResult<PathBuf> {
+        if is_valid_slot(slot) {
+            Ok(self.dir.join(format!("{}.{}", slot, SAVE_EXTENSION)))
+        } else {
+            Err(KuleError::InvalidSlot(slot.into()))
+        }
+    }
+    /// Check if a slot has a save
+    pub fn exists(&self, slot: &str) -> bool {
+        matches!(self.path(slot), Ok(path) if path.exists())
+    }
+    /// Save some state to a slot
+    pub fn save<T>(&self, slot: &str, state: &T) -> CanFail
+    where
+        T: Serialize,
+    {
+        self.save_impl(slot, state, None)
+    }
+    /// Save some state to a slot along with a thumbnail
+    pub fn save_with_thumbnail<T>(&self, slot: &str, state: &T, thumbnail: Thumbnail) -> CanFail
+    where
+        T: Serialize,
+    {
+        self.save_impl(slot, state, Some(thumbnail))
+    }
+    fn save_impl<T>(&self, slot: &str, state: &T, thumbnail: Option<Thumbnail>) -> CanFail
+    where
+        T: Serialize,
+    {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        let file = SaveFile {
+            version: self.version,
+            timestamp,
+            thumbnail,
+            data: bincode::serialize(state)?,
+        };
+        let path = self.path(slot)?;
+        // The header is never compressed, so whether the rest is can be read from it
+        let mut bytes = SAVE_MAGIC.to_vec();
+        bytes.push(FORMAT_VERSION);
+        if self.compress {
+            bytes.push(COMPRESSED);
+            let mut encoder = GzEncoder::new(bytes, Compression::default());
+            bincode::serialize_into(&mut encoder, &file)?;
+            bytes = encoder.finish()?;
+        } else {
+            bytes.push(0);
+            bincode::serialize_into(&mut bytes, &file)?;
+        }
+        fs::create_dir_all(&self.dir)?;
+        // Back up saves from older versions in case the migration loses something.
+        // This is best-effort, so a corrupt old save does not prevent saving.
+        if let Ok(Some(old)) = self.read(slot) {
+            if old.version < self.version {
+                let _ = fs::copy(&path, path.with_extension(format!("v{}.bak", old.version)));
+            }
+        }
+        // Write to a temporary file and then rename it so that the write is atomic
+        let temp_path = path.with_extension("tmp");
+        let mut file = fs::File::create(&temp_path)?;
+        file.write_all(&bytes)?;
+        file.sync_all()?;
+        drop(file);
+        fs::rename(temp_path, path)?;
+        Ok(())
+    }
+    /**
+    Read a slot's save file
+
+    Data that starts with the magic bytes is always read as a versioned save and
+    is rejected if its header is invalid. Only data without them is read as a save
+    from before versioning, so the two can never be confused.
+    */
+    fn read(&self, slot: &str) -> KuleResult<Option<SaveFile>> {
+        let path = self.path(slot)?;
+        let bytes = match fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        if let Some(header) = bytes.strip_prefix(&SAVE_MAGIC) {
+            let (format, flags, data) = match header {
+                [format, flags, data @ ..] => (*format, *flags, data),
+                _ => return Err(KuleError::InvalidSave("truncated header")),
+            };
+            if format != FORMAT_VERSION {
+                return Err(KuleError::InvalidSave("unsupported format version"));
+            }
+            return Ok(Some(if flags & COMPRESSED != 0 {
+                let mut decompressed = Vec::new();
+                GzDecoder::new(data).read_to_end(&mut decompressed)?;
+                bincode::deserialize(&decompressed)?
+            } else {
+                bincode::deserialize(data)?
+            }));
+        }
+        // Saves from before versioning are just the state
+        let modified = fs::metadata(&path)?.modified()?;
+        Ok(Some(SaveFile {
+            version: 0,
+            timestamp: modified
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis() as u64,
+            thumbnail: None,
+            data: bytes,
+        }))
+    }
+    /**
+    Load some state from a slot
+
+    Returns `None` if the slot has no save. Saves from older versions are migrated.
+    Saves from newer versions cannot be loaded.
+    */
+    pub fn load<T>(&self, slot: &str) -> KuleResult<Option<T>>
+    where
+        T: DeserializeOwned,
+    {
+        let file = if let Some(file) = self.read(slot)? {
+            file
+        } else {
+            return Ok(None);
+        };
+        if file.version > self.version {
+            return Err(KuleError::SaveTooNew(file.version, self.version));
+        }
+        let mut data = file.data;
+        for version in file.version..self.version {
+            let migration = self
+                .migrations
+                .get(&version)
+                .ok_or(KuleError::MissingMigration(version))?;
+            data = migration(data)?;
+        }
+        Ok(Some(bincode::deserialize(&data)?))
+    }
+    /// Get information about a slot's save, or `None` if the slot has no save
+    pub fn info(&self, slot: &str) -> KuleResult<Option<SaveInfo>> {
+        Ok(self.read(slot)?.map(|file| SaveInfo {
+            slot: slot.into(),
+            version: file.version,
+            timestamp: UNIX_EPOCH + Duration::from_millis(file.timestamp),
+            thumbnail: file.thumbnail,
+        }))
+    }
+    /// Get information about all slots that have saves, from newest to oldest
+    pub fn infos(&self) -> KuleResult<Vec<SaveInfo>> {
+        let mut infos = Vec::new();
+        for slot in self.slots()? {
+            infos.extend(self.info(&slot)?);
+        }
+        infos.sort_by_key(|info| std::cmp::Reverse(info.timestamp));
+        Ok(infos)
+    }
+    /// Delete a slot's save
+    pub fn delete(&self, slot: &str) -> CanFail {
+        match fs::remove_file(self.path(slot)?) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+    /// Get the names of all slots that have saves
+    pub fn slots(&self) -> KuleResult<Vec<String>> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let mut slots = Vec::new();
+        for entry in entries {
+            let path = entry?.path();
+            if path.extension() == Some(SAVE_EXTENSION.as_ref()) {
+                if let Some(stem) = path.file_stem().and_then(|stem| stem.to_str()) {
+                    if is_valid_slot(stem) {
+                        slots.push(stem.into());
+                    }
+                }
+            }
+        }
+        slots.sort();
+        Ok(slots)
+    }
+}
+
+/// Check that a slot name can be used as a file name without leaving the save directory
+fn is_valid_slot(slot: &str) -> bool {
+    !slot.is_empty()
+        && !slot
+            .chars()
+            .any(|c| matches!(c, '.' | '/' | '\\' | ':' | '\0'))
+}
+
+#[cfg(test)]
+#[test]
+fn saves_round_trip() {
+    let dir = std::env::temp_dir().join("kule_saves_test");
+    for &compress in &[false, true] {
+        let saves = Saves::in_dir(&dir).compressed(compress);
+        saves.save("slot", &(1u32, String::from("hi"))).unwrap();
+        assert_eq!(
+            saves.load::<(u32, String)>("slot").unwrap(),
+            Some((1, "hi".into()))
+        );
+        assert_eq!(saves.slots().unwrap(), vec![String::from("slot")]);
+        saves.delete("slot").unwrap();
+        assert!(saves.load::<(u32, String)>("slot").unwrap().is_none());
+        // Uncompressed data that starts like gzip data is not mistaken for it
+        saves.save("gz", &[0x1fu8, 0x8b]).unwrap();
+        assert_eq!(saves.load::<[u8; 2]>("gz").unwrap(), Some([0x1f, 0x8b]));
+        saves.delete("gz").unwrap();
+    }
+    for slot in &["", "../x", "a/b", "world.1", ".."] {
+        assert!(matches!(
+            Saves::in_dir(&dir).save(slot, &0u8),
+            Err(KuleError::InvalidSlot(_))
+        ));
+    }
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[cfg(test)]
+#[test]
+fn save_migration() {
+    let dir = std::env::temp_dir().join("kule_saves_migration_test");
+    let old = Saves::in_dir(&dir);
+    old.save("slot", &5u32).unwrap();
+    let new = Saves::in_dir(&dir)
+        .version(2)
+        .migration(0, |gold: u32| (gold, 0u8))
+        .migration(1, |(gold, gems): (u32, u8)| {
+            (gold, gems, String::from("hi"))
+        });
+    assert_eq!(
+        new.load::<(u32, u8, String)>("slot").unwrap(),
+        Some((5, 0, "hi".into()))
+    );
+    assert!(old.load::<u32>("slot").is_ok());
+    new.save_with_thumbnail(
+        "slot",
+        &(6u32, 1u8, String::new()),
+        Thumbnail::new(4, 2, vec![0; 32], 2).unwrap(),
+    )
+    .unwrap();
+    assert!(dir.join("slot.v0.bak").exists());
+    let info = new.info("slot").unwrap().unwrap();
+    assert_eq!(info.version, 2);
+    assert_eq!(info.thumbnail.map(|t| [t.width, t.height]), Some([2, 1]));
+    assert!(matches!(
+        old.load::<u32>("slot"),
+        Err(KuleError::SaveTooNew(2, 0))
+    ));
+    assert!(Thumbnail::new(4, 2, vec![0; 31], 2).is_err());
+    // A corrupt save can still be overwritten
+    fs::write(dir.join("slot.sav"), b"KSAV\x01").unwrap();
+    assert!(new.load::<(u32, u8, String)>("slot").is_err());
+    new.save("slot", &(7u32, 2u8, String::new())).unwrap();
+    assert_eq!(
+        new.load::<(u32, u8, String)>("slot").unwrap(),
+        Some((7, 2, String::new()))
+    );
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,220 @@
+use std::mem::take;
+
+use crate::{CanFail, Context, Drawer, Event, KuleResult, Resources, WindowCanvas};
+
+/**
+A change to the scene stack
+*/
+pub enum Transition<R = ()>
+where
+    R: Resources,
+{
+    /// Do nothing
+    None,
+    /// Push a scene on top of the stack
+    Push(Box<dyn Scene<R>>),
+    /// Pop the top scene off the stack
+    Pop,
+    /// Replace the top scene
+    Replace(Box<dyn Scene<R>>),
+    /// Remove all scenes and push a new one
+    Reset(Box<dyn Scene<R>>),
+    /// Remove all scenes
+    Clear,
+}
+
+/**
+A self-contained part of an app, like a menu, gameplay, or a pause screen
+
+Scenes are kept in a `SceneStack`. Only the top scene is updated and receives events.
+Scenes are drawn from the bottom up, starting with the highest scene that is not transparent.
+
+Scene methods return a `Transition` to change the stack.
+*/
+#[allow(unused_variables)]
+pub trait Scene<R = ()>
+where
+    R: Resources,
+{
+    /// Called when the scene is added to the stack
+    fn enter(&mut self, ctx: &mut Context<R>) -> CanFail {
+        Ok(())
+    }
+    /// Called when the scene is removed from the stack
+    fn exit(&mut self, ctx: &mut Context<R>) -> CanFail {
+        Ok(())
+    }
+    /// Called when another scene is pushed on top of this one
+    fn pause(&mut self, ctx: &mut Context<R>) -> CanFail {
+        Ok(())
+    }
+    /// Called when this scene becomes the top scene again
+    fn resume(&mut self, ctx: &mut Context<R>) -> CanFail {
+        Ok(())
+    }
+    /// Update function called often while this is the top scene
+    fn update(&mut self, dt: f32, ctx: &mut Context<R>) -> KuleResult<Transition<R>> {
+        Ok(Transition::None)
+    }
+    /// Handle events while this is the top scene
+    fn event(&mut self, event: Event, ctx: &mut Context<R>) -> KuleResult<Transition<R>> {
+        Ok(Transition::None)
+    }
+    /// Draw
+    fn draw(&self, draw: &mut Drawer<WindowCanvas, R>, ctx: &Context<R>) -> CanFail {
+        Ok(())
+    }
+    /// Whether the scenes below this one should also be drawn
+    fn transparent(&self) -> bool {
+        false
+    }
+}
+
+/**
+A stack of scenes
+
+The context's scene stack is updated, drawn, and sent events by the engine
+after the app itself. Changes made with `SceneStack::push` and the like
+take effect after the next scene update or event.
+*/
+pub struct SceneStack<R = ()>
+where
+    R: Resources,
+{
+    scenes: Vec<Box<dyn Scene<R>>>,
+    pending: Vec<Transition<R>>,
+}
+
+impl<R> Default for SceneStack<R>
+where
+    R: Resources,
+{
+    fn default() -> Self {
+        SceneStack {
+            scenes: Vec::new(),
+            pending: Vec::new(),
+        }
+    }
+}
+
+impl<R> SceneStack<R>
+where
+    R: Resources,
+{
+    /// Push a scene on top of the stack
+    pub fn push<S>(&mut self, scene: S)
+    where
+        S: Scene<R> + 'static,
+    {
+        self.pending.push(Transition::Push(Box::new(scene)));
+    }
+    /// Pop the top scene off the stack
+    pub fn pop(&mut self) {
+        self.pending.push(Transition::Pop);
+    }
+    /// Replace the top scene
+    pub fn replace<S>(&mut self, scene: S)
+    where
+        S: Scene<R> + 'static,
+    {
+        self.pending.push(Transition::Replace(Box::new(scene)));
+    }
+    /// Remove all scenes and push a new one
+    pub fn reset<S>(&mut self, scene: S)
+    where
+        S: Scene<R> + 'static,
+    {
+        self.pending.push(Transition::Reset(Box::new(scene)));
+    }
+    /// Remove all scenes
+    pub fn clear(&mut self) {
+        self.pending.push(Transition::Clear);
+    }
+    /// Get the number of scenes in the stack
+    pub fn len(&self) -> usize {
+        self.scenes.len()
+    }
+    /// Check if the stack is empty
+    pub fn is_empty(&self) -> bool {
+        self.scenes.is_empty()
+    }
+    fn apply(&mut self, transition: Transition<R>, ctx: &mut Context<R>) -> CanFail {
+        match transition {
+            Transition::None => {}
+            Transition::Push(mut scene) => {
+                if let Some(top) = self.scenes.last_mut() {
+                    top.pause(ctx)?;
+                }
+                scene.enter(ctx)?;
+                self.scenes.push(scene);
+            }
+            Transition::Pop => {
+                if let Some(mut scene) = self.scenes.pop() {
+                    scene.exit(ctx)?;
+                }
+                if let Some(top) = self.scenes.last_mut() {
+                    top.resume(ctx)?;
+                }
+            }
+            Transition::Replace(mut scene) => {
+                if let Some(mut top) = self.scenes.pop() {
+                    top.exit(ctx)?;
+                }
+                scene.enter(ctx)?;
+                self.scenes.push(scene);
+            }
+            Transition::Reset(scene) => {
+                self.apply(Transition::Clear, ctx)?;
+                self.apply(Transition::Push(scene), ctx)?;
+            }
+            Transition::Clear => {
+                while let Some(mut scene) = self.scenes.pop() {
+                    scene.exit(ctx)?;
+                }
+            }
+        }
+        Ok(())
+    }
+    /// Temporarily take the context's scene stack to call a function on it
+    fn with<F>(ctx: &mut Context<R>, f: F) -> CanFail
+    where
+        F: FnOnce(&mut Self, &mut Context<R>) -> KuleResult<Transition<R>>,
+    {
+        let mut stack = take(&mut ctx.scenes);
+        let res = f(&mut stack, ctx).and_then(|transition| {
+            stack.pending.push(transition);
+            // Include changes made through the context during the call
+            stack.pending.append(&mut ctx.scenes.pending);
+            for transition in take(&mut stack.pending) {
+                stack.apply(transition, ctx)?;
+            }
+            Ok(())
+        });
+        stack.pending.append(&mut ctx.scenes.pending);
+        ctx.scenes = stack;
+        res
+    }
+    pub(crate) fn update(ctx: &mut Context<R>, dt: f32) -> CanFail {
+        SceneStack::with(ctx, |stack, ctx| match stack.scenes.last_mut() {
+            Some(scene) => scene.update(dt, ctx),
+            None => Ok(Transition::None),
+        })
+    }
+    pub(crate) fn event(ctx: &mut Context<R>, event: Event) -> CanFail {
+        SceneStack::with(ctx, |stack, ctx| match stack.scenes.last_mut() {
+            Some(scene) => scene.event(event, ctx),
+            None => Ok(Transition::None),
+        })
+    }
+    pub(crate) fn draw(&self, draw: &mut Drawer<WindowCanvas, R>, ctx: &Context<R>) -> CanFail {
+        let start = self
+            .scenes
+            .iter()
+            .rposition(|scene| !scene.transparent())
+            .unwrap_or(0);
+        for scene in &self.scenes[start..] {
+            scene.draw(draw, ctx)?;
+        }
+        Ok(())
+    }
+}
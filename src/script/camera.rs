@@ -0,0 +1,101 @@
+use mlua::{Lua, MetaMethod, UserData, UserDataMethods, Value};
+
+use super::math::{to_rect, to_vec2, LuaRect, LuaVec2};
+use crate::Camera;
+
+/**
+Scripts can read and set the camera's `center`, `zoom`, and `pixel_perfect` fields,
+and read its `window_size` and `design_resolution`. Vectors are returned as `Vec2`s,
+but sequences like `{x, y}` can be passed in too.
+
+```lua
+camera.center = {0, 0}
+camera:zoom_by(1.1)
+local world = camera:pos_to_coords({10, 10})
+```
+*/
+impl UserData for Camera {
+    fn add_methods<'lua, M>(methods: &mut M)
+    where
+        M: UserDataMethods<'lua, Self>,
+    {
+        methods.add_method("pos_to_coords", |lua, camera, pos: Value| {
+            Ok(LuaVec2(camera.pos_to_coords(to_vec2(lua, pos)?)))
+        });
+        methods.add_method("coords_to_pos", |lua, camera, coords: Value| {
+            Ok(LuaVec2(camera.coords_to_pos(to_vec2(lua, coords)?)))
+        });
+        methods.add_method("view_rect", |_, camera, ()| Ok(LuaRect(camera.view_rect())));
+        methods.add_method("sees_point", |lua, camera, point: Value| {
+            Ok(camera.sees_point(to_vec2(lua, point)?))
+        });
+        methods.add_method("sees_rect", |lua, camera, rect: Value| {
+            Ok(camera.sees_rect(to_rect(lua, rect)?))
+        });
+        methods.add_method_mut("translate", |lua, camera, offset: Value| {
+            *camera = camera.translate(to_vec2(lua, offset)?);
+            Ok(())
+        });
+        methods.add_method_mut("zoom_by", |_, camera, by: f32| {
+            *camera = camera.zoom_by(by);
+            Ok(())
+        });
+        methods.add_method_mut("zoom_at", |lua, camera, (pos, by): (Value, f32)| {
+            *camera = camera.zoom_at(to_vec2(lua, pos)?, by);
+            Ok(())
+        });
+        methods.add_meta_method(MetaMethod::Index, |lua, camera, key: String| {
+            Ok(match key.as_str() {
+                "center" => lua.pack(LuaVec2(camera.center))?,
+                "zoom" => lua.pack(camera.zoom)?,
+                "pixel_perfect" => lua.pack(camera.pixel_perfect)?,
+                "window_size" => lua.pack(LuaVec2(camera.window_size()))?,
+                "design_resolution" => lua.pack(camera.design_resolution.map(LuaVec2))?,
+                _ => Value::Nil,
+            })
+        });
+        methods.add_meta_method_mut(
+            MetaMethod::NewIndex,
+            |lua: &Lua, camera, (key, value): (String, Value)| {
+                match key.as_str() {
+                    "center" => camera.center = to_vec2(lua, value)?,
+                    "zoom" => camera.zoom = lua.unpack(value)?,
+                    "pixel_perfect" => camera.pixel_perfect = lua.unpack(value)?,
+                    _ => {
+                        return Err(mlua::Error::RuntimeError(format!(
+                            "Camera has no settable field {:?}",
+                            key
+                        )))
+                    }
+                }
+                Ok(())
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn camera_userdata() {
+    let lua = Lua::new();
+    let camera = Camera {
+        center: [5.0, 0.0],
+        zoom: 2.0,
+        pixel_perfect: false,
+        design_resolution: None,
+        window_size: [100.0; 2],
+    };
+    lua.globals().set("camera", camera).unwrap();
+    lua.load(
+        r#"
+        assert(camera.zoom == 2)
+        camera.center = {camera.center[1] + 1, 3}
+        camera:zoom_by(2)
+        "#,
+    )
+    .exec()
+    .unwrap();
+    let camera: Camera = lua.globals().get("camera").unwrap();
+    assert_eq!(camera.center, [6.0, 3.0]);
+    assert_eq!(camera.zoom, 4.0);
+}
@@ -0,0 +1,225 @@
+use std::{collections::BTreeMap, fmt};
+
+use serde::{
+    de::{MapAccess, SeqAccess, Visitor},
+    ser::SerializeMap,
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+use crate::{KuleResult, Module};
+
+/**
+A value in a module's config or a setting's default
+
+It does not depend on the format of the config file. A [`ConfigFormat`](crate::ConfigFormat)
+only has to turn its text into a `ConfigValue` and back, and then use
+[`ConfigValue::into_modules`] and [`ConfigValue::from_modules`].
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigValue {
+    /// `true` or `false`
+    Bool(bool),
+    /// A whole number
+    Int(i64),
+    /// A number with a fractional part
+    Float(f64),
+    /// A string
+    String(String),
+    /// A list of values
+    Array(Vec<ConfigValue>),
+    /// Values with string keys
+    Table(BTreeMap<String, ConfigValue>),
+}
+
+impl ConfigValue {
+    /// Get the name of the value's type
+    pub fn type_str(&self) -> &'static str {
+        match self {
+            ConfigValue::Bool(_) => "boolean",
+            ConfigValue::Int(_) => "integer",
+            ConfigValue::Float(_) => "float",
+            ConfigValue::String(_) => "string",
+            ConfigValue::Array(_) => "array",
+            ConfigValue::Table(_) => "table",
+        }
+    }
+    /// Turn a list of modules into an array with a table for each module
+    pub fn from_modules(modules: &[Module]) -> KuleResult<Self> {
+        Ok(from_toml(toml::Value::try_from(modules)?))
+    }
+    /// Turn an array with a table for each module into a list of modules
+    pub fn into_modules(self) -> KuleResult<Vec<Module>> {
+        Ok(to_toml(self).try_into()?)
+    }
+}
+
+// TOML is only used to map modules to and from values, because it
+// already has a serializer and deserializer for its own value type
+
+fn from_toml(value: toml::Value) -> ConfigValue {
+    match value {
+        toml::Value::Boolean(b) => ConfigValue::Bool(b),
+        toml::Value::Integer(i) => ConfigValue::Int(i),
+        toml::Value::Float(f) => ConfigValue::Float(f),
+        toml::Value::String(s) => ConfigValue::String(s),
+        toml::Value::Datetime(dt) => ConfigValue::String(dt.to_string()),
+        toml::Value::Array(items) => ConfigValue::Array(items.into_iter().map(from_toml).collect()),
+        toml::Value::Table(table) => ConfigValue::Table(
+            table
+                .into_iter()
+                .map(|(key, value)| (key, from_toml(value)))
+                .collect(),
+        ),
+    }
+}
+
+fn to_toml(value: ConfigValue) -> toml::Value {
+    match value {
+        ConfigValue::Bool(b) => toml::Value::Boolean(b),
+        ConfigValue::Int(i) => toml::Value::Integer(i),
+        ConfigValue::Float(f) => toml::Value::Float(f),
+        ConfigValue::String(s) => toml::Value::String(s),
+        ConfigValue::Array(items) => toml::Value::Array(items.into_iter().map(to_toml).collect()),
+        ConfigValue::Table(table) => toml::Value::Table(
+            table
+                .into_iter()
+                .map(|(key, value)| (key, to_toml(value)))
+                .collect(),
+        ),
+    }
+}
+
+impl From<bool> for ConfigValue {
+    fn from(b: bool) -> Self {
+        ConfigValue::Bool(b)
+    }
+}
+
+impl From<i32> for ConfigValue {
+    fn from(i: i32) -> Self {
+        ConfigValue::Int(i.into())
+    }
+}
+
+impl From<i64> for ConfigValue {
+    fn from(i: i64) -> Self {
+        ConfigValue::Int(i)
+    }
+}
+
+impl From<f32> for ConfigValue {
+    fn from(f: f32) -> Self {
+        ConfigValue::Float(f.into())
+    }
+}
+
+impl From<f64> for ConfigValue {
+    fn from(f: f64) -> Self {
+        ConfigValue::Float(f)
+    }
+}
+
+impl From<&str> for ConfigValue {
+    fn from(s: &str) -> Self {
+        ConfigValue::String(s.into())
+    }
+}
+
+impl From<String> for ConfigValue {
+    fn from(s: String) -> Self {
+        ConfigValue::String(s)
+    }
+}
+
+impl Serialize for ConfigValue {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            ConfigValue::Bool(b) => serializer.serialize_bool(*b),
+            ConfigValue::Int(i) => serializer.serialize_i64(*i),
+            ConfigValue::Float(f) => serializer.serialize_f64(*f),
+            ConfigValue::String(s) => serializer.serialize_str(s),
+            ConfigValue::Array(items) => items.serialize(serializer),
+            ConfigValue::Table(table) => {
+                // TOML needs plain values to come before tables
+                let is_table = |value: &ConfigValue| match value {
+                    ConfigValue::Table(_) => true,
+                    ConfigValue::Array(items) => items
+                        .iter()
+                        .any(|item| matches!(item, ConfigValue::Table(_))),
+                    _ => false,
+                };
+                let mut map = serializer.serialize_map(Some(table.len()))?;
+                for (key, value) in table.iter().filter(|(_, value)| !is_table(value)) {
+                    map.serialize_entry(key, value)?;
+                }
+                for (key, value) in table.iter().filter(|(_, value)| is_table(value)) {
+                    map.serialize_entry(key, value)?;
+                }
+                map.end()
+            }
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ConfigValue {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(ConfigValueVisitor)
+    }
+}
+
+struct ConfigValueVisitor;
+
+impl<'de> Visitor<'de> for ConfigValueVisitor {
+    type Value = ConfigValue;
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "a boolean, number, string, array, or table")
+    }
+    fn visit_bool<E>(self, b: bool) -> Result<Self::Value, E> {
+        Ok(ConfigValue::Bool(b))
+    }
+    fn visit_i64<E>(self, i: i64) -> Result<Self::Value, E> {
+        Ok(ConfigValue::Int(i))
+    }
+    fn visit_u64<E>(self, u: u64) -> Result<Self::Value, E> {
+        if u <= i64::MAX as u64 {
+            Ok(ConfigValue::Int(u as i64))
+        } else {
+            Ok(ConfigValue::Float(u as f64))
+        }
+    }
+    fn visit_f64<E>(self, f: f64) -> Result<Self::Value, E> {
+        Ok(ConfigValue::Float(f))
+    }
+    fn visit_str<E>(self, s: &str) -> Result<Self::Value, E> {
+        Ok(ConfigValue::String(s.into()))
+    }
+    fn visit_string<E>(self, s: String) -> Result<Self::Value, E> {
+        Ok(ConfigValue::String(s))
+    }
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut items = Vec::new();
+        while let Some(item) = seq.next_element()? {
+            items.push(item);
+        }
+        Ok(ConfigValue::Array(items))
+    }
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut table = BTreeMap::new();
+        while let Some((key, value)) = map.next_entry()? {
+            table.insert(key, value);
+        }
+        Ok(ConfigValue::Table(table))
+    }
+}
@@ -0,0 +1,314 @@
+use std::collections::VecDeque;
+
+use mlua::{MultiValue, RegistryKey};
+use vector2math::*;
+
+use crate::{
+    ButtonState, Canvas, Col, Color, Drawer, Event, GlyphSpec, Key, KuleResult, LuaDeserializer,
+    Rect, Resources, Scripts, StateValue, Table,
+};
+
+/// The kind of a line in a `Console`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConsoleLineKind {
+    /// A line that was typed
+    Input,
+    /// A value that was returned or printed
+    Output,
+    /// An error
+    Error,
+}
+
+/// A line in a `Console`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConsoleLine {
+    /// The kind of line
+    pub kind: ConsoleLineKind,
+    /// The text
+    pub text: String,
+}
+
+/**
+An in-game console that evaluates Lua in the script environment
+
+While the console is open, typed lines are evaluated and the values they
+return are printed. `Up` and `Down` scroll through previously typed lines.
+
+The console is opt-in. It can be opened with `Console::set_open`, or with a key
+once `Console::toggle_key` is set.
+
+Each console has its own session. Locals declared in one line are visible in
+later lines, and globals set in the console do not leak into modules.
+Reads fall back to the shared globals, so module tables can be inspected and
+changed directly:
+
+```lua
+> core.speed
+5
+> local player = core.player
+> player.health = 100
+```
+
+The session is reset when the scripts are reloaded.
+*/
+#[derive(Debug)]
+pub struct Console {
+    open: bool,
+    /// The key that opens and closes the console, if any
+    pub toggle_key: Option<Key>,
+    input: String,
+    lines: VecDeque<ConsoleLine>,
+    capacity: usize,
+    history: Vec<String>,
+    history_index: Option<usize>,
+    swallow_char: bool,
+    session: Option<RegistryKey>,
+}
+
+impl Default for Console {
+    fn default() -> Self {
+        Console::new(200)
+    }
+}
+
+impl Console {
+    /// Create a new closed `Console` that keeps some number of lines
+    pub fn new(capacity: usize) -> Self {
+        Console {
+            open: false,
+            toggle_key: None,
+            input: String::new(),
+            lines: VecDeque::new(),
+            capacity,
+            history: Vec::new(),
+            history_index: None,
+            swallow_char: false,
+            session: None,
+        }
+    }
+    /// Check if the console is open
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+    /// Open or close the console
+    pub fn set_open(&mut self, open: bool) {
+        self.open = open;
+    }
+    /// Get the line currently being typed
+    pub fn input(&self) -> &str {
+        &self.input
+    }
+    /// Iterate over the lines from oldest to newest
+    pub fn lines(&self) -> impl DoubleEndedIterator<Item = &ConsoleLine> {
+        self.lines.iter()
+    }
+    /// Remove all lines
+    pub fn clear(&mut self) {
+        self.lines.clear();
+    }
+    /// Add a line
+    pub fn print<S>(&mut self, kind: ConsoleLineKind, text: S)
+    where
+        S: Into<String>,
+    {
+        if self.lines.len() == self.capacity {
+            self.lines.pop_front();
+        }
+        if self.capacity > 0 {
+            self.lines.push_back(ConsoleLine {
+                kind,
+                text: text.into(),
+            });
+        }
+    }
+    /**
+    Evaluate a line of Lua in the console's session and format the values it returns
+
+    Values are formatted like a `StateValue`. Multiple values are separated by tabs.
+    */
+    pub fn eval(&mut self, scripts: &Scripts, line: &str) -> KuleResult<String> {
+        let session = &mut self.session;
+        scripts.lua(|lua| {
+            let env: Table = match session
+                .as_ref()
+                .and_then(|key| lua.registry_value(key).ok())
+            {
+                Some(env) => env,
+                None => {
+                    let env = lua.create_table()?;
+                    let meta = lua.create_table()?;
+                    meta.set("__index", lua.globals())?;
+                    env.set_metatable(Some(meta));
+                    *session = Some(lua.create_registry_value(env.clone())?);
+                    env
+                }
+            };
+            // Locals would go out of scope at the end of the line, so they are
+            // stored in the session instead
+            let line = line.trim();
+            let source = line.strip_prefix("local ").unwrap_or(line);
+            let values: MultiValue = lua
+                .load(source)
+                .set_name("console")?
+                .set_environment(env)?
+                .eval()?;
+            Ok(values
+                .into_iter()
+                .map(|value| {
+                    let type_name = value.type_name();
+                    LuaDeserializer::new(lua, value)
+                        .deserialize::<StateValue>()
+                        .map_or_else(|_| type_name.into(), |value| value.to_string())
+                })
+                .collect::<Vec<_>>()
+                .join("\t"))
+        })
+    }
+    /// Evaluate a line and print it and its result
+    pub fn run(&mut self, scripts: &Scripts, line: &str) {
+        self.print(ConsoleLineKind::Input, format!("> {}", line));
+        if self.history.last().map(String::as_str) != Some(line) {
+            self.history.push(line.into());
+        }
+        self.history_index = None;
+        match self.eval(scripts, line) {
+            Ok(output) if output.is_empty() => {}
+            Ok(output) => self.print(ConsoleLineKind::Output, output),
+            Err(e) => self.print(ConsoleLineKind::Error, e.to_string()),
+        }
+    }
+    fn recall(&mut self, back: bool) {
+        let index = match (self.history_index, back) {
+            (None, true) => self.history.len().checked_sub(1),
+            (None, false) => None,
+            (Some(i), true) => Some(i.saturating_sub(1)),
+            (Some(i), false) if i + 1 < self.history.len() => Some(i + 1),
+            (Some(_), false) => None,
+        };
+        self.history_index = index;
+        self.input = index.map(|i| self.history[i].clone()).unwrap_or_default();
+    }
+    /// Handle typing while the console is open
+    ///
+    /// Keyboard events are consumed while the console is open
+    pub(crate) fn process(&mut self, scripts: &Scripts, events: Vec<Event>) -> Vec<Event> {
+        let mut processed = Vec::with_capacity(events.len());
+        for event in events {
+            let (key, pressed) = match event {
+                Event::Key { key, state, .. } => (Some(key), state == ButtonState::Pressed),
+                Event::KeyRepeat { key, .. } => (Some(key), true),
+                Event::Char(_) => (None, false),
+                _ => {
+                    processed.push(event);
+                    continue;
+                }
+            };
+            match event {
+                Event::Key {
+                    key,
+                    state: ButtonState::Pressed,
+                    ..
+                } if Some(key) == self.toggle_key => {
+                    self.open = !self.open;
+                    self.swallow_char = true;
+                    continue;
+                }
+                // The toggle key's character is not typed
+                Event::Char(_) if self.swallow_char => {
+                    self.swallow_char = false;
+                    continue;
+                }
+                Event::Key { .. } => self.swallow_char = false,
+                _ => {}
+            }
+            if !self.open {
+                processed.push(event);
+                continue;
+            }
+            match (event, key) {
+                (Event::Char(c), _) if !c.is_control() => self.input.push(c),
+                (_, Some(key)) if pressed => match key {
+                    Key::Back => {
+                        self.input.pop();
+                    }
+                    Key::Enter | Key::NumpadEnter if !self.input.trim().is_empty() => {
+                        let line = std::mem::take(&mut self.input);
+                        self.run(scripts, &line);
+                    }
+                    Key::Up => self.recall(true),
+                    Key::Down => self.recall(false),
+                    Key::Escape => self.open = false,
+                    _ => {}
+                },
+                _ => {}
+            }
+        }
+        processed
+    }
+}
+
+impl<'ctx, T, R> Drawer<'ctx, T, R>
+where
+    T: Canvas,
+    R: Resources,
+{
+    /**
+    Draw the console if it is open
+
+    The newest lines are drawn at the bottom of the rectangle, just above the
+    line being typed. This is best drawn in `Kule::draw_ui`.
+    */
+    pub fn console<E, L>(&mut self, console: &Console, rect: E, spec: L)
+    where
+        E: Rectangle<Scalar = f32>,
+        L: Into<GlyphSpec<R::FontId>>,
+    {
+        if !console.is_open() {
+            return;
+        }
+        let rect: Rect = rect.map();
+        let GlyphSpec { font_id, size } = spec.into();
+        let line_height = size.scale * 1.2;
+        self.rectangle(Col::black().with_alpha(0.8), rect);
+        let mut y = rect.bottom() - line_height * 0.3;
+        let prompt = format!("> {}_", console.input());
+        self.text(Col::white(), &prompt, GlyphSpec::new(font_id, size))
+            .transform(|t| t.translate([rect.left(), y]));
+        for line in console.lines().rev() {
+            y -= line_height;
+            if y - size.scale < rect.top() {
+                break;
+            }
+            let color = match line.kind {
+                ConsoleLineKind::Input => Col::gray(0.7),
+                ConsoleLineKind::Output => Col::white(),
+                ConsoleLineKind::Error => Col::red(1.0).lerp(Col::white(), 0.3),
+            };
+            self.text(color, &line.text, GlyphSpec::new(font_id, size))
+                .transform(|t| t.translate([rect.left(), y]));
+        }
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn console_session() {
+    use crate::{ScriptEnv, StdLib};
+    let dir = std::env::temp_dir().join("kule_console_test");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("modules.toml"), "[[mod]]\nname = \"a\"\n").unwrap();
+    std::fs::write(dir.join("a.lua"), "return { speed = 5 }").unwrap();
+    let scripts = Scripts::load(ScriptEnv::new(&dir, "modules", StdLib::ALL_SAFE)).unwrap();
+    let mut console = Console::default();
+    assert_eq!(console.eval(&scripts, "a.speed").unwrap(), "5");
+    console.eval(&scripts, "local x = a.speed * 2").unwrap();
+    assert_eq!(
+        console
+            .eval(&scripts, "x, {1, 2}, {y = true}, print")
+            .unwrap(),
+        "10\t{ 1, 2 }\t{ y = true }\tfunction"
+    );
+    console.run(&scripts, "error('oops')");
+    assert_eq!(console.lines().last().unwrap().kind, ConsoleLineKind::Error);
+    std::fs::remove_dir_all(dir).unwrap();
+}
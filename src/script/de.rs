@@ -1,48 +1,111 @@
-#![allow(dead_code, unused_variables, missing_docs)]
+use std::{fmt, vec};
 
-use std::{fmt, num::TryFromIntError};
+use mlua::{AnyUserData, FromLua, Lua, Table, Value};
+use serde::de::{value::StringDeserializer, *};
 
-use mlua::{FromLua, Table, Value};
-use serde::de::*;
+use crate::{LuaCirc, LuaCol, LuaRect, LuaSerializer, LuaTrans, LuaVec2};
 
-use crate::LuaContext;
+/**
+A deserializer that turns Lua values into deserializable values
 
+It reads the same layout that `LuaSerializer` writes:
+- Sequences, tuples, and tuple structs are array-like tables
+- Maps and structs are tables with keys
+- Unit enum variants are strings
+- Other enum variants are tables with a `variant` key. Newtype variants store
+  their value in a `value` key, tuple variants in the array part, and struct
+  variants in the other keys.
+
+The math userdata, like `Vec2` and `Col`, are read as the types they wrap.
+*/
 pub struct LuaDeserializer<'lua> {
-    ctx: LuaContext<'lua>,
+    lua: &'lua Lua,
     input: Value<'lua>,
 }
 
 impl<'lua> LuaDeserializer<'lua> {
-    /// Create a new `LuaDeserializer`
-    pub fn new(ctx: LuaContext<'lua>, input: Value<'lua>) -> Self {
-        LuaDeserializer { ctx, input }
+    /// Create a new `LuaDeserializer` for a Lua value
+    pub fn new(lua: &'lua Lua, input: Value<'lua>) -> Self {
+        let input = match &input {
+            Value::UserData(ud) => userdata_value(lua, ud).unwrap_or(input),
+            _ => input,
+        };
+        LuaDeserializer { lua, input }
+    }
+    /// Deserialize the Lua value
+    pub fn deserialize<T>(mut self) -> Result<T, LuaDeserializeError>
+    where
+        T: DeserializeOwned,
+    {
+        T::deserialize(&mut self)
     }
     fn value_as<T>(&self) -> mlua::Result<T>
     where
         T: FromLua<'lua>,
     {
-        T::from_lua(self.input.clone(), self.ctx)
+        T::from_lua(self.input.clone(), self.lua)
     }
     fn another(&self, input: Value<'lua>) -> Self {
-        LuaDeserializer::new(self.ctx, input)
+        LuaDeserializer::new(self.lua, input)
+    }
+    fn seq(&self) -> Result<LuaSeqAccess<'lua>, LuaDeserializeError> {
+        let values = self
+            .value_as::<Table>()?
+            .sequence_values()
+            .collect::<mlua::Result<Vec<Value>>>()?;
+        Ok(LuaSeqAccess {
+            lua: self.lua,
+            values: values.into_iter(),
+        })
+    }
+    fn map(&self, skip_variant: bool) -> Result<LuaMapAccess<'lua>, LuaDeserializeError> {
+        let pairs = self
+            .value_as::<Table>()?
+            .pairs::<Value, Value>()
+            .filter(|pair| match pair {
+                Ok((Value::String(key), _)) => !skip_variant || key.as_bytes() != b"variant",
+                _ => true,
+            })
+            .collect::<mlua::Result<Vec<(Value, Value)>>>()?;
+        Ok(LuaMapAccess {
+            lua: self.lua,
+            pairs: pairs.into_iter(),
+            value: None,
+        })
     }
 }
 
-/// An error generated when attempting to serialize into a lua value
+/// Serialize a math userdata's inner value so that it can be read like any other value
+fn userdata_value<'lua>(lua: &'lua Lua, ud: &AnyUserData<'lua>) -> Option<Value<'lua>> {
+    let mut ser = LuaSerializer::new(lua);
+    let value = if let Ok(v) = ud.borrow::<LuaVec2>() {
+        ser.serialize(&v.0)
+    } else if let Ok(rect) = ud.borrow::<LuaRect>() {
+        ser.serialize(&rect.0)
+    } else if let Ok(circ) = ud.borrow::<LuaCirc>() {
+        ser.serialize(&circ.0)
+    } else if let Ok(trans) = ud.borrow::<LuaTrans>() {
+        ser.serialize(&trans.0)
+    } else if let Ok(col) = ud.borrow::<LuaCol>() {
+        ser.serialize(&col.0)
+    } else {
+        return None;
+    };
+    value.ok()
+}
+
+/// An error generated when attempting to deserialize a lua value
 #[derive(Debug, Clone, thiserror::Error)]
 pub enum LuaDeserializeError {
     /// A custom error type output by serde
     #[error("{0}")]
     Custom(String),
-    /// Error converting integer
-    #[error("{0}")]
-    IntConversion(#[from] TryFromIntError),
     /// Lua error
     #[error("{0}")]
     Lua(#[from] mlua::Error),
 }
 
-impl<'lua> serde::de::Error for LuaDeserializeError {
+impl serde::de::Error for LuaDeserializeError {
     fn custom<T>(msg: T) -> Self
     where
         T: fmt::Display,
@@ -51,30 +114,107 @@ impl<'lua> serde::de::Error for LuaDeserializeError {
     }
 }
 
-struct LuaSeqAccess<'a, 'lua> {
-    de: &'a LuaDeserializer<'lua>,
-    i: usize,
+struct LuaSeqAccess<'lua> {
+    lua: &'lua Lua,
+    values: vec::IntoIter<Value<'lua>>,
 }
 
-impl<'de, 'a, 'lua> LuaSeqAccess<'a, 'lua> {
-    fn new(de: &'a LuaDeserializer<'lua>) -> Self {
-        LuaSeqAccess { de, i: 1 }
+impl<'de, 'lua> SeqAccess<'de> for LuaSeqAccess<'lua> {
+    type Error = LuaDeserializeError;
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        match self.values.next() {
+            Some(value) => seed
+                .deserialize(&mut LuaDeserializer::new(self.lua, value))
+                .map(Some),
+            None => Ok(None),
+        }
+    }
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.values.len())
     }
 }
 
-impl<'de, 'a, 'lua> SeqAccess<'de> for LuaSeqAccess<'a, 'lua> {
+struct LuaMapAccess<'lua> {
+    lua: &'lua Lua,
+    pairs: vec::IntoIter<(Value<'lua>, Value<'lua>)>,
+    value: Option<Value<'lua>>,
+}
+
+impl<'de, 'lua> MapAccess<'de> for LuaMapAccess<'lua> {
     type Error = LuaDeserializeError;
-    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>, Self::Error>
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
     where
-        T: DeserializeSeed<'de>,
+        K: DeserializeSeed<'de>,
     {
-        if let Ok(value) = self.de.value_as::<Table>()?.get::<_, Value>(self.i) {
-            self.i += 1;
-            seed.deserialize(&mut self.de.another(value)).map(Some)
-        } else {
-            Ok(None)
+        match self.pairs.next() {
+            Some((key, value)) => {
+                self.value = Some(value);
+                seed.deserialize(&mut LuaDeserializer::new(self.lua, key))
+                    .map(Some)
+            }
+            None => Ok(None),
         }
     }
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let value = self.value.take().unwrap_or(Value::Nil);
+        seed.deserialize(&mut LuaDeserializer::new(self.lua, value))
+    }
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.pairs.len())
+    }
+}
+
+struct LuaEnumAccess<'lua> {
+    de: LuaDeserializer<'lua>,
+    variant: String,
+}
+
+impl<'de, 'lua> EnumAccess<'de> for LuaEnumAccess<'lua> {
+    type Error = LuaDeserializeError;
+    type Variant = LuaDeserializer<'lua>;
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant), Self::Error>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let variant: StringDeserializer<LuaDeserializeError> = self.variant.into_deserializer();
+        Ok((seed.deserialize(variant)?, self.de))
+    }
+}
+
+impl<'de, 'lua> VariantAccess<'de> for LuaDeserializer<'lua> {
+    type Error = LuaDeserializeError;
+    fn unit_variant(self) -> Result<(), Self::Error> {
+        Ok(())
+    }
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value, Self::Error>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        let value = self.value_as::<Table>()?.get("value")?;
+        seed.deserialize(&mut self.another(value))
+    }
+    fn tuple_variant<V>(self, _len: usize, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_seq(self.seq()?)
+    }
+    fn struct_variant<V>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_map(self.map(true)?)
+    }
 }
 
 impl<'de, 'a, 'lua> Deserializer<'de> for &'a mut LuaDeserializer<'lua> {
@@ -83,7 +223,20 @@ impl<'de, 'a, 'lua> Deserializer<'de> for &'a mut LuaDeserializer<'lua> {
     where
         V: Visitor<'de>,
     {
-        todo!()
+        match &self.input {
+            Value::Nil => visitor.visit_unit(),
+            Value::Boolean(b) => visitor.visit_bool(*b),
+            Value::Integer(i) => visitor.visit_i64(*i),
+            Value::Number(n) => visitor.visit_f64(*n),
+            Value::String(s) => visitor.visit_string(s.to_str()?.into()),
+            // Tables with an array part are treated as sequences
+            Value::Table(table) if table.raw_len() > 0 => visitor.visit_seq(self.seq()?),
+            Value::Table(_) => visitor.visit_map(self.map(false)?),
+            value => Err(LuaDeserializeError::custom(format!(
+                "Cannot deserialize a Lua {}",
+                value.type_name()
+            ))),
+        }
     }
     fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
@@ -95,43 +248,43 @@ impl<'de, 'a, 'lua> Deserializer<'de> for &'a mut LuaDeserializer<'lua> {
     where
         V: Visitor<'de>,
     {
-        visitor.visit_u64(self.value_as()?)
+        visitor.visit_i8(self.value_as()?)
     }
     fn deserialize_i16<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_u64(self.value_as()?)
+        visitor.visit_i16(self.value_as()?)
     }
     fn deserialize_i32<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_u64(self.value_as()?)
+        visitor.visit_i32(self.value_as()?)
     }
     fn deserialize_i64<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_u64(self.value_as()?)
+        visitor.visit_i64(self.value_as()?)
     }
     fn deserialize_u8<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_u64(self.value_as()?)
+        visitor.visit_u8(self.value_as()?)
     }
     fn deserialize_u16<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_u64(self.value_as()?)
+        visitor.visit_u16(self.value_as()?)
     }
     fn deserialize_u32<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_u64(self.value_as()?)
+        visitor.visit_u32(self.value_as()?)
     }
     fn deserialize_u64<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
@@ -143,7 +296,7 @@ impl<'de, 'a, 'lua> Deserializer<'de> for &'a mut LuaDeserializer<'lua> {
     where
         V: Visitor<'de>,
     {
-        visitor.visit_f64(self.value_as()?)
+        visitor.visit_f32(self.value_as()?)
     }
     fn deserialize_f64<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
@@ -155,12 +308,15 @@ impl<'de, 'a, 'lua> Deserializer<'de> for &'a mut LuaDeserializer<'lua> {
     where
         V: Visitor<'de>,
     {
-        visitor.visit_char(
-            self.value_as::<String>()?
-                .chars()
-                .next()
-                .unwrap_or(b'0' as char),
-        )
+        let s = self.value_as::<String>()?;
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => visitor.visit_char(c),
+            _ => Err(LuaDeserializeError::invalid_length(
+                s.len(),
+                &"a single character",
+            )),
+        }
     }
     fn deserialize_str<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
@@ -178,31 +334,20 @@ impl<'de, 'a, 'lua> Deserializer<'de> for &'a mut LuaDeserializer<'lua> {
     where
         V: Visitor<'de>,
     {
-        let table = self.value_as::<Table>()?;
-        let mut bytes = Vec::new();
-        for i in 1.. {
-            if let Ok(u) = table.get::<_, u8>(i) {
-                bytes.push(u);
-            } else {
-                break;
-            }
-        }
-        visitor.visit_bytes(&bytes)
+        self.deserialize_byte_buf(visitor)
     }
     fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        let table = self.value_as::<Table>()?;
-        let mut bytes = Vec::new();
-        for i in 1.. {
-            if let Ok(u) = table.get::<_, u8>(i) {
-                bytes.push(u);
-            } else {
-                break;
-            }
+        match &self.input {
+            Value::String(s) => visitor.visit_byte_buf(s.as_bytes().to_vec()),
+            _ => visitor.visit_byte_buf(
+                self.value_as::<Table>()?
+                    .sequence_values()
+                    .collect::<mlua::Result<Vec<u8>>>()?,
+            ),
         }
-        visitor.visit_byte_buf(bytes)
     }
     fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
@@ -244,63 +389,101 @@ impl<'de, 'a, 'lua> Deserializer<'de> for &'a mut LuaDeserializer<'lua> {
     where
         V: Visitor<'de>,
     {
-        visitor.visit_seq(LuaSeqAccess::new(self))
+        visitor.visit_seq(self.seq()?)
     }
     fn deserialize_tuple<V>(self, _len: usize, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_seq(LuaSeqAccess::new(self))
+        visitor.visit_seq(self.seq()?)
     }
     fn deserialize_tuple_struct<V>(
         self,
-        name: &'static str,
-        len: usize,
+        _name: &'static str,
+        _len: usize,
         visitor: V,
     ) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        todo!()
+        visitor.visit_seq(self.seq()?)
     }
     fn deserialize_map<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        todo!()
+        visitor.visit_map(self.map(false)?)
     }
     fn deserialize_struct<V>(
         self,
-        name: &'static str,
-        fields: &'static [&'static str],
+        _name: &'static str,
+        _fields: &'static [&'static str],
         visitor: V,
     ) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        todo!()
+        visitor.visit_map(self.map(false)?)
     }
     fn deserialize_enum<V>(
         self,
-        name: &'static str,
-        variants: &'static [&'static str],
+        _name: &'static str,
+        _variants: &'static [&'static str],
         visitor: V,
     ) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        todo!()
+        let variant = match &self.input {
+            Value::String(s) => s.to_str()?.into(),
+            _ => self.value_as::<Table>()?.get("variant")?,
+        };
+        visitor.visit_enum(LuaEnumAccess {
+            de: self.another(self.input.clone()),
+            variant,
+        })
     }
     fn deserialize_identifier<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        todo!()
+        self.deserialize_any(visitor)
     }
     fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        todo!()
+        visitor.visit_unit()
     }
 }
+
+#[cfg(test)]
+#[test]
+fn lua_de() {
+    use crate::LuaSerializer;
+    #[derive(Debug, PartialEq, serde_derive::Serialize, serde_derive::Deserialize)]
+    enum MyEnum {
+        Foo,
+        Bar(u32),
+        Baz(f64, bool),
+        Qux { name: String, pos: [f32; 2] },
+    }
+    let lua = Lua::new();
+    for my_enum in [
+        MyEnum::Foo,
+        MyEnum::Bar(5),
+        MyEnum::Baz(3.5, true),
+        MyEnum::Qux {
+            name: "Dave".into(),
+            pos: [1.0, 2.0],
+        },
+    ] {
+        let value = LuaSerializer::new(&lua).serialize(&my_enum).unwrap();
+        let de: MyEnum = LuaDeserializer::new(&lua, value).deserialize().unwrap();
+        assert_eq!(de, my_enum);
+    }
+    let value = lua.load("{ a = 1, b = 2 }").eval().unwrap();
+    let map: std::collections::BTreeMap<String, i32> =
+        LuaDeserializer::new(&lua, value).deserialize().unwrap();
+    assert_eq!(map["b"], 2);
+}
@@ -0,0 +1,85 @@
+use std::rc::Rc;
+
+use mlua::{Lua, MultiValue, Value};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{KuleError, KuleResult, LuaDeserializer, LuaSerializer};
+
+/// A type-erased Rust function callable from Lua
+pub(crate) type RawEngineFn =
+    Rc<dyn for<'lua> Fn(&'lua Lua, MultiValue<'lua>) -> mlua::Result<Value<'lua>>>;
+
+/**
+A Rust function that can be registered with `Scripts::register_fn`
+
+This is implemented for closures of up to 6 arguments that return a `KuleResult`.
+Arguments are deserialized from Lua values with `LuaDeserializer`, and the return
+value is serialized with `LuaSerializer`.
+*/
+pub trait EngineFn<A>: 'static {
+    /// Call the function with Lua arguments
+    fn call_lua<'lua>(&self, lua: &'lua Lua, args: MultiValue<'lua>) -> KuleResult<Value<'lua>>;
+}
+
+macro_rules! engine_fn {
+    ($($arg:ident),*) => {
+        impl<F, R, $($arg),*> EngineFn<($($arg,)*)> for F
+        where
+            F: Fn($($arg),*) -> KuleResult<R> + 'static,
+            R: Serialize,
+            $($arg: DeserializeOwned,)*
+        {
+            #[allow(non_snake_case, unused_mut, unused_variables)]
+            fn call_lua<'lua>(
+                &self,
+                lua: &'lua Lua,
+                args: MultiValue<'lua>,
+            ) -> KuleResult<Value<'lua>> {
+                let mut args = args.into_iter();
+                $(let $arg: $arg =
+                    LuaDeserializer::new(lua, args.next().unwrap_or(Value::Nil)).deserialize()?;)*
+                let ret = self($($arg),*)?;
+                Ok(LuaSerializer::new(lua).serialize(&ret)?)
+            }
+        }
+    };
+}
+
+engine_fn!();
+engine_fn!(A);
+engine_fn!(A, B);
+engine_fn!(A, B, C);
+engine_fn!(A, B, C, D);
+engine_fn!(A, B, C, D, E);
+engine_fn!(A, B, C, D, E, G);
+
+/// Erase the type of an `EngineFn`
+pub(crate) fn raw_engine_fn<A, F>(f: F) -> RawEngineFn
+where
+    F: EngineFn<A>,
+{
+    Rc::new(move |lua, args| {
+        f.call_lua(lua, args).map_err(|e| match e {
+            KuleError::Lua(e) => e,
+            e => mlua::Error::RuntimeError(e.to_string()),
+        })
+    })
+}
+
+#[cfg(test)]
+#[test]
+fn engine_fns() {
+    let lua = Lua::new();
+    let raw = raw_engine_fn(|pos: crate::Vec2, scale: f32| Ok([pos[0] * scale, pos[1] * scale]));
+    let f = lua
+        .create_function(move |lua, args| raw(lua, args))
+        .unwrap();
+    lua.globals().set("scale", f).unwrap();
+    let scaled: Vec<f32> = lua.load("scale({1, 2}, 3)").eval().unwrap();
+    assert_eq!(scaled, [3.0, 6.0]);
+    assert!(lua.load("scale('oops', 3)").exec().is_err());
+    // Math userdata are passed as the types they wrap
+    super::math::install_math(&lua).unwrap();
+    let scaled: Vec<f32> = lua.load("scale(Vec2(1, 2), 3)").eval().unwrap();
+    assert_eq!(scaled, [3.0, 6.0]);
+}
@@ -0,0 +1,304 @@
+use std::{collections::BTreeMap, fmt::Write, iter::Peekable, str::CharIndices};
+
+use crate::{ConfigFormat, ConfigValue, KuleError, KuleResult, Module};
+
+impl ConfigFormat {
+    /**
+    A format that lists modules in a JSON array
+
+    Modules are listed in order:
+
+    ```json
+    [
+        { "name": "core" },
+        { "name": "extra", "enabled": false }
+    ]
+    ```
+
+    `null` is not allowed anywhere in the file. Leave out a field instead.
+    */
+    pub const JSON: ConfigFormat = ConfigFormat {
+        extension: "json",
+        parse: parse_json,
+        write: write_json,
+    };
+}
+
+fn parse_json(text: &str) -> KuleResult<Vec<Module>> {
+    let mut parser = Parser {
+        text,
+        chars: text.char_indices().peekable(),
+    };
+    let value = parser.value()?;
+    parser.skip_whitespace();
+    if parser.chars.peek().is_some() {
+        return Err(parser.error("expected the end of the file"));
+    }
+    value.into_modules()
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&mut self, message: &str) -> KuleError {
+        let pos = self.chars.peek().map_or(self.text.len(), |&(i, _)| i);
+        let line = self.text[..pos].matches('\n').count() + 1;
+        KuleError::InvalidConfig(format!("{} on line {}", message, line))
+    }
+    fn skip_whitespace(&mut self) {
+        while matches!(self.chars.peek(), Some((_, c)) if c.is_whitespace()) {
+            self.chars.next();
+        }
+    }
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        if matches!(self.chars.peek(), Some(&(_, next)) if next == c) {
+            self.chars.next();
+            true
+        } else {
+            false
+        }
+    }
+    fn expect(&mut self, c: char) -> KuleResult<()> {
+        if self.eat(c) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", c)))
+        }
+    }
+    fn value(&mut self) -> KuleResult<ConfigValue> {
+        self.skip_whitespace();
+        Ok(match self.chars.peek().map(|&(_, c)| c) {
+            Some('{') => {
+                self.chars.next();
+                let mut table = BTreeMap::new();
+                if !self.eat('}') {
+                    loop {
+                        self.skip_whitespace();
+                        let key = self.string()?;
+                        self.expect(':')?;
+                        table.insert(key, self.value()?);
+                        if self.eat('}') {
+                            break;
+                        }
+                        self.expect(',')?;
+                    }
+                }
+                ConfigValue::Table(table)
+            }
+            Some('[') => {
+                self.chars.next();
+                let mut items = Vec::new();
+                if !self.eat(']') {
+                    loop {
+                        items.push(self.value()?);
+                        if self.eat(']') {
+                            break;
+                        }
+                        self.expect(',')?;
+                    }
+                }
+                ConfigValue::Array(items)
+            }
+            Some('"') => ConfigValue::String(self.string()?),
+            Some(c) if c == '-' || c.is_ascii_digit() => self.number()?,
+            Some(c) if c.is_ascii_alphabetic() => {
+                let start = self.chars.peek().unwrap().0;
+                let mut end = start;
+                while let Some(&(i, c)) = self.chars.peek() {
+                    if !c.is_ascii_alphabetic() {
+                        break;
+                    }
+                    end = i + c.len_utf8();
+                    self.chars.next();
+                }
+                match &self.text[start..end] {
+                    "true" => ConfigValue::Bool(true),
+                    "false" => ConfigValue::Bool(false),
+                    "null" => return Err(self.error("null is not supported")),
+                    word => return Err(self.error(&format!("unexpected {:?}", word))),
+                }
+            }
+            _ => return Err(self.error("expected a value")),
+        })
+    }
+    fn string(&mut self) -> KuleResult<String> {
+        if !matches!(self.chars.next(), Some((_, '"'))) {
+            return Err(self.error("expected a string"));
+        }
+        let mut s = String::new();
+        loop {
+            match self.chars.next() {
+                Some((_, '"')) => return Ok(s),
+                Some((_, '\\')) => match self.chars.next() {
+                    Some((_, '"')) => s.push('"'),
+                    Some((_, '\\')) => s.push('\\'),
+                    Some((_, '/')) => s.push('/'),
+                    Some((_, 'b')) => s.push('\u{8}'),
+                    Some((_, 'f')) => s.push('\u{c}'),
+                    Some((_, 'n')) => s.push('\n'),
+                    Some((_, 'r')) => s.push('\r'),
+                    Some((_, 't')) => s.push('\t'),
+                    Some((_, 'u')) => {
+                        let mut code = self.hex4()?;
+                        // Characters outside the BMP are written as surrogate pairs
+                        if (0xD800..0xDC00).contains(&code) {
+                            let low = match (self.chars.next(), self.chars.next()) {
+                                (Some((_, '\\')), Some((_, 'u'))) => self.hex4()?,
+                                _ => return Err(self.error("invalid unicode escape")),
+                            };
+                            if !(0xDC00..0xE000).contains(&low) {
+                                return Err(self.error("invalid unicode escape"));
+                            }
+                            code = 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00);
+                        }
+                        match std::char::from_u32(code) {
+                            Some(c) => s.push(c),
+                            None => return Err(self.error("invalid unicode escape")),
+                        }
+                    }
+                    _ => return Err(self.error("invalid escape")),
+                },
+                Some((_, c)) => s.push(c),
+                None => return Err(self.error("unterminated string")),
+            }
+        }
+    }
+    fn hex4(&mut self) -> KuleResult<u32> {
+        let mut code = 0;
+        for _ in 0..4 {
+            match self.chars.next().and_then(|(_, c)| c.to_digit(16)) {
+                Some(digit) => code = code * 16 + digit,
+                None => return Err(self.error("invalid unicode escape")),
+            }
+        }
+        Ok(code)
+    }
+    fn number(&mut self) -> KuleResult<ConfigValue> {
+        let start = self.chars.peek().unwrap().0;
+        let mut end = start;
+        let mut float = false;
+        while let Some(&(i, c)) = self.chars.peek() {
+            match c {
+                '0'..='9' | '-' | '+' => {}
+                '.' | 'e' | 'E' => float = true,
+                _ => break,
+            }
+            end = i + 1;
+            self.chars.next();
+        }
+        let number = &self.text[start..end];
+        let value = if float {
+            number.parse().ok().map(ConfigValue::Float)
+        } else {
+            number.parse().ok().map(ConfigValue::Int)
+        };
+        value.ok_or_else(|| self.error(&format!("invalid number {:?}", number)))
+    }
+}
+
+fn write_json(modules: &[Module]) -> KuleResult<String> {
+    let mut json = String::new();
+    write_value(&ConfigValue::from_modules(modules)?, 0, &mut json)?;
+    json.push('\n');
+    Ok(json)
+}
+
+fn write_value(value: &ConfigValue, indent: usize, json: &mut String) -> KuleResult<()> {
+    match value {
+        ConfigValue::String(s) => write_string(s, json),
+        ConfigValue::Int(i) => {
+            let _ = write!(json, "{}", i);
+        }
+        ConfigValue::Float(f) if !f.is_finite() => {
+            return Err(KuleError::InvalidConfig(format!(
+                "{} cannot be written as JSON",
+                f
+            )))
+        }
+        ConfigValue::Float(f) => {
+            let _ = write!(json, "{:?}", f);
+        }
+        ConfigValue::Bool(b) => {
+            let _ = write!(json, "{}", b);
+        }
+        ConfigValue::Array(items) if items.is_empty() => json.push_str("[]"),
+        ConfigValue::Array(items) => {
+            json.push_str("[\n");
+            for (i, item) in items.iter().enumerate() {
+                push_indent(indent + 1, json);
+                write_value(item, indent + 1, json)?;
+                json.push_str(if i + 1 < items.len() { ",\n" } else { "\n" });
+            }
+            push_indent(indent, json);
+            json.push(']');
+        }
+        ConfigValue::Table(table) if table.is_empty() => json.push_str("{}"),
+        ConfigValue::Table(table) => {
+            json.push_str("{\n");
+            for (i, (key, value)) in table.iter().enumerate() {
+                push_indent(indent + 1, json);
+                write_string(key, json);
+                json.push_str(": ");
+                write_value(value, indent + 1, json)?;
+                json.push_str(if i + 1 < table.len() { ",\n" } else { "\n" });
+            }
+            push_indent(indent, json);
+            json.push('}');
+        }
+    }
+    Ok(())
+}
+
+fn push_indent(indent: usize, json: &mut String) {
+    for _ in 0..indent {
+        json.push_str("    ");
+    }
+}
+
+fn write_string(s: &str, json: &mut String) {
+    json.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(json, "\\u{:04x}", c as u32);
+            }
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+}
+
+#[cfg(test)]
+#[test]
+fn json_config_format() {
+    let modules = (ConfigFormat::JSON.parse)(
+        r#"
+        [
+            { "name": "core", "settings": { "speed": { "type": "float", "default": 1.5 } } },
+            { "name": "extra", "enabled": false, "config": { "odd key": "a \"quoted\"\nline é" } }
+        ]
+        "#,
+    )
+    .unwrap();
+    assert_eq!(modules[1].name(), "extra");
+    assert!(!modules[1].enabled);
+    let written = (ConfigFormat::JSON.write)(&modules).unwrap();
+    let reparsed = (ConfigFormat::JSON.parse)(&written).unwrap();
+    assert_eq!(
+        (ConfigFormat::JSON.write)(&reparsed).unwrap(),
+        written,
+        "{}",
+        written
+    );
+    assert!((ConfigFormat::JSON.parse)(r#"[{ "name": null }]"#).is_err());
+    assert!((ConfigFormat::JSON.parse)(r#"[{ "name": "core" }"#).is_err());
+}
@@ -0,0 +1,178 @@
+use std::fmt::Write;
+
+use mlua::{HookTriggers, Lua, Value};
+
+use crate::{ConfigFormat, ConfigValue, KuleResult, LuaDeserializer, Module};
+
+/// The maximum number of instructions a Lua config file may run
+const CONFIG_INSTRUCTION_LIMIT: u64 = 1_000_000;
+
+const KEYWORDS: [&str; 22] = [
+    "and", "break", "do", "else", "elseif", "end", "false", "for", "function", "goto", "if", "in",
+    "local", "nil", "not", "or", "repeat", "return", "then", "true", "until", "while",
+];
+
+impl ConfigFormat {
+    /**
+    A format that lists modules in a Lua table
+
+    The config file is run without access to any globals, so it can only build data.
+    Modules are listed in order:
+
+    ```lua
+    return {
+        { name = "core" },
+        { name = "extra", enabled = false },
+    }
+    ```
+
+    The config file is in the same directory as the modules, so it should not
+    have the same name as one of them.
+    */
+    pub const LUA: ConfigFormat = ConfigFormat {
+        extension: "lua",
+        parse: parse_lua,
+        write: write_lua,
+    };
+}
+
+fn parse_lua(text: &str) -> KuleResult<Vec<Module>> {
+    let lua = Lua::new();
+    let mut count = 0;
+    lua.set_hook(
+        HookTriggers {
+            every_nth_instruction: Some(super::INSTRUCTION_CHECK_INTERVAL),
+            ..Default::default()
+        },
+        move |_, _| {
+            count += super::INSTRUCTION_CHECK_INTERVAL as u64;
+            if count > CONFIG_INSTRUCTION_LIMIT {
+                Err(mlua::Error::RuntimeError(
+                    "The script config file ran for too long".into(),
+                ))
+            } else {
+                Ok(())
+            }
+        },
+    )?;
+    let value: Value = lua
+        .load(text)
+        .set_name("config")?
+        .set_environment(lua.create_table()?)?
+        .eval()?;
+    Ok(LuaDeserializer::new(&lua, value).deserialize()?)
+}
+
+fn write_lua(modules: &[Module]) -> KuleResult<String> {
+    let value = ConfigValue::from_modules(modules)?;
+    let mut lua = String::from("return ");
+    write_value(&value, 0, &mut lua);
+    lua.push('\n');
+    Ok(lua)
+}
+
+fn write_value(value: &ConfigValue, indent: usize, lua: &mut String) {
+    match value {
+        ConfigValue::String(s) => write_string(s, lua),
+        ConfigValue::Int(i) => {
+            let _ = write!(lua, "{}", i);
+        }
+        ConfigValue::Float(f) if f.is_nan() => lua.push_str("0/0"),
+        ConfigValue::Float(f) if f.is_infinite() => {
+            lua.push_str(if *f > 0.0 { "1/0" } else { "-1/0" })
+        }
+        ConfigValue::Float(f) => {
+            let _ = write!(lua, "{:?}", f);
+        }
+        ConfigValue::Bool(b) => {
+            let _ = write!(lua, "{}", b);
+        }
+        ConfigValue::Array(items) if items.is_empty() => lua.push_str("{}"),
+        ConfigValue::Array(items) => {
+            lua.push_str("{\n");
+            for item in items {
+                push_indent(indent + 1, lua);
+                write_value(item, indent + 1, lua);
+                lua.push_str(",\n");
+            }
+            push_indent(indent, lua);
+            lua.push('}');
+        }
+        ConfigValue::Table(table) if table.is_empty() => lua.push_str("{}"),
+        ConfigValue::Table(table) => {
+            lua.push_str("{\n");
+            for (key, value) in table {
+                push_indent(indent + 1, lua);
+                if is_identifier(key) {
+                    lua.push_str(key);
+                } else {
+                    lua.push('[');
+                    write_string(key, lua);
+                    lua.push(']');
+                }
+                lua.push_str(" = ");
+                write_value(value, indent + 1, lua);
+                lua.push_str(",\n");
+            }
+            push_indent(indent, lua);
+            lua.push('}');
+        }
+    }
+}
+
+fn push_indent(indent: usize, lua: &mut String) {
+    for _ in 0..indent {
+        lua.push_str("    ");
+    }
+}
+
+fn write_string(s: &str, lua: &mut String) {
+    lua.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => lua.push_str("\\\""),
+            '\\' => lua.push_str("\\\\"),
+            '\n' => lua.push_str("\\n"),
+            '\r' => lua.push_str("\\r"),
+            '\t' => lua.push_str("\\t"),
+            c if c.is_control() && (c as u32) < 0x80 => {
+                let _ = write!(lua, "\\{:03}", c as u32);
+            }
+            c => lua.push(c),
+        }
+    }
+    lua.push('"');
+}
+
+fn is_identifier(key: &str) -> bool {
+    let mut chars = key.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !KEYWORDS.contains(&key)
+}
+
+#[cfg(test)]
+#[test]
+fn lua_config_format() {
+    let modules = (ConfigFormat::LUA.parse)(
+        r#"
+        return {
+            { name = "core", settings = { speed = { type = "float", default = 1.5 } } },
+            { name = "extra", enabled = false, config = { ["odd key"] = "a \"quoted\"\nline" } },
+        }
+        "#,
+    )
+    .unwrap();
+    assert_eq!(modules[1].name(), "extra");
+    let written = (ConfigFormat::LUA.write)(&modules).unwrap();
+    let reparsed = (ConfigFormat::LUA.parse)(&written).unwrap();
+    assert_eq!(
+        (ConfigFormat::LUA.write)(&reparsed).unwrap(),
+        written,
+        "{}",
+        written
+    );
+    // Config files cannot reach globals or run forever
+    assert!((ConfigFormat::LUA.parse)("return { { name = os.getenv('HOME') } }").is_err());
+    assert!((ConfigFormat::LUA.parse)("while true do end").is_err());
+}
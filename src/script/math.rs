@@ -0,0 +1,473 @@
+/*!
+Lua userdata for the math types
+
+Constructors are available to scripts as globals:
+
+```lua
+local pos = Vec2(1, 2) + Vec2(3, 4) * 2
+local rect = Rect(0, 0, 100, 50)
+local bounds = Circle(pos, 10)
+local spin = Trans():rotate(math.pi / 2):translate(Vec2(5, 0))
+local tint = Col(1, 0.5, 0):lerp(Col.hsv(200, 1, 1), 0.5)
+```
+
+Wherever a script passes a vector, a sequence like `{x, y}` also works.
+Components can be read and set by name or by index, so `v.x` is the same as `v[1]`.
+*/
+
+use mlua::{FromLua, Lua, MetaMethod, UserData, UserDataMethods, Value};
+use vector2math::*;
+
+use crate::{Circ, Col, Color, KuleResult, Rect, Trans, Vec2};
+
+/// A `Vec2` that can be passed to Lua
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LuaVec2(pub Vec2);
+
+/// A `Rect` that can be passed to Lua
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LuaRect(pub Rect);
+
+/// A `Circ` that can be passed to Lua
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LuaCirc(pub Circ);
+
+/// A `Trans` that can be passed to Lua
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LuaTrans(pub Trans);
+
+/// A `Col` that can be passed to Lua
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LuaCol(pub Col);
+
+/// Get some number of components from a sequence
+fn components<'lua>(lua: &'lua Lua, value: Value<'lua>, n: usize) -> mlua::Result<Vec<f32>> {
+    let components: Vec<f32> = lua.unpack(value)?;
+    if components.len() == n {
+        Ok(components)
+    } else {
+        Err(mlua::Error::RuntimeError(format!(
+            "Expected {} components, but there are {}",
+            n,
+            components.len()
+        )))
+    }
+}
+
+/// Convert a `Vec2` userdata or a sequence to a vector
+pub(crate) fn to_vec2<'lua>(lua: &'lua Lua, value: Value<'lua>) -> mlua::Result<Vec2> {
+    if let Ok(LuaVec2(v)) = LuaVec2::from_lua(value.clone(), lua) {
+        return Ok(v);
+    }
+    let c = components(lua, value, 2)?;
+    Ok([c[0], c[1]])
+}
+
+/// Convert a number to a square vector, or anything else to a vector
+fn to_vec2_or_scalar<'lua>(lua: &'lua Lua, value: Value<'lua>) -> mlua::Result<Vec2> {
+    match value {
+        Value::Integer(_) | Value::Number(_) => Ok(Vec2::square(lua.unpack(value)?)),
+        value => to_vec2(lua, value),
+    }
+}
+
+pub(crate) fn to_rect<'lua>(lua: &'lua Lua, value: Value<'lua>) -> mlua::Result<Rect> {
+    if let Ok(LuaRect(rect)) = LuaRect::from_lua(value.clone(), lua) {
+        return Ok(rect);
+    }
+    let c = components(lua, value, 4)?;
+    Ok([c[0], c[1], c[2], c[3]])
+}
+
+fn to_col<'lua>(lua: &'lua Lua, value: Value<'lua>) -> mlua::Result<Col> {
+    if let Ok(LuaCol(col)) = LuaCol::from_lua(value.clone(), lua) {
+        return Ok(col);
+    }
+    let c: Vec<f32> = lua.unpack(value)?;
+    match c.as_slice() {
+        [r, g, b] => Ok(Col::rgb(*r, *g, *b)),
+        [r, g, b, a] => Ok(Col::rgba(*r, *g, *b, *a)),
+        _ => Err(mlua::Error::RuntimeError(format!(
+            "Expected 3 or 4 color components, but there are {}",
+            c.len()
+        ))),
+    }
+}
+
+/// Get the index of a named or numbered component
+fn component_index(key: &Value, names: &[&str]) -> Option<usize> {
+    match key {
+        Value::Integer(i) if *i >= 1 && (*i as usize) <= names.len() => Some(*i as usize - 1),
+        Value::String(s) => {
+            let s = s.to_str().ok()?;
+            names.iter().position(|name| *name == s)
+        }
+        _ => None,
+    }
+}
+
+fn no_field(type_name: &str, key: &Value) -> mlua::Error {
+    mlua::Error::RuntimeError(format!(
+        "{} has no field {}",
+        type_name,
+        match key {
+            Value::String(s) => s.to_str().unwrap_or_default().to_string(),
+            key => key.type_name().to_string(),
+        }
+    ))
+}
+
+impl UserData for LuaVec2 {
+    fn add_methods<'lua, M>(methods: &mut M)
+    where
+        M: UserDataMethods<'lua, Self>,
+    {
+        methods.add_method("dot", |lua, v, other: Value| {
+            Ok(v.0.dot(to_vec2(lua, other)?))
+        });
+        methods.add_method("mag", |_, v, ()| Ok(v.0.mag()));
+        methods.add_method("dist", |lua, v, other: Value| {
+            Ok(v.0.dist(to_vec2(lua, other)?))
+        });
+        methods.add_method("unit", |_, v, ()| Ok(LuaVec2(v.0.unit())));
+        methods.add_method("atan", |_, v, ()| Ok(v.0.atan()));
+        methods.add_method("rotate", |_, v, radians: f32| {
+            Ok(LuaVec2(v.0.rotate(radians)))
+        });
+        methods.add_method("rotate_about", |lua, v, (radians, pivot): (f32, Value)| {
+            Ok(LuaVec2(v.0.rotate_about(radians, to_vec2(lua, pivot)?)))
+        });
+        methods.add_method("lerp", |lua, v, (other, t): (Value, f32)| {
+            Ok(LuaVec2(v.0.lerp(to_vec2(lua, other)?, t)))
+        });
+        methods.add_method("unpack", |_, v, ()| Ok((v.0[0], v.0[1])));
+        methods.add_meta_function(MetaMethod::Add, |lua, (a, b): (Value, Value)| {
+            Ok(LuaVec2(to_vec2(lua, a)?.add(to_vec2(lua, b)?)))
+        });
+        methods.add_meta_function(MetaMethod::Sub, |lua, (a, b): (Value, Value)| {
+            Ok(LuaVec2(to_vec2(lua, a)?.sub(to_vec2(lua, b)?)))
+        });
+        methods.add_meta_function(MetaMethod::Mul, |lua, (a, b): (Value, Value)| {
+            Ok(LuaVec2(
+                to_vec2_or_scalar(lua, a)?.mul2(to_vec2_or_scalar(lua, b)?),
+            ))
+        });
+        methods.add_meta_function(MetaMethod::Div, |lua, (a, b): (Value, Value)| {
+            Ok(LuaVec2(
+                to_vec2_or_scalar(lua, a)?.div2(to_vec2_or_scalar(lua, b)?),
+            ))
+        });
+        methods.add_meta_method(MetaMethod::Unm, |_, v, ()| Ok(LuaVec2(v.0.neg())));
+        methods.add_meta_method(MetaMethod::Eq, |_, v, other: LuaVec2| Ok(*v == other));
+        methods.add_meta_method(MetaMethod::ToString, |_, v, ()| {
+            Ok(format!("Vec2({}, {})", v.0[0], v.0[1]))
+        });
+        methods.add_meta_method(MetaMethod::Index, |_, v, key: Value| {
+            Ok(component_index(&key, &["x", "y"]).map(|i| v.0[i]))
+        });
+        methods.add_meta_method_mut(MetaMethod::NewIndex, |_, v, (key, value): (Value, f32)| {
+            let i = component_index(&key, &["x", "y"]).ok_or_else(|| no_field("Vec2", &key))?;
+            v.0[i] = value;
+            Ok(())
+        });
+    }
+}
+
+impl UserData for LuaRect {
+    fn add_methods<'lua, M>(methods: &mut M)
+    where
+        M: UserDataMethods<'lua, Self>,
+    {
+        methods.add_method("center", |_, r, ()| Ok(LuaVec2(r.0.center())));
+        methods.add_method("size", |_, r, ()| Ok(LuaVec2(r.0.size())));
+        methods.add_method("top_left", |_, r, ()| Ok(LuaVec2(r.0.top_left())));
+        methods.add_method("top_right", |_, r, ()| Ok(LuaVec2(r.0.top_right())));
+        methods.add_method("bottom_left", |_, r, ()| Ok(LuaVec2(r.0.bottom_left())));
+        methods.add_method("bottom_right", |_, r, ()| Ok(LuaVec2(r.0.bottom_right())));
+        methods.add_method("left", |_, r, ()| Ok(r.0.left()));
+        methods.add_method("right", |_, r, ()| Ok(r.0.right()));
+        methods.add_method("top", |_, r, ()| Ok(r.0.top()));
+        methods.add_method("bottom", |_, r, ()| Ok(r.0.bottom()));
+        methods.add_method("area", |_, r, ()| Ok(r.0.area()));
+        methods.add_method("perimeter", |_, r, ()| Ok(r.0.perimeter()));
+        methods.add_method("contains", |lua, r, point: Value| {
+            Ok(Rectangle::contains(r.0, to_vec2(lua, point)?))
+        });
+        methods.add_method("translated", |lua, r, offset: Value| {
+            Ok(LuaRect(r.0.translated(to_vec2(lua, offset)?)))
+        });
+        methods.add_method("scaled", |lua, r, scale: Value| {
+            Ok(LuaRect(r.0.scaled2(to_vec2_or_scalar(lua, scale)?)))
+        });
+        methods.add_method("with_center", |lua, r, center: Value| {
+            Ok(LuaRect(r.0.with_center(to_vec2(lua, center)?)))
+        });
+        methods.add_method("with_size", |lua, r, size: Value| {
+            Ok(LuaRect(r.0.with_size(to_vec2(lua, size)?)))
+        });
+        methods.add_method("inner_margin", |_, r, margin: f32| {
+            Ok(LuaRect(r.0.inner_margin(margin)))
+        });
+        methods.add_method("outer_margin", |_, r, margin: f32| {
+            Ok(LuaRect(r.0.outer_margin(margin)))
+        });
+        methods.add_method("unpack", |_, r, ()| Ok((r.0[0], r.0[1], r.0[2], r.0[3])));
+        methods.add_meta_method(MetaMethod::Eq, |_, r, other: LuaRect| Ok(*r == other));
+        methods.add_meta_method(MetaMethod::ToString, |_, r, ()| {
+            Ok(format!(
+                "Rect({}, {}, {}, {})",
+                r.0[0], r.0[1], r.0[2], r.0[3]
+            ))
+        });
+        methods.add_meta_method(MetaMethod::Index, |_, r, key: Value| {
+            Ok(component_index(&key, &["x", "y", "w", "h"]).map(|i| r.0[i]))
+        });
+        methods.add_meta_method_mut(MetaMethod::NewIndex, |_, r, (key, value): (Value, f32)| {
+            let i = component_index(&key, &["x", "y", "w", "h"])
+                .ok_or_else(|| no_field("Rect", &key))?;
+            r.0[i] = value;
+            Ok(())
+        });
+    }
+}
+
+impl UserData for LuaCirc {
+    fn add_methods<'lua, M>(methods: &mut M)
+    where
+        M: UserDataMethods<'lua, Self>,
+    {
+        methods.add_method("area", |_, c, ()| Ok(c.0.area()));
+        methods.add_method("diameter", |_, c, ()| Ok(c.0.diameter()));
+        methods.add_method("circumference", |_, c, ()| Ok(c.0.circumference()));
+        methods.add_method("contains", |lua, c, point: Value| {
+            Ok(Circle::contains(c.0, to_vec2(lua, point)?))
+        });
+        methods.add_method("translated", |lua, c, offset: Value| {
+            Ok(LuaCirc(c.0.translated(to_vec2(lua, offset)?)))
+        });
+        methods.add_method("scaled", |_, c, scale: f32| Ok(LuaCirc(c.0.scaled(scale))));
+        methods.add_method("to_square", |_, c, ()| Ok(LuaRect(c.0.to_square())));
+        methods.add_meta_method(MetaMethod::Eq, |_, c, other: LuaCirc| Ok(*c == other));
+        methods.add_meta_method(MetaMethod::ToString, |_, c, ()| {
+            Ok(format!(
+                "Circle(Vec2({}, {}), {})",
+                c.0 .0[0], c.0 .0[1], c.0 .1
+            ))
+        });
+        methods.add_meta_method(MetaMethod::Index, |lua, c, key: String| {
+            Ok(match key.as_str() {
+                "center" => lua.pack(LuaVec2(c.0.center()))?,
+                "radius" => lua.pack(c.0.radius())?,
+                _ => Value::Nil,
+            })
+        });
+        methods.add_meta_method_mut(
+            MetaMethod::NewIndex,
+            |lua: &Lua, c, (key, value): (String, Value)| {
+                match key.as_str() {
+                    "center" => c.0 .0 = to_vec2(lua, value)?,
+                    "radius" => c.0 .1 = lua.unpack(value)?,
+                    _ => return Err(no_field("Circle", &Value::String(lua.create_string(&key)?))),
+                }
+                Ok(())
+            },
+        );
+    }
+}
+
+impl UserData for LuaTrans {
+    fn add_methods<'lua, M>(methods: &mut M)
+    where
+        M: UserDataMethods<'lua, Self>,
+    {
+        methods.add_method("translate", |lua, t, offset: Value| {
+            Ok(LuaTrans(t.0.translate(to_vec2(lua, offset)?)))
+        });
+        methods.add_method("rotate", |_, t, radians: f32| {
+            Ok(LuaTrans(t.0.rotate(radians)))
+        });
+        methods.add_method("rotate_about", |lua, t, (radians, pivot): (f32, Value)| {
+            Ok(LuaTrans(t.0.rotate_about(radians, to_vec2(lua, pivot)?)))
+        });
+        methods.add_method("scale", |lua, t, ratio: Value| {
+            Ok(LuaTrans(t.0.scale(to_vec2_or_scalar(lua, ratio)?)))
+        });
+        methods.add_method("then", |_, t, next: LuaTrans| {
+            Ok(LuaTrans(t.0.then(next.0)))
+        });
+        methods.add_method("but_first", |_, t, prev: LuaTrans| {
+            Ok(LuaTrans(t.0.but_first(prev.0)))
+        });
+        methods.add_method("apply", |lua, t, v: Value| {
+            Ok(LuaVec2(t.0.apply(to_vec2(lua, v)?)))
+        });
+        // `t * v` applies the transform to a vector
+        methods.add_meta_method(MetaMethod::Mul, |lua, t, v: Value| {
+            Ok(LuaVec2(t.0.apply(to_vec2(lua, v)?)))
+        });
+        methods.add_meta_method(MetaMethod::Eq, |_, t, other: LuaTrans| Ok(*t == other));
+        methods.add_meta_method(MetaMethod::ToString, |_, t, ()| {
+            Ok(format!("Trans({:?}, {:?})", t.0[0], t.0[1]))
+        });
+    }
+}
+
+impl UserData for LuaCol {
+    fn add_methods<'lua, M>(methods: &mut M)
+    where
+        M: UserDataMethods<'lua, Self>,
+    {
+        methods.add_method("with_alpha", |_, c, alpha: f32| {
+            Ok(LuaCol(c.0.with_alpha(alpha)))
+        });
+        methods.add_method("lerp", |lua, c, (other, t): (Value, f32)| {
+            Ok(LuaCol(Color::lerp(c.0, to_col(lua, other)?, t)))
+        });
+        methods.add_method("hue", |_, c, ()| Ok(c.0.hue()));
+        methods.add_method("with_hue", |_, c, hue: f32| Ok(LuaCol(c.0.with_hue(hue))));
+        methods.add_method("shift_hue", |_, c, degrees: f32| {
+            Ok(LuaCol(c.0.shift_hue(degrees)))
+        });
+        methods.add_method("to_hsv", |_, c, ()| {
+            let [h, s, v] = c.0.to_hsv();
+            Ok((h, s, v))
+        });
+        methods.add_method("as_gray", |_, c, ()| Ok(c.0.as_gray()));
+        methods.add_method("multiply", |lua, c, top: Value| {
+            Ok(LuaCol(c.0.multiply(to_col(lua, top)?)))
+        });
+        methods.add_method("screen", |lua, c, top: Value| {
+            Ok(LuaCol(c.0.screen(to_col(lua, top)?)))
+        });
+        methods.add_method("overlay", |lua, c, top: Value| {
+            Ok(LuaCol(c.0.overlay(to_col(lua, top)?)))
+        });
+        methods.add_method("additive", |lua, c, top: Value| {
+            Ok(LuaCol(c.0.additive(to_col(lua, top)?)))
+        });
+        methods.add_method("over", |lua, c, top: Value| {
+            Ok(LuaCol(c.0.over(to_col(lua, top)?)))
+        });
+        methods.add_method("unpack", |_, c, ()| Ok((c.0[0], c.0[1], c.0[2], c.0[3])));
+        // `c * n` scales the rgb components, and `a * b` multiplies colors component-wise
+        methods.add_meta_function(MetaMethod::Mul, |lua, (a, b): (Value, Value)| {
+            Ok(LuaCol(match (a, b) {
+                (c, n @ Value::Integer(_)) | (c, n @ Value::Number(_)) => {
+                    Color::mul(to_col(lua, c)?, lua.unpack(n)?)
+                }
+                (n @ Value::Integer(_), c) | (n @ Value::Number(_), c) => {
+                    Color::mul(to_col(lua, c)?, lua.unpack(n)?)
+                }
+                (a, b) => to_col(lua, a)?.mul_color(to_col(lua, b)?),
+            }))
+        });
+        methods.add_meta_method(MetaMethod::Eq, |_, c, other: LuaCol| Ok(*c == other));
+        methods.add_meta_method(MetaMethod::ToString, |_, c, ()| {
+            Ok(format!(
+                "Col({}, {}, {}, {})",
+                c.0[0], c.0[1], c.0[2], c.0[3]
+            ))
+        });
+        methods.add_meta_method(MetaMethod::Index, |_, c, key: Value| {
+            Ok(component_index(&key, &["r", "g", "b", "a"]).map(|i| c.0[i]))
+        });
+        methods.add_meta_method_mut(MetaMethod::NewIndex, |_, c, (key, value): (Value, f32)| {
+            let i = component_index(&key, &["r", "g", "b", "a"])
+                .ok_or_else(|| no_field("Col", &key))?;
+            c.0[i] = value;
+            Ok(())
+        });
+    }
+}
+
+/// Set a global constructor table that can be called like a function
+fn constructor<'lua, A, R, F>(lua: &'lua Lua, name: &str, new: F) -> KuleResult<mlua::Table<'lua>>
+where
+    A: mlua::FromLuaMulti<'lua>,
+    R: mlua::ToLuaMulti<'lua>,
+    F: 'static + Fn(&'lua Lua, A) -> mlua::Result<R>,
+{
+    let table = lua.create_table()?;
+    let meta = lua.create_table()?;
+    // The first argument of `__call` is the table itself
+    meta.set(
+        "__call",
+        lua.create_function(move |lua, (_, args): (Value, A)| new(lua, args))?,
+    )?;
+    table.set_metatable(Some(meta));
+    lua.globals().set(name, table.clone())?;
+    Ok(table)
+}
+
+/// Add the math type constructors to the globals
+pub(crate) fn install_math(lua: &Lua) -> KuleResult<()> {
+    constructor(lua, "Vec2", |_, (x, y): (f32, f32)| Ok(LuaVec2([x, y])))?;
+    constructor(lua, "Rect", |_, (x, y, w, h): (f32, f32, f32, f32)| {
+        Ok(LuaRect([x, y, w, h]))
+    })?;
+    constructor(lua, "Circle", |lua, (center, radius): (Value, f32)| {
+        Ok(LuaCirc((to_vec2(lua, center)?, radius)))
+    })?;
+    let trans = constructor(lua, "Trans", |_, ()| Ok(LuaTrans(Trans::identity())))?;
+    trans.set(
+        "rotation",
+        lua.create_function(|_, radians: f32| Ok(LuaTrans(Trans::new_rotate(radians))))?,
+    )?;
+    trans.set(
+        "translation",
+        lua.create_function(|lua, offset: Value| {
+            Ok(LuaTrans(Trans::new_translate(to_vec2(lua, offset)?)))
+        })?,
+    )?;
+    let col = constructor(
+        lua,
+        "Col",
+        |_, (r, g, b, a): (f32, f32, f32, Option<f32>)| {
+            Ok(LuaCol(Col::rgba(r, g, b, a.unwrap_or(1.0))))
+        },
+    )?;
+    col.set(
+        "hsv",
+        lua.create_function(|_, (h, s, v, a): (f32, f32, f32, Option<f32>)| {
+            Ok(LuaCol(Col::hsva(h, s, v, a.unwrap_or(1.0))))
+        })?,
+    )?;
+    col.set(
+        "hsl",
+        lua.create_function(|_, (h, s, l, a): (f32, f32, f32, Option<f32>)| {
+            Ok(LuaCol(Col::hsla(h, s, l, a.unwrap_or(1.0))))
+        })?,
+    )?;
+    col.set(
+        "gray",
+        lua.create_function(|_, v: f32| Ok(LuaCol(Col::gray(v))))?,
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+#[test]
+fn math_userdata() {
+    let lua = Lua::new();
+    install_math(&lua).unwrap();
+    lua.load(
+        r#"
+        local v = Vec2(1, 2) + {3, 4} + Vec2(3, 4)
+        assert(v == Vec2(7, 10) and v.x == v[1])
+        v.y = 0
+        assert(v:mag() == 7)
+        local rect = Rect(0, 0, 10, 10)
+        assert(rect:contains(rect:center()) and not rect:contains({20, 0}))
+        assert(Circle({0, 0}, 2):to_square() == Rect(-2, -2, 4, 4))
+        local moved = Trans():translate({5, 0}) * Vec2(1, 1)
+        assert(moved == Vec2(6, 1))
+        local c = Col(1, 0, 0) * Col.gray(0.5)
+        assert(c.r == 0.5 and c.a == 1)
+        assert(tostring(Vec2(1, 2)) == "Vec2(1, 2)")
+        "#,
+    )
+    .exec()
+    .unwrap();
+    let v: LuaVec2 = lua.load("Vec2(3, 4):unit()").eval().unwrap();
+    assert_eq!(v.0, [0.6, 0.8]);
+}
@@ -1,21 +1,55 @@
+mod camera;
+mod config_value;
+mod console;
+mod de;
+mod engine;
+#[cfg(feature = "json")]
+mod json_config;
+mod lua_config;
+mod math;
+mod module;
+mod reload;
+#[cfg(feature = "ron")]
+mod ron_config;
+mod sandbox;
 mod ser;
+mod state;
+mod task;
+pub use config_value::ConfigValue;
+pub use console::*;
+pub use de::*;
+pub use engine::EngineFn;
+pub use math::{LuaCirc, LuaCol, LuaRect, LuaTrans, LuaVec2};
+pub use module::*;
 pub use ser::*;
-// mod de;
-// pub use de::*;
+pub use state::*;
 
+#[cfg(feature = "manifest")]
+use std::sync::Arc;
 use std::{
-    env, fs,
+    cell::RefCell,
+    collections::{BTreeMap, HashMap, HashSet},
+    ffi::OsStr,
+    fs,
     path::{Path, PathBuf},
 };
 
-use mlua::{FromLua, Function, ToLua};
-use serde::ser::*;
+use mlua::{FromLua, FromLuaMulti, Function, HookTriggers, Thread, ToLua, ToLuaMulti, Value};
+use serde::{de::DeserializeOwned, ser::*};
 
-use crate::KuleResult;
+use crate::{Camera, KuleError, KuleResult};
+use engine::{raw_engine_fn, RawEngineFn};
+use task::SharedTasks;
 
 pub use mlua;
 pub use mlua::{Lua, StdLib, Table};
 
+/// How many instructions run between checks of a module's instruction limit
+const INSTRUCTION_CHECK_INTERVAL: u32 = 1000;
+
+/// The registry key of the name of the module whose code is running
+const CURRENT_MODULE: &str = "kule_current_module";
+
 /// Defines where script modules should be saved to and loaded from
 #[derive(Debug, Clone)]
 pub struct ScriptEnv {
@@ -23,13 +57,42 @@ pub struct ScriptEnv {
     pub dir: PathBuf,
     /// The name of the config file
     ///
-    /// This will be joined onto `dir` and given a `toml` extension
+    /// This will be joined onto `dir`. Its extension selects which of the `formats`
+    /// it is parsed with. If it has no extension, it is given a `toml` extension.
     pub config: String,
+    /// The formats the config file can be written in
+    ///
+    /// `ConfigFormat::TOML` and `ConfigFormat::LUA` are included by default, as are
+    /// `ConfigFormat::JSON` and `ConfigFormat::RON` if their features are enabled
+    pub formats: Vec<ConfigFormat>,
     /// The Lua standard library to use
     ///
     /// An error will occur if you include `StdLib::DEBUG`. `ScriptEnv::new`
     /// automatically removes `DEBUG` from whatever flags you pass it.
     pub std_lib: StdLib,
+    /// The default maximum number of Lua instructions a module may run each time
+    /// it is loaded or called
+    ///
+    /// Modules can override this in the config file
+    pub instruction_limit: Option<u64>,
+    /// The default maximum number of bytes a module may allocate each time it is
+    /// loaded or called
+    ///
+    /// This is a budget for each call, not a cap on the memory a module holds.
+    /// Memory that is still in use from earlier calls does not count against it.
+    ///
+    /// Modules can override this in the config file
+    pub memory_limit: Option<usize>,
+    /// The default number of batch calls in a row a module may fail before it is
+    /// disabled until the scripts are reloaded
+    ///
+    /// Modules can override this in the config file
+    pub max_failures: Option<u32>,
+    #[cfg(feature = "manifest")]
+    /// An asset pack to load modules from instead of the file system
+    ///
+    /// When this is set, `dir` is a directory inside the pack
+    pub pack: Option<Arc<crate::AssetPack>>,
 }
 
 impl Default for ScriptEnv {
@@ -49,44 +112,161 @@ impl ScriptEnv {
             dir: dir.as_ref().into(),
             config: config.into(),
             std_lib: std_lib & StdLib::ALL_SAFE,
+            instruction_limit: None,
+            memory_limit: None,
+            max_failures: None,
+            formats: vec![
+                ConfigFormat::TOML,
+                ConfigFormat::LUA,
+                #[cfg(feature = "json")]
+                ConfigFormat::JSON,
+                #[cfg(feature = "ron")]
+                ConfigFormat::RON,
+            ],
+            #[cfg(feature = "manifest")]
+            pack: None,
+        }
+    }
+    /// Add a format that the config file can be written in
+    pub fn format(mut self, format: ConfigFormat) -> Self {
+        self.formats.push(format);
+        self
+    }
+    /// Get the format of the config file based on its extension
+    ///
+    /// Returns an error if the extension does not match any of the `formats`
+    pub fn config_format(&self) -> KuleResult<ConfigFormat> {
+        let extension = match Path::new(&self.config).extension() {
+            Some(extension) => extension,
+            None => return Ok(ConfigFormat::TOML),
+        };
+        self.formats
+            .iter()
+            .copied()
+            .find(|format| extension == OsStr::new(format.extension))
+            .ok_or_else(|| KuleError::UnknownConfigFormat(extension.to_string_lossy().into()))
+    }
+    #[cfg(feature = "manifest")]
+    /**
+    Load modules from an asset pack instead of the file system
+
+    This allows a set of modules to be distributed as a single file.
+    `require` finds modules in the pack as well.
+
+    ```no_run
+    # use kule::*;
+    let pack = AssetPack::from_dir("my_mod").unwrap();
+    pack.save("my_mod.pack").unwrap();
+    // Later...
+    let pack = AssetPack::load("my_mod.pack").unwrap();
+    let scripts = Scripts::load(ScriptEnv::new("", "modules", StdLib::ALL_SAFE).pack(pack));
+    ```
+    */
+    pub fn pack(self, pack: crate::AssetPack) -> Self {
+        ScriptEnv {
+            pack: Some(Arc::new(pack)),
+            ..self
         }
     }
     /// Get the file name of the config file
     pub fn config_file(&self) -> PathBuf {
-        PathBuf::from(&self.config).with_extension("toml")
+        let file = PathBuf::from(&self.config);
+        if file.extension().is_some() {
+            file
+        } else {
+            file.with_extension(ConfigFormat::TOML.extension)
+        }
     }
     /// Get the path to the config file
     pub fn config_path(&self) -> PathBuf {
         self.dir.join(self.config_file())
     }
+    /// Load the module list from the config file
+    pub(crate) fn load_modules(&self) -> KuleResult<Modules> {
+        Modules::load(&self.config_path(), &self.config_format()?, |path| {
+            self.read_file(path)
+        })
+    }
+    /// Check if a file exists in the file system or the pack
+    pub(crate) fn is_file(&self, path: &Path) -> bool {
+        #[cfg(feature = "manifest")]
+        if let Some(pack) = &self.pack {
+            return pack.contains(path);
+        }
+        path.is_file()
+    }
+    /// Read a file from the file system or the pack, returning `None` if it does not exist
+    pub(crate) fn read_file(&self, path: &Path) -> KuleResult<Option<String>> {
+        #[cfg(feature = "manifest")]
+        if let Some(pack) = &self.pack {
+            return match pack.get(path) {
+                Ok(bytes) => Ok(Some(String::from_utf8(bytes.to_vec()).map_err(|e| {
+                    KuleError::AssetLoad(format!("{}: {}", path.display(), e))
+                })?)),
+                Err(_) => Ok(None),
+            };
+        }
+        match fs::read_to_string(path) {
+            Ok(text) => Ok(Some(text)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
 }
 
-/// A handle to a scripting environment
+/**
+A handle to a scripting environment
+
+Each module runs in its own environment table. Globals that a module sets are only
+visible to that module, while reads fall back to the shared globals. Modules can read
+each other's tables by name, but cannot change them. Shared tables like `string` and
+`math` are read-only too. `_G`, `rawset`, and `package` are hidden from modules, and
+chunks that a module loads with `require` or `load` run in that module's environment. Each module's table is also stored in the shared globals, which
+the [`Console`] and [`Scripts::lua`] can reach.
+
+Modules can be given instruction and memory limits, either in the `ScriptEnv` or per
+module in the config file. Both limits apply to each load or call separately:
+
+```toml
+[[mod]]
+name = "community_mod"
+instruction_limit = 1000000
+memory_limit = 10485760
+max_failures = 10
+```
+
+If `max_failures` is set, a module that fails that many batch calls in a row is
+disabled until the scripts are reloaded. See [`Scripts::batch_call`].
+
+A module's settings, with defaults filled in, are visible to it as the `settings`
+global. See [`Setting`] for how settings are declared.
+*/
 pub struct Scripts {
     /// The list of modules
     pub modules: Vec<Module>,
     /// The script environment
     pub env: ScriptEnv,
     lua: Lua,
+    engine_fns: Vec<(String, RawEngineFn)>,
+    tasks: SharedTasks,
+    pending: Option<reload::PendingReload>,
+    failures: RefCell<HashMap<String, u32>>,
+    disabled: RefCell<HashSet<String>>,
 }
 
 impl Scripts {
     /**
     Access the Lua environment
 
-    For the duration of the passed closue, the program's current directory
-    will be the script modules directory
+    `require` looks for modules in the script modules directory. Other relative
+    paths, such as those passed to `io.open`, are relative to the program's
+    current directory.
     */
     pub fn lua<F, R>(&self, f: F) -> KuleResult<R>
     where
         F: FnOnce(&Lua) -> KuleResult<R>,
     {
-        let current_dir = env::current_dir()?;
-        fs::create_dir_all(&self.env.dir)?;
-        env::set_current_dir(&self.env.dir)?;
-        let res = f(&self.lua)?;
-        env::set_current_dir(current_dir)?;
-        Ok(res)
+        f(&self.lua)
     }
     /// Serialize a value into a global Lua value
     pub fn serialize_global<T>(&self, name: &str, val: &T) -> KuleResult<()>
@@ -100,48 +280,238 @@ impl Scripts {
             Ok(())
         })
     }
+    /**
+    Run a closure with a camera available to scripts as the global `camera`
+
+    Changes that scripts make to the camera are written back afterward.
+    */
+    pub fn with_camera<F, T>(&self, camera: &mut Camera, f: F) -> KuleResult<T>
+    where
+        F: FnOnce(&Self) -> T,
+    {
+        self.lua(|lua| Ok(lua.globals().set("camera", *camera)?))?;
+        let res = f(self);
+        *camera = self.lua(|lua| Ok(lua.globals().get("camera")?))?;
+        Ok(res)
+    }
+    /**
+    Register a Rust function in the global `engine` table so scripts can call it
+
+    Arguments are deserialized from Lua values and the return value is serialized
+    back, so the function can take and return any serde types. Registered functions
+    are kept when the scripts are reloaded.
+
+    ```no_run
+    # use kule::*;
+    # let mut scripts = Scripts::load(ScriptEnv::default()).unwrap();
+    scripts
+        .register_fn("spawn_enemy", |pos: Vec2, kind: String| {
+            println!("Spawning a {} at {:?}", kind, pos);
+            Ok(())
+        })
+        .unwrap();
+    ```
+
+    Scripts call it like any other function:
+
+    ```lua
+    engine.spawn_enemy({100, 50}, "slime")
+    ```
+    */
+    pub fn register_fn<A, F>(&mut self, name: &str, f: F) -> KuleResult<()>
+    where
+        F: EngineFn<A>,
+    {
+        let raw = raw_engine_fn(f);
+        self.engine_fns.retain(|(n, _)| n != name);
+        self.engine_fns.push((name.into(), raw.clone()));
+        self.lua(|lua| install_engine_fn(lua, name, raw))
+    }
     /// Load scripts with the given lua std library
     pub fn load(env: ScriptEnv) -> KuleResult<Self> {
         let mut scripts = Scripts {
             lua: Lua::new(),
             modules: Vec::new(),
             env,
+            engine_fns: Vec::new(),
+            tasks: SharedTasks::default(),
+            pending: None,
+            failures: RefCell::new(HashMap::new()),
+            disabled: RefCell::new(HashSet::new()),
         };
         scripts.reload()?;
         Ok(scripts)
     }
-    /// Reload the scripts
-    #[allow(clippy::redundant_closure)]
+    /**
+    Reload the scripts
+
+    Reloading creates a new Lua environment, so module state is lost unless the module
+    implements `save_state` and `load_state`. `save_state` is called before the reload and
+    can return any value made of nil, booleans, numbers, strings, and tables. That value is
+    passed to `load_state` after the module is loaded again.
+
+    ```lua
+    local quest = { stage = 1 }
+    function quest:save_state() return { stage = self.stage } end
+    function quest:load_state(state) self.stage = state.stage end
+    return quest
+    ```
+
+    If saving or loading state fails, the old scripts keep running.
+
+    This blocks until every module is loaded. See [`Scripts::start_reload`] for a
+    reload that is spread across updates.
+    */
     pub fn reload(&mut self) -> KuleResult<()> {
-        let (lua, modules) = self.lua(|_| {
-            let config_text = fs::read_to_string(self.env.config_file())?;
-            let modules: Modules = toml::from_str(&config_text)?;
-            let lua = Lua::new_with(self.env.std_lib)?;
-            // Load modules
-            lua.load(
-                &modules
-                    .list
-                    .iter()
-                    .filter(|m| m.enabled)
-                    .map(|m| format!("{0} = require(\"{0}\")\n", m.name))
-                    .collect::<String>(),
-            )
-            .exec()?;
-            Ok((lua, modules))
+        let modules = self.env.load_modules()?;
+        let (lua, tasks) = self.new_lua()?;
+        for module in modules.enabled() {
+            let source = read_module(&self.env, &module.name)?;
+            self.run_module(&lua, module, &source)?;
+        }
+        self.finish_reload(lua, tasks, modules)
+    }
+    /// Create a new Lua environment with the engine's globals installed
+    fn new_lua(&self) -> KuleResult<(Lua, SharedTasks)> {
+        let lua = Lua::new_with(self.env.std_lib)?;
+        install_searcher(&lua, &self.env)?;
+        sandbox::install_sandbox(&lua, &self.env)?;
+        math::install_math(&lua)?;
+        let tasks = SharedTasks::default();
+        task::install_tasks(&lua, &engine_table(&lua)?, &tasks)?;
+        for (name, raw) in &self.engine_fns {
+            install_engine_fn(&lua, name, raw.clone())?;
+        }
+        Ok((lua, tasks))
+    }
+    /// Run a module's source and store its table in the globals
+    fn run_module(&self, lua: &Lua, module: &Module, source: &str) -> KuleResult<()> {
+        let name = module.name.as_str();
+        let settings = LuaSerializer::new(lua).serialize(&module.setting_values()?)?;
+        let table = self.limited(lua, Some(module), |lua| {
+            load_module(lua, name, source, settings)
         })?;
+        sandbox::add_module(lua, name, table.clone())?;
+        lua.globals().set(name, table.clone())?;
+        lua.globals()
+            .val::<Table>("package")?
+            .val::<Table>("loaded")?
+            .set(name, table)?;
+        Ok(())
+    }
+    /// Move module state into a new Lua environment and replace the current one
+    fn finish_reload(&mut self, lua: Lua, tasks: SharedTasks, modules: Modules) -> KuleResult<()> {
+        let states = self.module_states()?;
+        for module in modules.enabled() {
+            let name = module.name.as_str();
+            let state = match states.get(name) {
+                Some(state) => state,
+                None => continue,
+            };
+            let table = match lua.globals().get(name)? {
+                Value::Table(table) => table,
+                _ => continue,
+            };
+            if let Ok(load_state) = table.get::<_, Function>("load_state") {
+                let state = LuaSerializer::new(&lua).serialize(state)?;
+                self.limited(&lua, Some(module), |_| {
+                    Ok(load_state.call::<_, ()>((table, state))?)
+                })
+                .map_err(|e| script_error(name, "load_state", e))?;
+            }
+        }
         self.lua = lua;
+        self.tasks = tasks;
         self.modules = modules.list;
+        self.failures.borrow_mut().clear();
+        self.disabled.borrow_mut().clear();
         Ok(())
     }
+    /**
+    Get the state of each enabled module that implements `save_state`
+
+    This is called automatically by [`Scripts::reload`].
+    */
+    pub fn module_states(&self) -> KuleResult<BTreeMap<String, StateValue>> {
+        let mut states = BTreeMap::new();
+        for name in self.enabled_modules() {
+            let loaded = self.lua(|lua| Ok(matches!(lua.globals().get(name)?, Value::Table(_))))?;
+            if !loaded {
+                continue;
+            }
+            if let Some(state) = self.call_ret(name, "save_state", ())? {
+                states.insert(name.into(), state);
+            }
+        }
+        Ok(states)
+    }
     /// Save the script modules
+    ///
+    /// Modules loaded from an asset pack cannot be saved
     pub fn save_modules(&self) -> KuleResult<()> {
-        self.lua(|_| {
-            Modules {
-                list: self.modules.clone(),
-            }
-            .save(&self.env.config_path())
-        })?;
-        Ok(())
+        #[cfg(feature = "manifest")]
+        if self.env.pack.is_some() {
+            return Err(KuleError::Static(
+                "Script modules loaded from an asset pack cannot be saved",
+            ));
+        }
+        fs::create_dir_all(&self.env.dir)?;
+        Modules {
+            list: self.modules.clone(),
+        }
+        .save(&self.env.config_path(), &self.env.config_format()?)
+    }
+    /// Run a closure with a module's instruction and memory limits applied
+    fn limited<'lua, F, R>(&self, lua: &'lua Lua, module: Option<&Module>, f: F) -> KuleResult<R>
+    where
+        F: FnOnce(&'lua Lua) -> KuleResult<R>,
+    {
+        let instruction_limit = module
+            .and_then(|m| m.instruction_limit)
+            .or(self.env.instruction_limit);
+        let memory_limit = module
+            .and_then(|m| m.memory_limit)
+            .or(self.env.memory_limit);
+        if let Some(limit) = instruction_limit {
+            let name = module.map(|m| m.name.clone()).unwrap_or_default();
+            let mut count = 0;
+            lua.set_hook(
+                HookTriggers {
+                    every_nth_instruction: Some(INSTRUCTION_CHECK_INTERVAL),
+                    ..Default::default()
+                },
+                move |_, _| {
+                    count += INSTRUCTION_CHECK_INTERVAL as u64;
+                    if count > limit {
+                        Err(mlua::Error::RuntimeError(format!(
+                            "Module {:?} exceeded its limit of {} instructions",
+                            name, limit
+                        )))
+                    } else {
+                        Ok(())
+                    }
+                },
+            )?;
+        }
+        // Remember the running module so that tasks it starts are run with its limits
+        let prev_module: Value = lua.named_registry_value(CURRENT_MODULE)?;
+        if let Some(module) = module {
+            lua.set_named_registry_value(CURRENT_MODULE, module.name.as_str())?;
+        }
+        // The memory limit is a budget for this call on top of what is already in use
+        let prev_memory_limit = match memory_limit {
+            Some(limit) => Some(lua.set_memory_limit(lua.used_memory() + limit)?),
+            None => None,
+        };
+        let res = f(lua);
+        if instruction_limit.is_some() {
+            lua.remove_hook();
+        }
+        if let Some(prev) = prev_memory_limit {
+            lua.set_memory_limit(prev)?;
+        }
+        lua.set_named_registry_value(CURRENT_MODULE, prev_module)?;
+        res
     }
     /// Iterate over the names of the enabled modules
     pub fn enabled_modules(&self) -> impl Iterator<Item = &str> {
@@ -156,19 +526,87 @@ impl Scripts {
     The `call` closure takes the Lua environment, the module table, and the function.
     This allows the method to be defined with either a `.` or a `:`.
 
-    Nothing happens if the module table does not contain the method
+    Lua values only live as long as the closure, so whatever it returns must not borrow
+    from the Lua environment.
+
+    Errors that happen during the call are returned as `KuleError::Script`.
+
+    Returns `None` if the module table does not contain the method
     */
-    pub fn call<'lua, F>(&self, module_name: &str, method_name: &str, call: F) -> KuleResult<()>
+    pub fn call<F, R>(&self, module_name: &str, method_name: &str, call: F) -> KuleResult<Option<R>>
     where
-        F: Fn(&'lua Lua, Table<'lua>, Function<'lua>) -> KuleResult<()>,
+        F: for<'lua> FnOnce(&'lua Lua, Table<'lua>, Function<'lua>) -> KuleResult<R>,
     {
-        self.lua(|ctx| {
-            let globals = ctx.globals();
-            let table: Table = unsafe { std::mem::transmute(globals.val::<Table>(module_name)?) };
-            if let Ok(function) = table.get(method_name) {
-                call(unsafe { std::mem::transmute(ctx) }, table.clone(), function)?;
+        let module = self.modules.iter().find(|m| m.name == module_name);
+        self.lua(|lua| {
+            let table: Table = lua.globals().val(module_name)?;
+            match table.get::<_, Function>(method_name) {
+                Ok(function) => self
+                    .limited(lua, module, |lua| call(lua, table, function))
+                    .map(Some)
+                    .map_err(|e| script_error(module_name, method_name, e)),
+                Err(_) => Ok(None),
             }
-            Ok(())
+        })
+    }
+    /**
+    Call a module method with some arguments and get its return value
+
+    The module table is passed before the arguments, so the method should be defined
+    with a `:`.
+
+    Returns `None` if the module table does not contain the method
+
+    ```no_run
+    # use kule::*;
+    # let scripts = Scripts::load(ScriptEnv::default()).unwrap();
+    let damage: Option<f32> = scripts.call_method("combat", "damage", (10.0, "fire")).unwrap();
+    ```
+    */
+    pub fn call_method<A, R>(
+        &self,
+        module_name: &str,
+        method_name: &str,
+        args: A,
+    ) -> KuleResult<Option<R>>
+    where
+        A: for<'lua> ToLuaMulti<'lua>,
+        R: for<'lua> FromLuaMulti<'lua>,
+    {
+        self.call(module_name, method_name, |_, table, function| {
+            Ok(function.call((table, args))?)
+        })
+    }
+    /**
+    Call a module method with some arguments and deserialize its return value
+
+    Unlike [`Scripts::call_method`], the return value can be any type that implements
+    `Deserialize`. It is deserialized with a [`LuaDeserializer`].
+
+    The module table is passed before the arguments, so the method should be defined
+    with a `:`.
+
+    Returns `None` if the module table does not contain the method
+
+    ```no_run
+    # use kule::*;
+    # let scripts = Scripts::load(ScriptEnv::default()).unwrap();
+    let drops: Option<Vec<String>> = scripts.call_ret("loot", "drops", "goblin").unwrap();
+    ```
+    */
+    pub fn call_ret<A, T>(
+        &self,
+        module_name: &str,
+        method_name: &str,
+        args: A,
+    ) -> KuleResult<Option<T>>
+    where
+        A: for<'lua> ToLuaMulti<'lua>,
+        T: DeserializeOwned,
+    {
+        self.call(module_name, method_name, |lua, table, function| {
+            let value: Value = function.call((table, args))?;
+            Ok(LuaDeserializer::new(lua, value).deserialize()?)
         })
     }
     /**
@@ -177,66 +615,309 @@ impl Scripts {
     The `call` closure takes the Lua environment, the module table, and the function.
     This allows the method to be defined with either a `.` or a `:`.
 
-    Module order is respected.
+    Module order is respected. A module that fails does not stop the rest of the
+    modules from being called. The results report which modules returned, which
+    failed, and which were disabled.
+
+    If a module fails more batch calls in a row than its `max_failures`, it is disabled
+    and skipped by batch calls until the scripts are reloaded or
+    [`Scripts::reset_failures`] is called.
 
     This makes it easy to have multiple modules define the same type of behavior
     and execute it all at once.
     */
-    pub fn batch_call<'lua, F>(&self, method_name: &str, call: F) -> KuleResult<()>
+    pub fn batch_call<F, R>(&self, method_name: &str, call: F) -> BatchResults<R>
     where
-        F: Fn(&'lua Lua, Table<'lua>, Function<'lua>) -> KuleResult<()>,
+        F: for<'lua> Fn(&'lua Lua, Table<'lua>, Function<'lua>) -> KuleResult<R>,
     {
-        for name in self.enabled_modules() {
-            self.call(name, method_name, &call)?;
+        self.batch(|name| self.call(name, method_name, &call))
+    }
+    /**
+    Call the same method with the same arguments in each module that has it and
+    collect the return values
+
+    Return values are deserialized as in [`Scripts::call_ret`]. Module order is
+    respected, and a module that fails does not stop the rest of the modules from
+    being called.
+
+    This is useful for queries that any module can answer:
+
+    ```no_run
+    # use kule::*;
+    # let scripts = Scripts::load(ScriptEnv::default()).unwrap();
+    let can_enter = scripts
+        .batch_call_ret::<_, bool>("can_enter", (3, 4))
+        .values()
+        .all(|&can| can);
+    ```
+    */
+    pub fn batch_call_ret<A, T>(&self, method_name: &str, args: A) -> BatchResults<T>
+    where
+        A: for<'lua> ToLuaMulti<'lua> + Clone,
+        T: DeserializeOwned,
+    {
+        self.batch(|name| self.call_ret(name, method_name, args.clone()))
+    }
+    /// Call a function for each enabled module that has not been disabled for failing
+    fn batch<F, R>(&self, mut call: F) -> BatchResults<R>
+    where
+        F: FnMut(&str) -> KuleResult<Option<R>>,
+    {
+        let mut results = BatchResults {
+            returns: Vec::new(),
+            errors: Vec::new(),
+            disabled: Vec::new(),
+        };
+        for module in self.modules.iter().filter(|m| m.enabled) {
+            let name = module.name.as_str();
+            if self.is_disabled(name) {
+                continue;
+            }
+            match call(name) {
+                Ok(Some(value)) => {
+                    self.failures.borrow_mut().remove(name);
+                    results.returns.push((name.into(), value));
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    results.errors.push(e);
+                    let mut failures = self.failures.borrow_mut();
+                    let count = failures.entry(name.into()).or_insert(0);
+                    *count += 1;
+                    let max_failures = module.max_failures.or(self.env.max_failures);
+                    if matches!(max_failures, Some(max) if *count >= max) {
+                        self.disabled.borrow_mut().insert(name.into());
+                        results.disabled.push(name.into());
+                    }
+                }
+            }
         }
-        Ok(())
+        results
     }
-}
+    /// Check if a module was disabled for failing too many batch calls in a row
+    pub fn is_disabled(&self, module_name: &str) -> bool {
+        self.disabled.borrow().contains(module_name)
+    }
+    /// Get the number of batch calls in a row a module has failed
+    pub fn failures(&self, module_name: &str) -> u32 {
+        self.failures
+            .borrow()
+            .get(module_name)
+            .copied()
+            .unwrap_or(0)
+    }
+    /// Reset a module's failure count, enabling it again if it was disabled for failing
+    pub fn reset_failures(&self, module_name: &str) {
+        self.failures.borrow_mut().remove(module_name);
+        self.disabled.borrow_mut().remove(module_name);
+    }
+    /**
+    Resume the tasks started with `engine.start_task`
+
+    Tasks are resumed on the update after they are started. A task can call `wait(seconds)`
+    to sleep for some time, or `wait_event(name)` to sleep until an event with the given
+    variant name is passed to [`Scripts::task_event`]. `wait_event` returns the event.
 
-fn default_enabled() -> bool {
-    true
+    ```lua
+    engine.start_task(function()
+        show_text("Press a key to continue")
+        local event = wait_event("Key")
+        wait(0.5)
+        show_text("Pressed " .. event.key)
+    end)
+    ```
+
+    `engine.start_task` returns an id that can be passed to `engine.cancel_task`.
+
+    The context's scripts are updated automatically. All tasks are stopped when the scripts
+    are reloaded. Errors from tasks are returned and the failed tasks are stopped.
+    */
+    pub fn update_tasks(&self, dt: f32) -> Vec<KuleError> {
+        task::update_tasks(&self.lua, &self.tasks, dt, |module, thread, arg| {
+            self.resume_task(module, thread, arg)
+        })
+    }
+    /// Resume the tasks waiting for an event
+    ///
+    /// The context's scripts are passed events automatically
+    pub fn task_event<E>(&self, event: &E) -> Vec<KuleError>
+    where
+        E: Serialize,
+    {
+        match LuaSerializer::new(&self.lua).serialize(event) {
+            Ok(event) => task::event_tasks(&self.lua, &self.tasks, event, |module, thread, arg| {
+                self.resume_task(module, thread, arg)
+            }),
+            Err(e) => vec![e.into()],
+        }
+    }
+    /// Get the number of running tasks
+    pub fn task_count(&self) -> usize {
+        self.tasks.borrow().len()
+    }
+    /// Resume a task's thread with the limits of the module that started it
+    fn resume_task<'lua>(
+        &self,
+        module_name: Option<&str>,
+        thread: Thread<'lua>,
+        arg: Value<'lua>,
+    ) -> KuleResult<(Value<'lua>, Value<'lua>)> {
+        let module = module_name.and_then(|name| self.modules.iter().find(|m| m.name == name));
+        self.limited(&self.lua, module, |_| Ok(thread.resume(arg)?))
+            .map_err(|e| match module_name {
+                Some(name) => script_error(name, "task", e),
+                None => e,
+            })
+    }
 }
 
-/// An identifier for a script module
-#[derive(Debug, Clone, serde_derive::Serialize, serde_derive::Deserialize)]
-pub struct Module {
-    name: String,
-    #[serde(default = "default_enabled", skip_serializing_if = "Clone::clone")]
-    enabled: bool,
+/// The results of calling a method in multiple modules
+#[derive(Debug)]
+pub struct BatchResults<T> {
+    /// The names of the modules that returned successfully and their return values,
+    /// in module order
+    pub returns: Vec<(String, T)>,
+    /// The errors from the modules that failed, in module order
+    pub errors: Vec<KuleError>,
+    /// The names of the modules that were disabled because they failed too many
+    /// times in a row
+    pub disabled: Vec<String>,
 }
 
-impl Module {
-    /// Get the module name
-    pub fn name(&self) -> &str {
-        &self.name
+impl<T> BatchResults<T> {
+    /// Iterate over the return values in module order
+    pub fn values(&self) -> impl DoubleEndedIterator<Item = &T> {
+        self.returns.iter().map(|(_, value)| value)
     }
-    /// Get whether the module is enabled
-    pub fn is_enabled(&self) -> bool {
-        self.enabled
+    /// Get the return value of a specific module
+    pub fn get(&self, module_name: &str) -> Option<&T> {
+        self.returns
+            .iter()
+            .find(|(name, _)| name == module_name)
+            .map(|(_, value)| value)
     }
-    /// Set whether the module is enabled
-    ///
-    /// After enabling or disabling a module [`Scripts::reload`] must
-    /// be called to actually see the changes
-    pub fn set_enabled(&mut self, enabled: bool) {
-        self.enabled = enabled;
+    /// Check if no module failed
+    pub fn is_ok(&self) -> bool {
+        self.errors.is_empty()
+    }
+    /// Get the errors followed by a `KuleError::ScriptDisabled` for each disabled module
+    pub fn into_errors(self) -> Vec<KuleError> {
+        let mut errors = self.errors;
+        errors.extend(self.disabled.into_iter().map(KuleError::ScriptDisabled));
+        errors
     }
 }
 
-#[derive(Debug, Default, serde_derive::Serialize, serde_derive::Deserialize)]
-struct Modules {
-    #[serde(rename = "mod")]
-    list: Vec<Module>,
+/// Get the `engine` table, creating it if necessary
+fn engine_table(lua: &Lua) -> KuleResult<Table<'_>> {
+    let globals = lua.globals();
+    Ok(match globals.get::<_, Option<Table>>("engine")? {
+        Some(engine) => engine,
+        None => {
+            let engine = lua.create_table()?;
+            globals.set("engine", engine.clone())?;
+            engine
+        }
+    })
 }
 
-impl Modules {
-    fn save(&self, path: &Path) -> KuleResult<()> {
-        let bytes = toml::to_vec(self)?;
-        fs::write(path, &bytes)?;
-        Ok(())
+/// Put a Rust function in the `engine` table
+fn install_engine_fn(lua: &Lua, name: &str, raw: RawEngineFn) -> KuleResult<()> {
+    engine_table(lua)?.set(name, lua.create_function(move |lua, args| raw(lua, args))?)?;
+    Ok(())
+}
+
+/// Wrap an error from a module method in a `KuleError::Script`
+fn script_error(module: &str, method: &str, error: KuleError) -> KuleError {
+    KuleError::Script {
+        module: module.into(),
+        method: method.into(),
+        traceback: match error {
+            KuleError::Lua(e) => traceback(&e),
+            e => e.to_string(),
+        },
     }
 }
 
+/// Format a Lua error with its cause and stack traceback
+fn traceback(error: &mlua::Error) -> String {
+    match error {
+        mlua::Error::CallbackError {
+            traceback: trace,
+            cause,
+        } => {
+            format!("{}\n{}", traceback(cause), trace)
+        }
+        error => error.to_string(),
+    }
+}
+
+/// Find the file for a module name, where dots separate directories
+fn module_path(env: &ScriptEnv, name: &str) -> Option<PathBuf> {
+    let rel = name.replace('.', "/");
+    let file = env.dir.join(format!("{}.lua", rel));
+    let init = env.dir.join(rel).join("init.lua");
+    if env.is_file(&file) {
+        Some(file)
+    } else if env.is_file(&init) {
+        Some(init)
+    } else {
+        None
+    }
+}
+
+/// Add a `require` searcher that finds modules in the modules directory
+///
+/// It is checked right after `package.preload`
+fn install_searcher(lua: &Lua, env: &ScriptEnv) -> KuleResult<()> {
+    let package = match lua.globals().get::<_, Option<Table>>("package")? {
+        Some(package) => package,
+        None => return Ok(()),
+    };
+    let env = env.clone();
+    let searcher =
+        lua.create_function(move |lua, name: String| match module_path(&env, &name) {
+            Some(path) => {
+                let source = match env.read_file(&path) {
+                    Ok(source) => source.unwrap_or_default(),
+                    Err(e) => return Err(mlua::Error::RuntimeError(e.to_string())),
+                };
+                let loader = lua.load(&source).set_name(&name)?.into_function()?;
+                lua.pack_multi((loader, path.to_string_lossy().into_owned()))
+            }
+            None => lua.pack_multi(format!("\n\tno module {:?} in {}", name, env.dir.display())),
+        })?;
+    package.val::<Table>("searchers")?.raw_insert(2, searcher)?;
+    Ok(())
+}
+
+/// Read a module's source from the modules directory
+fn read_module(env: &ScriptEnv, name: &str) -> KuleResult<String> {
+    let path = module_path(env, name).unwrap_or_else(|| env.dir.join(format!("{}.lua", name)));
+    env.read_file(&path)?.ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("Script module {:?} not found at {}", name, path.display()),
+        )
+        .into()
+    })
+}
+
+/// Load a module's source in its own environment and get the value it returns
+fn load_module<'lua>(
+    lua: &'lua Lua,
+    name: &str,
+    source: &str,
+    settings: Value<'lua>,
+) -> KuleResult<Value<'lua>> {
+    let env = sandbox::module_env(lua, name, settings)?;
+    Ok(lua
+        .load(source)
+        .set_name(name)?
+        .set_environment(env)?
+        .eval()?)
+}
+
 /// Convenience methods for a Lua tables
 pub trait TableExt<'lua, K> {
     /// Get a value based on a key
@@ -256,3 +937,85 @@ where
         Ok(Table::get::<K, V>(self, key)?)
     }
 }
+
+#[cfg(test)]
+#[test]
+fn module_sandboxing() {
+    let dir = std::env::temp_dir().join("kule_sandbox_test");
+    fs::create_dir_all(dir.join("lib")).unwrap();
+    fs::write(dir.join("lib/util.lua"), "return { one = 1 }").unwrap();
+    fs::write(dir.join("lib/env.lua"), "return x").unwrap();
+    fs::write(
+        dir.join("modules.toml"),
+        "[[mod]]\nname = \"a\"\nmemory_limit = 100000\n[[mod]]\nname = \"b\"\ninstruction_limit = 10000\nmax_failures = 2\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.join("a.lua"),
+        "x = require('lib.util').one\nreturn { get = function() return x end, add = function(self, y) return x + y end }",
+    )
+    .unwrap();
+    fs::write(
+        dir.join("b.lua"),
+        "x = 2\nreturn {\n spin = function() while true do end end,\n add = function(self, y) return x * y end,\n spin_task = function() engine.start_task(function() while true do end end) end,\n sandboxed = function() return _G == nil and rawset == nil and package == nil and require('lib.env') == 2 and a.get() == 1 and not pcall(function() a.get = nil end) and not pcall(function() string.rep = nil end) and string.rep('a', 2) == 'aa' and require('string').rep('b', 2) == 'bb' end,\n}",
+    )
+    .unwrap();
+    let scripts = Scripts::load(ScriptEnv::new(&dir, "modules", StdLib::ALL_SAFE)).unwrap();
+    scripts
+        .call("a", "get", |_, _, f| {
+            assert_eq!(f.call::<_, i32>(())?, 1);
+            Ok(())
+        })
+        .unwrap();
+    assert_eq!(scripts.call_method("a", "add", 2).unwrap(), Some(3));
+    assert_eq!(
+        scripts.call_method("b", "sandboxed", ()).unwrap(),
+        Some(true)
+    );
+    assert_eq!(
+        scripts.call_method::<_, i32>("a", "none", ()).unwrap(),
+        None
+    );
+    let sums = scripts.batch_call_ret::<_, i32>("add", 3);
+    assert!(sums.is_ok());
+    assert_eq!(sums.values().copied().collect::<Vec<_>>(), [4, 6]);
+    assert_eq!(sums.get("b"), Some(&6));
+    let spin = || scripts.batch_call("spin", |_, _, f| Ok(f.call::<_, ()>(())?));
+    let results = spin();
+    assert!(matches!(&results.errors[..], [KuleError::Script { module, .. }] if module == "b"));
+    assert!(results.disabled.is_empty());
+    assert_eq!(spin().disabled, ["b"]);
+    assert!(scripts.is_disabled("b"));
+    assert!(spin().errors.is_empty());
+    scripts.reset_failures("b");
+    assert_eq!(spin().errors.len(), 1);
+    // Tasks run with the limits of the module that started them
+    scripts.call_method::<_, ()>("b", "spin_task", ()).unwrap();
+    let errors = scripts.update_tasks(0.0);
+    assert!(
+        matches!(&errors[..], [KuleError::Script { module, method, .. }] if module == "b" && method == "task")
+    );
+    assert_eq!(scripts.task_count(), 0);
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[cfg(all(test, feature = "manifest"))]
+#[test]
+fn pack_modules() {
+    let dir = std::env::temp_dir().join("kule_pack_modules_test");
+    fs::create_dir_all(dir.join("lib")).unwrap();
+    fs::write(dir.join("modules.toml"), "[[mod]]\nname = \"a\"\n").unwrap();
+    fs::write(dir.join("lib/util.lua"), "return { one = 1 }").unwrap();
+    fs::write(
+        dir.join("a.lua"),
+        "return { get = function() return require('lib.util').one end }",
+    )
+    .unwrap();
+    let pack = crate::AssetPack::from_dir(&dir).unwrap();
+    fs::remove_dir_all(dir).unwrap();
+    let pack = crate::AssetPack::from_bytes(&pack.to_bytes()).unwrap();
+    let scripts =
+        Scripts::load(ScriptEnv::new("", "modules", StdLib::ALL_SAFE).pack(pack)).unwrap();
+    assert_eq!(scripts.call_method("a", "get", ()).unwrap(), Some(1));
+    assert!(scripts.save_modules().is_err());
+}
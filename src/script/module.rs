@@ -0,0 +1,436 @@
+/*!
+The list of script modules and their settings
+
+Nothing here depends on the scripting language. The config file lists modules
+in the order they are loaded and called:
+
+```toml
+[[mod]]
+name = "core"
+
+[[mod]]
+name = "extra"
+enabled = false
+```
+*/
+
+use std::{collections::BTreeMap, fs, io, path::Path};
+
+use crate::{ConfigValue, KuleError, KuleResult};
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// An identifier for a script module
+#[derive(Debug, Clone, serde_derive::Serialize, serde_derive::Deserialize)]
+pub struct Module {
+    pub(crate) name: String,
+    #[serde(default = "default_enabled", skip_serializing_if = "Clone::clone")]
+    pub(crate) enabled: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) instruction_limit: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) memory_limit: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) max_failures: Option<u32>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub(crate) settings: BTreeMap<String, Setting>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub(crate) config: BTreeMap<String, ConfigValue>,
+    #[serde(skip)]
+    pub(crate) companion_settings: BTreeMap<String, Setting>,
+}
+
+/// The type of a module setting
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde_derive::Serialize, serde_derive::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SettingType {
+    /// `true` or `false`
+    Bool,
+    /// A whole number
+    Int,
+    /// A number. Integers are converted
+    Float,
+    /// A string
+    String,
+}
+
+/**
+The declaration of a module setting
+
+A module can declare settings with types, defaults, and ranges. Values set by
+the user go in the module's `config` table:
+
+```toml
+[[mod]]
+name = "combat"
+
+[mod.settings.difficulty]
+type = "float"
+default = 1.0
+min = 0.5
+max = 3.0
+
+[mod.settings.mode]
+type = "string"
+default = "normal"
+choices = ["easy", "normal", "hard"]
+
+[mod.config]
+difficulty = 2.0
+```
+
+Settings can also be declared in a companion file next to the config file
+named `<module>.settings.toml`, which contains only the settings tables:
+
+```toml
+[difficulty]
+type = "float"
+default = 1.0
+```
+
+The module sees the values, with defaults filled in, as its `settings` global:
+
+```lua
+local damage = 10 * settings.difficulty
+```
+*/
+#[derive(Debug, Clone, serde_derive::Serialize, serde_derive::Deserialize)]
+pub struct Setting {
+    /// The type of the setting
+    #[serde(rename = "type")]
+    pub ty: SettingType,
+    /// The value used when the user has not set one
+    pub default: ConfigValue,
+    /// The minimum value of a number setting
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min: Option<f64>,
+    /// The maximum value of a number setting
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<f64>,
+    /// The allowed values of a string setting
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub choices: Vec<String>,
+    /// A description of the setting
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+impl Setting {
+    /// Check that a value fits the setting, converting it to the setting's type if necessary
+    pub fn validate(&self, value: &ConfigValue) -> Result<ConfigValue, String> {
+        use ConfigValue::*;
+        let value = match (self.ty, value) {
+            (SettingType::Bool, Bool(_))
+            | (SettingType::Int, Int(_))
+            | (SettingType::Float, Float(_))
+            | (SettingType::String, String(_)) => value.clone(),
+            (SettingType::Float, Int(i)) => Float(*i as f64),
+            (ty, value) => return Err(format!("expected {:?}, found {}", ty, value.type_str())),
+        };
+        let number = match &value {
+            Int(i) => Some(*i as f64),
+            Float(f) => Some(*f),
+            _ => None,
+        };
+        if let Some(n) = number {
+            if let Some(min) = self.min.filter(|&min| n < min) {
+                return Err(format!("{} is less than the minimum of {}", n, min));
+            }
+            if let Some(max) = self.max.filter(|&max| n > max) {
+                return Err(format!("{} is greater than the maximum of {}", n, max));
+            }
+        }
+        if let String(s) = &value {
+            if !self.choices.is_empty() && !self.choices.contains(s) {
+                return Err(format!("{:?} is not one of {:?}", s, self.choices));
+            }
+        }
+        Ok(value)
+    }
+}
+
+impl Module {
+    /// Get the module name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    /// Get whether the module is enabled
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+    /// Set whether the module is enabled
+    ///
+    /// After enabling or disabling a module [`Scripts::reload`](crate::Scripts::reload) must
+    /// be called to actually see the changes
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.enabled = enabled;
+    }
+    /// Get the maximum number of Lua instructions the module may run each call,
+    /// if it overrides `ScriptEnv::instruction_limit`
+    pub fn instruction_limit(&self) -> Option<u64> {
+        self.instruction_limit
+    }
+    /// Get the maximum number of bytes the module may allocate each call,
+    /// if it overrides `ScriptEnv::memory_limit`
+    pub fn memory_limit(&self) -> Option<usize> {
+        self.memory_limit
+    }
+    /// Get the number of batch calls in a row the module may fail before it is disabled,
+    /// if it overrides `ScriptEnv::max_failures`
+    pub fn max_failures(&self) -> Option<u32> {
+        self.max_failures
+    }
+    /// Iterate over the module's declared settings, including those from its companion file
+    pub fn settings(&self) -> impl Iterator<Item = (&str, &Setting)> {
+        self.companion_settings
+            .iter()
+            .chain(&self.settings)
+            .map(|(name, setting)| (name.as_str(), setting))
+    }
+    /// Get the declaration of a setting
+    pub fn setting(&self, name: &str) -> Option<&Setting> {
+        self.settings
+            .get(name)
+            .or_else(|| self.companion_settings.get(name))
+    }
+    fn invalid_setting(&self, setting: &str, reason: String) -> KuleError {
+        KuleError::InvalidSetting {
+            module: self.name.clone(),
+            setting: setting.into(),
+            reason,
+        }
+    }
+    /**
+    Get the values of all the module's settings
+
+    Values set by the user are validated, and defaults are filled in for the
+    rest. If the module declares no settings, its config is returned as-is.
+    */
+    pub fn setting_values(&self) -> KuleResult<BTreeMap<String, ConfigValue>> {
+        if self.settings.is_empty() && self.companion_settings.is_empty() {
+            return Ok(self.config.clone());
+        }
+        if let Some(name) = self.config.keys().find(|name| self.setting(name).is_none()) {
+            return Err(self.invalid_setting(name, "the setting is not declared".into()));
+        }
+        self.settings()
+            .map(|(name, setting)| {
+                let value = self.config.get(name).unwrap_or(&setting.default);
+                setting
+                    .validate(value)
+                    .map(|value| (name.into(), value))
+                    .map_err(|reason| self.invalid_setting(name, reason))
+            })
+            .collect()
+    }
+    /**
+    Set the value of a setting
+
+    The value is validated against the setting's declaration, if there is one.
+    [`Scripts::save_modules`](crate::Scripts::save_modules) must be called to persist
+    the change, and [`Scripts::reload`](crate::Scripts::reload) to show it to the module.
+    */
+    pub fn set_setting<V>(&mut self, name: &str, value: V) -> KuleResult<()>
+    where
+        V: Into<ConfigValue>,
+    {
+        let mut value = value.into();
+        if let Some(setting) = self.setting(name) {
+            value = setting
+                .validate(&value)
+                .map_err(|reason| self.invalid_setting(name, reason))?;
+        } else if self.settings().next().is_some() {
+            return Err(self.invalid_setting(name, "the setting is not declared".into()));
+        }
+        self.config.insert(name.into(), value);
+        Ok(())
+    }
+    /// Reset a setting to its default value
+    pub fn reset_setting(&mut self, name: &str) {
+        self.config.remove(name);
+    }
+}
+
+/**
+A format that the module config file can be written in
+
+[`ConfigFormat::TOML`] and [`ConfigFormat::LUA`] are supported out of the box,
+and `ConfigFormat::JSON` and `ConfigFormat::RON` are behind the `json` and `ron`
+features. Other formats can be added to a `ScriptEnv` with `ScriptEnv::format`.
+The format is selected by the config file's extension, and an extension that
+matches no format is an error. The `settings` tables of modules are parsed like any
+other field, but companion settings files are always TOML.
+
+A format that reads into a [`ConfigValue`] can use [`ConfigValue::into_modules`]
+and [`ConfigValue::from_modules`] instead of dealing with modules directly.
+
+```
+# use kule::*;
+// One module name per line
+const LINES: ConfigFormat = ConfigFormat {
+    extension: "lines",
+    parse: |text| {
+        let names: Vec<String> = text.lines().map(|name| format!("{{ name = {:?} }}", name)).collect();
+        (ConfigFormat::LUA.parse)(&format!("return {{ {} }}", names.join(",")))
+    },
+    write: |modules| Ok(modules.iter().map(Module::name).collect::<Vec<_>>().join("\n")),
+};
+let env = ScriptEnv::new("modules", "modules.lines", StdLib::ALL_SAFE).format(LINES);
+let modules = (env.config_format().unwrap().parse)("core\nextra").unwrap();
+assert_eq!(modules[1].name(), "extra");
+```
+*/
+#[derive(Debug, Clone, Copy)]
+pub struct ConfigFormat {
+    /// The file extension, without the dot
+    pub extension: &'static str,
+    /// Parse a list of modules
+    pub parse: fn(&str) -> KuleResult<Vec<Module>>,
+    /// Write a list of modules
+    pub write: fn(&[Module]) -> KuleResult<String>,
+}
+
+impl ConfigFormat {
+    /**
+    The default format
+
+    Modules are listed in a `mod` array of tables:
+
+    ```toml
+    [[mod]]
+    name = "core"
+    ```
+    */
+    pub const TOML: ConfigFormat = ConfigFormat {
+        extension: "toml",
+        parse: |text| Ok(toml::from_str::<Modules>(text)?.list),
+        write: |modules| {
+            Ok(toml::to_string(&Modules {
+                list: modules.to_vec(),
+            })?)
+        },
+    };
+}
+
+#[derive(Debug, Default, serde_derive::Serialize, serde_derive::Deserialize)]
+pub(crate) struct Modules {
+    #[serde(rename = "mod")]
+    pub(crate) list: Vec<Module>,
+}
+
+impl Modules {
+    /// Load the module list from a config file
+    ///
+    /// `read` gets the contents of a file, or `None` if it does not exist
+    pub(crate) fn load<F>(path: &Path, format: &ConfigFormat, read: F) -> KuleResult<Self>
+    where
+        F: Fn(&Path) -> KuleResult<Option<String>>,
+    {
+        let config_text = read(path)?.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("No script config file at {}", path.display()),
+            )
+        })?;
+        let mut modules = Modules {
+            list: (format.parse)(&config_text)?,
+        };
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        for module in &mut modules.list {
+            let companion = dir.join(format!("{}.settings.toml", module.name));
+            if let Some(text) = read(&companion)? {
+                module.companion_settings = toml::from_str(&text)?;
+            }
+        }
+        Ok(modules)
+    }
+    /// Save the module list to a config file
+    pub(crate) fn save(&self, path: &Path, format: &ConfigFormat) -> KuleResult<()> {
+        fs::write(path, (format.write)(&self.list)?)?;
+        Ok(())
+    }
+    /// Iterate over the enabled modules in order
+    pub(crate) fn enabled(&self) -> impl Iterator<Item = &Module> {
+        self.list.iter().filter(|m| m.enabled)
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn module_settings() {
+    let mut modules: Modules = toml::from_str(
+        r#"
+        [[mod]]
+        name = "combat"
+        [mod.settings.difficulty]
+        type = "float"
+        default = 1.0
+        min = 0.5
+        max = 3.0
+        [mod.settings.mode]
+        type = "string"
+        default = "normal"
+        choices = ["easy", "normal", "hard"]
+        [mod.config]
+        difficulty = 2
+        "#,
+    )
+    .unwrap();
+    let module = &mut modules.list[0];
+    let values = module.setting_values().unwrap();
+    assert_eq!(values["difficulty"], ConfigValue::Float(2.0));
+    assert_eq!(values["mode"], ConfigValue::from("normal"));
+    assert!(module.set_setting("difficulty", 5.0).is_err());
+    assert!(module.set_setting("mode", "impossible").is_err());
+    assert!(module.set_setting("speed", 1).is_err());
+    module.set_setting("mode", "hard").unwrap();
+    let saved = toml::to_string(&modules).unwrap();
+    let loaded: Modules = toml::from_str(&saved).unwrap();
+    assert_eq!(
+        loaded.list[0].setting_values().unwrap()["mode"],
+        ConfigValue::from("hard")
+    );
+}
+
+#[cfg(test)]
+#[test]
+fn custom_config_format() {
+    use crate::{ScriptEnv, StdLib};
+    // One module name per line
+    const LINES: ConfigFormat = ConfigFormat {
+        extension: "lines",
+        parse: |text| {
+            Ok(toml::from_str::<Modules>(&format!(
+                "mod = [{}]",
+                text.lines()
+                    .map(|name| format!("{{ name = {:?} }}", name))
+                    .collect::<Vec<_>>()
+                    .join(",")
+            ))?
+            .list)
+        },
+        write: |modules| {
+            Ok(modules
+                .iter()
+                .map(|m| m.name.clone())
+                .collect::<Vec<_>>()
+                .join("\n"))
+        },
+    };
+    let env = ScriptEnv::new("modules", "modules.lines", StdLib::ALL_SAFE);
+    assert_eq!(env.config_file(), Path::new("modules.lines"));
+    assert!(matches!(
+        env.config_format(),
+        Err(KuleError::UnknownConfigFormat(ext)) if ext == "lines"
+    ));
+    let env = env.format(LINES);
+    let format = env.config_format().unwrap();
+    let modules = (format.parse)("a\nb").unwrap();
+    assert_eq!(modules[1].name(), "b");
+    assert_eq!((format.write)(&modules).unwrap(), "a\nb");
+    let env = ScriptEnv::new("modules", "modules", StdLib::ALL_SAFE);
+    assert_eq!(env.config_file(), Path::new("modules.toml"));
+    assert_eq!(env.config_format().unwrap().extension, "toml");
+}
@@ -0,0 +1,217 @@
+use std::{
+    collections::VecDeque,
+    sync::mpsc::{self, TryRecvError},
+    thread,
+};
+
+use mlua::Lua;
+
+use super::{read_module, task::SharedTasks};
+use crate::{KuleError, KuleResult, Modules, ScriptEnv, Scripts};
+
+/// Data read by the reload worker
+enum Staged {
+    Modules(Modules),
+    Source(String),
+}
+
+type StagedResult = Result<Staged, String>;
+
+/// A reload that is spread across updates
+pub(crate) struct PendingReload {
+    recv: mpsc::Receiver<StagedResult>,
+    modules: Option<Modules>,
+    lua: Option<(Lua, SharedTasks)>,
+    sources: VecDeque<String>,
+    loaded: usize,
+    done_reading: bool,
+}
+
+/// Read the config and module sources and send them back in module order
+fn stage(env: ScriptEnv, send: mpsc::Sender<StagedResult>) {
+    let modules = match env.load_modules() {
+        Ok(modules) => modules,
+        Err(e) => {
+            let _ = send.send(Err(e.to_string()));
+            return;
+        }
+    };
+    let names: Vec<String> = modules.enabled().map(|m| m.name.clone()).collect();
+    if send.send(Ok(Staged::Modules(modules))).is_err() {
+        return;
+    }
+    // Lua functions cannot be sent between threads, so modules are compiled here
+    // only to report syntax errors before anything runs
+    let lua = Lua::new();
+    for name in names {
+        let res = read_module(&env, &name).and_then(|source| {
+            lua.load(&source).set_name(&name)?.into_function()?;
+            Ok(source)
+        });
+        let failed = res.is_err();
+        if send
+            .send(res.map(Staged::Source).map_err(|e| e.to_string()))
+            .is_err()
+            || failed
+        {
+            return;
+        }
+    }
+}
+
+impl Scripts {
+    /**
+    Start reloading the scripts without blocking
+
+    The config file and module sources are read and checked for syntax errors on a
+    background thread. Each call to [`Scripts::step_reload`] then runs at most one module,
+    and once every module has run, the new modules replace the old ones.
+    The old modules keep running until then.
+
+    Starting a reload cancels any reload that is in progress.
+
+    When scripts are hot-reloaded, the context's scripts are reloaded this way, and an
+    `Event::ScriptsReloaded` is sent once the reload is finished.
+    */
+    pub fn start_reload(&mut self) {
+        let (send, recv) = mpsc::channel();
+        let env = self.env.clone();
+        thread::spawn(move || stage(env, send));
+        self.pending = Some(PendingReload {
+            recv,
+            modules: None,
+            lua: None,
+            sources: VecDeque::new(),
+            loaded: 0,
+            done_reading: false,
+        });
+    }
+    /// Check if a reload started with [`Scripts::start_reload`] is in progress
+    pub fn is_reloading(&self) -> bool {
+        self.pending.is_some()
+    }
+    /**
+    Get the progress of the reload in progress from `0.0` to `1.0`
+
+    This is the fraction of modules that have been run.
+    Returns `None` if no reload is in progress.
+    */
+    pub fn reload_progress(&self) -> Option<f32> {
+        let pending = self.pending.as_ref()?;
+        Some(match &pending.modules {
+            Some(modules) => {
+                let total = modules.enabled().count();
+                if total == 0 {
+                    1.0
+                } else {
+                    pending.loaded as f32 / total as f32
+                }
+            }
+            None => 0.0,
+        })
+    }
+    /**
+    Advance a reload started with [`Scripts::start_reload`]
+
+    Returns `Some(Ok(()))` once the new modules have replaced the old ones,
+    and `Some(Err(_))` as soon as the reload fails, in which case the old modules
+    keep running. Returns `None` while the reload is in progress or if there is
+    no reload in progress.
+
+    The context's scripts are advanced automatically once per frame.
+    */
+    pub fn step_reload(&mut self) -> Option<KuleResult<()>> {
+        let mut pending = self.pending.take()?;
+        match self.advance_reload(&mut pending) {
+            Ok(true) => {
+                let (lua, tasks) = pending.lua.take()?;
+                let modules = pending.modules.take()?;
+                Some(self.finish_reload(lua, tasks, modules))
+            }
+            Ok(false) => {
+                self.pending = Some(pending);
+                None
+            }
+            Err(e) => Some(Err(e)),
+        }
+    }
+    /// Receive staged data and run the next module, returning whether every module has run
+    fn advance_reload(&self, pending: &mut PendingReload) -> KuleResult<bool> {
+        loop {
+            match pending.recv.try_recv() {
+                Ok(Ok(Staged::Modules(modules))) => {
+                    pending.lua = Some(self.new_lua()?);
+                    pending.modules = Some(modules);
+                }
+                Ok(Ok(Staged::Source(source))) => pending.sources.push_back(source),
+                Ok(Err(e)) => return Err(KuleError::ScriptInitialization(e)),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    pending.done_reading = true;
+                    break;
+                }
+            }
+        }
+        let (modules, (lua, _)) = match (&pending.modules, &pending.lua) {
+            (Some(modules), Some(lua)) => (modules, lua),
+            _ if pending.done_reading => {
+                return Err(KuleError::ScriptInitialization(
+                    "The script reload stopped unexpectedly".into(),
+                ))
+            }
+            _ => return Ok(false),
+        };
+        if let Some(source) = pending.sources.pop_front() {
+            if let Some(module) = modules.enabled().nth(pending.loaded) {
+                self.run_module(lua, module, &source)?;
+            }
+            pending.loaded += 1;
+        }
+        Ok(pending.done_reading && pending.sources.is_empty())
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn staged_reload() {
+    use crate::StdLib;
+    let dir = std::env::temp_dir().join("kule_reload_test");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+        dir.join("modules.toml"),
+        "[[mod]]\nname = \"a\"\n[[mod]]\nname = \"b\"\n",
+    )
+    .unwrap();
+    std::fs::write(dir.join("a.lua"), "return { n = 1 }").unwrap();
+    std::fs::write(dir.join("b.lua"), "return { n = a.n + 1 }").unwrap();
+    let mut scripts = Scripts::load(ScriptEnv::new(&dir, "modules", StdLib::ALL_SAFE)).unwrap();
+    let n = |scripts: &Scripts, name: &str| -> i64 {
+        scripts
+            .lua(|lua| Ok(lua.globals().get::<_, mlua::Table>(name)?.get("n")?))
+            .unwrap()
+    };
+    std::fs::write(dir.join("a.lua"), "return { n = 10 }").unwrap();
+    scripts.start_reload();
+    let res = loop {
+        assert_eq!(n(&scripts, "b"), 2);
+        if let Some(res) = scripts.step_reload() {
+            break res;
+        }
+        thread::yield_now();
+    };
+    res.unwrap();
+    assert!(!scripts.is_reloading());
+    assert_eq!(n(&scripts, "b"), 11);
+    // Syntax errors are reported and the old modules keep running
+    std::fs::write(dir.join("b.lua"), "return {").unwrap();
+    scripts.start_reload();
+    let res = loop {
+        if let Some(res) = scripts.step_reload() {
+            break res;
+        }
+        thread::yield_now();
+    };
+    assert!(res.is_err());
+    assert_eq!(n(&scripts, "b"), 11);
+    std::fs::remove_dir_all(dir).unwrap();
+}
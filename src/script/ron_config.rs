@@ -0,0 +1,369 @@
+use std::{collections::BTreeMap, fmt::Write, iter::Peekable, str::CharIndices};
+
+use crate::{ConfigFormat, ConfigValue, KuleError, KuleResult, Module};
+
+impl ConfigFormat {
+    /**
+    A format that lists modules in a RON list
+
+    Modules are listed in order:
+
+    ```ron
+    [
+        (name: "core"),
+        (name: "extra", enabled: false),
+    ]
+    ```
+
+    Structs, with or without a name, and maps are both read as tables. Tables are
+    written as structs when all of their keys are identifiers. `Some(x)` is read
+    as `x`, and a field that is `None` is left out.
+    */
+    pub const RON: ConfigFormat = ConfigFormat {
+        extension: "ron",
+        parse: parse_ron,
+        write: write_ron,
+    };
+}
+
+fn parse_ron(text: &str) -> KuleResult<Vec<Module>> {
+    let mut parser = Parser {
+        text,
+        chars: text.char_indices().peekable(),
+    };
+    let value = parser.some_value()?;
+    parser.skip_whitespace()?;
+    if parser.chars.peek().is_some() {
+        return Err(parser.error("expected the end of the file"));
+    }
+    value.into_modules()
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&mut self, message: &str) -> KuleError {
+        let pos = self.pos();
+        let line = self.text[..pos].matches('\n').count() + 1;
+        KuleError::InvalidConfig(format!("{} on line {}", message, line))
+    }
+    fn pos(&mut self) -> usize {
+        self.chars.peek().map_or(self.text.len(), |&(i, _)| i)
+    }
+    fn peek(&mut self) -> Option<char> {
+        self.chars.peek().map(|&(_, c)| c)
+    }
+    /// Skip whitespace and comments
+    fn skip_whitespace(&mut self) -> KuleResult<()> {
+        loop {
+            match self.peek() {
+                Some(c) if c.is_whitespace() => {
+                    self.chars.next();
+                }
+                Some('/') => {
+                    let rest = &self.text[self.pos()..];
+                    if rest.starts_with("//") {
+                        while !matches!(self.chars.next(), Some((_, '\n')) | None) {}
+                    } else if rest.starts_with("/*") {
+                        match rest.find("*/") {
+                            Some(end) => {
+                                let end = self.pos() + end + 2;
+                                while self.pos() < end {
+                                    self.chars.next();
+                                }
+                            }
+                            None => return Err(self.error("unterminated comment")),
+                        }
+                    } else {
+                        return Ok(());
+                    }
+                }
+                _ => return Ok(()),
+            }
+        }
+    }
+    fn eat(&mut self, c: char) -> KuleResult<bool> {
+        self.skip_whitespace()?;
+        Ok(if self.peek() == Some(c) {
+            self.chars.next();
+            true
+        } else {
+            false
+        })
+    }
+    fn expect(&mut self, c: char) -> KuleResult<()> {
+        if self.eat(c)? {
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", c)))
+        }
+    }
+    /// Parse a comma-separated sequence that ends with `close`
+    fn sequence<F>(&mut self, close: char, mut item: F) -> KuleResult<()>
+    where
+        F: FnMut(&mut Self) -> KuleResult<()>,
+    {
+        loop {
+            if self.eat(close)? {
+                return Ok(());
+            }
+            item(self)?;
+            if !self.eat(',')? {
+                return self.expect(close);
+            }
+        }
+    }
+    fn identifier(&mut self) -> &'a str {
+        let start = self.pos();
+        while matches!(self.peek(), Some(c) if c.is_ascii_alphanumeric() || c == '_') {
+            self.chars.next();
+        }
+        &self.text[start..self.pos()]
+    }
+    /// Parse a value that is not `None`
+    fn some_value(&mut self) -> KuleResult<ConfigValue> {
+        match self.value()? {
+            Some(value) => Ok(value),
+            None => Err(self.error("None is only allowed as a field")),
+        }
+    }
+    /// Parse a value, or `None`
+    fn value(&mut self) -> KuleResult<Option<ConfigValue>> {
+        self.skip_whitespace()?;
+        Ok(Some(match self.peek() {
+            Some('[') => {
+                self.chars.next();
+                let mut items = Vec::new();
+                self.sequence(']', |parser| {
+                    items.push(parser.some_value()?);
+                    Ok(())
+                })?;
+                ConfigValue::Array(items)
+            }
+            Some('{') => {
+                self.chars.next();
+                let mut table = BTreeMap::new();
+                self.sequence('}', |parser| {
+                    parser.skip_whitespace()?;
+                    let key = parser.string()?;
+                    parser.expect(':')?;
+                    if let Some(value) = parser.value()? {
+                        table.insert(key, value);
+                    }
+                    Ok(())
+                })?;
+                ConfigValue::Table(table)
+            }
+            Some('(') => self.fields()?,
+            Some('"') => ConfigValue::String(self.string()?),
+            Some(c) if c == '-' || c == '+' || c == '.' || c.is_ascii_digit() => self.number()?,
+            Some(c) if c.is_ascii_alphabetic() || c == '_' => match self.identifier() {
+                "true" => ConfigValue::Bool(true),
+                "false" => ConfigValue::Bool(false),
+                "inf" => ConfigValue::Float(f64::INFINITY),
+                "NaN" => ConfigValue::Float(f64::NAN),
+                "None" => return Ok(None),
+                "Some" => {
+                    self.expect('(')?;
+                    let value = self.some_value()?;
+                    self.eat(',')?;
+                    self.expect(')')?;
+                    value
+                }
+                // A named struct
+                _ => {
+                    self.skip_whitespace()?;
+                    if self.peek() != Some('(') {
+                        return Err(self.error("expected '('"));
+                    }
+                    self.fields()?
+                }
+            },
+            _ => return Err(self.error("expected a value")),
+        }))
+    }
+    /// Parse the fields of a struct
+    fn fields(&mut self) -> KuleResult<ConfigValue> {
+        self.expect('(')?;
+        let mut table = BTreeMap::new();
+        self.sequence(')', |parser| {
+            parser.skip_whitespace()?;
+            let key = parser.identifier();
+            if key.is_empty() {
+                return Err(parser.error("expected a field name"));
+            }
+            parser.expect(':')?;
+            if let Some(value) = parser.value()? {
+                table.insert(key.into(), value);
+            }
+            Ok(())
+        })?;
+        Ok(ConfigValue::Table(table))
+    }
+    fn string(&mut self) -> KuleResult<String> {
+        if !matches!(self.chars.next(), Some((_, '"'))) {
+            return Err(self.error("expected a string"));
+        }
+        let mut s = String::new();
+        loop {
+            match self.chars.next() {
+                Some((_, '"')) => return Ok(s),
+                Some((_, '\\')) => match self.chars.next() {
+                    Some((_, '"')) => s.push('"'),
+                    Some((_, '\\')) => s.push('\\'),
+                    Some((_, 'n')) => s.push('\n'),
+                    Some((_, 'r')) => s.push('\r'),
+                    Some((_, 't')) => s.push('\t'),
+                    Some((_, '0')) => s.push('\0'),
+                    Some((_, 'u')) => {
+                        self.expect('{')?;
+                        let digits = self.identifier();
+                        let c = u32::from_str_radix(digits, 16)
+                            .ok()
+                            .and_then(std::char::from_u32);
+                        self.expect('}')?;
+                        match c {
+                            Some(c) => s.push(c),
+                            None => return Err(self.error("invalid unicode escape")),
+                        }
+                    }
+                    _ => return Err(self.error("invalid escape")),
+                },
+                Some((_, c)) => s.push(c),
+                None => return Err(self.error("unterminated string")),
+            }
+        }
+    }
+    fn number(&mut self) -> KuleResult<ConfigValue> {
+        let start = self.pos();
+        while matches!(self.peek(), Some(c) if c.is_ascii_alphanumeric() || "+-._".contains(c)) {
+            self.chars.next();
+        }
+        let number = self.text[start..self.pos()].replace('_', "");
+        let value = match number.as_str() {
+            "inf" | "+inf" => Some(ConfigValue::Float(f64::INFINITY)),
+            "-inf" => Some(ConfigValue::Float(f64::NEG_INFINITY)),
+            _ if number.contains(['.', 'e', 'E']) => number.parse().ok().map(ConfigValue::Float),
+            _ => number.parse().ok().map(ConfigValue::Int),
+        };
+        value.ok_or_else(|| self.error(&format!("invalid number {:?}", number)))
+    }
+}
+
+fn write_ron(modules: &[Module]) -> KuleResult<String> {
+    let mut ron = String::new();
+    write_value(&ConfigValue::from_modules(modules)?, 0, &mut ron);
+    ron.push('\n');
+    Ok(ron)
+}
+
+fn write_value(value: &ConfigValue, indent: usize, ron: &mut String) {
+    match value {
+        ConfigValue::String(s) => write_string(s, ron),
+        ConfigValue::Int(i) => {
+            let _ = write!(ron, "{}", i);
+        }
+        ConfigValue::Float(f) => {
+            let _ = write!(ron, "{:?}", f);
+        }
+        ConfigValue::Bool(b) => {
+            let _ = write!(ron, "{}", b);
+        }
+        ConfigValue::Array(items) if items.is_empty() => ron.push_str("[]"),
+        ConfigValue::Array(items) => {
+            ron.push_str("[\n");
+            for item in items {
+                push_indent(indent + 1, ron);
+                write_value(item, indent + 1, ron);
+                ron.push_str(",\n");
+            }
+            push_indent(indent, ron);
+            ron.push(']');
+        }
+        ConfigValue::Table(table) if table.is_empty() => ron.push_str("{}"),
+        ConfigValue::Table(table) => {
+            let is_struct = table.keys().all(|key| is_identifier(key));
+            ron.push_str(if is_struct { "(\n" } else { "{\n" });
+            for (key, value) in table {
+                push_indent(indent + 1, ron);
+                if is_struct {
+                    ron.push_str(key);
+                } else {
+                    write_string(key, ron);
+                }
+                ron.push_str(": ");
+                write_value(value, indent + 1, ron);
+                ron.push_str(",\n");
+            }
+            push_indent(indent, ron);
+            ron.push(if is_struct { ')' } else { '}' });
+        }
+    }
+}
+
+fn push_indent(indent: usize, ron: &mut String) {
+    for _ in 0..indent {
+        ron.push_str("    ");
+    }
+}
+
+fn write_string(s: &str, ron: &mut String) {
+    ron.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => ron.push_str("\\\""),
+            '\\' => ron.push_str("\\\\"),
+            '\n' => ron.push_str("\\n"),
+            '\r' => ron.push_str("\\r"),
+            '\t' => ron.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(ron, "\\u{{{:x}}}", c as u32);
+            }
+            c => ron.push(c),
+        }
+    }
+    ron.push('"');
+}
+
+fn is_identifier(key: &str) -> bool {
+    let mut chars = key.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !matches!(key, "true" | "false" | "inf" | "NaN" | "None" | "Some")
+}
+
+#[cfg(test)]
+#[test]
+fn ron_config_format() {
+    let modules = (ConfigFormat::RON.parse)(
+        r#"
+        // The modules
+        [
+            Module(name: "core", settings: { "speed": (type: "float", default: 1.5) }),
+            (
+                name: "extra",
+                enabled: false,
+                instruction_limit: Some(1_000),
+                memory_limit: None,
+                config: { "odd key": "a \"quoted\"\nline \u{e9}" },
+            ),
+        ]
+        "#,
+    )
+    .unwrap();
+    assert_eq!(modules[1].name(), "extra");
+    assert_eq!(modules[1].instruction_limit, Some(1000));
+    let written = (ConfigFormat::RON.write)(&modules).unwrap();
+    let reparsed = (ConfigFormat::RON.parse)(&written).unwrap();
+    assert_eq!(
+        (ConfigFormat::RON.write)(&reparsed).unwrap(),
+        written,
+        "{}",
+        written
+    );
+    assert!((ConfigFormat::RON.parse)("[(name: None)]").is_err());
+    assert!((ConfigFormat::RON.parse)(r#"[(name: "core")"#).is_err());
+}
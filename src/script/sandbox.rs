@@ -0,0 +1,187 @@
+use mlua::{Function, Lua, Table, Value};
+
+use crate::{KuleResult, ScriptEnv};
+
+/// The registry key of the table of loaded modules
+const MODULES: &str = "kule_modules";
+/// The registry key of the function that makes module environments
+const MAKE_ENV: &str = "kule_make_env";
+
+/// Makes module environments
+///
+/// It is run with the real globals, which modules never get a reference to.
+const SANDBOX: &str = r#"
+local globals, modules, find_module = ...
+local error, load, next, pairs, setmetatable, type = error, load, next, pairs, setmetatable, type
+local package = globals.package
+
+-- Globals that would let a module reach past its own environment
+local hidden = {
+    _G = true,
+    rawset = true,
+    load = true,
+    loadfile = true,
+    dofile = true,
+    package = true,
+    require = true,
+}
+
+-- The standard libraries that `require` can return
+local libs = {}
+if package then
+    for name, lib in pairs(package.loaded) do
+        if not hidden[name] then
+            libs[name] = lib
+        end
+    end
+end
+
+-- Read-only views of other modules' tables and the shared tables
+local proxies = setmetatable({}, { __mode = "k" })
+local function proxy(t)
+    local p = proxies[t]
+    if p then
+        return p
+    end
+    p = setmetatable({}, {
+        __index = function(_, k)
+            local v = t[k]
+            if type(v) == "table" then
+                return proxy(v)
+            end
+            return v
+        end,
+        __newindex = function()
+            error("another module's table cannot be changed", 2)
+        end,
+        __pairs = function()
+            return function(_, k)
+                local v
+                k, v = next(t, k)
+                if type(v) == "table" then
+                    v = proxy(v)
+                end
+                return k, v
+            end, p, nil
+        end,
+        __len = function()
+            return #t
+        end,
+        __call = function(_, ...)
+            return t(...)
+        end,
+        __metatable = false,
+    })
+    proxies[t] = p
+    return p
+end
+
+return function(name, settings)
+    local env = { settings = settings }
+    -- A module sees its own table as it is and other modules through proxies
+    local function find(k)
+        local m = modules[k]
+        if m == nil or k == name then
+            return m
+        end
+        return proxy(m)
+    end
+    -- Chunks are loaded in the module's environment unless they are given another
+    env.load = function(chunk, chunkname, _, chunk_env)
+        return load(chunk, chunkname, "t", chunk_env or env)
+    end
+    if package then
+        local loaded = {}
+        env.require = function(req)
+            if loaded[req] ~= nil then
+                return loaded[req]
+            end
+            local found = find(req)
+            if found == nil and libs[req] ~= nil then
+                found = proxy(libs[req])
+            end
+            if found ~= nil then
+                return found
+            end
+            local source, path = find_module(req)
+            if source == nil then
+                error(("module %q not found:%s"):format(req, path), 2)
+            end
+            local loader = assert(load(source, req, "t", env))
+            local result = loader(req, path)
+            if result == nil then
+                result = true
+            end
+            loaded[req] = result
+            return result
+        end
+    end
+    return setmetatable(env, {
+        __index = function(_, k)
+            local m = find(k)
+            if m ~= nil then
+                return m
+            end
+            if hidden[k] then
+                return nil
+            end
+            -- Shared tables, like `string` and `math`, are read-only so that one
+            -- module cannot change them for the others
+            local v = globals[k]
+            if type(v) == "table" then
+                return proxy(v)
+            end
+            return v
+        end,
+        __metatable = false,
+    })
+end
+"#;
+
+/// Install the function that makes module environments
+pub(crate) fn install_sandbox(lua: &Lua, env: &ScriptEnv) -> KuleResult<()> {
+    let modules = lua.create_table()?;
+    let env = env.clone();
+    let find_module = lua.create_function(move |_, name: String| {
+        Ok(match super::module_path(&env, &name) {
+            Some(path) => match env.read_file(&path) {
+                Ok(source) => (source, path.to_string_lossy().into_owned()),
+                Err(e) => return Err(mlua::Error::RuntimeError(e.to_string())),
+            },
+            None => (
+                None,
+                format!("\n\tno module {:?} in {}", name, env.dir.display()),
+            ),
+        })
+    })?;
+    let make_env: Function = lua.load(SANDBOX).set_name("sandbox")?.call((
+        lua.globals(),
+        modules.clone(),
+        find_module,
+    ))?;
+    lua.set_named_registry_value(MODULES, modules)?;
+    lua.set_named_registry_value(MAKE_ENV, make_env)?;
+    Ok(())
+}
+
+/// Make the environment a module's source is run in
+///
+/// Globals that the module sets stay in the environment. Reads fall back to the
+/// shared globals, except that other modules and shared tables like `string` are
+/// read-only and the globals that reach past the environment, like `_G` and
+/// `rawset`, are hidden.
+pub(crate) fn module_env<'lua>(
+    lua: &'lua Lua,
+    name: &str,
+    settings: Value<'lua>,
+) -> KuleResult<Table<'lua>> {
+    let make_env: Function = lua.named_registry_value(MAKE_ENV)?;
+    Ok(make_env.call((name, settings))?)
+}
+
+/// Make a module's table visible to other modules
+pub(crate) fn add_module<'lua>(lua: &'lua Lua, name: &str, table: Value<'lua>) -> KuleResult<()> {
+    let modules: Table = lua.named_registry_value(MODULES)?;
+    modules.set(name, table)?;
+    Ok(())
+}
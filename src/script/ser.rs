@@ -402,6 +402,12 @@ fn lua_ser() {
     let module = crate::Module {
         name: "core".into(),
         enabled: false,
+        instruction_limit: None,
+        memory_limit: None,
+        max_failures: None,
+        settings: Default::default(),
+        config: Default::default(),
+        companion_settings: Default::default(),
     };
     #[derive(Debug, serde_derive::Serialize, serde_derive::Deserialize)]
     enum MyEnum {
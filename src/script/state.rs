@@ -0,0 +1,100 @@
+use std::{collections::BTreeMap, fmt};
+
+/**
+A language-independent copy of a script value
+
+This is what module state is stored as while the scripts are reloaded.
+It can be serialized and deserialized with any self-describing format.
+
+Keys of tables that are not sequences are converted to strings.
+*/
+#[derive(Debug, Clone, Default, PartialEq, serde_derive::Serialize, serde_derive::Deserialize)]
+#[serde(untagged)]
+pub enum StateValue {
+    /// No value
+    #[default]
+    Nil,
+    /// A boolean
+    Bool(bool),
+    /// An integer
+    Int(i64),
+    /// A floating-point number
+    Float(f64),
+    /// A string
+    String(String),
+    /// A sequence of values
+    List(Vec<StateValue>),
+    /// A map of string keys to values
+    Map(BTreeMap<String, StateValue>),
+}
+
+/// Values are formatted the way they would be written in Lua
+impl fmt::Display for StateValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StateValue::Nil => write!(f, "nil"),
+            StateValue::Bool(b) => write!(f, "{}", b),
+            StateValue::Int(i) => write!(f, "{}", i),
+            StateValue::Float(n) => write!(f, "{:?}", n),
+            StateValue::String(s) => write!(f, "{:?}", s),
+            StateValue::List(list) if list.is_empty() => write!(f, "{{}}"),
+            StateValue::List(list) => {
+                write!(f, "{{ ")?;
+                for (i, value) in list.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", value)?;
+                }
+                write!(f, " }}")
+            }
+            StateValue::Map(map) if map.is_empty() => write!(f, "{{}}"),
+            StateValue::Map(map) => {
+                write!(f, "{{ ")?;
+                for (i, (key, value)) in map.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    let identifier = key.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+                        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+                    if identifier {
+                        write!(f, "{} = {}", key, value)?;
+                    } else {
+                        write!(f, "[{:?}] = {}", key, value)?;
+                    }
+                }
+                write!(f, " }}")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn state_across_reload() {
+    use crate::{ScriptEnv, Scripts, StdLib};
+    let dir = std::env::temp_dir().join("kule_state_test");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("modules.toml"), "[[mod]]\nname = \"a\"\n").unwrap();
+    std::fs::write(
+        dir.join("a.lua"),
+        r#"
+        local a = { count = 0, seen = {} }
+        function a:bump() self.count = self.count + 1; table.insert(self.seen, self.count) end
+        function a:save_state() return { count = self.count, seen = self.seen } end
+        function a:load_state(state) self.count = state.count; self.seen = state.seen end
+        return a
+        "#,
+    )
+    .unwrap();
+    let mut scripts = Scripts::load(ScriptEnv::new(&dir, "modules", StdLib::ALL_SAFE)).unwrap();
+    scripts.call_method::<_, ()>("a", "bump", ()).unwrap();
+    scripts.call_method::<_, ()>("a", "bump", ()).unwrap();
+    scripts.reload().unwrap();
+    scripts.call_method::<_, ()>("a", "bump", ()).unwrap();
+    let seen: Option<Vec<i64>> = scripts
+        .call("a", "bump", |_, table, _| Ok(table.get("seen")?))
+        .unwrap();
+    assert_eq!(seen.unwrap(), [1, 2, 3]);
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,260 @@
+use std::{cell::RefCell, rc::Rc};
+
+use mlua::{Function, Lua, RegistryKey, StdLib, Table, Thread, ThreadStatus, Value};
+
+use crate::{KuleError, KuleResult};
+
+/// What a task is waiting for
+enum Wait {
+    Update,
+    Seconds(f32),
+    Event(String),
+}
+
+struct Task {
+    id: u64,
+    /// The module that started the task
+    module: Option<String>,
+    thread: RegistryKey,
+    wait: Wait,
+}
+
+/// The tasks started by scripts
+#[derive(Default)]
+pub(crate) struct Tasks {
+    next_id: u64,
+    list: Vec<Task>,
+    cancelled: Vec<u64>,
+}
+
+pub(crate) type SharedTasks = Rc<RefCell<Tasks>>;
+
+impl Tasks {
+    /// Get the number of running tasks
+    pub(crate) fn len(&self) -> usize {
+        self.list.len()
+    }
+}
+
+/// Install `engine.start_task`, `engine.cancel_task`, `wait`, and `wait_event`
+pub(crate) fn install_tasks(lua: &Lua, engine: &Table, tasks: &SharedTasks) -> KuleResult<()> {
+    // Tasks yield with `coroutine.yield`, so the library must be loaded
+    if lua
+        .globals()
+        .get::<_, Option<Table>>("coroutine")?
+        .is_none()
+    {
+        lua.load_from_std_lib(StdLib::COROUTINE)?;
+    }
+    let start_tasks = tasks.clone();
+    engine.set(
+        "start_task",
+        lua.create_function(move |lua, f: Function| {
+            let module = lua.named_registry_value::<_, Option<String>>(super::CURRENT_MODULE)?;
+            let thread = lua.create_registry_value(lua.create_thread(f)?)?;
+            let mut tasks = start_tasks.borrow_mut();
+            tasks.next_id += 1;
+            let id = tasks.next_id;
+            tasks.list.push(Task {
+                id,
+                module,
+                thread,
+                wait: Wait::Update,
+            });
+            Ok(id)
+        })?,
+    )?;
+    let cancel_tasks = tasks.clone();
+    engine.set(
+        "cancel_task",
+        lua.create_function(move |_, id: u64| {
+            let mut tasks = cancel_tasks.borrow_mut();
+            tasks.list.retain(|task| task.id != id);
+            tasks.cancelled.push(id);
+            Ok(())
+        })?,
+    )?;
+    lua.load(
+        r#"
+        local yield = coroutine.yield
+        function wait(seconds) return yield("seconds", seconds or 0) end
+        function wait_event(name) return yield("event", name) end
+        "#,
+    )
+    .set_name("tasks")?
+    .exec()?;
+    Ok(())
+}
+
+/**
+Resume the tasks for which `ready` returns a value to resume them with
+
+Each thread is resumed by `run`, which is passed the name of the module that started the task
+*/
+fn resume<'lua, F, R>(
+    lua: &'lua Lua,
+    tasks: &SharedTasks,
+    mut ready: F,
+    mut run: R,
+) -> Vec<KuleError>
+where
+    F: FnMut(&mut Wait) -> Option<Value<'lua>>,
+    R: FnMut(Option<&str>, Thread<'lua>, Value<'lua>) -> KuleResult<(Value<'lua>, Value<'lua>)>,
+{
+    // The list is taken so that tasks can start and cancel other tasks
+    let running = std::mem::take(&mut tasks.borrow_mut().list);
+    let mut kept = Vec::with_capacity(running.len());
+    let mut errors = Vec::new();
+    for mut task in running {
+        if tasks.borrow().cancelled.contains(&task.id) {
+            continue;
+        }
+        let arg = match ready(&mut task.wait) {
+            Some(arg) => arg,
+            None => {
+                kept.push(task);
+                continue;
+            }
+        };
+        let thread: Thread = match lua.registry_value(&task.thread) {
+            Ok(thread) => thread,
+            Err(e) => {
+                errors.push(e.into());
+                continue;
+            }
+        };
+        match run(task.module.as_deref(), thread.clone(), arg) {
+            Ok(_) if thread.status() != ThreadStatus::Resumable => {}
+            Ok((Value::String(kind), value)) => {
+                task.wait = match (kind.to_str(), value) {
+                    (Ok("seconds"), Value::Integer(i)) => Wait::Seconds(i as f32),
+                    (Ok("seconds"), Value::Number(n)) => Wait::Seconds(n as f32),
+                    (Ok("event"), Value::String(name)) => {
+                        Wait::Event(name.to_str().unwrap_or_default().into())
+                    }
+                    _ => Wait::Update,
+                };
+                kept.push(task);
+            }
+            Ok(_) => {
+                task.wait = Wait::Update;
+                kept.push(task);
+            }
+            Err(e) => errors.push(e),
+        }
+    }
+    let mut tasks = tasks.borrow_mut();
+    kept.append(&mut tasks.list);
+    let cancelled = std::mem::take(&mut tasks.cancelled);
+    kept.retain(|task| !cancelled.contains(&task.id));
+    tasks.list = kept;
+    errors
+}
+
+/// Resume tasks that are waiting for the next update or whose wait time has passed
+pub(crate) fn update_tasks<'lua, R>(
+    lua: &'lua Lua,
+    tasks: &SharedTasks,
+    dt: f32,
+    run: R,
+) -> Vec<KuleError>
+where
+    R: FnMut(Option<&str>, Thread<'lua>, Value<'lua>) -> KuleResult<(Value<'lua>, Value<'lua>)>,
+{
+    resume(
+        lua,
+        tasks,
+        |wait| match wait {
+            Wait::Update => Some(Value::Nil),
+            Wait::Seconds(seconds) => {
+                *seconds -= dt;
+                if *seconds <= 0.0 {
+                    Some(Value::Nil)
+                } else {
+                    None
+                }
+            }
+            Wait::Event(_) => None,
+        },
+        run,
+    )
+}
+
+/**
+Resume tasks that are waiting for an event
+
+An event's name is its string value, or the `variant` field if it is a table
+*/
+pub(crate) fn event_tasks<'lua, R>(
+    lua: &'lua Lua,
+    tasks: &SharedTasks,
+    event: Value<'lua>,
+    run: R,
+) -> Vec<KuleError>
+where
+    R: FnMut(Option<&str>, Thread<'lua>, Value<'lua>) -> KuleResult<(Value<'lua>, Value<'lua>)>,
+{
+    let name = match &event {
+        Value::String(name) => name.to_str().ok().map(String::from),
+        Value::Table(table) => table.get::<_, Option<String>>("variant").ok().flatten(),
+        _ => None,
+    };
+    let name = match name {
+        Some(name) => name,
+        None => return Vec::new(),
+    };
+    resume(
+        lua,
+        tasks,
+        |wait| match wait {
+            Wait::Event(waiting) if *waiting == name => Some(event.clone()),
+            _ => None,
+        },
+        run,
+    )
+}
+
+#[cfg(test)]
+#[test]
+fn task_waits() {
+    let lua = Lua::new();
+    let engine = lua.create_table().unwrap();
+    let tasks = SharedTasks::default();
+    install_tasks(&lua, &engine, &tasks).unwrap();
+    lua.globals().set("engine", engine).unwrap();
+    lua.load(
+        r#"
+        steps = {}
+        engine.start_task(function()
+            table.insert(steps, "start")
+            wait(1)
+            table.insert(steps, "waited")
+            local event = wait_event("Key")
+            table.insert(steps, event.key)
+        end)
+        "#,
+    )
+    .exec()
+    .unwrap();
+    let steps = || -> Vec<String> { lua.globals().get("steps").unwrap() };
+    fn run<'lua>(
+        _: Option<&str>,
+        thread: Thread<'lua>,
+        arg: Value<'lua>,
+    ) -> KuleResult<(Value<'lua>, Value<'lua>)> {
+        Ok(thread.resume(arg)?)
+    }
+    assert!(update_tasks(&lua, &tasks, 0.5, run).is_empty());
+    assert_eq!(steps(), ["start"]);
+    update_tasks(&lua, &tasks, 0.5, run);
+    assert_eq!(steps(), ["start"]);
+    update_tasks(&lua, &tasks, 0.5, run);
+    assert_eq!(steps(), ["start", "waited"]);
+    let event = lua
+        .load("{ variant = 'Key', key = 'Space' }")
+        .eval()
+        .unwrap();
+    assert!(event_tasks(&lua, &tasks, event, run).is_empty());
+    assert_eq!(steps(), ["start", "waited", "Space"]);
+    assert_eq!(tasks.borrow().len(), 0);
+}
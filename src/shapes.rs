@@ -0,0 +1,192 @@
+use std::f32::consts::{PI, TAU};
+
+use vector2math::*;
+
+use crate::{Circ, Rect, Rng, Vec2};
+
+fn angle_point(center: Vec2, radius: f32, angle: f32) -> Vec2 {
+    center.add([angle.cos(), angle.sin()].mul(radius))
+}
+
+/**
+Generate an organic blob by perturbing a circle with smooth noise
+
+`roughness` is the largest fraction of the radius that the edge moves in or out.
+The same `Rng` state always produces the same blob.
+
+```
+# use kule::*;
+let blob = blob(([0.0, 0.0], 10.0), 0.2, 48, &mut Rng::new(1));
+assert_eq!(blob.len(), 48);
+assert!(blob.iter().all(|v| v.mag() <= 12.0001 && v.mag() >= 7.9999));
+```
+*/
+pub fn blob<C>(circ: C, roughness: f32, resolution: u16, rng: &mut Rng) -> Vec<Vec2>
+where
+    C: Circle<Scalar = f32>,
+{
+    let circ: Circ = circ.map();
+    // Sum a few octaves of sine waves that fit evenly around the circle
+    let waves: Vec<(f32, f32, f32)> = (2..6)
+        .map(|k| {
+            let freq = k as f32;
+            (freq, rng.range(0.0..TAU), 1.0 / freq)
+        })
+        .collect();
+    let total: f32 = waves.iter().map(|(_, _, amp)| amp).sum();
+    (0..resolution)
+        .map(|i| {
+            let angle = i as f32 / resolution as f32 * TAU;
+            let noise = waves
+                .iter()
+                .map(|(freq, phase, amp)| (angle * freq + phase).sin() * amp)
+                .sum::<f32>()
+                / total;
+            angle_point(
+                circ.center(),
+                circ.radius() * (1.0 + roughness * noise),
+                angle,
+            )
+        })
+        .collect()
+}
+
+/// Generate a star with some number of points
+pub fn star(center: Vec2, outer_radius: f32, inner_radius: f32, points: u16) -> Vec<Vec2> {
+    (0..points * 2)
+        .map(|i| {
+            let radius = if i % 2 == 0 {
+                outer_radius
+            } else {
+                inner_radius
+            };
+            let angle = i as f32 / (points * 2) as f32 * TAU - PI / 2.0;
+            angle_point(center, radius, angle)
+        })
+        .collect()
+}
+
+/**
+Generate a gear with some number of teeth
+
+The teeth reach the circle's radius and are `tooth_depth` deep.
+*/
+pub fn gear<C>(circ: C, teeth: u16, tooth_depth: f32) -> Vec<Vec2>
+where
+    C: Circle<Scalar = f32>,
+{
+    let circ: Circ = circ.map();
+    let root = circ.radius() - tooth_depth;
+    let tooth = TAU / teeth as f32;
+    (0..teeth)
+        .flat_map(|i| {
+            let start = i as f32 * tooth;
+            // Each tooth is a trapezoid that narrows toward its tip
+            [
+                (0.0, root),
+                (0.15, circ.radius()),
+                (0.4, circ.radius()),
+                (0.55, root),
+            ]
+            .iter()
+            .map(move |&(frac, radius)| angle_point(circ.center(), radius, start + frac * tooth))
+            .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/**
+Round the corners of a polygon
+
+Each corner is replaced by a curve through `corner_resolution` points.
+Corners are rounded less where edges are too short for the full radius.
+*/
+pub fn rounded_polygon(vertices: &[Vec2], radius: f32, corner_resolution: u16) -> Vec<Vec2> {
+    let n = vertices.len();
+    if n < 3 || corner_resolution == 0 {
+        return vertices.to_vec();
+    }
+    let mut rounded = Vec::with_capacity(n * corner_resolution as usize);
+    for i in 0..n {
+        let prev = vertices[(i + n - 1) % n];
+        let corner = vertices[i];
+        let next = vertices[(i + 1) % n];
+        let (to_prev, to_next) = (prev.sub(corner), next.sub(corner));
+        let angle = to_prev.unit().dot(to_next.unit()).clamp(-1.0, 1.0).acos();
+        if angle.is_nan() || angle >= PI - 1e-4 {
+            rounded.push(corner);
+            continue;
+        }
+        // The distance from the corner to where the curve meets each edge
+        let tangent = (radius / (angle / 2.0).tan())
+            .min(to_prev.mag() / 2.0)
+            .min(to_next.mag() / 2.0);
+        let start = corner.add(to_prev.unit().mul(tangent));
+        let end = corner.add(to_next.unit().mul(tangent));
+        for j in 0..corner_resolution {
+            let t = if corner_resolution == 1 {
+                0.5
+            } else {
+                j as f32 / (corner_resolution - 1) as f32
+            };
+            // A quadratic curve with the corner as its control point
+            let a = start.lerp(corner, t);
+            let b = corner.lerp(end, t);
+            rounded.push(a.lerp(b, t));
+        }
+    }
+    rounded
+}
+
+/// Generate a rectangle with rounded corners
+pub fn rounded_rect<R>(rect: R, radius: f32, corner_resolution: u16) -> Vec<Vec2>
+where
+    R: Rectangle<Scalar = f32>,
+{
+    let rect: Rect = rect.map();
+    rounded_polygon(
+        &[
+            rect.abs_top_left(),
+            rect.abs_top_right(),
+            rect.abs_bottom_right(),
+            rect.abs_bottom_left(),
+        ],
+        radius,
+        corner_resolution,
+    )
+}
+
+/**
+Generate a superellipse
+
+An `exponent` of `2.0` makes an ellipse. Larger exponents approach a rectangle,
+and exponents between `0.0` and `1.0` make a pinched star-like shape.
+*/
+pub fn superellipse(center: Vec2, radii: Vec2, exponent: f32, resolution: u16) -> Vec<Vec2> {
+    let power = 2.0 / exponent;
+    (0..resolution)
+        .map(|i| {
+            let angle = i as f32 / resolution as f32 * TAU;
+            let (sin, cos) = angle.sin_cos();
+            center.add([
+                radii.x() * cos.signum() * cos.abs().powf(power),
+                radii.y() * sin.signum() * sin.abs().powf(power),
+            ])
+        })
+        .collect()
+}
+
+#[cfg(test)]
+#[test]
+fn shape_generators() {
+    assert_eq!(star([0.0; 2], 2.0, 1.0, 5).len(), 10);
+    assert_eq!(gear(([0.0; 2], 5.0), 8, 1.0).len(), 32);
+    let ellipse = superellipse([0.0; 2], [3.0, 3.0], 2.0, 16);
+    assert!(ellipse.iter().all(|v| (v.mag() - 3.0).abs() < 1e-4));
+    let rect = rounded_rect([0.0, 0.0, 4.0, 2.0], 0.5, 4);
+    assert_eq!(rect.len(), 16);
+    assert!(rect
+        .iter()
+        .all(|v| v.x() >= 0.0 && v.x() <= 4.0 && v.y() >= 0.0 && v.y() <= 2.0));
+    assert_eq!(rect[0], [0.0, 0.5]);
+}
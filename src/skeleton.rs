@@ -0,0 +1,454 @@
+use std::collections::HashMap;
+
+use vector2math::*;
+
+use crate::{Canvas, Col, Color, Drawer, Interpolate, Resources, Timeline, Trans, Vec2};
+
+/// The position, rotation, and scale of a bone relative to its parent
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(
+    feature = "ser",
+    derive(serde_derive::Serialize, serde_derive::Deserialize)
+)]
+pub struct BoneTransform {
+    /// The offset from the parent bone
+    pub position: Vec2,
+    /// The rotation in radians
+    pub rotation: f32,
+    /// The scale
+    pub scale: Vec2,
+}
+
+impl Default for BoneTransform {
+    fn default() -> Self {
+        BoneTransform::new([0.0; 2])
+    }
+}
+
+impl BoneTransform {
+    /// Create a new unrotated, unscaled `BoneTransform` at an offset
+    pub fn new(position: Vec2) -> Self {
+        BoneTransform {
+            position,
+            rotation: 0.0,
+            scale: [1.0; 2],
+        }
+    }
+    /// Set the rotation
+    pub fn rotation(self, rotation: f32) -> Self {
+        BoneTransform { rotation, ..self }
+    }
+    /// Set the scale
+    pub fn scale(self, scale: Vec2) -> Self {
+        BoneTransform { scale, ..self }
+    }
+    /// Get the transformation that scales, then rotates, then translates
+    pub fn transform(&self) -> Trans {
+        Trans::identity()
+            .scale(self.scale)
+            .rotate(self.rotation)
+            .translate(self.position)
+    }
+}
+
+impl Interpolate for BoneTransform {
+    fn interpolate(self, other: Self, t: f32) -> Self {
+        BoneTransform {
+            position: self.position.interpolate(other.position, t),
+            rotation: self.rotation.interpolate(other.rotation, t),
+            scale: self.scale.interpolate(other.scale, t),
+        }
+    }
+}
+
+/// An id for a bone in a `Skeleton`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BoneId(usize);
+
+impl BoneId {
+    /// Get the index of the bone in its skeleton
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+/// A bone in a `Skeleton`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bone {
+    /// The bone's name
+    pub name: String,
+    /// The bone's parent
+    pub parent: Option<BoneId>,
+    /// The bone's transform when it is not animated
+    pub rest: BoneTransform,
+}
+
+/// A shape drawn attached to a bone
+#[derive(Debug, Clone, PartialEq)]
+pub enum AttachmentShape<M = ()> {
+    /// A polygon
+    Polygon(Vec<Vec2>),
+    /// A cached mesh
+    Mesh(M),
+}
+
+/// A shape attached to a bone
+#[derive(Debug, Clone, PartialEq)]
+pub struct Attachment<M = ()> {
+    /// The bone the shape is attached to
+    pub bone: BoneId,
+    /// The shape
+    pub shape: AttachmentShape<M>,
+    /// The shape's color
+    pub color: Col,
+    /// The shape's transform relative to the bone
+    pub offset: Trans,
+}
+
+/**
+A hierarchy of bones with shapes attached to them
+
+Each bone is transformed relative to its parent, so moving a bone moves
+all of its children and their attachments. Attachments are drawn in the
+order they were attached with `Drawer::skeleton`.
+
+`M` is the mesh id type used for mesh attachments.
+
+```
+# use kule::*;
+let mut skeleton = Skeleton::<()>::new();
+let body = skeleton.add_bone("body", None, BoneTransform::new([0.0, 0.0]));
+let arm = skeleton.add_bone("arm", Some(body), BoneTransform::new([10.0, 0.0]));
+skeleton.attach(arm, AttachmentShape::Polygon(vec![[0.0, 0.0], [8.0, -1.0], [8.0, 1.0]]), [1.0; 4]);
+
+let mut pose = skeleton.rest_pose();
+pose[body].position = [5.0, 5.0];
+let transforms = skeleton.world_transforms(&pose);
+assert_eq!(transforms[arm.index()].apply([0.0, 0.0]), [15.0, 5.0]);
+```
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct Skeleton<M = ()> {
+    bones: Vec<Bone>,
+    attachments: Vec<Attachment<M>>,
+}
+
+impl<M> Default for Skeleton<M> {
+    fn default() -> Self {
+        Skeleton {
+            bones: Vec::new(),
+            attachments: Vec::new(),
+        }
+    }
+}
+
+impl<M> Skeleton<M> {
+    /// Create a new empty `Skeleton`
+    pub fn new() -> Self {
+        Skeleton::default()
+    }
+    /**
+    Add a bone
+
+    # Panics
+
+    Panics if the parent is not a bone in this skeleton
+    */
+    pub fn add_bone(
+        &mut self,
+        name: impl Into<String>,
+        parent: Option<BoneId>,
+        rest: BoneTransform,
+    ) -> BoneId {
+        if let Some(parent) = parent {
+            assert!(
+                parent.0 < self.bones.len(),
+                "{:?} is not in the skeleton",
+                parent
+            );
+        }
+        self.bones.push(Bone {
+            name: name.into(),
+            parent,
+            rest,
+        });
+        BoneId(self.bones.len() - 1)
+    }
+    /// Attach a shape to a bone
+    pub fn attach<C>(&mut self, bone: BoneId, shape: AttachmentShape<M>, color: C)
+    where
+        C: Color,
+    {
+        self.attachments.push(Attachment {
+            bone,
+            shape,
+            color: color.map(),
+            offset: Trans::identity(),
+        });
+    }
+    /// Add an attachment
+    pub fn add_attachment(&mut self, attachment: Attachment<M>) {
+        self.attachments.push(attachment);
+    }
+    /// Get the id of a bone by its name
+    pub fn bone_id(&self, name: &str) -> Option<BoneId> {
+        self.bones
+            .iter()
+            .position(|bone| bone.name == name)
+            .map(BoneId)
+    }
+    /// Get a bone
+    pub fn bone(&self, id: BoneId) -> Option<&Bone> {
+        self.bones.get(id.0)
+    }
+    /// Get all bones, where parents always come before their children
+    pub fn bones(&self) -> &[Bone] {
+        &self.bones
+    }
+    /// Get all attachments
+    pub fn attachments(&self) -> &[Attachment<M>] {
+        &self.attachments
+    }
+    /// Get the pose where every bone is at rest
+    pub fn rest_pose(&self) -> SkeletonPose {
+        SkeletonPose {
+            locals: self.bones.iter().map(|bone| bone.rest).collect(),
+        }
+    }
+    /**
+    Get the pose of an animation timeline at some time
+
+    Each track animates the bone with the same name. Track positions and rotations
+    are added to the bone's rest transform, and track scales multiply it.
+    Track colors are ignored.
+    */
+    pub fn pose_from_timeline(&self, timeline: &Timeline, time: f32) -> SkeletonPose {
+        let mut pose = self.rest_pose();
+        for (name, track) in timeline.sample(time) {
+            if let Some(id) = self.bone_id(name) {
+                let local = &mut pose[id];
+                local.position = local.position.add(track.position);
+                local.rotation += track.rotation;
+                local.scale = local.scale.mul2(track.scale);
+            }
+        }
+        pose
+    }
+    /// Get the world transform of every bone in a pose, indexed by `BoneId::index`
+    pub fn world_transforms(&self, pose: &SkeletonPose) -> Vec<Trans> {
+        let mut transforms: Vec<Trans> = Vec::with_capacity(self.bones.len());
+        for (i, bone) in self.bones.iter().enumerate() {
+            let local = pose.locals.get(i).copied().unwrap_or(bone.rest).transform();
+            let world = match bone.parent {
+                Some(parent) => local.then(transforms[parent.0]),
+                None => local,
+            };
+            transforms.push(world);
+        }
+        transforms
+    }
+}
+
+/// The local transforms of every bone in a `Skeleton`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SkeletonPose {
+    locals: Vec<BoneTransform>,
+}
+
+impl SkeletonPose {
+    /// Get a bone's local transform
+    pub fn get(&self, bone: BoneId) -> Option<&BoneTransform> {
+        self.locals.get(bone.0)
+    }
+    /// Get a bone's local transform mutably
+    pub fn get_mut(&mut self, bone: BoneId) -> Option<&mut BoneTransform> {
+        self.locals.get_mut(bone.0)
+    }
+    /// Blend this pose with another, where `t` is from `0.0` to `1.0`
+    pub fn blend(&self, other: &SkeletonPose, t: f32) -> SkeletonPose {
+        SkeletonPose {
+            locals: self
+                .locals
+                .iter()
+                .zip(&other.locals)
+                .map(|(a, b)| a.interpolate(*b, t))
+                .collect(),
+        }
+    }
+}
+
+impl std::ops::Index<BoneId> for SkeletonPose {
+    type Output = BoneTransform;
+    fn index(&self, bone: BoneId) -> &Self::Output {
+        &self.locals[bone.0]
+    }
+}
+
+impl std::ops::IndexMut<BoneId> for SkeletonPose {
+    fn index_mut(&mut self, bone: BoneId) -> &mut Self::Output {
+        &mut self.locals[bone.0]
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Playing {
+    name: String,
+    time: f32,
+}
+
+/**
+Plays named animation timelines on a skeleton
+
+Switching animations crossfades from the old pose to the new one.
+*/
+#[derive(Debug, Clone)]
+pub struct SkeletonAnimator {
+    animations: HashMap<String, Timeline>,
+    current: Option<Playing>,
+    previous: Option<Playing>,
+    fade: f32,
+    fade_elapsed: f32,
+    /// The playback speed multiplier
+    pub speed: f32,
+}
+
+impl Default for SkeletonAnimator {
+    fn default() -> Self {
+        SkeletonAnimator {
+            animations: HashMap::new(),
+            current: None,
+            previous: None,
+            fade: 0.0,
+            fade_elapsed: 0.0,
+            speed: 1.0,
+        }
+    }
+}
+
+impl SkeletonAnimator {
+    /// Create a new `SkeletonAnimator` with no animations
+    pub fn new() -> Self {
+        SkeletonAnimator::default()
+    }
+    /// Add an animation
+    pub fn add(&mut self, name: impl Into<String>, timeline: Timeline) {
+        self.animations.insert(name.into(), timeline);
+    }
+    /// Add an animation
+    pub fn with(mut self, name: impl Into<String>, timeline: Timeline) -> Self {
+        self.add(name, timeline);
+        self
+    }
+    /**
+    Start playing an animation from the beginning, crossfading from the current one over some seconds
+
+    If no animation is playing, the crossfade starts from the rest pose.
+
+    Does nothing if the animation is already playing.
+    */
+    pub fn play(&mut self, name: &str, fade: f32) {
+        if self.current() == Some(name) {
+            return;
+        }
+        self.previous = self.current.take();
+        self.current = Some(Playing {
+            name: name.into(),
+            time: 0.0,
+        });
+        self.fade = fade;
+        self.fade_elapsed = 0.0;
+    }
+    /// Stop playing, returning to the rest pose
+    pub fn stop(&mut self) {
+        self.current = None;
+        self.previous = None;
+    }
+    /// Get the name of the current animation
+    pub fn current(&self) -> Option<&str> {
+        self.current.as_ref().map(|playing| playing.name.as_str())
+    }
+    /// Get the time into the current animation
+    pub fn time(&self) -> f32 {
+        self.current.as_ref().map_or(0.0, |playing| playing.time)
+    }
+    /// Advance the animations
+    pub fn update(&mut self, dt: f32) {
+        let dt = dt * self.speed;
+        for playing in self.current.iter_mut().chain(&mut self.previous) {
+            playing.time += dt;
+        }
+        self.fade_elapsed += dt;
+        if self.fade_elapsed >= self.fade {
+            self.previous = None;
+        }
+    }
+    fn playing_pose<M>(&self, playing: &Option<Playing>, skeleton: &Skeleton<M>) -> SkeletonPose {
+        playing
+            .as_ref()
+            .and_then(|playing| {
+                self.animations
+                    .get(&playing.name)
+                    .map(|timeline| skeleton.pose_from_timeline(timeline, playing.time))
+            })
+            .unwrap_or_else(|| skeleton.rest_pose())
+    }
+    /// Get the current pose of a skeleton
+    pub fn pose<M>(&self, skeleton: &Skeleton<M>) -> SkeletonPose {
+        let current = self.playing_pose(&self.current, skeleton);
+        if self.fade_elapsed >= self.fade {
+            return current;
+        }
+        self.playing_pose(&self.previous, skeleton)
+            .blend(&current, self.fade_elapsed / self.fade)
+    }
+}
+
+impl<'ctx, T, R> Drawer<'ctx, T, R>
+where
+    T: Canvas,
+    R: Resources,
+{
+    /// Draw the attachments of a skeleton in a pose
+    pub fn skeleton(&mut self, skeleton: &Skeleton<R::MeshId>, pose: &SkeletonPose) {
+        let transforms = skeleton.world_transforms(pose);
+        for attachment in &skeleton.attachments {
+            let transform = match transforms.get(attachment.bone.0) {
+                Some(bone) => attachment.offset.then(*bone),
+                None => continue,
+            };
+            match &attachment.shape {
+                AttachmentShape::Polygon(vertices) => {
+                    self.polygon(attachment.color, vertices)
+                        .transform(|t| t.then(transform));
+                }
+                AttachmentShape::Mesh(mesh_id) => {
+                    if let Some(mut mesh) = self.mesh(attachment.color, *mesh_id) {
+                        mesh.transform(|t| t.then(transform));
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn skeleton_blending() {
+    let mut skeleton = Skeleton::<()>::new();
+    let root = skeleton.add_bone("root", None, BoneTransform::default());
+    let mut animator = SkeletonAnimator::new().with(
+        "raise",
+        Timeline::new().track(
+            "root",
+            crate::Track {
+                position: vec![crate::Keyframe::new(0.0, [0.0, 10.0])],
+                ..Default::default()
+            },
+        ),
+    );
+    animator.play("raise", 1.0);
+    animator.update(0.25);
+    assert_eq!(animator.pose(&skeleton)[root].position, [0.0, 2.5]);
+    animator.update(1.0);
+    assert_eq!(animator.pose(&skeleton)[root].position, [0.0, 10.0]);
+}
@@ -1,7 +1,10 @@
 use std::{
+    any::Any,
+    cell::{Cell, RefCell},
     collections::HashMap,
     hash::Hash,
     io::Cursor,
+    mem,
     sync::{
         atomic::{AtomicBool, Ordering},
         mpsc, Arc, Mutex,
@@ -13,47 +16,673 @@ use std::{
 use crossbeam_utils::atomic::AtomicCell;
 use rodio::{decoder::*, dynamic_mixer::*, source::Zero, Sample, Sink};
 
-pub use rodio::{self, Source};
+use crate::{EffectChain, EffectSource, KuleError, KuleResult, MusicTrack};
 
-pub(crate) fn sink() -> Sink {
-    let (send, recv) = mpsc::channel();
-    thread::spawn(move || {
-        let device = rodio::default_output_device().unwrap();
-        let sink = Sink::new(&device);
-        send.send(sink).unwrap();
-        loop {
-            thread::sleep(Duration::from_secs(100));
-        }
-    });
-    recv.recv().unwrap()
-}
+pub use rodio::{self, Source};
 
 /// A master audio mixer
 pub struct Mixer {
+    /// Owns the output stream, which stops when the sink is dropped
+    _sink: Option<Sink>,
     mixer: Arc<DynamicMixerController<f32>>,
     volume: VolumeControl,
+    effects: EffectChain,
+    limiter: Limiter,
+    paused: Arc<AtomicCell<bool>>,
+    focus_paused: Arc<AtomicCell<bool>>,
+    music: Option<MusicTrack>,
+    last_beat: Cell<Option<u64>>,
+    next_id: Cell<u64>,
+    voices: RefCell<Vec<Voice>>,
+    voice_limit: Cell<Option<usize>>,
+    voice_limit_per_sound: Cell<Option<usize>>,
+    voice_stealing: Cell<VoiceStealing>,
+    finished_send: mpsc::Sender<u64>,
+    finished_recv: mpsc::Receiver<u64>,
 }
 
 impl Mixer {
-    pub(crate) fn new(sink: &Sink) -> Self {
+    /**
+    Open the default output device and create a sink for it
+
+    The device is opened on its own thread so that it does not conflict with
+    the windowing system's initialization of the main thread
+    */
+    fn open_sink() -> KuleResult<Sink> {
+        thread::spawn(|| rodio::default_output_device().map(|device| Sink::new(&device)))
+            .join()
+            .map_err(|_| KuleError::AudioDevice("Failed to open the output device".into()))?
+            .ok_or_else(|| KuleError::AudioDevice("No output device available".into()))
+    }
+    /// Create a new mixer, which only plays sound if `output` is `true`
+    pub(crate) fn new(output: bool) -> KuleResult<Self> {
+        let sink = if output {
+            Some(Self::open_sink()?)
+        } else {
+            None
+        };
         let (mixer, mixer_source) = mixer::<f32>(2, 44100);
         mixer.add(Zero::new(2, 44100));
         let volume = VolumeControl::default();
-        let controlled_mixer = volume.control(mixer_source);
-        sink.append(controlled_mixer);
-        Mixer { mixer, volume }
+        let effects = EffectChain::default();
+        let limiter = Limiter::default();
+        let paused = Arc::new(AtomicCell::new(false));
+        let focus_paused = Arc::new(AtomicCell::new(false));
+        let controlled_mixer = PauseSource {
+            source: limiter.control(volume.control(effects.apply(mixer_source))),
+            paused: paused.clone(),
+            focus_paused: focus_paused.clone(),
+            silent: false,
+            channel: 0,
+        };
+        if let Some(sink) = &sink {
+            sink.append(controlled_mixer);
+        }
+        let (finished_send, finished_recv) = mpsc::channel();
+        Ok(Mixer {
+            _sink: sink,
+            mixer,
+            volume,
+            effects,
+            limiter,
+            paused,
+            focus_paused,
+            music: None,
+            last_beat: Cell::new(None),
+            next_id: Cell::new(0),
+            voices: RefCell::new(Vec::new()),
+            voice_limit: Cell::new(None),
+            voice_limit_per_sound: Cell::new(None),
+            voice_stealing: Cell::new(VoiceStealing::Oldest),
+            finished_send,
+            finished_recv,
+        })
     }
     /// Get a reference to the volume controller
     pub fn volume(&self) -> &VolumeControl {
         &self.volume
     }
+    /// Get a reference to the effects applied to the master output
+    pub fn effects(&self) -> &EffectChain {
+        &self.effects
+    }
+    /// Get a reference to the limiter applied to the master output
+    pub fn limiter(&self) -> &Limiter {
+        &self.limiter
+    }
+    /// Pause all sounds
+    pub fn pause(&self) {
+        self.paused.store(true);
+    }
+    /// Resume all sounds
+    pub fn resume(&self) {
+        self.paused.store(false);
+    }
+    /// Check if the mixer is paused
+    ///
+    /// This does not include being paused because the window lost focus
+    pub fn is_paused(&self) -> bool {
+        self.paused.load()
+    }
+    pub(crate) fn set_focus_paused(&self, paused: bool) {
+        self.focus_paused.store(paused);
+    }
     /// Play a sound source
-    pub fn play<S>(&self, source: S)
+    ///
+    /// The returned handle can be used to control the sound while it plays
+    pub fn play<S>(&self, source: S) -> SoundHandle
+    where
+        S: Source + Send + 'static,
+        S::Item: Sample,
+    {
+        self.play_with(source, PlayOptions::default())
+    }
+    /**
+    Set the maximum number of sounds that can play at once
+
+    Music does not count toward the limit. When the limit is reached,
+    a playing sound is stolen according to the voice stealing mode.
+    */
+    pub fn set_voice_limit(&self, limit: Option<usize>) {
+        self.voice_limit.set(limit);
+    }
+    /// Get the maximum number of sounds that can play at once
+    pub fn voice_limit(&self) -> Option<usize> {
+        self.voice_limit.get()
+    }
+    /**
+    Set the maximum number of instances of the same sound id that can play at once
+
+    This only applies to sounds played from the sound cache via the `Context`
+    */
+    pub fn set_voice_limit_per_sound(&self, limit: Option<usize>) {
+        self.voice_limit_per_sound.set(limit);
+    }
+    /// Get the maximum number of instances of the same sound id that can play at once
+    pub fn voice_limit_per_sound(&self) -> Option<usize> {
+        self.voice_limit_per_sound.get()
+    }
+    /// Set how a playing sound is chosen to be stopped when a voice limit is reached
+    pub fn set_voice_stealing(&self, stealing: VoiceStealing) {
+        self.voice_stealing.set(stealing);
+    }
+    /// Get how a playing sound is chosen to be stopped when a voice limit is reached
+    pub fn voice_stealing(&self) -> VoiceStealing {
+        self.voice_stealing.get()
+    }
+    /// Get the number of sounds currently playing, not including music
+    pub fn voice_count(&self) -> usize {
+        let mut voices = self.voices.borrow_mut();
+        voices.retain(|voice| !voice.handle.is_finished());
+        voices.len()
+    }
+    fn steal_voice(&self, voices: &mut Vec<Voice>, group: Option<u64>) {
+        let candidates = voices
+            .iter()
+            .enumerate()
+            .filter(|(_, voice)| group.is_none() || voice.group == group);
+        let victim = match self.voice_stealing.get() {
+            VoiceStealing::Oldest => candidates.map(|(i, _)| i).next(),
+            VoiceStealing::Quietest => candidates
+                .min_by(|(_, a), (_, b)| {
+                    let a = a.handle.volume.volume();
+                    let b = b.handle.volume.volume();
+                    a.partial_cmp(&b).unwrap_or(std::cmp::Ordering::Equal)
+                })
+                .map(|(i, _)| i),
+        };
+        if let Some(i) = victim {
+            voices.remove(i).handle.fade_out(STEAL_FADE);
+        }
+    }
+    /// Play a sound source with some options
+    pub fn play_with<S>(&self, source: S, options: PlayOptions) -> SoundHandle
+    where
+        S: Source + Send + 'static,
+        S::Item: Sample,
+    {
+        self.play_grouped(source, options, None)
+    }
+    /// Play a sound source that counts toward the per-sound voice limit of some group
+    pub(crate) fn play_grouped<S>(
+        &self,
+        source: S,
+        options: PlayOptions,
+        group: Option<u64>,
+    ) -> SoundHandle
+    where
+        S: Source + Send + 'static,
+        S::Item: Sample,
+    {
+        let handle = self.play_untracked(source, options);
+        let mut voices = self.voices.borrow_mut();
+        voices.retain(|voice| !voice.handle.is_finished());
+        if let (Some(limit), Some(_)) = (self.voice_limit_per_sound.get(), group) {
+            while voices.iter().filter(|voice| voice.group == group).count() >= limit.max(1) {
+                self.steal_voice(&mut voices, group);
+            }
+        }
+        if let Some(limit) = self.voice_limit.get() {
+            while voices.len() >= limit.max(1) {
+                self.steal_voice(&mut voices, None);
+            }
+        }
+        voices.push(Voice {
+            handle: handle.clone(),
+            group,
+        });
+        handle
+    }
+    pub(crate) fn play_untracked<S>(&self, source: S, options: PlayOptions) -> SoundHandle
+    where
+        S: Source + Send + 'static,
+        S::Item: Sample,
+    {
+        let handle = SoundHandle {
+            id: self.next_id.get(),
+            volume: VolumeControl::default(),
+            effects: EffectChain::default(),
+            playback: Arc::new(Playback::default()),
+        };
+        self.next_id.set(handle.id + 1);
+        handle.set_volume(options.volume);
+        handle.set_position(options.start_at);
+        if let Some(duration) = options.fade_in {
+            handle.queue_fade(Fade {
+                from: Some(0.0),
+                to: 1.0,
+                duration,
+                stop: false,
+            });
+        }
+        // A `SoundSource` seeks its buffer directly. Other sources skip samples
+        // on the audio thread so that the caller does not wait on them.
+        let mut source = source;
+        let skip = match (&mut source as &mut dyn Any).downcast_mut::<SoundSource>() {
+            Some(sound) => {
+                sound.seek(options.start_at);
+                Duration::from_secs(0)
+            }
+            None => options.start_at,
+        };
+        let mut source = handle.control(source.convert_samples(), self.finished_send.clone());
+        source.skip = skip;
+        self.mixer.add(source);
+        handle
+    }
+    /**
+    Play a sound source as music
+
+    Only one music source plays at a time. The current music, if any, is
+    faded out while the new music fades in over the `crossfade` duration.
+    */
+    pub fn play_music<S>(&mut self, source: S, crossfade: Duration) -> SoundHandle
     where
         S: Source + Send + 'static,
         S::Item: Sample,
     {
-        self.mixer.add(source.convert_samples());
+        self.play_layered_music(Some(source), 1.0, crossfade)
+            .layers()[0]
+            .clone()
+    }
+    /**
+    Play several synchronized sound sources as the layers of a single music track
+
+    The layers that are audible are determined by the `intensity`.
+    See `MusicTrack::set_intensity` for details.
+
+    The current music, if any, is faded out while the new music fades in
+    over the `crossfade` duration.
+    */
+    pub fn play_layered_music<I, S>(
+        &mut self,
+        layers: I,
+        intensity: f32,
+        crossfade: Duration,
+    ) -> MusicTrack
+    where
+        I: IntoIterator<Item = S>,
+        S: Source + Send + 'static,
+        S::Item: Sample,
+    {
+        self.stop_music(crossfade);
+        let track = MusicTrack::new(
+            layers
+                .into_iter()
+                .map(|source| self.play_untracked(source, PlayOptions::new()))
+                .collect(),
+        );
+        track.fade_in(intensity, crossfade);
+        self.music = Some(track.clone());
+        self.last_beat.set(None);
+        track
+    }
+    /// Fade out and stop the current music
+    pub fn stop_music(&mut self, fade_out: Duration) {
+        if let Some(music) = self.music.take() {
+            music.fade_out(fade_out);
+        }
+    }
+    /// Get the current music track
+    pub fn music(&self) -> Option<&MusicTrack> {
+        self.music.as_ref()
+    }
+    /// Get the beats and bars of the music that have passed since the last call
+    pub(crate) fn music_beats(&self) -> Vec<(u64, u64)> {
+        let (clock, beat) = match self.music.as_ref().and_then(|music| {
+            let clock = music.beat_clock()?;
+            Some((clock, music.beat()?))
+        }) {
+            Some((clock, beat)) if beat >= 0.0 => (clock, beat as u64),
+            _ => return Vec::new(),
+        };
+        let first = match self.last_beat.get() {
+            Some(last) if last <= beat => last + 1,
+            _ => beat,
+        };
+        self.last_beat.set(Some(beat));
+        (first..=beat)
+            .map(|beat| (beat, clock.bar_of(beat)))
+            .collect()
+    }
+    /// Get the ids of sounds that have finished since the last call
+    pub(crate) fn finished_sounds(&self) -> impl Iterator<Item = u64> + '_ {
+        self.finished_recv.try_iter()
+    }
+}
+
+const STEAL_FADE: Duration = Duration::from_millis(5);
+
+/// How a playing sound is chosen to be stopped when a voice limit is reached
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VoiceStealing {
+    /// Stop the sound that started playing first
+    Oldest,
+    /// Stop the sound with the lowest volume
+    Quietest,
+}
+
+struct Voice {
+    handle: SoundHandle,
+    group: Option<u64>,
+}
+
+/// Options for playing a sound
+#[derive(Debug, Clone, Copy)]
+pub struct PlayOptions {
+    /// The initial volume
+    pub volume: f32,
+    /// The duration over which the sound fades in
+    pub fade_in: Option<Duration>,
+    /// The offset into the sound at which to start playing
+    pub start_at: Duration,
+}
+
+impl Default for PlayOptions {
+    fn default() -> Self {
+        PlayOptions::new()
+    }
+}
+
+impl PlayOptions {
+    /// Create a new `PlayOptions` with full volume and no fade
+    pub const fn new() -> Self {
+        PlayOptions {
+            volume: 1.0,
+            fade_in: None,
+            start_at: Duration::from_secs(0),
+        }
+    }
+    /// Set the initial volume
+    pub const fn volume(self, volume: f32) -> Self {
+        PlayOptions { volume, ..self }
+    }
+    /// Fade the sound in over some duration
+    pub const fn fade_in(self, duration: Duration) -> Self {
+        PlayOptions {
+            fade_in: Some(duration),
+            ..self
+        }
+    }
+    /// Start playing the sound at some offset
+    pub const fn start_at(self, start_at: Duration) -> Self {
+        PlayOptions { start_at, ..self }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Fade {
+    from: Option<f32>,
+    to: f32,
+    duration: Duration,
+    stop: bool,
+}
+
+#[derive(Debug)]
+struct Playback {
+    stopped: AtomicCell<bool>,
+    paused: AtomicCell<bool>,
+    finished: AtomicCell<bool>,
+    fade: AtomicCell<Option<Fade>>,
+    position: AtomicCell<f64>,
+}
+
+impl Default for Playback {
+    fn default() -> Self {
+        Playback {
+            stopped: AtomicCell::new(false),
+            paused: AtomicCell::new(false),
+            finished: AtomicCell::new(false),
+            fade: AtomicCell::new(None),
+            position: AtomicCell::new(0.0),
+        }
+    }
+}
+
+/**
+A handle to a playing sound
+
+Handles are cheap to clone. Dropping a handle does not stop the sound.
+
+When the sound finishes, an `Event::SoundFinished` with the handle's id is sent to the app.
+*/
+#[derive(Debug, Clone)]
+pub struct SoundHandle {
+    id: u64,
+    volume: VolumeControl,
+    effects: EffectChain,
+    playback: Arc<Playback>,
+}
+
+impl SoundHandle {
+    fn control<S>(&self, source: S, finished: mpsc::Sender<u64>) -> HandleSource<S> {
+        HandleSource {
+            id: self.id,
+            source: self.volume.control(self.effects.apply(source)),
+            playback: self.playback.clone(),
+            finished,
+            gain: 1.0,
+            target: 1.0,
+            step: 0.0,
+            stop_at_target: false,
+            paused: false,
+            channel: 0,
+            skip: Duration::from_secs(0),
+        }
+    }
+    /// Get the id of the sound
+    ///
+    /// Ids are unique for each `Mixer`
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+    /// Get a reference to the volume controller
+    pub fn volume(&self) -> &VolumeControl {
+        &self.volume
+    }
+    /// Set the volume
+    pub fn set_volume(&self, volume: f32) {
+        self.volume.set_volume(volume);
+    }
+    /// Get a reference to the effects applied to the sound
+    pub fn effects(&self) -> &EffectChain {
+        &self.effects
+    }
+    /// Stop the sound
+    ///
+    /// A stopped sound cannot be resumed
+    pub fn stop(&self) {
+        self.playback.stopped.store(true);
+    }
+    /// Pause the sound
+    pub fn pause(&self) {
+        self.playback.paused.store(true);
+    }
+    /// Resume the sound if it is paused
+    pub fn resume(&self) {
+        self.playback.paused.store(false);
+    }
+    /**
+    Fade the sound's gain to some value over some duration
+
+    The gain is applied on top of the volume. A gain of `1.0` is unchanged.
+    */
+    pub fn fade_to(&self, gain: f32, duration: Duration) {
+        self.queue_fade(Fade {
+            from: None,
+            to: gain,
+            duration,
+            stop: false,
+        });
+    }
+    pub(crate) fn fade_from(&self, from: f32, to: f32, duration: Duration) {
+        self.queue_fade(Fade {
+            from: Some(from),
+            to,
+            duration,
+            stop: false,
+        });
+    }
+    /// Fade the sound out over some duration, then stop it
+    pub fn fade_out(&self, duration: Duration) {
+        self.queue_fade(Fade {
+            from: None,
+            to: 0.0,
+            duration,
+            stop: true,
+        });
+    }
+    /// Hand a fade to the audio thread
+    ///
+    /// If an earlier fade has not started yet and set the starting gain, the new
+    /// fade starts from that gain too, so that a fade in is not lost.
+    fn queue_fade(&self, mut fade: Fade) {
+        if let Some(pending) = self.playback.fade.swap(None) {
+            fade.from = fade.from.or(pending.from);
+        }
+        self.playback.fade.store(Some(fade));
+    }
+    /// Check if the sound is paused
+    pub fn is_paused(&self) -> bool {
+        self.playback.paused.load()
+    }
+    /// Check if the sound has finished playing or has been stopped
+    pub fn is_finished(&self) -> bool {
+        self.playback.finished.load()
+    }
+    /// Check if the sound is currently playing
+    pub fn is_playing(&self) -> bool {
+        !self.is_finished() && !self.is_paused()
+    }
+    /**
+    Get how far into the sound playback is
+
+    This can be passed to `PlayOptions::start_at` to resume the sound later
+    */
+    pub fn position(&self) -> Duration {
+        Duration::from_secs_f64(self.playback.position.load())
+    }
+    pub(crate) fn set_position(&self, position: Duration) {
+        self.playback.position.store(position.as_secs_f64());
+    }
+}
+
+struct HandleSource<T> {
+    id: u64,
+    source: VolumeControlSource<EffectSource<T>>,
+    playback: Arc<Playback>,
+    finished: mpsc::Sender<u64>,
+    gain: f32,
+    target: f32,
+    step: f32,
+    stop_at_target: bool,
+    /// Whether the current channel frame is paused
+    paused: bool,
+    /// The channel of the next sample
+    channel: u16,
+    /// The duration to skip before the first sample
+    skip: Duration,
+}
+
+impl<T> HandleSource<T>
+where
+    T: Source<Item = f32>,
+{
+    fn start_fade(&mut self, fade: Fade) {
+        if let Some(from) = fade.from {
+            self.gain = from;
+        }
+        self.target = fade.to;
+        self.stop_at_target = fade.stop;
+        let samples = fade.duration.as_secs_f32()
+            * self.source.sample_rate() as f32
+            * self.source.channels() as f32;
+        self.step = if samples >= 1.0 {
+            (self.target - self.gain) / samples
+        } else {
+            self.target - self.gain
+        };
+    }
+    fn skip_duration(&mut self, duration: Duration) {
+        let samples = samples_in(duration, self.source.sample_rate(), self.source.channels());
+        for _ in 0..samples {
+            if self.source.next().is_none() {
+                break;
+            }
+        }
+    }
+    fn finish(&mut self) {
+        if !self.playback.finished.swap(true) {
+            let _ = self.finished.send(self.id);
+        }
+    }
+    fn advance_fade(&mut self) {
+        if self.gain == self.target {
+            return;
+        }
+        self.gain += self.step;
+        if self.step >= 0.0 && self.gain >= self.target
+            || self.step <= 0.0 && self.gain <= self.target
+        {
+            self.gain = self.target;
+        }
+    }
+}
+
+impl<T> Iterator for HandleSource<T>
+where
+    T: Source<Item = f32>,
+{
+    type Item = f32;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.skip > Duration::from_secs(0) {
+            let skip = mem::take(&mut self.skip);
+            self.skip_duration(skip);
+        }
+        if let Some(fade) = self.playback.fade.swap(None) {
+            self.start_fade(fade);
+        }
+        if self.playback.stopped.load() || self.stop_at_target && self.gain == self.target {
+            self.finish();
+            None
+        } else {
+            // Pausing only takes effect between channel frames so that channels stay in order
+            if self.channel == 0 {
+                self.paused = self.playback.paused.load();
+            }
+            self.channel = (self.channel + 1) % self.source.channels().max(1);
+            if self.paused {
+                return Some(0.0);
+            }
+            let sample = self.source.next();
+            if sample.is_none() {
+                self.finish();
+            } else {
+                let rate = self.source.sample_rate() as f64 * self.source.channels().max(1) as f64;
+                let position = self.playback.position.load();
+                self.playback.position.store(position + 1.0 / rate);
+            }
+            self.advance_fade();
+            sample.map(|s| s * self.gain)
+        }
+    }
+}
+
+impl<T> Source for HandleSource<T>
+where
+    T: Source<Item = f32>,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        self.source.current_frame_len()
+    }
+    fn sample_rate(&self) -> u32 {
+        self.source.sample_rate()
+    }
+    fn channels(&self) -> u16 {
+        self.source.channels()
+    }
+    fn total_duration(&self) -> Option<Duration> {
+        self.source.total_duration()
     }
 }
 
@@ -92,12 +721,86 @@ where
     }
 }
 
+/**
+Sounds being loaded on a background thread
+
+Loaded sounds are moved into a `Sounds` cache with `SoundPreload::poll`
+*/
+pub struct SoundPreload<S> {
+    recv: mpsc::Receiver<(S, Result<Option<SoundBuffer>, String>)>,
+    total: usize,
+    loaded: usize,
+}
+
+impl<S> SoundPreload<S>
+where
+    S: Eq + Hash + Send + 'static,
+{
+    pub(crate) fn spawn<I, F>(sound_ids: I, load: F) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        F: Fn(&S) -> KuleResult<Option<SoundBuffer>> + Send + 'static,
+    {
+        let sound_ids: Vec<S> = sound_ids.into_iter().collect();
+        let total = sound_ids.len();
+        let (send, recv) = mpsc::channel();
+        thread::spawn(move || {
+            for sound_id in sound_ids {
+                let buffer = load(&sound_id).map_err(|e| e.to_string());
+                if send.send((sound_id, buffer)).is_err() {
+                    break;
+                }
+            }
+        });
+        SoundPreload {
+            recv,
+            total,
+            loaded: 0,
+        }
+    }
+    /**
+    Move any sounds that have finished loading into a sound cache
+
+    If loading a sound failed, the error is returned. Polling can continue afterward.
+    */
+    pub fn poll(&mut self, sounds: &mut Sounds<S>) -> KuleResult<()> {
+        for (sound_id, buffer) in self.recv.try_iter() {
+            self.loaded += 1;
+            if let Some(buffer) = buffer.map_err(KuleError::SoundLoad)? {
+                sounds.insert(sound_id, buffer);
+            }
+        }
+        Ok(())
+    }
+    /// Get the number of sounds that have been loaded
+    pub fn loaded(&self) -> usize {
+        self.loaded
+    }
+    /// Get the total number of sounds being loaded
+    pub fn total(&self) -> usize {
+        self.total
+    }
+    /// Get the loading progress from `0.0` to `1.0`
+    pub fn progress(&self) -> f32 {
+        if self.total == 0 {
+            1.0
+        } else {
+            self.loaded as f32 / self.total as f32
+        }
+    }
+    /// Check if all sounds have been loaded
+    pub fn is_done(&self) -> bool {
+        self.loaded == self.total
+    }
+}
+
 /// A streamed-in buffer of audio samples
 #[derive(Debug, Clone)]
 pub struct SoundBuffer {
     samples: Arc<Mutex<Vec<f32>>>,
     sample_rate: u32,
     channels: u16,
+    duration: Option<Duration>,
     done: Arc<AtomicBool>,
 }
 
@@ -113,6 +816,7 @@ impl SoundBuffer {
     /// Load the sound from raw audio data
     pub fn from_raw(samples: Vec<f32>, sample_rate: u32, channels: u16) -> Self {
         SoundBuffer {
+            duration: Some(samples_duration(samples.len(), sample_rate, channels)),
             samples: Arc::new(Mutex::new(samples)),
             sample_rate,
             channels,
@@ -124,15 +828,23 @@ impl SoundBuffer {
 
     The samples are streamed into the buffer as they are decoded
 
-    Supports MP3, WAV, Vorbis and Flac
+    Supports MP3, WAV, Vorbis and Flac. Opus is supported with the `opus` feature,
+    and AAC/M4A is supported with the `aac` feature.
     */
     pub fn decode<T>(bytes: T) -> Result<Self, DecoderError>
     where
         T: AsRef<[u8]> + Send + 'static,
     {
+        #[cfg(any(feature = "opus", feature = "aac"))]
+        let decoder = match crate::codec::decoder(bytes.as_ref()) {
+            Some(decoder) => decoder?,
+            None => crate::codec::CodecDecoder::from(Decoder::new(Cursor::new(bytes))?),
+        };
+        #[cfg(not(any(feature = "opus", feature = "aac")))]
         let decoder = Decoder::new(Cursor::new(bytes))?;
         let sample_rate = decoder.sample_rate();
         let channels = decoder.channels();
+        let duration = decoder.total_duration();
         let samples = Arc::new(Mutex::new(Vec::new()));
         let samples_clone = Arc::clone(&samples);
         let done = Arc::new(AtomicBool::new(false));
@@ -147,6 +859,7 @@ impl SoundBuffer {
             samples,
             sample_rate,
             channels,
+            duration,
             done,
         })
     }
@@ -154,6 +867,47 @@ impl SoundBuffer {
     pub fn finished_decoding(&self) -> bool {
         self.done.load(Ordering::Relaxed)
     }
+    /**
+    Get the duration of the sound
+
+    If the sound is still being decoded, the duration is taken from the
+    encoded data's metadata. Returns `None` if the metadata does not contain
+    a duration and decoding is not finished.
+    */
+    pub fn duration(&self) -> Option<Duration> {
+        self.duration.or_else(|| {
+            if self.finished_decoding() {
+                Some(samples_duration(
+                    self.samples.lock().unwrap().len(),
+                    self.sample_rate,
+                    self.channels,
+                ))
+            } else {
+                None
+            }
+        })
+    }
+    #[cfg(any(test, feature = "capture"))]
+    pub(crate) fn streaming(sample_rate: u32, channels: u16) -> Self {
+        SoundBuffer {
+            samples: Arc::new(Mutex::new(Vec::new())),
+            sample_rate,
+            channels,
+            duration: None,
+            done: Arc::new(AtomicBool::new(false)),
+        }
+    }
+    #[cfg(any(test, feature = "capture"))]
+    pub(crate) fn extend<I>(&self, samples: I)
+    where
+        I: IntoIterator<Item = f32>,
+    {
+        self.samples.lock().unwrap().extend(samples);
+    }
+    #[cfg(any(test, feature = "capture"))]
+    pub(crate) fn finish(&self) {
+        self.done.store(true, Ordering::Relaxed);
+    }
 }
 
 /// A playable handle to a `SoundBuffer`
@@ -161,31 +915,90 @@ impl SoundBuffer {
 pub struct SoundSource {
     buffer: Arc<SoundBuffer>,
     i: usize,
+    silence: u16,
 }
 
 impl From<Arc<SoundBuffer>> for SoundSource {
     fn from(buffer: Arc<SoundBuffer>) -> Self {
-        SoundSource { buffer, i: 0 }
+        SoundSource {
+            buffer,
+            i: 0,
+            silence: 0,
+        }
+    }
+}
+
+impl SoundSource {
+    /**
+    Seek to some position in the sound
+
+    Seeking past the samples that have been decoded so far is allowed.
+    Playback will continue once decoding catches up.
+    */
+    pub fn seek(&mut self, position: Duration) {
+        self.i = samples_in(position, self.buffer.sample_rate, self.buffer.channels);
+        self.silence = 0;
     }
+    /// Get the current position in the sound
+    pub fn position(&self) -> Duration {
+        samples_duration(self.i, self.buffer.sample_rate, self.buffer.channels)
+    }
+}
+
+fn samples_in(duration: Duration, sample_rate: u32, channels: u16) -> usize {
+    let frames = (duration.as_secs_f64() * sample_rate as f64) as usize;
+    frames * channels.max(1) as usize
+}
+
+fn samples_duration(samples: usize, sample_rate: u32, channels: u16) -> Duration {
+    let frames = samples / channels.max(1) as usize;
+    Duration::from_secs_f64(frames as f64 / sample_rate.max(1) as f64)
 }
 
 impl Iterator for SoundSource {
     type Item = f32;
     fn next(&mut self) -> Option<Self::Item> {
-        if let Some(sample) = self.buffer.samples.lock().unwrap().get(self.i).copied() {
+        if self.silence > 0 {
+            self.silence -= 1;
+            return Some(0.0);
+        }
+        let channels = self.buffer.channels.max(1);
+        // Check this before looking at the samples so that no samples are missed
+        let done = self.buffer.finished_decoding();
+        let samples = self.buffer.samples.lock().unwrap();
+        // If decoding has not caught up, play a whole frame of silence so that channels stay aligned
+        let frame_end = self.i - self.i % channels as usize + channels as usize;
+        if !done && samples.len() < frame_end {
+            self.silence = channels - 1;
+            return Some(0.0);
+        }
+        let sample = samples.get(self.i).copied();
+        if sample.is_some() {
             self.i += 1;
-            Some(sample)
-        } else if Arc::strong_count(&self.buffer.samples) > 1 {
-            Some(0.0)
-        } else {
-            None
         }
+        sample
     }
 }
 
 impl Source for SoundSource {
     fn current_frame_len(&self) -> Option<usize> {
-        Some(self.buffer.samples.lock().unwrap().len() - self.i)
+        if self.silence > 0 {
+            return Some(self.silence as usize);
+        }
+        let channels = self.buffer.channels.max(1) as usize;
+        let done = self.buffer.finished_decoding();
+        let len = self.buffer.samples.lock().unwrap().len();
+        Some(if done {
+            len.saturating_sub(self.i)
+        } else {
+            // Only whole frames that have been decoded can be played without interruption
+            let end = len - len % channels;
+            if end > self.i {
+                end - self.i
+            } else {
+                channels - self.i % channels
+            }
+        })
     }
     fn channels(&self) -> u16 {
         self.buffer.channels
@@ -194,15 +1007,21 @@ impl Source for SoundSource {
         self.buffer.sample_rate
     }
     fn total_duration(&self) -> Option<Duration> {
-        None
+        self.buffer.duration()
     }
 }
 
-/// A volume controller
+/**
+A volume controller
+
+Changes to the volume and mute state are ramped over a short duration
+to avoid audible clicks
+*/
 #[derive(Debug, Clone)]
 pub struct VolumeControl {
     mute: Arc<AtomicCell<bool>>,
     volume: Arc<AtomicCell<f32>>,
+    ramp: Arc<AtomicCell<f32>>,
 }
 
 impl Default for VolumeControl {
@@ -210,10 +1029,14 @@ impl Default for VolumeControl {
         VolumeControl {
             mute: Arc::new(AtomicCell::new(false)),
             volume: Arc::new(AtomicCell::new(1.0)),
+            ramp: Arc::new(AtomicCell::new(DEFAULT_VOLUME_RAMP)),
         }
     }
 }
 
+/// The default volume ramp duration in seconds
+const DEFAULT_VOLUME_RAMP: f32 = 0.01;
+
 impl VolumeControl {
     /// Use this volume to control a source
     pub(crate) fn control<S>(&self, source: S) -> VolumeControlSource<S> {
@@ -221,6 +1044,8 @@ impl VolumeControl {
             source,
             mute: self.mute.clone(),
             volume: self.volume.clone(),
+            ramp: self.ramp.clone(),
+            current: self.target(),
         }
     }
     /// Get the mute state
@@ -239,27 +1064,212 @@ impl VolumeControl {
     pub fn set_volume(&self, volume: f32) {
         self.volume.store(volume);
     }
+    /// Get the duration over which volume changes are ramped
+    pub fn ramp(&self) -> Duration {
+        Duration::from_secs_f32(self.ramp.load())
+    }
+    /**
+    Set the duration over which volume changes are ramped
+
+    This is the time it takes to ramp between a volume of `0.0` and `1.0`.
+    A ramp of zero applies changes immediately.
+    */
+    pub fn set_ramp(&self, ramp: Duration) {
+        self.ramp.store(ramp.as_secs_f32());
+    }
+    fn target(&self) -> f32 {
+        if self.mute.load() {
+            0.0
+        } else {
+            self.volume.load()
+        }
+    }
+}
+
+/**
+A limiter that keeps the master output from clipping
+
+When the output would exceed the threshold, its gain is reduced immediately,
+then smoothly released once the output is quiet again.
+
+The limiter is enabled by default with a threshold of `1.0`
+*/
+#[derive(Debug, Clone)]
+pub struct Limiter {
+    enabled: Arc<AtomicCell<bool>>,
+    threshold: Arc<AtomicCell<f32>>,
+}
+
+impl Default for Limiter {
+    fn default() -> Self {
+        Limiter {
+            enabled: Arc::new(AtomicCell::new(true)),
+            threshold: Arc::new(AtomicCell::new(1.0)),
+        }
+    }
+}
+
+impl Limiter {
+    fn control<S>(&self, source: S) -> LimiterSource<S> {
+        LimiterSource {
+            source,
+            enabled: self.enabled.clone(),
+            threshold: self.threshold.clone(),
+            gain: 1.0,
+        }
+    }
+    /// Check if the limiter is enabled
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load()
+    }
+    /// Enable or disable the limiter
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled);
+    }
+    /// Get the threshold
+    pub fn threshold(&self) -> f32 {
+        self.threshold.load()
+    }
+    /// Set the threshold
+    ///
+    /// The output will not exceed this amplitude
+    pub fn set_threshold(&self, threshold: f32) {
+        self.threshold.store(threshold.max(0.0));
+    }
+}
+
+/// The amount the limiter's gain recovers each sample
+const LIMITER_RELEASE: f32 = 0.0001;
+
+struct LimiterSource<T> {
+    source: T,
+    enabled: Arc<AtomicCell<bool>>,
+    threshold: Arc<AtomicCell<f32>>,
+    gain: f32,
+}
+
+impl<T> Iterator for LimiterSource<T>
+where
+    T: Iterator<Item = f32>,
+{
+    type Item = f32;
+    fn next(&mut self) -> Option<Self::Item> {
+        let sample = self.source.next()?;
+        if !self.enabled.load() {
+            self.gain = 1.0;
+            return Some(sample);
+        }
+        let threshold = self.threshold.load();
+        let level = sample.abs();
+        if level * self.gain > threshold {
+            self.gain = threshold / level;
+        } else {
+            self.gain = (self.gain + (1.0 - self.gain) * LIMITER_RELEASE).min(1.0);
+        }
+        Some(sample * self.gain)
+    }
+}
+
+impl<T> Source for LimiterSource<T>
+where
+    T: Source<Item = f32>,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        self.source.current_frame_len()
+    }
+    fn sample_rate(&self) -> u32 {
+        self.source.sample_rate()
+    }
+    fn channels(&self) -> u16 {
+        self.source.channels()
+    }
+    fn total_duration(&self) -> Option<Duration> {
+        self.source.total_duration()
+    }
+}
+
+struct PauseSource<T> {
+    source: T,
+    paused: Arc<AtomicCell<bool>>,
+    focus_paused: Arc<AtomicCell<bool>>,
+    /// Whether the current channel frame is silenced
+    silent: bool,
+    /// The channel of the next sample
+    channel: u16,
+}
+
+impl<T> Iterator for PauseSource<T>
+where
+    T: Source<Item = f32>,
+{
+    type Item = f32;
+    fn next(&mut self) -> Option<Self::Item> {
+        // Pausing only takes effect between channel frames so that channels stay in order
+        if self.channel == 0 {
+            self.silent = self.paused.load() || self.focus_paused.load();
+        }
+        self.channel = (self.channel + 1) % self.source.channels().max(1);
+        if self.silent {
+            Some(0.0)
+        } else {
+            self.source.next()
+        }
+    }
+}
+
+impl<T> Source for PauseSource<T>
+where
+    T: Source<Item = f32>,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        self.source.current_frame_len()
+    }
+    fn sample_rate(&self) -> u32 {
+        self.source.sample_rate()
+    }
+    fn channels(&self) -> u16 {
+        self.source.channels()
+    }
+    fn total_duration(&self) -> Option<Duration> {
+        self.source.total_duration()
+    }
 }
 
 pub(crate) struct VolumeControlSource<T> {
     source: T,
     mute: Arc<AtomicCell<bool>>,
     volume: Arc<AtomicCell<f32>>,
+    ramp: Arc<AtomicCell<f32>>,
+    current: f32,
 }
 
 impl<T> Iterator for VolumeControlSource<T>
 where
-    T: Iterator<Item = f32>,
+    T: Source<Item = f32>,
 {
     type Item = f32;
     fn next(&mut self) -> Option<Self::Item> {
-        self.source.next().map(|s| {
-            if self.mute.load() {
-                0.0
+        let sample = self.source.next()?;
+        let target = if self.mute.load() {
+            0.0
+        } else {
+            self.volume.load()
+        };
+        if self.current != target {
+            let samples =
+                self.ramp.load() * self.source.sample_rate() as f32 * self.source.channels() as f32;
+            if samples >= 1.0 {
+                let step = 1.0 / samples;
+                self.current = if self.current < target {
+                    (self.current + step).min(target)
+                } else {
+                    (self.current - step).max(target)
+                };
             } else {
-                s * self.volume.load()
+                self.current = target;
             }
-        })
+        }
+        Some(sample * self.current)
     }
 }
 
@@ -280,3 +1290,71 @@ where
         self.source.total_duration()
     }
 }
+
+#[cfg(test)]
+#[test]
+fn sound_source_end() {
+    let buffer = Arc::new(SoundBuffer::streaming(44100, 2));
+    let mut source = SoundSource::from(buffer.clone());
+    buffer.extend(vec![1.0, 2.0, 3.0]);
+    assert_eq!(source.next(), Some(1.0));
+    assert_eq!(source.next(), Some(2.0));
+    assert_eq!(source.next(), Some(0.0));
+    assert_eq!(source.next(), Some(0.0));
+    buffer.extend(vec![4.0]);
+    buffer.finish();
+    assert_eq!(source.collect::<Vec<_>>(), vec![3.0, 4.0]);
+}
+
+#[cfg(test)]
+#[test]
+fn start_at_does_not_pull_samples() {
+    // A source that has no samples yet, like a stream that is still loading
+    struct Pending;
+    impl Iterator for Pending {
+        type Item = f32;
+        fn next(&mut self) -> Option<f32> {
+            panic!("samples were pulled on the calling thread")
+        }
+    }
+    impl Source for Pending {
+        fn current_frame_len(&self) -> Option<usize> {
+            None
+        }
+        fn sample_rate(&self) -> u32 {
+            44100
+        }
+        fn channels(&self) -> u16 {
+            2
+        }
+        fn total_duration(&self) -> Option<Duration> {
+            None
+        }
+    }
+    let mixer = Mixer::new(false).unwrap();
+    let start_at = Duration::from_secs(1);
+    let handle = mixer.play_with(Pending, PlayOptions::new().start_at(start_at));
+    assert_eq!(handle.position(), start_at);
+    let handle = mixer.play_with(
+        SoundSource::from(Arc::new(SoundBuffer::streaming(44100, 2))),
+        PlayOptions::new().start_at(start_at),
+    );
+    assert_eq!(handle.position(), start_at);
+}
+
+#[cfg(test)]
+#[test]
+fn pending_fade_keeps_start() {
+    let mut mixer = Mixer::new(false).unwrap();
+    let track = mixer.play_layered_music(
+        Some(Zero::<f32>::new(2, 44100)),
+        1.0,
+        Duration::from_secs(1),
+    );
+    // The fade in has not started, so the new fade still starts from silence
+    track.set_intensity(0.5, Duration::from_secs(2));
+    let fade = track.layers()[0].playback.fade.load().unwrap();
+    assert_eq!(fade.from, Some(0.0));
+    assert_eq!(fade.to, 0.5);
+    assert_eq!(fade.duration, Duration::from_secs(2));
+}
@@ -0,0 +1,279 @@
+use std::{
+    collections::{HashMap, HashSet},
+    hash::Hash,
+};
+
+use vector2math::*;
+
+use crate::{circle_rect, ray_rect, Circ, Rect, Vec2};
+
+type Cell = [i32; 2];
+
+/**
+A spatial hash for broad-phase queries
+
+Ids are inserted with bounding rectangles and are bucketed into a grid of square cells.
+Queries only check the ids in the cells they touch, so they stay fast as the number of
+ids grows. The cell size should be around the size of a typical object.
+
+Moving an object is done by inserting its id again with the new bounds.
+
+```
+# use kule::*;
+let mut hash = SpatialHash::new(10.0);
+hash.insert(1, [0.0, 0.0, 5.0, 5.0]);
+hash.insert(2, [50.0, 0.0, 5.0, 5.0]);
+assert_eq!(hash.query_rect([-1.0, -1.0, 10.0, 10.0]), vec![1]);
+assert_eq!(hash.query_circle(([52.0, 2.0], 1.0)), vec![2]);
+let hits = hash.query_ray([-10.0, 2.0], [1.0, 0.0], 100.0);
+assert_eq!(hits, vec![(1, 10.0), (2, 60.0)]);
+```
+*/
+#[derive(Debug, Clone)]
+pub struct SpatialHash<T> {
+    cell_size: f32,
+    cells: HashMap<Cell, Vec<T>>,
+    bounds: HashMap<T, Rect>,
+}
+
+impl<T> SpatialHash<T>
+where
+    T: Copy + Eq + Hash,
+{
+    /**
+    Create a new `SpatialHash` with the given cell size
+
+    # Panics
+
+    Panics if the cell size is not positive
+    */
+    pub fn new(cell_size: f32) -> Self {
+        assert!(cell_size > 0.0, "Spatial hash cell size must be positive");
+        SpatialHash {
+            cell_size,
+            cells: HashMap::new(),
+            bounds: HashMap::new(),
+        }
+    }
+    /// Get the cell size
+    pub fn cell_size(&self) -> f32 {
+        self.cell_size
+    }
+    /// Get the number of ids
+    pub fn len(&self) -> usize {
+        self.bounds.len()
+    }
+    /// Check if there are no ids
+    pub fn is_empty(&self) -> bool {
+        self.bounds.is_empty()
+    }
+    /// Remove all ids
+    pub fn clear(&mut self) {
+        self.cells.clear();
+        self.bounds.clear();
+    }
+    /// Get the bounds of an id
+    pub fn bounds(&self, id: T) -> Option<Rect> {
+        self.bounds.get(&id).copied()
+    }
+    /// Iterate over all ids and their bounds
+    pub fn iter(&self) -> impl Iterator<Item = (T, Rect)> + '_ {
+        self.bounds.iter().map(|(id, rect)| (*id, *rect))
+    }
+    fn cell(&self, pos: Vec2) -> Cell {
+        [
+            (pos.x() / self.cell_size).floor() as i32,
+            (pos.y() / self.cell_size).floor() as i32,
+        ]
+    }
+    fn cells_in(&self, rect: Rect) -> impl Iterator<Item = Cell> {
+        let [x0, y0] = self.cell(rect.abs_top_left());
+        let [x1, y1] = self.cell(rect.abs_bottom_right());
+        (x0..=x1).flat_map(move |x| (y0..=y1).map(move |y| [x, y]))
+    }
+    /**
+    Insert an id with some bounds
+
+    If the id is already present, its bounds are updated.
+    Only the cells that the id enters or leaves are touched.
+    */
+    pub fn insert<R>(&mut self, id: T, bounds: R)
+    where
+        R: Rectangle<Scalar = f32>,
+    {
+        let bounds: Rect = bounds.map();
+        if let Some(old) = self.bounds.insert(id, bounds) {
+            if self.cell(old.abs_top_left()) == self.cell(bounds.abs_top_left())
+                && self.cell(old.abs_bottom_right()) == self.cell(bounds.abs_bottom_right())
+            {
+                return;
+            }
+            self.remove_from_cells(id, old);
+        }
+        for cell in self.cells_in(bounds).collect::<Vec<_>>() {
+            self.cells.entry(cell).or_default().push(id);
+        }
+    }
+    /// Remove an id, returning its bounds
+    pub fn remove(&mut self, id: T) -> Option<Rect> {
+        let bounds = self.bounds.remove(&id)?;
+        self.remove_from_cells(id, bounds);
+        Some(bounds)
+    }
+    fn remove_from_cells(&mut self, id: T, bounds: Rect) {
+        for cell in self.cells_in(bounds).collect::<Vec<_>>() {
+            if let Some(ids) = self.cells.get_mut(&cell) {
+                ids.retain(|&other| other != id);
+                if ids.is_empty() {
+                    self.cells.remove(&cell);
+                }
+            }
+        }
+    }
+    /// Clear the hash and insert new ids
+    pub fn rebuild<I, R>(&mut self, items: I)
+    where
+        I: IntoIterator<Item = (T, R)>,
+        R: Rectangle<Scalar = f32>,
+    {
+        self.clear();
+        for (id, bounds) in items {
+            self.insert(id, bounds);
+        }
+    }
+    fn candidates(&self, rect: Rect) -> impl Iterator<Item = (T, Rect)> + '_ {
+        let mut seen = HashSet::new();
+        self.cells_in(rect)
+            .filter_map(move |cell| self.cells.get(&cell))
+            .flatten()
+            .filter(move |id| seen.insert(**id))
+            .map(move |id| (*id, self.bounds[id]))
+    }
+    /// Get the ids whose bounds overlap a rectangle
+    pub fn query_rect<R>(&self, rect: R) -> Vec<T>
+    where
+        R: Rectangle<Scalar = f32>,
+    {
+        let rect: Rect = rect.map();
+        self.candidates(rect)
+            .filter(|(_, bounds)| {
+                bounds.abs_left() <= rect.abs_right()
+                    && rect.abs_left() <= bounds.abs_right()
+                    && bounds.abs_top() <= rect.abs_bottom()
+                    && rect.abs_top() <= bounds.abs_bottom()
+            })
+            .map(|(id, _)| id)
+            .collect()
+    }
+    /// Get the ids whose bounds overlap a circle
+    pub fn query_circle<C>(&self, circ: C) -> Vec<T>
+    where
+        C: Circle<Scalar = f32>,
+    {
+        let circ: Circ = circ.map();
+        self.candidates(circ.to_square())
+            .filter(|(_, bounds)| circle_rect(circ, *bounds).is_some())
+            .map(|(id, _)| id)
+            .collect()
+    }
+    /// Get the ids whose bounds contain a point
+    pub fn query_point(&self, pos: Vec2) -> Vec<T> {
+        self.query_rect([pos.x(), pos.y(), 0.0, 0.0])
+    }
+    /**
+    Get the ids whose bounds a ray passes through, along with the distance to each
+
+    Results are sorted by distance. Only ids within `max_dist` of the origin are returned.
+    `max_dist` may be infinite, in which case the search stops once the ray leaves the
+    occupied cells.
+    */
+    pub fn query_ray(&self, origin: Vec2, dir: Vec2, max_dist: f32) -> Vec<(T, f32)> {
+        let dir = dir.unit();
+        if dir.x().is_nan() {
+            return Vec::new();
+        }
+        // The ray can stop once it leaves the range of occupied cells
+        let (min, max) = match self
+            .cells
+            .keys()
+            .fold(None, |range: Option<(Cell, Cell)>, &cell| match range {
+                None => Some((cell, cell)),
+                Some(([min_x, min_y], [max_x, max_y])) => Some((
+                    [min_x.min(cell[0]), min_y.min(cell[1])],
+                    [max_x.max(cell[0]), max_y.max(cell[1])],
+                )),
+            }) {
+            Some(range) => range,
+            None => return Vec::new(),
+        };
+        // Walk the cells along the ray
+        let mut cell = self.cell(origin);
+        let step = [dir.x().signum() as i32, dir.y().signum() as i32];
+        let mut t_max = [0.0f32; 2];
+        let mut t_delta = [f32::INFINITY; 2];
+        for axis in 0..2 {
+            if dir[axis] != 0.0 {
+                let next = (cell[axis] + (step[axis] > 0) as i32) as f32 * self.cell_size;
+                t_max[axis] = (next - origin[axis]) / dir[axis];
+                t_delta[axis] = self.cell_size / dir[axis].abs();
+            } else {
+                t_max[axis] = f32::INFINITY;
+            }
+        }
+        let mut seen = HashSet::new();
+        let mut hits = Vec::new();
+        loop {
+            let left = (0..2).any(|axis| {
+                step[axis] >= 0 && cell[axis] > max[axis]
+                    || step[axis] <= 0 && cell[axis] < min[axis]
+            });
+            if left {
+                break;
+            }
+            for &id in self.cells.get(&cell).into_iter().flatten() {
+                if seen.insert(id) {
+                    if let Some(dist) = ray_rect(origin, dir, self.bounds[&id]) {
+                        if dist <= max_dist {
+                            hits.push((id, dist));
+                        }
+                    }
+                }
+            }
+            let axis = if t_max[0] < t_max[1] { 0 } else { 1 };
+            if t_max[axis] > max_dist {
+                break;
+            }
+            cell[axis] += step[axis];
+            t_max[axis] += t_delta[axis];
+        }
+        hits.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+        hits
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn spatial_hash_update() {
+    let mut hash = SpatialHash::new(4.0);
+    hash.insert('a', [-6.0, -6.0, 3.0, 3.0]);
+    hash.insert('b', [1.0, 1.0, 10.0, 1.0]);
+    assert_eq!(hash.query_point([10.0, 1.5]), vec!['b']);
+    hash.insert('b', [-5.0, -5.0, 1.0, 1.0]);
+    assert!(hash.query_point([10.0, 1.5]).is_empty());
+    let mut found = hash.query_rect([-6.0, -6.0, 2.0, 2.0]);
+    found.sort();
+    assert_eq!(found, vec!['a', 'b']);
+    assert_eq!(hash.remove('a'), Some([-6.0, -6.0, 3.0, 3.0]));
+    assert_eq!(hash.len(), 1);
+    assert_eq!(
+        hash.query_ray([-4.5, 0.0], [0.0, -1.0], 10.0),
+        vec![('b', 4.0)]
+    );
+    assert_eq!(
+        hash.query_ray([-4.5, 0.0], [0.0, -1.0], f32::INFINITY),
+        vec![('b', 4.0)]
+    );
+    assert!(hash
+        .query_ray([-4.5, 0.0], [1.0, 0.0], f32::INFINITY)
+        .is_empty());
+}
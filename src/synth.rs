@@ -0,0 +1,248 @@
+use std::{f32::consts::PI, time::Duration};
+
+use crate::Source;
+
+/// The sample rate used by synthesized sounds
+pub const SYNTH_SAMPLE_RATE: u32 = 44100;
+
+/// The shape of an oscillator's wave
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Waveform {
+    /// A sine wave
+    Sine,
+    /// A square wave
+    Square,
+    /// A sawtooth wave
+    Saw,
+    /// A triangle wave
+    Triangle,
+    /// White noise
+    Noise,
+}
+
+/**
+An attack-decay-sustain-release envelope
+
+The attack, decay, and sustain phases happen while the note is held.
+The release phase begins when the note is released.
+*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Adsr {
+    /// The time it takes to go from silence to full amplitude
+    pub attack: Duration,
+    /// The time it takes to go from full amplitude to the sustain level
+    pub decay: Duration,
+    /// The amplitude level held until the note is released
+    pub sustain: f32,
+    /// The time it takes to go from the sustain level to silence
+    pub release: Duration,
+}
+
+impl Default for Adsr {
+    fn default() -> Self {
+        Adsr::new(
+            Duration::from_millis(5),
+            Duration::from_millis(0),
+            1.0,
+            Duration::from_millis(5),
+        )
+    }
+}
+
+impl Adsr {
+    /// Create a new `Adsr`
+    pub const fn new(attack: Duration, decay: Duration, sustain: f32, release: Duration) -> Self {
+        Adsr {
+            attack,
+            decay,
+            sustain,
+            release,
+        }
+    }
+    /// Get the envelope's amplitude at some time after the note starts
+    ///
+    /// `held` is how long the note is held before being released
+    pub fn amplitude(&self, t: f32, held: f32) -> f32 {
+        let attack = self.attack.as_secs_f32();
+        let decay = self.decay.as_secs_f32();
+        let release = self.release.as_secs_f32();
+        let held_amp = |t: f32| {
+            if t < attack {
+                t / attack
+            } else if t < attack + decay {
+                1.0 + (self.sustain - 1.0) * (t - attack) / decay
+            } else {
+                self.sustain
+            }
+        };
+        if t < held {
+            held_amp(t)
+        } else if t < held + release {
+            held_amp(held) * (1.0 - (t - held) / release)
+        } else {
+            0.0
+        }
+    }
+}
+
+/**
+A builder for procedurally synthesized sounds
+
+```
+# use kule::*;
+# use std::time::Duration;
+let source = Synth::new(Waveform::Square, 440.0)
+    .amplitude(0.3)
+    .duration(Duration::from_millis(200))
+    .source();
+```
+*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Synth {
+    /// The oscillator waveform
+    pub waveform: Waveform,
+    /// The frequency in Hz
+    pub frequency: f32,
+    /// The peak amplitude
+    pub amplitude: f32,
+    /// How long the note is held before being released
+    pub duration: Duration,
+    /// The amplitude envelope
+    pub envelope: Adsr,
+    /// The seed used for noise
+    pub seed: u32,
+}
+
+impl Synth {
+    /// Create a new `Synth` with a waveform and frequency
+    pub fn new(waveform: Waveform, frequency: f32) -> Self {
+        Synth {
+            waveform,
+            frequency,
+            amplitude: 1.0,
+            duration: Duration::from_millis(500),
+            envelope: Adsr::default(),
+            seed: 0x9E37_79B9,
+        }
+    }
+    /// Set the peak amplitude
+    pub fn amplitude(self, amplitude: f32) -> Self {
+        Synth { amplitude, ..self }
+    }
+    /// Set how long the note is held before being released
+    pub fn duration(self, duration: Duration) -> Self {
+        Synth { duration, ..self }
+    }
+    /// Set the amplitude envelope
+    pub fn envelope(self, envelope: Adsr) -> Self {
+        Synth { envelope, ..self }
+    }
+    /// Set the seed used for noise
+    pub fn seed(self, seed: u32) -> Self {
+        Synth {
+            seed: seed.max(1),
+            ..self
+        }
+    }
+    /// Get the total length of the sound, including the release
+    pub fn total_duration(&self) -> Duration {
+        self.duration + self.envelope.release
+    }
+    /// Create a playable source
+    pub fn source(self) -> SynthSource {
+        SynthSource {
+            total: (self.total_duration().as_secs_f32() * SYNTH_SAMPLE_RATE as f32) as usize,
+            rng: self.seed.max(1),
+            synth: self,
+            i: 0,
+        }
+    }
+}
+
+/// A playable synthesized sound
+#[derive(Debug, Clone)]
+pub struct SynthSource {
+    synth: Synth,
+    i: usize,
+    total: usize,
+    rng: u32,
+}
+
+impl SynthSource {
+    fn noise(&mut self) -> f32 {
+        // xorshift32
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 17;
+        self.rng ^= self.rng << 5;
+        self.rng as f32 / u32::MAX as f32 * 2.0 - 1.0
+    }
+}
+
+impl Iterator for SynthSource {
+    type Item = f32;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.i >= self.total {
+            return None;
+        }
+        let t = self.i as f32 / SYNTH_SAMPLE_RATE as f32;
+        self.i += 1;
+        let phase = (t * self.synth.frequency).fract();
+        let wave = match self.synth.waveform {
+            Waveform::Sine => (phase * 2.0 * PI).sin(),
+            Waveform::Square => {
+                if phase < 0.5 {
+                    1.0
+                } else {
+                    -1.0
+                }
+            }
+            Waveform::Saw => phase * 2.0 - 1.0,
+            Waveform::Triangle => 1.0 - (phase * 4.0 - 2.0).abs(),
+            Waveform::Noise => self.noise(),
+        };
+        let envelope = self
+            .synth
+            .envelope
+            .amplitude(t, self.synth.duration.as_secs_f32());
+        Some(wave * envelope * self.synth.amplitude)
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.total - self.i;
+        (remaining, Some(remaining))
+    }
+}
+
+impl Source for SynthSource {
+    fn current_frame_len(&self) -> Option<usize> {
+        Some(self.total - self.i)
+    }
+    fn channels(&self) -> u16 {
+        1
+    }
+    fn sample_rate(&self) -> u32 {
+        SYNTH_SAMPLE_RATE
+    }
+    fn total_duration(&self) -> Option<Duration> {
+        Some(self.synth.total_duration())
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn synth_envelope() {
+    let adsr = Adsr::new(
+        Duration::from_millis(100),
+        Duration::from_millis(100),
+        0.5,
+        Duration::from_millis(100),
+    );
+    assert_eq!(adsr.amplitude(0.0, 1.0), 0.0);
+    assert!((adsr.amplitude(0.1, 1.0) - 1.0).abs() < 1e-5);
+    assert!((adsr.amplitude(0.5, 1.0) - 0.5).abs() < 1e-5);
+    assert!((adsr.amplitude(1.05, 1.0) - 0.25).abs() < 1e-5);
+    assert_eq!(adsr.amplitude(2.0, 1.0), 0.0);
+    let synth = Synth::new(Waveform::Sine, 440.0)
+        .duration(Duration::from_millis(100))
+        .envelope(adsr);
+    assert_eq!(synth.source().count(), SYNTH_SAMPLE_RATE as usize / 5);
+}
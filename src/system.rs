@@ -0,0 +1,390 @@
+use std::{
+    any::{type_name, Any, TypeId},
+    collections::HashMap,
+    fmt,
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+    panic,
+    sync::{RwLock, RwLockReadGuard, RwLockWriteGuard},
+    thread,
+};
+
+use crate::{CanFail, KuleError};
+
+type Data = Box<dyn Any + Send + Sync>;
+
+/**
+Shared data that systems read and write
+
+Each piece of data is identified by its type
+*/
+#[derive(Default)]
+pub struct SystemData {
+    data: HashMap<TypeId, RwLock<Data>>,
+}
+
+impl SystemData {
+    /// Create a new empty `SystemData`
+    pub fn new() -> Self {
+        SystemData::default()
+    }
+    /// Insert some data, replacing any data of the same type
+    pub fn insert<T>(&mut self, value: T)
+    where
+        T: Send + Sync + 'static,
+    {
+        self.data
+            .insert(TypeId::of::<T>(), RwLock::new(Box::new(value)));
+    }
+    /// Remove some data
+    pub fn remove<T>(&mut self) -> Option<T>
+    where
+        T: Send + Sync + 'static,
+    {
+        self.data
+            .remove(&TypeId::of::<T>())
+            .and_then(|lock| lock.into_inner().ok())
+            .and_then(|data| data.downcast().ok())
+            .map(|data| *data)
+    }
+    /// Get a reference to some data
+    pub fn get<T>(&self) -> Option<DataRef<'_, T>>
+    where
+        T: Send + Sync + 'static,
+    {
+        self.data.get(&TypeId::of::<T>()).map(|lock| DataRef {
+            guard: lock.read().unwrap(),
+            pd: PhantomData,
+        })
+    }
+    /// Get a mutable reference to some data
+    pub fn get_mut<T>(&mut self) -> Option<&mut T>
+    where
+        T: Send + Sync + 'static,
+    {
+        self.data
+            .get_mut(&TypeId::of::<T>())
+            .and_then(|lock| lock.get_mut().ok())
+            .and_then(|data| data.downcast_mut())
+    }
+}
+
+/// A read lock on system data
+pub struct DataRef<'a, T> {
+    guard: RwLockReadGuard<'a, Data>,
+    pd: PhantomData<T>,
+}
+
+impl<'a, T> Deref for DataRef<'a, T>
+where
+    T: 'static,
+{
+    type Target = T;
+    fn deref(&self) -> &Self::Target {
+        self.guard.downcast_ref().unwrap()
+    }
+}
+
+/// A write lock on system data
+pub struct DataMut<'a, T> {
+    guard: RwLockWriteGuard<'a, Data>,
+    pd: PhantomData<T>,
+}
+
+impl<'a, T> Deref for DataMut<'a, T>
+where
+    T: 'static,
+{
+    type Target = T;
+    fn deref(&self) -> &Self::Target {
+        self.guard.downcast_ref().unwrap()
+    }
+}
+
+impl<'a, T> DerefMut for DataMut<'a, T>
+where
+    T: 'static,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.guard.downcast_mut().unwrap()
+    }
+}
+
+/**
+A system's view of the shared data
+
+Only data that the system declared access to can be accessed
+*/
+pub struct SystemContext<'a> {
+    data: &'a SystemData,
+    system: &'a System,
+    /// The time since the last run
+    pub dt: f32,
+}
+
+impl<'a> SystemContext<'a> {
+    fn lock<T>(&self) -> &'a RwLock<Data>
+    where
+        T: 'static,
+    {
+        self.data.data.get(&TypeId::of::<T>()).unwrap_or_else(|| {
+            panic!(
+                "System {:?} accessed missing data {}",
+                self.system.name,
+                type_name::<T>()
+            )
+        })
+    }
+    /**
+    Read some data
+
+    # Panics
+
+    Panics if the system did not declare that it reads or writes the data,
+    or if the data is missing
+    */
+    pub fn read<T>(&self) -> DataRef<'a, T>
+    where
+        T: Send + Sync + 'static,
+    {
+        let id = TypeId::of::<T>();
+        assert!(
+            self.system.reads.contains(&id) || self.system.writes.contains(&id),
+            "System {:?} did not declare that it reads {}",
+            self.system.name,
+            type_name::<T>()
+        );
+        DataRef {
+            guard: self.lock::<T>().read().unwrap(),
+            pd: PhantomData,
+        }
+    }
+    /**
+    Write some data
+
+    # Panics
+
+    Panics if the system did not declare that it writes the data, or if the data is missing
+    */
+    pub fn write<T>(&self) -> DataMut<'a, T>
+    where
+        T: Send + Sync + 'static,
+    {
+        assert!(
+            self.system.writes.contains(&TypeId::of::<T>()),
+            "System {:?} did not declare that it writes {}",
+            self.system.name,
+            type_name::<T>()
+        );
+        DataMut {
+            guard: self.lock::<T>().write().unwrap(),
+            pd: PhantomData,
+        }
+    }
+}
+
+type SystemFn = Box<dyn Fn(&SystemContext) -> CanFail + Send + Sync>;
+
+/**
+A named unit of per-frame work with declared data access
+
+Declaring access lets `Systems` run systems that do not conflict at the same time
+*/
+pub struct System {
+    name: String,
+    reads: Vec<TypeId>,
+    writes: Vec<TypeId>,
+    run: SystemFn,
+}
+
+impl fmt::Debug for System {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("System").field("name", &self.name).finish()
+    }
+}
+
+impl System {
+    /// Create a new system that does not access any data
+    pub fn new<F>(name: impl Into<String>, run: F) -> Self
+    where
+        F: Fn(&SystemContext) -> CanFail + Send + Sync + 'static,
+    {
+        System {
+            name: name.into(),
+            reads: Vec::new(),
+            writes: Vec::new(),
+            run: Box::new(run),
+        }
+    }
+    /// Declare that the system reads some data
+    pub fn reads<T>(mut self) -> Self
+    where
+        T: Send + Sync + 'static,
+    {
+        self.reads.push(TypeId::of::<T>());
+        self
+    }
+    /// Declare that the system reads and writes some data
+    pub fn writes<T>(mut self) -> Self
+    where
+        T: Send + Sync + 'static,
+    {
+        self.writes.push(TypeId::of::<T>());
+        self
+    }
+    /// Get the system's name
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    fn conflicts_with(&self, other: &System) -> bool {
+        self.writes
+            .iter()
+            .any(|id| other.reads.contains(id) || other.writes.contains(id))
+            || other.writes.iter().any(|id| self.reads.contains(id))
+    }
+}
+
+/**
+Runs systems, in parallel where their data access allows
+
+Systems are grouped into stages in the order they were added. A system joins the
+current stage unless it conflicts with a system already in it, so systems that
+read and write the same data still run in the order they were added. The systems
+in a stage run on separate threads.
+
+Systems are opt-in. Call `Systems::run` from `Kule::update` or `Kule::fixed_update`
+while the main thread keeps handling drawing.
+
+```
+# use kule::*;
+struct Positions(Vec<f32>);
+struct Total(f32);
+
+let mut data = SystemData::new();
+data.insert(Positions(vec![1.0, 2.0]));
+data.insert(Total(0.0));
+
+let mut systems = Systems::new();
+systems.add(System::new("move", |ctx| {
+    for pos in &mut ctx.write::<Positions>().0 {
+        *pos += ctx.dt;
+    }
+    Ok(())
+}).writes::<Positions>());
+systems.add(System::new("sum", |ctx| {
+    ctx.write::<Total>().0 = ctx.read::<Positions>().0.iter().sum();
+    Ok(())
+}).reads::<Positions>().writes::<Total>());
+
+systems.run(&data, 1.0).unwrap();
+assert_eq!(data.get::<Total>().unwrap().0, 5.0);
+```
+*/
+#[derive(Debug)]
+pub struct Systems {
+    systems: Vec<System>,
+    stages: Vec<Vec<usize>>,
+    parallel: bool,
+}
+
+impl Default for Systems {
+    fn default() -> Self {
+        Systems::new()
+    }
+}
+
+impl Systems {
+    /// Create a new empty `Systems`
+    pub fn new() -> Self {
+        Systems {
+            systems: Vec::new(),
+            stages: Vec::new(),
+            parallel: true,
+        }
+    }
+    /// Add a system
+    pub fn add(&mut self, system: System) {
+        let i = self.systems.len();
+        let conflicts = match self.stages.last() {
+            Some(stage) => stage
+                .iter()
+                .any(|&j| self.systems[j].conflicts_with(&system)),
+            None => true,
+        };
+        if conflicts {
+            self.stages.push(vec![i]);
+        } else {
+            self.stages.last_mut().unwrap().push(i);
+        }
+        self.systems.push(system);
+    }
+    /// Get the names of the systems in each stage
+    pub fn stages(&self) -> Vec<Vec<&str>> {
+        self.stages
+            .iter()
+            .map(|stage| stage.iter().map(|&i| self.systems[i].name()).collect())
+            .collect()
+    }
+    /// Set whether systems in the same stage run in parallel
+    ///
+    /// Running them sequentially can make debugging easier
+    pub fn set_parallel(&mut self, parallel: bool) {
+        self.parallel = parallel;
+    }
+    /**
+    Run all systems
+
+    Every system in a stage runs even if one fails.
+    The first error is returned after the stage finishes.
+    Errors are converted to `KuleError::System` so that they can be sent between threads.
+
+    Each system in a parallel stage runs on its own scoped thread. If a system panics,
+    the panic is resumed on this thread once the stage has finished.
+    */
+    pub fn run(&self, data: &SystemData, dt: f32) -> CanFail {
+        for stage in &self.stages {
+            let contexts = stage.iter().map(|&i| SystemContext {
+                data,
+                system: &self.systems[i],
+                dt,
+            });
+            let run = |ctx: SystemContext| {
+                (ctx.system.run)(&ctx).map_err(|e| (ctx.system.name.clone(), e.to_string()))
+            };
+            let results: Vec<Result<(), (String, String)>> = if self.parallel && stage.len() > 1 {
+                thread::scope(|scope| {
+                    let handles: Vec<_> =
+                        contexts.map(|ctx| scope.spawn(move || run(ctx))).collect();
+                    handles
+                        .into_iter()
+                        .map(|handle| {
+                            handle
+                                .join()
+                                .unwrap_or_else(|payload| panic::resume_unwind(payload))
+                        })
+                        .collect()
+                })
+            } else {
+                contexts.map(run).collect()
+            };
+            for res in results {
+                res.map_err(|(name, message)| KuleError::System(name, message))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn system_stages() {
+    struct A;
+    struct B;
+    let mut systems = Systems::new();
+    systems.add(System::new("a1", |_| Ok(())).reads::<A>());
+    systems.add(System::new("a2", |_| Ok(())).reads::<A>().writes::<B>());
+    systems.add(System::new("b", |_| Ok(())).reads::<B>());
+    systems.add(System::new("a3", |_| Ok(())).writes::<A>());
+    assert_eq!(systems.stages(), vec![vec!["a1", "a2"], vec!["b", "a3"]]);
+    systems.run(&SystemData::new(), 0.0).unwrap();
+}
@@ -0,0 +1,265 @@
+use std::collections::BTreeMap;
+
+use vector2math::*;
+
+use crate::{Col, Color, Easing, Interpolate, Trans, Vec2};
+
+/**
+A value at a point in time
+
+The easing shapes the transition from this keyframe to the next one.
+*/
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(
+    feature = "ser",
+    derive(serde_derive::Serialize, serde_derive::Deserialize)
+)]
+pub struct Keyframe<T> {
+    /// The time in seconds
+    pub time: f32,
+    /// The value at this time
+    pub value: T,
+    /// The easing used to move to the next keyframe
+    #[cfg_attr(feature = "ser", serde(default))]
+    pub easing: Easing,
+}
+
+impl<T> Keyframe<T> {
+    /// Create a new linear `Keyframe`
+    pub fn new(time: f32, value: T) -> Self {
+        Keyframe {
+            time,
+            value,
+            easing: Easing::Linear,
+        }
+    }
+    /// Set the easing used to move to the next keyframe
+    pub fn easing(self, easing: Easing) -> Self {
+        Keyframe { easing, ..self }
+    }
+}
+
+fn sample_keys<T>(keys: &[Keyframe<T>], time: f32) -> Option<T>
+where
+    T: Interpolate,
+{
+    let next = keys.partition_point(|key| key.time <= time);
+    match (next.checked_sub(1).map(|i| &keys[i]), keys.get(next)) {
+        (Some(prev), Some(next)) => {
+            let t = (time - prev.time) / (next.time - prev.time);
+            Some(prev.value.interpolate(next.value, prev.easing.apply(t)))
+        }
+        (Some(key), None) | (None, Some(key)) => Some(key.value),
+        (None, None) => None,
+    }
+}
+
+/// The transformation and color of a timeline track at some time
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pose {
+    /// The position
+    pub position: Vec2,
+    /// The rotation in radians
+    pub rotation: f32,
+    /// The scale
+    pub scale: Vec2,
+    /// The color
+    pub color: Col,
+}
+
+impl Default for Pose {
+    fn default() -> Self {
+        Pose {
+            position: [0.0; 2],
+            rotation: 0.0,
+            scale: [1.0; 2],
+            color: Col::white(),
+        }
+    }
+}
+
+impl Pose {
+    /// Get the transformation that scales, then rotates, then translates
+    pub fn transform(&self) -> Trans {
+        Trans::identity()
+            .scale(self.scale)
+            .rotate(self.rotation)
+            .translate(self.position)
+    }
+}
+
+/**
+Keyframes for the properties of one animated thing
+
+Properties with no keyframes keep their `Pose::default` values.
+*/
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(
+    feature = "ser",
+    derive(serde_derive::Serialize, serde_derive::Deserialize)
+)]
+pub struct Track {
+    /// Position keyframes
+    #[cfg_attr(feature = "ser", serde(default))]
+    pub position: Vec<Keyframe<Vec2>>,
+    /// Rotation keyframes in radians
+    #[cfg_attr(feature = "ser", serde(default))]
+    pub rotation: Vec<Keyframe<f32>>,
+    /// Scale keyframes
+    #[cfg_attr(feature = "ser", serde(default))]
+    pub scale: Vec<Keyframe<Vec2>>,
+    /// Color keyframes
+    #[cfg_attr(feature = "ser", serde(default))]
+    pub color: Vec<Keyframe<Col>>,
+}
+
+impl Track {
+    /// Sort all keyframes by time
+    ///
+    /// Sampling assumes the keyframes are sorted
+    pub fn sort(&mut self) {
+        fn sort<T>(keys: &mut [Keyframe<T>]) {
+            keys.sort_by(|a, b| a.time.total_cmp(&b.time));
+        }
+        sort(&mut self.position);
+        sort(&mut self.rotation);
+        sort(&mut self.scale);
+        sort(&mut self.color);
+    }
+    /// Get the time of the last keyframe
+    pub fn duration(&self) -> f32 {
+        let position = self.position.iter().map(|key| key.time);
+        let rotation = self.rotation.iter().map(|key| key.time);
+        let scale = self.scale.iter().map(|key| key.time);
+        let color = self.color.iter().map(|key| key.time);
+        position
+            .chain(rotation)
+            .chain(scale)
+            .chain(color)
+            .fold(0.0, f32::max)
+    }
+    /// Get the pose at some time
+    pub fn sample(&self, time: f32) -> Pose {
+        let default = Pose::default();
+        Pose {
+            position: sample_keys(&self.position, time).unwrap_or(default.position),
+            rotation: sample_keys(&self.rotation, time).unwrap_or(default.rotation),
+            scale: sample_keys(&self.scale, time).unwrap_or(default.scale),
+            color: sample_keys(&self.color, time).unwrap_or(default.color),
+        }
+    }
+}
+
+/**
+A keyframed animation made of named tracks
+
+Timelines are meant to be written as data and loaded at runtime.
+
+```toml
+looping = true
+
+[tracks.body]
+position = [
+    { time = 0, value = [0, 0] },
+    { time = 0.5, value = [0, -20], easing = "OutQuad" },
+    { time = 1, value = [0, 0] },
+]
+color = [{ time = 0, value = [1, 1, 1, 1] }, { time = 1, value = [1, 0, 0, 1] }]
+
+[tracks.arm]
+rotation = [{ time = 0, value = 0 }, { time = 1, value = 3.14 }]
+```
+*/
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(
+    feature = "ser",
+    derive(serde_derive::Serialize, serde_derive::Deserialize)
+)]
+pub struct Timeline {
+    /// Whether sampling past the end wraps back to the start
+    #[cfg_attr(feature = "ser", serde(default))]
+    pub looping: bool,
+    /// The tracks by name
+    #[cfg_attr(feature = "ser", serde(default))]
+    pub tracks: BTreeMap<String, Track>,
+}
+
+impl Timeline {
+    /// Create a new empty `Timeline`
+    pub fn new() -> Self {
+        Timeline::default()
+    }
+    #[cfg(feature = "ser")]
+    /// Parse a timeline from toml
+    pub fn from_toml(text: &str) -> crate::KuleResult<Self> {
+        let mut timeline: Self = toml::from_str(text)?;
+        for track in timeline.tracks.values_mut() {
+            track.sort();
+        }
+        Ok(timeline)
+    }
+    #[cfg(feature = "ser")]
+    /// Load a timeline from a toml file
+    pub fn load<P>(path: P) -> crate::KuleResult<Self>
+    where
+        P: AsRef<std::path::Path>,
+    {
+        Timeline::from_toml(&std::fs::read_to_string(path)?)
+    }
+    /// Add a track, replacing any track with the same name
+    pub fn track(mut self, name: impl Into<String>, mut track: Track) -> Self {
+        track.sort();
+        self.tracks.insert(name.into(), track);
+        self
+    }
+    /// Get the time of the last keyframe of any track
+    pub fn duration(&self) -> f32 {
+        self.tracks
+            .values()
+            .map(Track::duration)
+            .fold(0.0, f32::max)
+    }
+    fn local_time(&self, time: f32) -> f32 {
+        let duration = self.duration();
+        if self.looping && duration > 0.0 {
+            time.rem_euclid(duration)
+        } else {
+            time
+        }
+    }
+    /// Get the pose of every track at some time
+    pub fn sample(&self, time: f32) -> impl Iterator<Item = (&str, Pose)> {
+        let time = self.local_time(time);
+        self.tracks
+            .iter()
+            .map(move |(name, track)| (name.as_str(), track.sample(time)))
+    }
+    /// Get the pose of a single track at some time
+    pub fn sample_track(&self, name: &str, time: f32) -> Option<Pose> {
+        let time = self.local_time(time);
+        self.tracks.get(name).map(|track| track.sample(time))
+    }
+}
+
+#[cfg(all(test, feature = "ser"))]
+#[test]
+fn timeline_sampling() {
+    let timeline = Timeline::from_toml(
+        r#"
+        looping = true
+        [tracks.a]
+        position = [{ time = 2, value = [4, 0] }, { time = 0, value = [0, 0] }]
+        rotation = [{ time = 0, value = 0 }, { time = 1, value = 1, easing = "OutQuad" }]
+        "#,
+    )
+    .unwrap();
+    assert_eq!(timeline.duration(), 2.0);
+    let pose = timeline.sample_track("a", 1.0).unwrap();
+    assert_eq!(pose.position, [2.0, 0.0]);
+    assert_eq!(pose.rotation, 1.0);
+    assert_eq!(pose.scale, [1.0, 1.0]);
+    assert_eq!(
+        timeline.sample_track("a", 2.5).unwrap().position,
+        [1.0, 0.0]
+    );
+}
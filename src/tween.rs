@@ -0,0 +1,510 @@
+use std::{any::Any, f32::consts::PI, marker::PhantomData, mem::take};
+
+use crate::{CanFail, Context, Interpolate, Resources, Vec2};
+
+/// An easing curve that shapes how a tween moves between values
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(
+    feature = "ser",
+    derive(serde_derive::Serialize, serde_derive::Deserialize)
+)]
+pub enum Easing {
+    /// Constant speed
+    #[default]
+    Linear,
+    /// Quadratic ease in
+    InQuad,
+    /// Quadratic ease out
+    OutQuad,
+    /// Quadratic ease in and out
+    InOutQuad,
+    /// Cubic ease in
+    InCubic,
+    /// Cubic ease out
+    OutCubic,
+    /// Cubic ease in and out
+    InOutCubic,
+    /// Sinusoidal ease in
+    InSine,
+    /// Sinusoidal ease out
+    OutSine,
+    /// Sinusoidal ease in and out
+    InOutSine,
+    /// Exponential ease in
+    InExpo,
+    /// Exponential ease out
+    OutExpo,
+    /// Ease in that pulls back before moving forward
+    InBack,
+    /// Ease out that overshoots before settling
+    OutBack,
+    /// Ease out that springs past the end a few times
+    OutElastic,
+    /// Ease out that bounces against the end
+    OutBounce,
+}
+
+impl Easing {
+    /// Map a linear progress from `0.0` to `1.0` onto the curve
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        const BACK: f32 = 1.70158;
+        match self {
+            Easing::Linear => t,
+            Easing::InQuad => t * t,
+            Easing::OutQuad => 1.0 - (1.0 - t).powi(2),
+            Easing::InOutQuad => {
+                if t < 0.5 {
+                    2.0 * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(2) / 2.0
+                }
+            }
+            Easing::InCubic => t.powi(3),
+            Easing::OutCubic => 1.0 - (1.0 - t).powi(3),
+            Easing::InOutCubic => {
+                if t < 0.5 {
+                    4.0 * t.powi(3)
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+                }
+            }
+            Easing::InSine => 1.0 - (t * PI / 2.0).cos(),
+            Easing::OutSine => (t * PI / 2.0).sin(),
+            Easing::InOutSine => -((PI * t).cos() - 1.0) / 2.0,
+            Easing::InExpo => {
+                if t == 0.0 {
+                    0.0
+                } else {
+                    2f32.powf(10.0 * t - 10.0)
+                }
+            }
+            Easing::OutExpo => {
+                if t == 1.0 {
+                    1.0
+                } else {
+                    1.0 - 2f32.powf(-10.0 * t)
+                }
+            }
+            Easing::InBack => (BACK + 1.0) * t.powi(3) - BACK * t * t,
+            Easing::OutBack => 1.0 + (BACK + 1.0) * (t - 1.0).powi(3) + BACK * (t - 1.0).powi(2),
+            Easing::OutElastic => {
+                if t == 0.0 || t == 1.0 {
+                    t
+                } else {
+                    2f32.powf(-10.0 * t) * ((t * 10.0 - 0.75) * (2.0 * PI / 3.0)).sin() + 1.0
+                }
+            }
+            Easing::OutBounce => {
+                const N: f32 = 7.5625;
+                const D: f32 = 2.75;
+                if t < 1.0 / D {
+                    N * t * t
+                } else if t < 2.0 / D {
+                    let t = t - 1.5 / D;
+                    N * t * t + 0.75
+                } else if t < 2.5 / D {
+                    let t = t - 2.25 / D;
+                    N * t * t + 0.9375
+                } else {
+                    let t = t - 2.625 / D;
+                    N * t * t + 0.984375
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Segment<T> {
+    end: T,
+    duration: f32,
+    easing: Easing,
+}
+
+/**
+A value that moves between other values over time
+
+Tweens can be updated manually, or added to the context's `Tweens`
+to be advanced by the engine every update.
+
+```
+# use kule::*;
+let mut tween = Tween::new(0.0, 10.0, 1.0, Easing::Linear).then(0.0, 1.0, Easing::OutCubic);
+tween.update(0.5);
+assert_eq!(tween.value(), 5.0);
+tween.update(1.5);
+assert_eq!(tween.value(), 0.0);
+assert!(tween.is_finished());
+```
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tween<T> {
+    start: T,
+    segments: Vec<Segment<T>>,
+    elapsed: f32,
+}
+
+impl<T> Tween<T>
+where
+    T: Interpolate,
+{
+    /// Create a new `Tween` that moves from a start value to an end value over some seconds
+    pub fn new(start: T, end: T, duration: f32, easing: Easing) -> Self {
+        Tween {
+            start,
+            segments: Vec::new(),
+            elapsed: 0.0,
+        }
+        .then(end, duration, easing)
+    }
+    /// Continue moving to another value after the tween reaches its current end
+    pub fn then(mut self, end: T, duration: f32, easing: Easing) -> Self {
+        self.push(end, duration, easing);
+        self
+    }
+    fn push(&mut self, end: T, duration: f32, easing: Easing) {
+        self.segments.push(Segment {
+            end,
+            duration: duration.max(0.0),
+            easing,
+        });
+    }
+    /// Hold the current end value for some seconds
+    pub fn wait(self, duration: f32) -> Self {
+        let end = self.end();
+        self.then(end, duration, Easing::Linear)
+    }
+    /// Get the final value
+    pub fn end(&self) -> T {
+        self.segments.last().map_or(self.start, |seg| seg.end)
+    }
+    /// Get the total duration in seconds
+    pub fn duration(&self) -> f32 {
+        self.segments.iter().map(|seg| seg.duration).sum()
+    }
+    /// Get the number of seconds the tween has run
+    pub fn elapsed(&self) -> f32 {
+        self.elapsed
+    }
+    /// Check if the tween has reached its final value
+    pub fn is_finished(&self) -> bool {
+        self.elapsed >= self.duration()
+    }
+    /// Advance the tween by some seconds
+    pub fn update(&mut self, dt: f32) {
+        self.elapsed = (self.elapsed + dt).min(self.duration());
+    }
+    /// Restart the tween from the beginning
+    pub fn reset(&mut self) {
+        self.elapsed = 0.0;
+    }
+    /// Get the value at some number of seconds after the start
+    pub fn sample(&self, time: f32) -> T {
+        let mut from = self.start;
+        let mut time = time.max(0.0);
+        for seg in &self.segments {
+            if time < seg.duration {
+                return from.interpolate(seg.end, seg.easing.apply(time / seg.duration));
+            }
+            time -= seg.duration;
+            from = seg.end;
+        }
+        from
+    }
+    /// Get the current value
+    pub fn value(&self) -> T {
+        self.sample(self.elapsed)
+    }
+}
+
+/// A handle to a tween being run by the context's `Tweens`
+pub struct TweenHandle<T> {
+    id: u64,
+    pd: PhantomData<fn() -> T>,
+}
+
+impl<T> Clone for TweenHandle<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for TweenHandle<T> {}
+
+impl<T> PartialEq for TweenHandle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl<T> Eq for TweenHandle<T> {}
+
+impl<T> std::fmt::Debug for TweenHandle<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "TweenHandle({})", self.id)
+    }
+}
+
+type Binding<R> = Box<dyn FnMut(&dyn Any, &mut Context<R>)>;
+type Callback<R> = Box<dyn FnOnce(&mut Context<R>) -> CanFail>;
+
+trait AnyTween {
+    fn update(&mut self, dt: f32) -> bool;
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T> AnyTween for Tween<T>
+where
+    T: Interpolate + 'static,
+{
+    fn update(&mut self, dt: f32) -> bool {
+        Tween::update(self, dt);
+        self.is_finished()
+    }
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+struct Running<R>
+where
+    R: Resources,
+{
+    id: u64,
+    tween: Box<dyn AnyTween>,
+    bindings: Vec<Binding<R>>,
+    on_complete: Vec<Callback<R>>,
+}
+
+/**
+The tweens being run by the engine
+
+Tweens are advanced after each update. Each can be bound to functions that
+receive its value every update, such as one that moves the camera. Once a tween
+finishes, its completion callbacks are run and it is removed.
+
+```
+# use kule::*;
+# fn f<R: Resources>(ctx: &mut Context<R>) {
+let fade = ctx.tween(1.0f32, 0.0, 0.5, Easing::OutQuad);
+ctx.tweens.bind(fade, |alpha, ctx| ctx.camera.zoom = 1.0 + alpha);
+ctx.tweens.on_complete(fade, |_| {
+    println!("done!");
+    Ok(())
+});
+ctx.tween_camera_center([100.0, 0.0], 2.0, Easing::InOutSine);
+# }
+```
+*/
+pub struct Tweens<R>
+where
+    R: Resources,
+{
+    next_id: u64,
+    running: Vec<Running<R>>,
+}
+
+impl<R> Default for Tweens<R>
+where
+    R: Resources,
+{
+    fn default() -> Self {
+        Tweens {
+            next_id: 0,
+            running: Vec::new(),
+        }
+    }
+}
+
+impl<R> Tweens<R>
+where
+    R: Resources,
+{
+    /// Start running a tween
+    pub fn add<T>(&mut self, tween: Tween<T>) -> TweenHandle<T>
+    where
+        T: Interpolate + 'static,
+    {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.running.push(Running {
+            id,
+            tween: Box::new(tween),
+            bindings: Vec::new(),
+            on_complete: Vec::new(),
+        });
+        TweenHandle {
+            id,
+            pd: PhantomData,
+        }
+    }
+    fn running_mut<T>(&mut self, handle: TweenHandle<T>) -> Option<&mut Running<R>> {
+        self.running
+            .iter_mut()
+            .find(|running| running.id == handle.id)
+    }
+    /// Get a running tween
+    pub fn get<T>(&self, handle: TweenHandle<T>) -> Option<&Tween<T>>
+    where
+        T: 'static,
+    {
+        self.running
+            .iter()
+            .find(|running| running.id == handle.id)
+            .and_then(|running| running.tween.as_any().downcast_ref())
+    }
+    /// Get a running tween mutably
+    pub fn get_mut<T>(&mut self, handle: TweenHandle<T>) -> Option<&mut Tween<T>>
+    where
+        T: 'static,
+    {
+        self.running_mut(handle)
+            .and_then(|running| running.tween.as_any_mut().downcast_mut())
+    }
+    /// Get the current value of a running tween
+    pub fn value<T>(&self, handle: TweenHandle<T>) -> Option<T>
+    where
+        T: Interpolate + 'static,
+    {
+        self.get(handle).map(Tween::value)
+    }
+    /// Check if a tween is still running
+    pub fn is_running<T>(&self, handle: TweenHandle<T>) -> bool {
+        self.running.iter().any(|running| running.id == handle.id)
+    }
+    /// Continue a running tween to another value after it reaches its current end
+    pub fn then<T>(&mut self, handle: TweenHandle<T>, end: T, duration: f32, easing: Easing)
+    where
+        T: Interpolate + 'static,
+    {
+        if let Some(tween) = self.get_mut(handle) {
+            tween.push(end, duration, easing);
+        }
+    }
+    /// Call a function with a tween's value every time it is advanced
+    pub fn bind<T, F>(&mut self, handle: TweenHandle<T>, mut f: F)
+    where
+        T: Interpolate + 'static,
+        F: FnMut(T, &mut Context<R>) + 'static,
+    {
+        if let Some(running) = self.running_mut(handle) {
+            running.bindings.push(Box::new(move |tween, ctx| {
+                if let Some(tween) = tween.downcast_ref::<Tween<T>>() {
+                    f(tween.value(), ctx);
+                }
+            }));
+        }
+    }
+    /// Call a function once a tween finishes
+    pub fn on_complete<T, F>(&mut self, handle: TweenHandle<T>, f: F)
+    where
+        F: FnOnce(&mut Context<R>) -> CanFail + 'static,
+    {
+        if let Some(running) = self.running_mut(handle) {
+            running.on_complete.push(Box::new(f));
+        }
+    }
+    /// Stop a tween without running its completion callbacks
+    pub fn cancel<T>(&mut self, handle: TweenHandle<T>) -> bool {
+        let len = self.running.len();
+        self.running.retain(|running| running.id != handle.id);
+        self.running.len() != len
+    }
+    /// Stop all tweens without running their completion callbacks
+    pub fn clear(&mut self) {
+        self.running.clear();
+    }
+    /// Get the number of running tweens
+    pub fn len(&self) -> usize {
+        self.running.len()
+    }
+    /// Check if no tweens are running
+    pub fn is_empty(&self) -> bool {
+        self.running.is_empty()
+    }
+    /**
+    Advance all tweens
+
+    The engine calls this after each update. Every completion callback runs
+    even if one fails. The first error is returned.
+    */
+    pub fn update(ctx: &mut Context<R>, dt: f32) -> CanFail {
+        let mut running = take(&mut ctx.tweens.running);
+        let mut callbacks = Vec::new();
+        running.retain_mut(|running| {
+            let finished = running.tween.update(dt);
+            for binding in &mut running.bindings {
+                binding(running.tween.as_any(), ctx);
+            }
+            if finished {
+                callbacks.append(&mut running.on_complete);
+            }
+            !finished
+        });
+        // Keep tweens that were added during the update
+        running.append(&mut ctx.tweens.running);
+        ctx.tweens.running = running;
+        let mut res = Ok(());
+        for callback in callbacks {
+            let callback_res = callback(ctx);
+            if res.is_ok() {
+                res = callback_res;
+            }
+        }
+        res
+    }
+}
+
+impl<R> Context<R>
+where
+    R: Resources,
+{
+    /// Start running a tween between two values
+    pub fn tween<T>(&mut self, start: T, end: T, duration: f32, easing: Easing) -> TweenHandle<T>
+    where
+        T: Interpolate + 'static,
+    {
+        self.tweens.add(Tween::new(start, end, duration, easing))
+    }
+    /// Move the camera's center from where it is to a new position
+    pub fn tween_camera_center(
+        &mut self,
+        end: Vec2,
+        duration: f32,
+        easing: Easing,
+    ) -> TweenHandle<Vec2> {
+        let handle = self.tween(self.camera.center, end, duration, easing);
+        self.tweens
+            .bind(handle, |center, ctx| ctx.camera.center = center);
+        handle
+    }
+    /// Change the camera's zoom from what it is to a new value
+    pub fn tween_camera_zoom(
+        &mut self,
+        end: f32,
+        duration: f32,
+        easing: Easing,
+    ) -> TweenHandle<f32> {
+        let handle = self.tween(self.camera.zoom, end, duration, easing);
+        self.tweens.bind(handle, |zoom, ctx| ctx.camera.zoom = zoom);
+        handle
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn easing_endpoints() {
+    use Easing::*;
+    for easing in [
+        Linear, InQuad, OutQuad, InOutQuad, InCubic, OutCubic, InOutCubic, InSine, OutSine,
+        InOutSine, InExpo, OutExpo, InBack, OutBack, OutElastic, OutBounce,
+    ] {
+        assert!(easing.apply(0.0).abs() < 1e-5, "{:?}", easing);
+        assert!((easing.apply(1.0) - 1.0).abs() < 1e-5, "{:?}", easing);
+    }
+}
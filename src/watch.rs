@@ -0,0 +1,172 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
+
+use crate::Resources;
+
+/// An id for a watched asset path
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(
+    feature = "ser",
+    derive(serde_derive::Serialize, serde_derive::Deserialize)
+)]
+pub struct WatchId(pub u64);
+
+/// The kind of asset at a watched path, which determines how it is reloaded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchedAsset<R = ()>
+where
+    R: Resources,
+{
+    /// A font file that is reloaded into the font cache
+    Font(R::FontId),
+    #[cfg(feature = "sound")]
+    /// A sound whose cached buffer is removed so that it is reloaded with `Kule::load_sound`
+    Sound(R::SoundId),
+    #[cfg(feature = "script")]
+    /// The script modules, which are all reloaded
+    Scripts,
+    /// Any other file or directory, which the app reloads itself
+    Other,
+}
+
+struct Watched<R>
+where
+    R: Resources,
+{
+    id: WatchId,
+    path: PathBuf,
+    asset: WatchedAsset<R>,
+    modified: Option<SystemTime>,
+}
+
+/**
+Watches asset files for changes so they can be reloaded while the app runs
+
+Changes are detected by polling file modification times. Watching a directory
+detects changes to any file in it. When a change to a watched path is detected,
+the asset is reloaded into the corresponding cache and an `Event::AssetChanged`
+is sent with the path's `WatchId`.
+*/
+pub struct AssetWatcher<R = ()>
+where
+    R: Resources,
+{
+    watched: Vec<Watched<R>>,
+    interval: Duration,
+    last_poll: Instant,
+    next_id: u64,
+}
+
+impl<R> Default for AssetWatcher<R>
+where
+    R: Resources,
+{
+    fn default() -> Self {
+        AssetWatcher {
+            watched: Vec::new(),
+            interval: Duration::from_millis(500),
+            last_poll: Instant::now(),
+            next_id: 0,
+        }
+    }
+}
+
+impl<R> AssetWatcher<R>
+where
+    R: Resources,
+{
+    /// Start watching a file or directory
+    pub fn watch<P>(&mut self, path: P, asset: WatchedAsset<R>) -> WatchId
+    where
+        P: AsRef<Path>,
+    {
+        let id = WatchId(self.next_id);
+        self.next_id += 1;
+        let path = path.as_ref().to_path_buf();
+        let modified = last_modified(&path);
+        self.watched.push(Watched {
+            id,
+            path,
+            asset,
+            modified,
+        });
+        id
+    }
+    /// Stop watching a path
+    pub fn unwatch(&mut self, id: WatchId) {
+        self.watched.retain(|watched| watched.id != id);
+    }
+    /// Get the path and asset kind of a watched path
+    pub fn get(&self, id: WatchId) -> Option<(&Path, WatchedAsset<R>)> {
+        self.watched
+            .iter()
+            .find(|watched| watched.id == id)
+            .map(|watched| (watched.path.as_path(), watched.asset))
+    }
+    /// Get how often watched paths are checked for changes
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+    /// Set how often watched paths are checked for changes
+    pub fn set_interval(&mut self, interval: Duration) {
+        self.interval = interval;
+    }
+    /// Check if no paths are being watched
+    pub fn is_empty(&self) -> bool {
+        self.watched.is_empty()
+    }
+    /// Get the watched paths that have changed since the last poll
+    pub(crate) fn poll(&mut self, now: Instant) -> Vec<(WatchId, PathBuf, WatchedAsset<R>)> {
+        if self.watched.is_empty() || now < self.last_poll + self.interval {
+            return Vec::new();
+        }
+        self.last_poll = now;
+        let mut changed = Vec::new();
+        for watched in &mut self.watched {
+            let modified = last_modified(&watched.path);
+            if modified != watched.modified {
+                watched.modified = modified;
+                changed.push((watched.id, watched.path.clone(), watched.asset));
+            }
+        }
+        changed
+    }
+}
+
+/// Get the latest modification time of a file or of any file in a directory
+fn last_modified(path: &Path) -> Option<SystemTime> {
+    let metadata = fs::metadata(path).ok()?;
+    let mut modified = metadata.modified().ok();
+    if metadata.is_dir() {
+        for entry in fs::read_dir(path).ok()?.filter_map(Result::ok) {
+            modified = modified.max(last_modified(&entry.path()));
+        }
+    }
+    modified
+}
+
+#[cfg(test)]
+#[test]
+fn watcher_detects_changes() {
+    let dir = std::env::temp_dir().join("kule_watch_test");
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("asset.txt");
+    fs::write(&path, "a").unwrap();
+    let mut watcher = AssetWatcher::<()>::default();
+    watcher.set_interval(Duration::from_secs(0));
+    let id = watcher.watch(&dir, WatchedAsset::Other);
+    assert!(watcher.poll(Instant::now()).is_empty());
+    fs::File::options()
+        .write(true)
+        .open(&path)
+        .unwrap()
+        .set_modified(SystemTime::now() + Duration::from_secs(10))
+        .unwrap();
+    let changed = watcher.poll(Instant::now());
+    assert_eq!(changed.len(), 1);
+    assert_eq!(changed[0].0, id);
+    fs::remove_dir_all(dir).unwrap();
+}